@@ -0,0 +1,44 @@
+use std::path::Path;
+use std::process::Command;
+
+/// 记录当前git提交，用于标记回测记录对应的代码版本
+///
+/// 工作区有未提交的修改时追加 `-dirty`，避免回测记录声称对应某个提交而实际运行的代码不同。
+fn main() {
+    let git_hash = git(&["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+        .is_some_and(|status| !status.is_empty());
+
+    println!(
+        "cargo:rustc-env=GIT_HASH={}{}",
+        git_hash,
+        if dirty { "-dirty" } else { "" }
+    );
+
+    // HEAD指向分支时，提交只会改变分支的ref文件 (或打包后的packed-refs)，需要分别监听
+    let git_dir = Path::new(".git");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Ok(head) = std::fs::read_to_string(git_dir.join("HEAD"))
+        && let Some(reference) = head.trim().strip_prefix("ref: ")
+    {
+        println!("cargo:rerun-if-changed=.git/{}", reference);
+    }
+    println!("cargo:rerun-if-changed=.git/packed-refs");
+
+    // 暂存区和源码变化时重新判断工作区是否有未提交的修改
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+}
+
+/// 执行git命令并返回去掉首尾空白的输出，失败时返回None
+fn git(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|output| output.trim().to_string())
+}
@@ -0,0 +1,627 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+
+use crate::storage::Storage;
+use crate::types::{
+    ArbitrageLeg, ArbitrageOpportunity, BookQuote, InstrumentRules, LatencySurvival,
+    TriangularArbConfig,
+};
+
+/// 兑换方向: 用计价货币买入基础货币，或卖出基础货币换回计价货币
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Buy,
+    Sell,
+}
+
+/// 闭环路径中的一笔兑换
+#[derive(Debug, Clone)]
+struct Step {
+    symbol: String, // 交易对
+    side: Side,     // 兑换方向
+}
+
+/// 从起始币种出发、经三个交易对换回起始币种的闭环
+#[derive(Debug, Clone)]
+struct Cycle {
+    path: String,     // 兑换路径 (如 USDT>BTC>ETH>USDT)
+    steps: Vec<Step>, // 三笔兑换
+}
+
+/// 等待延迟后重新评估的纸面执行
+#[derive(Debug, Clone)]
+struct PendingCheck {
+    due: DateTime<Utc>, // 模拟订单到达交易所的时间
+    cycle: usize,       // 闭环序号
+    edge: f64,          // 检测时的收益率
+}
+
+/// 三角套利检测器
+///
+/// 按最优买卖报价评估每个三角组合的两个兑换方向，扣除手续费并按下单规则取整后
+/// 收益率达到阈值时报告机会。同一闭环的收益率回落到阈值以下之前只报告一次。
+/// 配置了延迟时，每个机会在延迟后按届时的盘口重新评估，统计仍可成交的比例。
+pub struct TriangularArbitrage {
+    config: TriangularArbConfig,                   // 检测配置
+    instruments: HashMap<String, InstrumentRules>, // 交易对下单规则
+    cycles: Vec<Cycle>,                            // 全部闭环
+    quotes: HashMap<String, BookQuote>,            // 各交易对最新报价
+    active: HashSet<usize>,                        // 收益率仍高于阈值的闭环
+    pending: VecDeque<PendingCheck>,               // 等待延迟评估的机会 (按到期时间排序)
+    survival: LatencySurvival,                     // 延迟存活统计
+}
+
+impl TriangularArbitrage {
+    /// 创建检测器，校验每个三角组合都能从起始币种构成闭环
+    pub fn new(config: TriangularArbConfig, instruments: Vec<InstrumentRules>) -> Result<Self> {
+        if !(config.fee_rate >= 0.0 && config.fee_rate < 1.0) {
+            return Err(anyhow::anyhow!("手续费率必须在0到1之间"));
+        }
+        if config.start_amount.is_nan() || config.start_amount <= 0.0 {
+            return Err(anyhow::anyhow!("起始投入必须大于0"));
+        }
+        if config.latency_ms.is_some_and(|ms| ms < 0) {
+            return Err(anyhow::anyhow!("纸面执行延迟不能为负数"));
+        }
+
+        let instruments: HashMap<String, InstrumentRules> = instruments
+            .into_iter()
+            .map(|rules| (rules.symbol.clone(), rules))
+            .collect();
+        let mut cycles = Vec::new();
+        for triangle in &config.triangles {
+            cycles.extend(build_cycles(
+                &config.start_currency,
+                triangle,
+                &instruments,
+            )?);
+        }
+
+        Ok(Self {
+            config,
+            instruments,
+            cycles,
+            quotes: HashMap::new(),
+            active: HashSet::new(),
+            pending: VecDeque::new(),
+            survival: LatencySurvival::default(),
+        })
+    }
+
+    /// 检测涉及的全部交易对
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .cycles
+            .iter()
+            .flat_map(|cycle| cycle.steps.iter().map(|step| step.symbol.clone()))
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// 延迟存活统计
+    pub fn survival(&self) -> &LatencySurvival {
+        &self.survival
+    }
+
+    /// 处理一条最新报价，返回新出现的套利机会
+    pub fn on_quote(&mut self, quote: BookQuote) -> Vec<ArbitrageOpportunity> {
+        // 先按到期时的盘口评估已到期的纸面执行，本条报价此时尚未生效
+        self.settle_pending(quote.timestamp - Duration::milliseconds(1));
+
+        let symbol = quote.symbol.clone();
+        let timestamp = quote.timestamp;
+        self.quotes.insert(symbol.clone(), quote);
+
+        let mut opportunities = Vec::new();
+        for index in 0..self.cycles.len() {
+            let cycle = &self.cycles[index];
+            if !cycle.steps.iter().any(|step| step.symbol == symbol) {
+                continue;
+            }
+            match self.evaluate(cycle, timestamp) {
+                Some(opportunity) if opportunity.edge >= self.config.min_edge => {
+                    if !self.active.insert(index) {
+                        continue;
+                    }
+                    info!(
+                        "发现三角套利机会: {} 投入 {:.4} 换回 {:.4}，收益率 {:.4}% (理论 {:.4}%)",
+                        opportunity.path,
+                        opportunity.start_amount,
+                        opportunity.end_amount,
+                        opportunity.edge * 100.0,
+                        opportunity.gross_edge * 100.0
+                    );
+                    self.survival.detected += 1;
+                    if let Some(latency_ms) = self.config.latency_ms {
+                        self.pending.push_back(PendingCheck {
+                            due: timestamp + Duration::milliseconds(latency_ms),
+                            cycle: index,
+                            edge: opportunity.edge,
+                        });
+                    }
+                    opportunities.push(opportunity);
+                }
+                _ => {
+                    self.active.remove(&index);
+                }
+            }
+        }
+        opportunities
+    }
+
+    /// 评估到期时间不晚于 now 的全部纸面执行 (回放结束时调用)
+    pub fn flush(&mut self, now: DateTime<Utc>) {
+        self.settle_pending(now);
+    }
+
+    /// 按当前盘口重新评估到期的纸面执行
+    fn settle_pending(&mut self, now: DateTime<Utc>) {
+        while let Some(check) = self.pending.front()
+            && check.due <= now
+        {
+            let check = self.pending.pop_front().expect("队首存在");
+            let cycle = &self.cycles[check.cycle];
+            let realized = self
+                .evaluate(cycle, check.due)
+                .map(|opportunity| opportunity.edge)
+                .filter(|edge| *edge >= self.config.min_edge);
+
+            self.survival.checked += 1;
+            self.survival.detected_edge_sum += check.edge;
+            if let Some(edge) = realized {
+                self.survival.survived += 1;
+                self.survival.survived_edge_sum += edge;
+            }
+            info!(
+                "纸面执行 {}: 检测收益率 {:.4}%，延迟后{}",
+                cycle.path,
+                check.edge * 100.0,
+                match realized {
+                    Some(edge) => format!("仍可成交，收益率 {:.4}%", edge * 100.0),
+                    None => "机会已消失".to_string(),
+                }
+            );
+        }
+    }
+
+    /// 按当前盘口评估闭环，缺少报价或数量低于最小下单量时返回None
+    fn evaluate(&self, cycle: &Cycle, timestamp: DateTime<Utc>) -> Option<ArbitrageOpportunity> {
+        let quotes: Vec<&BookQuote> = cycle
+            .steps
+            .iter()
+            .map(|step| self.quotes.get(&step.symbol))
+            .collect::<Option<_>>()?;
+        let fee = self.config.fee_rate;
+
+        // 理论汇率之积，以及最优档位数量允许的投入比例
+        let mut gross = 1.0;
+        let mut scale: f64 = 1.0;
+        let mut amount = self.config.start_amount;
+        for (step, quote) in cycle.steps.iter().zip(&quotes) {
+            let (price, size) = top_of_book(step.side, quote);
+            if price.is_nan() || price <= 0.0 {
+                return None;
+            }
+            let base_quantity = match step.side {
+                Side::Buy => amount / price,
+                Side::Sell => amount,
+            };
+            if let Some(size) = size
+                && base_quantity > size
+            {
+                scale = scale.min(size / base_quantity);
+            }
+            gross *= match step.side {
+                Side::Buy => 1.0 / price,
+                Side::Sell => price,
+            };
+            amount = match step.side {
+                Side::Buy => base_quantity,
+                Side::Sell => base_quantity * price,
+            } * (1.0 - fee);
+        }
+
+        // 按实际投入逐笔取整、扣费，取整剩余的零头留在中间币种，不计入收益
+        let mut amount = self.config.start_amount * scale;
+        let mut start_amount = None;
+        let mut legs = Vec::with_capacity(cycle.steps.len());
+        for (step, quote) in cycle.steps.iter().zip(&quotes) {
+            let rules = self.instruments.get(&step.symbol)?;
+            let (price, _) = top_of_book(step.side, quote);
+            let quantity = match step.side {
+                Side::Buy => rules.round_lot(amount / price),
+                Side::Sell => rules.round_lot(amount),
+            };
+            if quantity <= 0.0 || quantity < rules.min_size {
+                return None;
+            }
+            let (spent, received) = match step.side {
+                Side::Buy => (quantity * price, quantity * (1.0 - fee)),
+                Side::Sell => (quantity, quantity * price * (1.0 - fee)),
+            };
+            start_amount.get_or_insert(spent);
+            legs.push(ArbitrageLeg {
+                symbol: step.symbol.clone(),
+                side: match step.side {
+                    Side::Buy => "buy".to_string(),
+                    Side::Sell => "sell".to_string(),
+                },
+                price,
+                quantity,
+                received,
+            });
+            amount = received;
+        }
+
+        let start_amount = start_amount?;
+        Some(ArbitrageOpportunity {
+            timestamp,
+            path: cycle.path.clone(),
+            legs,
+            start_amount,
+            end_amount: amount,
+            gross_edge: gross - 1.0,
+            edge: amount / start_amount - 1.0,
+        })
+    }
+}
+
+/// 吃单成交的价格与数量: 买入按卖一，卖出按买一
+fn top_of_book(side: Side, quote: &BookQuote) -> (f64, Option<f64>) {
+    match side {
+        Side::Buy => (quote.ask_price, quote.ask_size),
+        Side::Sell => (quote.bid_price, quote.bid_size),
+    }
+}
+
+/// 枚举三角组合中从起始币种出发、依次经过三个交易对回到起始币种的闭环
+fn build_cycles(
+    start_currency: &str,
+    triangle: &[String; 3],
+    instruments: &HashMap<String, InstrumentRules>,
+) -> Result<Vec<Cycle>> {
+    let rules = triangle
+        .iter()
+        .map(|symbol| {
+            instruments
+                .get(symbol)
+                .ok_or_else(|| anyhow::anyhow!("缺少交易对 {} 的下单规则", symbol))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut cycles = Vec::new();
+    for first in 0..3 {
+        for second in (0..3).filter(|&second| second != first) {
+            let third = 3 - first - second;
+            let mut currency = start_currency.to_string();
+            let mut path = vec![currency.clone()];
+            let mut steps = Vec::new();
+            for index in [first, second, third] {
+                let rule = rules[index];
+                let (side, next) = if rule.quote_currency == currency {
+                    (Side::Buy, &rule.base_currency)
+                } else if rule.base_currency == currency {
+                    (Side::Sell, &rule.quote_currency)
+                } else {
+                    break;
+                };
+                steps.push(Step {
+                    symbol: rule.symbol.clone(),
+                    side,
+                });
+                currency = next.clone();
+                path.push(currency.clone());
+            }
+            if steps.len() == 3 && currency == start_currency {
+                cycles.push(Cycle {
+                    path: path.join(">"),
+                    steps,
+                });
+            }
+        }
+    }
+
+    if cycles.is_empty() {
+        return Err(anyhow::anyhow!(
+            "三角组合 {} 无法从 {} 出发构成闭环",
+            triangle.join("/"),
+            start_currency
+        ));
+    }
+    Ok(cycles)
+}
+
+/// 按时间顺序回放已存储的Ticker快照，返回检测到的套利机会
+///
+/// 快照不含盘口数量，回放时投入不受最优档位数量限制。
+pub async fn replay_tickers(
+    storage: &dyn Storage,
+    detector: &mut TriangularArbitrage,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+) -> Result<Vec<ArbitrageOpportunity>> {
+    let mut snapshots = Vec::new();
+    for symbol in detector.symbols() {
+        snapshots.extend(storage.get_tickers(&symbol, start_time, end_time).await?);
+    }
+    snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+
+    let mut opportunities = Vec::new();
+    for snapshot in &snapshots {
+        opportunities.extend(detector.on_quote(BookQuote::from(snapshot)));
+    }
+    detector.flush(end_time);
+    Ok(opportunities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_db::MemoryDatabase;
+    use crate::types::TickerData;
+
+    fn rules(symbol: &str, lot_size: f64, min_size: f64) -> InstrumentRules {
+        let (base, quote) = symbol.split_once('-').unwrap();
+        InstrumentRules {
+            symbol: symbol.to_string(),
+            base_currency: base.to_string(),
+            quote_currency: quote.to_string(),
+            lot_size,
+            min_size,
+        }
+    }
+
+    fn instruments() -> Vec<InstrumentRules> {
+        vec![
+            rules("BTC-USDT", 0.00001, 0.00001),
+            rules("ETH-BTC", 0.001, 0.001),
+            rules("ETH-USDT", 0.0001, 0.0001),
+        ]
+    }
+
+    fn quote(symbol: &str, ms: i64, bid: f64, ask: f64) -> BookQuote {
+        BookQuote {
+            symbol: symbol.to_string(),
+            timestamp: DateTime::from_timestamp_millis(ms).unwrap(),
+            bid_price: bid,
+            bid_size: None,
+            ask_price: ask,
+            ask_size: None,
+        }
+    }
+
+    fn detector(latency_ms: Option<i64>) -> TriangularArbitrage {
+        let config = TriangularArbConfig {
+            latency_ms,
+            ..TriangularArbConfig::default()
+        };
+        TriangularArbitrage::new(config, instruments()).unwrap()
+    }
+
+    /// 定价: BTC=50000, ETH=3000 USDT，ETH-BTC 卖一 0.059 低于公允值 0.06
+    fn feed_mispriced(detector: &mut TriangularArbitrage, ms: i64) -> Vec<ArbitrageOpportunity> {
+        let mut found = detector.on_quote(quote("BTC-USDT", ms, 49999.0, 50000.0));
+        found.extend(detector.on_quote(quote("ETH-USDT", ms, 3000.0, 3001.0)));
+        found.extend(detector.on_quote(quote("ETH-BTC", ms, 0.0589, 0.059)));
+        found
+    }
+
+    #[test]
+    fn test_cycles_and_invalid_triangle() {
+        let detector = detector(None);
+        let paths: Vec<&str> = detector.cycles.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&"USDT>BTC>ETH>USDT"));
+        assert!(paths.contains(&"USDT>ETH>BTC>USDT"));
+
+        let config = TriangularArbConfig {
+            triangles: vec![[
+                "BTC-USDT".to_string(),
+                "ETH-USDT".to_string(),
+                "SOL-USDT".to_string(),
+            ]],
+            ..TriangularArbConfig::default()
+        };
+        let mut list = instruments();
+        list.push(rules("SOL-USDT", 0.001, 0.01));
+        assert!(TriangularArbitrage::new(config, list).is_err());
+    }
+
+    #[test]
+    fn test_edge_with_fees_and_lot_rounding() {
+        let mut detector = detector(None);
+        let found = feed_mispriced(&mut detector, 1_000);
+        assert_eq!(found.len(), 1);
+        let opportunity = &found[0];
+        assert_eq!(opportunity.path, "USDT>BTC>ETH>USDT");
+
+        // 1000 USDT 买 0.02 BTC，扣费后 0.01998 BTC 买 0.338 ETH (按0.001取整)
+        let legs = &opportunity.legs;
+        assert!((legs[0].quantity - 0.02).abs() < 1e-12);
+        assert!((legs[1].quantity - 0.338).abs() < 1e-12);
+        assert!((legs[2].quantity - 0.3376).abs() < 1e-12);
+        let end = 0.3376 * 3000.0 * 0.999;
+        assert!((opportunity.start_amount - 1000.0).abs() < 1e-9);
+        assert!((opportunity.end_amount - end).abs() < 1e-9);
+        assert!((opportunity.edge - (end / 1000.0 - 1.0)).abs() < 1e-12);
+        // 理论收益率未扣费: 3000 / (50000 * 0.059) - 1
+        let gross = 3000.0 / (50000.0 * 0.059) - 1.0;
+        assert!((opportunity.gross_edge - gross).abs() < 1e-12);
+        assert!(opportunity.edge < opportunity.gross_edge);
+
+        // 公允定价时扣费后无利可图
+        let mut fair = self::detector(None);
+        fair.on_quote(quote("BTC-USDT", 1_000, 49999.0, 50000.0));
+        fair.on_quote(quote("ETH-USDT", 1_000, 3000.0, 3001.0));
+        assert!(
+            fair.on_quote(quote("ETH-BTC", 1_000, 0.0599, 0.06))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_book_size_and_min_size_limit_amount() {
+        let mut detector = detector(None);
+        detector.on_quote(quote("BTC-USDT", 1_000, 49999.0, 50000.0));
+        detector.on_quote(quote("ETH-USDT", 1_000, 3000.0, 3001.0));
+        // ETH-BTC 卖一只有 0.1 ETH，投入按比例缩小
+        let mut limited = quote("ETH-BTC", 1_000, 0.0589, 0.059);
+        limited.ask_size = Some(0.1);
+        let found = detector.on_quote(limited);
+        assert_eq!(found.len(), 1);
+        assert!(found[0].legs[1].quantity <= 0.1);
+        assert!(found[0].start_amount < 400.0);
+
+        // 数量低于最小下单量时无法成交
+        let config = TriangularArbConfig {
+            start_amount: 0.01,
+            ..TriangularArbConfig::default()
+        };
+        let mut tiny = TriangularArbitrage::new(config, instruments()).unwrap();
+        assert!(feed_mispriced(&mut tiny, 1_000).is_empty());
+    }
+
+    #[test]
+    fn test_opportunity_reported_once_until_it_closes() {
+        let mut detector = detector(None);
+        assert_eq!(feed_mispriced(&mut detector, 1_000).len(), 1);
+        // 价差持续期间不重复报告
+        assert!(
+            detector
+                .on_quote(quote("BTC-USDT", 2_000, 49999.0, 50000.0))
+                .is_empty()
+        );
+        // 价差消失后再次出现，重新报告
+        assert!(
+            detector
+                .on_quote(quote("ETH-BTC", 3_000, 0.0599, 0.06))
+                .is_empty()
+        );
+        assert_eq!(
+            detector
+                .on_quote(quote("ETH-BTC", 4_000, 0.0589, 0.059))
+                .len(),
+            1
+        );
+        assert_eq!(detector.survival().detected, 2);
+        assert_eq!(detector.survival().checked, 0);
+    }
+
+    #[test]
+    fn test_latency_survival() {
+        let mut detector = detector(Some(200));
+        // 第一次机会在 100ms 后被修复，延迟 200ms 到达时已消失
+        feed_mispriced(&mut detector, 1_000);
+        detector.on_quote(quote("ETH-BTC", 1_100, 0.0599, 0.06));
+        // 第二次机会持续超过延迟
+        detector.on_quote(quote("ETH-BTC", 2_000, 0.0589, 0.059));
+        detector.on_quote(quote("ETH-BTC", 2_500, 0.0599, 0.06));
+
+        let survival = detector.survival();
+        assert_eq!(survival.detected, 2);
+        assert_eq!(survival.checked, 2);
+        assert_eq!(survival.survived, 1);
+        assert!((survival.survival_rate() - 0.5).abs() < 1e-12);
+        assert!(survival.survived_edge_sum > 0.0);
+    }
+
+    #[test]
+    fn test_reverse_cycle_needs_every_leg_quote() {
+        let mut detector = detector(None);
+        // 缺少任一交易对报价时不评估
+        assert!(
+            detector
+                .on_quote(quote("ETH-USDT", 1_000, 3000.0, 3001.0))
+                .is_empty()
+        );
+        assert!(
+            detector
+                .on_quote(quote("ETH-BTC", 1_000, 0.061, 0.0611))
+                .is_empty()
+        );
+
+        // ETH-BTC 买一 0.061 高于公允值 0.06，反向闭环获利
+        let found = detector.on_quote(quote("BTC-USDT", 1_000, 49999.0, 50000.0));
+        assert_eq!(found.len(), 1);
+        let opportunity = &found[0];
+        assert_eq!(opportunity.path, "USDT>ETH>BTC>USDT");
+        let sides: Vec<&str> = opportunity
+            .legs
+            .iter()
+            .map(|leg| leg.side.as_str())
+            .collect();
+        assert_eq!(sides, ["buy", "sell", "sell"]);
+        let prices: Vec<f64> = opportunity.legs.iter().map(|leg| leg.price).collect();
+        assert_eq!(prices, [3001.0, 0.061, 49999.0]);
+        assert!(opportunity.edge >= TriangularArbConfig::default().min_edge);
+    }
+
+    #[test]
+    fn test_latency_check_uses_book_at_arrival() {
+        // 修复报价早于到达时间 1ms: 机会已消失
+        let mut early = detector(Some(200));
+        feed_mispriced(&mut early, 1_000);
+        early.on_quote(quote("ETH-BTC", 1_199, 0.0599, 0.06));
+        early.flush(DateTime::from_timestamp_millis(2_000).unwrap());
+        assert_eq!(early.survival().checked, 1);
+        assert_eq!(early.survival().survived, 0);
+
+        // 修复报价晚于到达时间 1ms: 到达时仍可成交
+        let mut late = detector(Some(200));
+        feed_mispriced(&mut late, 1_000);
+        late.on_quote(quote("ETH-BTC", 1_201, 0.0599, 0.06));
+        assert_eq!(late.survival().checked, 1);
+        assert_eq!(late.survival().survived, 1);
+
+        // flush 只评估已到期的纸面执行
+        let mut pending = detector(Some(200));
+        feed_mispriced(&mut pending, 1_000);
+        pending.flush(DateTime::from_timestamp_millis(1_199).unwrap());
+        assert_eq!(pending.survival().checked, 0);
+        pending.flush(DateTime::from_timestamp_millis(1_200).unwrap());
+        assert_eq!(pending.survival().survived, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_stored_tickers() {
+        let storage = MemoryDatabase::new();
+        let ticker = |symbol: &str, ms: i64, bid: &str, ask: &str| TickerData {
+            inst_id: symbol.to_string(),
+            last: ask.to_string(),
+            last_sz: "1".to_string(),
+            ask_px: ask.to_string(),
+            ask_sz: "1".to_string(),
+            bid_px: bid.to_string(),
+            bid_sz: "1".to_string(),
+            open24h: ask.to_string(),
+            high24h: ask.to_string(),
+            low24h: bid.to_string(),
+            vol_ccy24h: "0".to_string(),
+            vol24h: "0".to_string(),
+            ts: ms.to_string(),
+        };
+        for data in [
+            ticker("BTC-USDT", 1_000, "49999", "50000"),
+            ticker("ETH-USDT", 1_000, "3000", "3001"),
+            ticker("ETH-BTC", 1_000, "0.0589", "0.059"),
+            ticker("ETH-BTC", 5_000, "0.0599", "0.06"),
+        ] {
+            storage.save_ticker(&data).await.unwrap();
+        }
+
+        let mut detector = detector(Some(200));
+        let start = DateTime::from_timestamp_millis(0).unwrap();
+        let end = DateTime::from_timestamp_millis(10_000).unwrap();
+        let found = replay_tickers(&storage, &mut detector, start, end)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(detector.survival().checked, 1);
+        assert_eq!(detector.survival().survived, 1);
+    }
+}
@@ -0,0 +1,827 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::record::Field;
+use parquet::schema::parser::parse_message_type;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::coverage::STORED_TIMEFRAME;
+use crate::storage::Storage;
+use crate::types::{
+    ArchiveDataset, ArchiveFormat, CandleData, ExportRequest, ImportSummary, TickerSnapshot,
+    Timeframe,
+};
+
+/// 导出数据到CSV或Parquet文件 (按扩展名判断格式)，返回导出行数
+///
+/// 时间列统一为UTC毫秒时间戳，Parquet中标注为 `TIMESTAMP(MILLIS)`。
+pub async fn export(storage: &dyn Storage, request: &ExportRequest, path: &Path) -> Result<usize> {
+    let format = ArchiveFormat::from_path(path)?;
+    let table = build_table(storage, request).await?;
+
+    match format {
+        ArchiveFormat::Csv => write_csv(&table, path)?,
+        ArchiveFormat::Parquet => write_parquet(&table, path)?,
+    }
+
+    Ok(table.row_count())
+}
+
+/// 导入K线归档 (本系统导出的文件或OKX历史数据CSV)
+///
+/// timeframe为文件的K线周期，数据库只存储1m K线，其他周期的文件被拒绝；
+/// 时间戳未对齐周期边界或K线间隔与周期不符的文件同样被拒绝，避免把更大周期的K线混入1m数据。
+/// symbol为None时从文件的交易对列读取。文件内重复及数据库中已存在的K线会被跳过，
+/// 带 `confirm` 列且值为0的未完结K线不导入。
+pub async fn import_candles(
+    storage: &dyn Storage,
+    path: &Path,
+    symbol: Option<&str>,
+    timeframe: Timeframe,
+) -> Result<ImportSummary> {
+    if timeframe != STORED_TIMEFRAME {
+        return Err(anyhow::anyhow!(
+            "数据库仅存储{}周期K线，不能导入{}周期的归档",
+            STORED_TIMEFRAME.as_str(),
+            timeframe.as_str()
+        ));
+    }
+
+    let raw = read_archive(path)?;
+    let mut summary = ImportSummary {
+        rows_read: raw.rows.len(),
+        ..Default::default()
+    };
+
+    let timestamp_col = raw.require(TIMESTAMP_ALIASES)?;
+    let open_col = raw.require(&["open", "o"])?;
+    let high_col = raw.require(&["high", "h"])?;
+    let low_col = raw.require(&["low", "l"])?;
+    let close_col = raw.require(&["close", "c"])?;
+    let volume_col = raw.require(&["volume", "vol"])?;
+    let symbol_col = raw.symbol_column(symbol)?;
+    let confirm_col = raw.column(&["confirm"]);
+
+    let mut parsed: BTreeMap<(String, i64), CandleData> = BTreeMap::new();
+    for row in &raw.rows {
+        if confirm_col.is_some_and(|i| row[i].trim() == "0") {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let candle = (|| {
+            Some(CandleData {
+                timestamp: parse_timestamp(&row[timestamp_col])?,
+                symbol: symbol
+                    .map(str::to_string)
+                    .or_else(|| symbol_col.map(|i| row[i].trim().to_string()))?,
+                open: row[open_col].trim().parse().ok()?,
+                high: row[high_col].trim().parse().ok()?,
+                low: row[low_col].trim().parse().ok()?,
+                close: row[close_col].trim().parse().ok()?,
+                volume: row[volume_col].trim().parse().ok()?,
+            })
+        })();
+
+        let Some(candle) = candle else {
+            summary.skipped += 1;
+            continue;
+        };
+
+        match parsed.entry((candle.symbol.clone(), candle.timestamp.timestamp_millis())) {
+            Entry::Occupied(_) => summary.duplicates += 1,
+            Entry::Vacant(entry) => {
+                entry.insert(candle);
+            }
+        }
+    }
+
+    let grouped = group_by_symbol(parsed.into_values(), |c| &c.symbol);
+    for (symbol, candles) in &grouped {
+        check_candle_interval(symbol, candles, timeframe)?;
+    }
+
+    for (symbol, candles) in grouped {
+        let (first, last) = (candles[0].timestamp, candles[candles.len() - 1].timestamp);
+        let existing: HashSet<i64> = storage
+            .get_candles(&symbol, first, last, None)
+            .await?
+            .iter()
+            .map(|c| c.timestamp.timestamp_millis())
+            .collect();
+
+        let candles_len = candles.len();
+        let new_candles: Vec<CandleData> = candles
+            .into_iter()
+            .filter(|c| !existing.contains(&c.timestamp.timestamp_millis()))
+            .collect();
+        summary.duplicates += candles_len - new_candles.len();
+        summary.imported += new_candles.len();
+
+        storage.save_candles(&new_candles).await?;
+    }
+
+    Ok(summary)
+}
+
+/// 检查一个交易对按时间正序、已去重的K线是否符合周期：时间戳对齐周期边界，且最小间隔等于周期
+fn check_candle_interval(symbol: &str, candles: &[CandleData], timeframe: Timeframe) -> Result<()> {
+    let interval = timeframe.duration_ms();
+
+    if let Some(candle) = candles
+        .iter()
+        .find(|c| c.timestamp.timestamp_millis().rem_euclid(interval) != 0)
+    {
+        return Err(anyhow::anyhow!(
+            "{}的K线时间{}未对齐{}周期边界",
+            symbol,
+            candle.timestamp,
+            timeframe.as_str()
+        ));
+    }
+
+    let min_spacing = candles
+        .windows(2)
+        .map(|pair| pair[1].timestamp.timestamp_millis() - pair[0].timestamp.timestamp_millis())
+        .min();
+    if let Some(spacing) = min_spacing
+        && spacing != interval
+    {
+        return Err(anyhow::anyhow!(
+            "{}的K线最小间隔为{}分钟，与{}周期不符",
+            symbol,
+            spacing / 60_000,
+            timeframe.as_str()
+        ));
+    }
+
+    Ok(())
+}
+
+/// 导入Ticker快照归档，按交易对和时间戳去重
+pub async fn import_tickers(
+    storage: &dyn Storage,
+    path: &Path,
+    symbol: Option<&str>,
+) -> Result<ImportSummary> {
+    let raw = read_archive(path)?;
+    let mut summary = ImportSummary {
+        rows_read: raw.rows.len(),
+        ..Default::default()
+    };
+
+    let timestamp_col = raw.require(TIMESTAMP_ALIASES)?;
+    let last_col = raw.require(&["last_price", "last"])?;
+    let bid_col = raw.require(&["bid_price", "bidpx", "bid_px"])?;
+    let ask_col = raw.require(&["ask_price", "askpx", "ask_px"])?;
+    let volume_col = raw.require(&["volume_24h", "volccy24h", "vol_ccy24h"])?;
+    let symbol_col = raw.symbol_column(symbol)?;
+
+    let mut parsed: BTreeMap<(String, i64), TickerSnapshot> = BTreeMap::new();
+    for row in &raw.rows {
+        let ticker = (|| {
+            Some(TickerSnapshot {
+                timestamp: parse_timestamp(&row[timestamp_col])?,
+                symbol: symbol
+                    .map(str::to_string)
+                    .or_else(|| symbol_col.map(|i| row[i].trim().to_string()))?,
+                last_price: row[last_col].trim().parse().ok()?,
+                bid_price: row[bid_col].trim().parse().ok()?,
+                ask_price: row[ask_col].trim().parse().ok()?,
+                volume_24h: row[volume_col].trim().parse().ok()?,
+            })
+        })();
+
+        let Some(ticker) = ticker else {
+            summary.skipped += 1;
+            continue;
+        };
+
+        match parsed.entry((ticker.symbol.clone(), ticker.timestamp.timestamp_millis())) {
+            Entry::Occupied(_) => summary.duplicates += 1,
+            Entry::Vacant(entry) => {
+                entry.insert(ticker);
+            }
+        }
+    }
+
+    for (symbol, tickers) in group_by_symbol(parsed.into_values(), |t| &t.symbol) {
+        let (first, last) = (tickers[0].timestamp, tickers[tickers.len() - 1].timestamp);
+        let existing: HashSet<i64> = storage
+            .get_tickers(&symbol, first, last)
+            .await?
+            .iter()
+            .map(|t| t.timestamp.timestamp_millis())
+            .collect();
+
+        let tickers_len = tickers.len();
+        let new_tickers: Vec<TickerSnapshot> = tickers
+            .into_iter()
+            .filter(|t| !existing.contains(&t.timestamp.timestamp_millis()))
+            .collect();
+        summary.duplicates += tickers_len - new_tickers.len();
+        summary.imported += new_tickers.len();
+
+        storage.save_ticker_snapshots(&new_tickers).await?;
+    }
+
+    Ok(summary)
+}
+
+/// 时间戳列的可选列名 (含OKX历史数据下载的列名)
+const TIMESTAMP_ALIASES: &[&str] = &["timestamp", "ts", "open_time", "time"];
+
+/// 交易对列的可选列名
+const SYMBOL_ALIASES: &[&str] = &["symbol", "instrument_name", "instid", "inst_id"];
+
+/// 导出用的列数据
+enum Column {
+    Timestamp(Vec<i64>),     // 毫秒时间戳
+    Int(Vec<Option<i64>>),   // 整数
+    Float(Vec<Option<f64>>), // 浮点数
+    Text(Vec<String>),       // 文本
+}
+
+impl Column {
+    fn len(&self) -> usize {
+        match self {
+            Column::Timestamp(values) => values.len(),
+            Column::Int(values) => values.len(),
+            Column::Float(values) => values.len(),
+            Column::Text(values) => values.len(),
+        }
+    }
+
+    /// CSV单元格文本，空值输出为空字符串
+    fn cell(&self, row: usize) -> String {
+        match self {
+            Column::Timestamp(values) => values[row].to_string(),
+            Column::Int(values) => values[row].map(|v| v.to_string()).unwrap_or_default(),
+            Column::Float(values) => values[row].map(|v| v.to_string()).unwrap_or_default(),
+            Column::Text(values) => values[row].clone(),
+        }
+    }
+
+    /// Parquet schema中的列定义
+    fn parquet_field(&self, name: &str) -> String {
+        match self {
+            Column::Timestamp(_) => {
+                format!("REQUIRED INT64 {} (TIMESTAMP(MILLIS,true));", name)
+            }
+            Column::Int(_) => format!("OPTIONAL INT64 {};", name),
+            Column::Float(_) => format!("OPTIONAL DOUBLE {};", name),
+            Column::Text(_) => format!("REQUIRED BINARY {} (UTF8);", name),
+        }
+    }
+}
+
+/// 导出用的列式数据表
+#[derive(Default)]
+struct Table {
+    columns: Vec<(&'static str, Column)>,
+}
+
+impl Table {
+    fn timestamp(
+        mut self,
+        name: &'static str,
+        values: impl Iterator<Item = DateTime<Utc>>,
+    ) -> Self {
+        let values = values.map(|t| t.timestamp_millis()).collect();
+        self.columns.push((name, Column::Timestamp(values)));
+        self
+    }
+
+    fn int(mut self, name: &'static str, values: impl Iterator<Item = Option<i64>>) -> Self {
+        self.columns.push((name, Column::Int(values.collect())));
+        self
+    }
+
+    fn float(self, name: &'static str, values: impl Iterator<Item = f64>) -> Self {
+        self.optional_float(name, values.map(Some))
+    }
+
+    fn optional_float(
+        mut self,
+        name: &'static str,
+        values: impl Iterator<Item = Option<f64>>,
+    ) -> Self {
+        self.columns.push((name, Column::Float(values.collect())));
+        self
+    }
+
+    fn text(mut self, name: &'static str, values: impl Iterator<Item = String>) -> Self {
+        self.columns.push((name, Column::Text(values.collect())));
+        self
+    }
+
+    fn row_count(&self) -> usize {
+        self.columns.first().map(|(_, c)| c.len()).unwrap_or(0)
+    }
+}
+
+/// 按导出请求从存储读取数据并组装成数据表
+async fn build_table(storage: &dyn Storage, request: &ExportRequest) -> Result<Table> {
+    let (start, end) = (request.start_time, request.end_time);
+
+    let table = match request.dataset {
+        ArchiveDataset::Candles => {
+            let candles = if request.timeframe == Timeframe::M1 {
+                storage
+                    .get_candles(&request.symbol, start, end, None)
+                    .await?
+            } else {
+                storage
+                    .get_resampled_candles(
+                        &request.symbol,
+                        request.timeframe,
+                        request.session,
+                        start,
+                        end,
+                    )
+                    .await?
+            };
+
+            Table::default()
+                .timestamp("timestamp", candles.iter().map(|c| c.timestamp))
+                .text("symbol", candles.iter().map(|c| c.symbol.clone()))
+                .float("open", candles.iter().map(|c| c.open))
+                .float("high", candles.iter().map(|c| c.high))
+                .float("low", candles.iter().map(|c| c.low))
+                .float("close", candles.iter().map(|c| c.close))
+                .float("volume", candles.iter().map(|c| c.volume))
+        }
+        ArchiveDataset::Tickers => {
+            let tickers = storage.get_tickers(&request.symbol, start, end).await?;
+
+            Table::default()
+                .timestamp("timestamp", tickers.iter().map(|t| t.timestamp))
+                .text("symbol", tickers.iter().map(|t| t.symbol.clone()))
+                .float("last_price", tickers.iter().map(|t| t.last_price))
+                .float("bid_price", tickers.iter().map(|t| t.bid_price))
+                .float("ask_price", tickers.iter().map(|t| t.ask_price))
+                .float("volume_24h", tickers.iter().map(|t| t.volume_24h))
+        }
+        ArchiveDataset::Trades => {
+            let trades: Vec<_> = storage
+                .get_trades_by_time_range(start, end)
+                .await?
+                .into_iter()
+                .filter(|t| t.symbol == request.symbol)
+                .collect();
+
+            Table::default()
+                .int("id", trades.iter().map(|t| t.id))
+                .timestamp("timestamp", trades.iter().map(|t| t.timestamp))
+                .text("symbol", trades.iter().map(|t| t.symbol.clone()))
+                .text("side", trades.iter().map(|t| t.side.clone()))
+                .float("price", trades.iter().map(|t| t.price))
+                .float("quantity", trades.iter().map(|t| t.quantity))
+                .text("strategy", trades.iter().map(|t| t.strategy.clone()))
+                .text("strategy_id", trades.iter().map(|t| t.strategy_id.clone()))
+                .optional_float("pnl", trades.iter().map(|t| t.pnl))
+                .int("signal_id", trades.iter().map(|t| t.signal_id))
+        }
+        ArchiveDataset::BacktestRuns => {
+            // 回测数据区间与导出范围有重叠即导出
+            let runs: Vec<_> = storage
+                .get_backtest_runs(i64::MAX as usize)
+                .await?
+                .into_iter()
+                .filter(|r| {
+                    r.symbol == request.symbol
+                        && r.report.start_time <= end
+                        && r.report.end_time >= start
+                })
+                .collect();
+
+            let params = runs
+                .iter()
+                .map(|r| serde_json::to_string(&r.params))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Table::default()
+                .int("id", runs.iter().map(|r| r.id))
+                .timestamp("created_at", runs.iter().map(|r| r.created_at))
+                .text("symbol", runs.iter().map(|r| r.symbol.clone()))
+                .text("strategy", runs.iter().map(|r| r.strategy.clone()))
+                .timestamp("start_time", runs.iter().map(|r| r.report.start_time))
+                .timestamp("end_time", runs.iter().map(|r| r.report.end_time))
+                .int(
+                    "candle_count",
+                    runs.iter().map(|r| Some(r.candle_count as i64)),
+                )
+                .text(
+                    "data_fingerprint",
+                    runs.iter().map(|r| r.data_fingerprint.clone()),
+                )
+                .text("code_version", runs.iter().map(|r| r.code_version.clone()))
+                .text("params", params.into_iter())
+                .float(
+                    "initial_balance",
+                    runs.iter().map(|r| r.report.initial_balance),
+                )
+                .float("final_balance", runs.iter().map(|r| r.report.final_balance))
+                .float("total_return", runs.iter().map(|r| r.report.total_return))
+                .float("return_rate", runs.iter().map(|r| r.report.return_rate))
+                .float("max_drawdown", runs.iter().map(|r| r.report.max_drawdown))
+                .int(
+                    "total_trades",
+                    runs.iter().map(|r| Some(r.report.total_trades as i64)),
+                )
+                .float("win_rate", runs.iter().map(|r| r.report.win_rate))
+                .float("avg_return", runs.iter().map(|r| r.report.avg_return))
+                .float("sharpe_ratio", runs.iter().map(|r| r.report.sharpe_ratio))
+        }
+        ArchiveDataset::BacktestEquity(run_id) => {
+            let equity = storage.get_backtest_equity(run_id).await?;
+
+            Table::default()
+                .timestamp("timestamp", equity.iter().map(|(t, _)| *t))
+                .float("equity", equity.iter().map(|(_, e)| *e))
+        }
+    };
+
+    Ok(table)
+}
+
+/// 写入CSV文件 (首行为列名)
+fn write_csv(table: &Table, path: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+
+    writer.write_record(table.columns.iter().map(|(name, _)| *name))?;
+    for row in 0..table.row_count() {
+        writer.write_record(table.columns.iter().map(|(_, column)| column.cell(row)))?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// 写入Parquet文件 (单个行组，Snappy压缩)
+fn write_parquet(table: &Table, path: &Path) -> Result<()> {
+    let fields: Vec<String> = table
+        .columns
+        .iter()
+        .map(|(name, column)| column.parquet_field(name))
+        .collect();
+    let schema = Arc::new(parse_message_type(&format!(
+        "message archive {{ {} }}",
+        fields.join(" ")
+    ))?);
+    let props = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+
+    let mut writer = SerializedFileWriter::new(File::create(path)?, schema, props)?;
+    let mut row_group = writer.next_row_group()?;
+
+    for (_, column) in &table.columns {
+        let Some(mut column_writer) = row_group.next_column()? else {
+            return Err(anyhow::anyhow!("Parquet列数量与schema不一致"));
+        };
+
+        match column {
+            Column::Timestamp(values) => {
+                column_writer
+                    .typed::<Int64Type>()
+                    .write_batch(values, None, None)?;
+            }
+            Column::Int(values) => {
+                let (present, levels) = definition_levels(values);
+                column_writer
+                    .typed::<Int64Type>()
+                    .write_batch(&present, Some(&levels), None)?;
+            }
+            Column::Float(values) => {
+                let (present, levels) = definition_levels(values);
+                column_writer
+                    .typed::<DoubleType>()
+                    .write_batch(&present, Some(&levels), None)?;
+            }
+            Column::Text(values) => {
+                let values: Vec<ByteArray> =
+                    values.iter().map(|v| ByteArray::from(v.as_str())).collect();
+                column_writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, None, None)?;
+            }
+        }
+
+        column_writer.close()?;
+    }
+
+    row_group.close()?;
+    writer.close()?;
+
+    Ok(())
+}
+
+/// 拆分可空列为非空值和定义级别
+fn definition_levels<T: Copy>(values: &[Option<T>]) -> (Vec<T>, Vec<i16>) {
+    let present = values.iter().flatten().copied().collect();
+    let levels = values.iter().map(|v| v.is_some() as i16).collect();
+    (present, levels)
+}
+
+/// 读取后的原始归档数据 (全部单元格转为文本)
+struct RawArchive {
+    headers: Vec<String>,   // 小写列名
+    rows: Vec<Vec<String>>, // 数据行
+}
+
+impl RawArchive {
+    /// 按候选列名查找列 (不区分大小写)
+    fn column(&self, aliases: &[&str]) -> Option<usize> {
+        aliases
+            .iter()
+            .find_map(|alias| self.headers.iter().position(|h| h == alias))
+    }
+
+    fn require(&self, aliases: &[&str]) -> Result<usize> {
+        self.column(aliases)
+            .ok_or_else(|| anyhow::anyhow!("归档缺少列: {}", aliases.join(" / ")))
+    }
+
+    /// 交易对列，未指定交易对时必须存在
+    fn symbol_column(&self, symbol: Option<&str>) -> Result<Option<usize>> {
+        match symbol {
+            Some(_) => Ok(self.column(SYMBOL_ALIASES)),
+            None => self.require(SYMBOL_ALIASES).map(Some),
+        }
+    }
+}
+
+/// 读取CSV或Parquet归档
+fn read_archive(path: &Path) -> Result<RawArchive> {
+    match ArchiveFormat::from_path(path)? {
+        ArchiveFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_path(path)?;
+
+            let headers = reader
+                .headers()?
+                .iter()
+                .map(|h| h.to_ascii_lowercase())
+                .collect::<Vec<_>>();
+
+            let mut rows = Vec::new();
+            for record in reader.records() {
+                let record = record?;
+                let mut row: Vec<String> = record.iter().map(str::to_string).collect();
+                row.resize(headers.len(), String::new());
+                rows.push(row);
+            }
+
+            Ok(RawArchive { headers, rows })
+        }
+        ArchiveFormat::Parquet => {
+            let reader = SerializedFileReader::new(File::open(path)?)?;
+
+            let headers = reader
+                .metadata()
+                .file_metadata()
+                .schema_descr()
+                .columns()
+                .iter()
+                .map(|c| c.name().to_ascii_lowercase())
+                .collect::<Vec<_>>();
+
+            let mut rows = Vec::new();
+            for row in reader.get_row_iter(None)? {
+                let row = row?;
+                rows.push(
+                    row.get_column_iter()
+                        .map(|(_, field)| field_to_string(field))
+                        .collect(),
+                );
+            }
+
+            Ok(RawArchive { headers, rows })
+        }
+    }
+}
+
+/// Parquet字段转文本，时间戳保留为毫秒数
+fn field_to_string(field: &Field) -> String {
+    match field {
+        Field::Null => String::new(),
+        Field::Str(value) => value.clone(),
+        Field::Long(value) => value.to_string(),
+        Field::Int(value) => value.to_string(),
+        Field::Double(value) => value.to_string(),
+        Field::Float(value) => value.to_string(),
+        Field::TimestampMillis(value) => value.to_string(),
+        Field::TimestampMicros(value) => (value / 1000).to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// 解析时间戳: 毫秒/秒整数、RFC3339，或不带时区的 `%Y-%m-%d %H:%M:%S` (按UTC处理)
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(number) = value.parse::<i64>() {
+        // 小于1e11视为秒级时间戳
+        let millis = if number.abs() < 100_000_000_000 {
+            number * 1000
+        } else {
+            number
+        };
+        return DateTime::from_timestamp_millis(millis);
+    }
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+/// 按交易对分组 (输入已按交易对和时间排序)
+fn group_by_symbol<T>(
+    items: impl Iterator<Item = T>,
+    symbol: impl Fn(&T) -> &String,
+) -> Vec<(String, Vec<T>)> {
+    let mut groups: Vec<(String, Vec<T>)> = Vec::new();
+
+    for item in items {
+        match groups.last_mut() {
+            Some((current, group)) if current == symbol(&item) => group.push(item),
+            _ => groups.push((symbol(&item).clone(), vec![item])),
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_db::MemoryDatabase;
+    use crate::types::SessionBoundary;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("okx_quant_{}_{}", std::process::id(), name))
+    }
+
+    fn candles() -> Vec<CandleData> {
+        (0..5)
+            .map(|i| CandleData {
+                timestamp: DateTime::from_timestamp_millis(1_704_067_200_000 + i * 60_000).unwrap(),
+                symbol: "SOL-USDT".to_string(),
+                open: 100.0 + i as f64,
+                high: 101.0 + i as f64,
+                low: 99.0 + i as f64,
+                close: 100.5 + i as f64,
+                volume: 10.0,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_candle_round_trip_and_dedup() {
+        let source = MemoryDatabase::new();
+        source.save_candles(&candles()).await.unwrap();
+
+        let request = ExportRequest {
+            dataset: ArchiveDataset::Candles,
+            symbol: "SOL-USDT".to_string(),
+            timeframe: Timeframe::M1,
+            session: SessionBoundary::Utc,
+            start_time: DateTime::<Utc>::MIN_UTC,
+            end_time: DateTime::<Utc>::MAX_UTC,
+        };
+
+        for name in ["candles.parquet", "candles.csv"] {
+            let path = temp_path(name);
+            assert_eq!(export(&source, &request, &path).await.unwrap(), 5);
+
+            let target = MemoryDatabase::new();
+            target.save_candles(&candles()[..2]).await.unwrap();
+
+            let summary = import_candles(&target, &path, None, Timeframe::M1)
+                .await
+                .unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(summary.rows_read, 5, "{}", name);
+            assert_eq!(summary.imported, 3, "{}", name);
+            assert_eq!(summary.duplicates, 2, "{}", name);
+
+            let restored = target
+                .get_candles(
+                    "SOL-USDT",
+                    DateTime::<Utc>::MIN_UTC,
+                    DateTime::<Utc>::MAX_UTC,
+                    None,
+                )
+                .await
+                .unwrap();
+            assert_eq!(restored.len(), 5);
+            assert_eq!(restored[4].close, 104.5);
+            assert_eq!(restored[4].timestamp, candles()[4].timestamp);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_okx_history_csv() {
+        let path = temp_path("okx_history.csv");
+        std::fs::write(
+            &path,
+            "instrument_name,open,high,low,close,vol,vol_ccy,vol_quote,open_time,confirm\n\
+             SOL-USDT,100,101,99,100.5,10,1000,1000,1704067200000,1\n\
+             SOL-USDT,100,101,99,100.5,10,1000,1000,1704067200000,1\n\
+             SOL-USDT,100.5,102,100,101,12,1200,1200,1704067260000,1\n\
+             SOL-USDT,101,101.5,100.8,101.2,3,300,300,1704067320000,0\n\
+             SOL-USDT,bad,101,99,100,1,1,1,1704067380000,1\n",
+        )
+        .unwrap();
+
+        let db = MemoryDatabase::new();
+        let summary = import_candles(&db, &path, None, Timeframe::M1)
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            summary,
+            ImportSummary {
+                rows_read: 5,
+                imported: 2,
+                duplicates: 1,
+                skipped: 2,
+            }
+        );
+        let latest = db.get_latest_candles("SOL-USDT", 10).await.unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[1].volume, 12.0);
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_other_timeframes() {
+        let source = MemoryDatabase::new();
+        source.save_candles(&candles()).await.unwrap();
+
+        // 本系统导出的5m K线
+        let path = temp_path("candles_5m.csv");
+        let request = ExportRequest {
+            dataset: ArchiveDataset::Candles,
+            symbol: "SOL-USDT".to_string(),
+            timeframe: Timeframe::M5,
+            session: SessionBoundary::Utc,
+            start_time: DateTime::<Utc>::MIN_UTC,
+            end_time: DateTime::<Utc>::MAX_UTC,
+        };
+        export(&source, &request, &path).await.unwrap();
+
+        let target = MemoryDatabase::new();
+        assert!(
+            import_candles(&target, &path, None, Timeframe::M5)
+                .await
+                .is_err()
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        // 声明为1m但实际为1H间隔，或时间戳未对齐分钟的文件
+        for (name, rows) in [
+            (
+                "hourly.csv",
+                "symbol,timestamp,open,high,low,close,volume
+                 SOL-USDT,1704067200000,100,101,99,100.5,10
+                 SOL-USDT,1704070800000,100,101,99,100.5,10
+",
+            ),
+            (
+                "misaligned.csv",
+                "symbol,timestamp,open,high,low,close,volume
+                 SOL-USDT,1704067200000,100,101,99,100.5,10
+                 SOL-USDT,1704067230000,100,101,99,100.5,10
+",
+            ),
+        ] {
+            let path = temp_path(name);
+            std::fs::write(&path, rows).unwrap();
+            let result = import_candles(&target, &path, None, Timeframe::M1).await;
+            std::fs::remove_file(&path).unwrap();
+            assert!(result.is_err(), "{}", name);
+        }
+
+        assert!(target.get_candle_symbols().await.unwrap().is_empty());
+    }
+}
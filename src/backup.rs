@@ -0,0 +1,341 @@
+use anyhow::Result;
+use chrono::Utc;
+use log::{info, warn};
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::db::Database;
+
+/// 快照文件名前缀
+const SNAPSHOT_PREFIX: &str = "trading-";
+
+/// 快照文件扩展名
+const SNAPSHOT_EXTENSION: &str = "db";
+
+/// SQLite日志文件的后缀
+const SIDECAR_SUFFIXES: [&str; 3] = ["-journal", "-wal", "-shm"];
+
+/// 生成带时间戳的数据库快照，并按保留数量清理旧快照
+///
+/// 先写入临时文件再重命名，目录中不会出现写了一半的快照。
+pub async fn create_snapshot(db: &Database, dir: &Path, retention: usize) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+
+    let name = format!(
+        "{}{}.{}",
+        SNAPSHOT_PREFIX,
+        Utc::now().format("%Y%m%d-%H%M%S-%3f"),
+        SNAPSHOT_EXTENSION
+    );
+    let path = dir.join(&name);
+    let partial = dir.join(format!("{}.partial", name));
+
+    if let Err(e) = db.backup_to(&partial).await {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, &path)?;
+    info!("数据库快照已生成: {}", path.display());
+
+    for removed in prune_snapshots(dir, retention)? {
+        info!("删除过期快照: {}", removed.display());
+    }
+
+    Ok(path)
+}
+
+/// 列出目录中的快照 (按时间从旧到新)
+pub fn list_snapshots(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_snapshot = path.is_file()
+            && path.extension().and_then(|e| e.to_str()) == Some(SNAPSHOT_EXTENSION)
+            && path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(SNAPSHOT_PREFIX));
+        if is_snapshot {
+            snapshots.push(path);
+        }
+    }
+
+    // 文件名中的时间戳可直接按字典序排序
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+/// 只保留最新的 retention 个快照，返回被删除的文件
+pub fn prune_snapshots(dir: &Path, retention: usize) -> Result<Vec<PathBuf>> {
+    let snapshots = list_snapshots(dir)?;
+    let excess = snapshots.len().saturating_sub(retention);
+
+    let mut removed = Vec::new();
+    for path in snapshots.into_iter().take(excess) {
+        std::fs::remove_file(&path)?;
+        removed.push(path);
+    }
+
+    Ok(removed)
+}
+
+/// 对数据库文件执行 `PRAGMA integrity_check`
+pub fn check_integrity(path: &Path) -> Result<()> {
+    if !path.is_file() {
+        return Err(anyhow::anyhow!("数据库文件不存在: {}", path.display()));
+    }
+
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let problems = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if problems.len() == 1 && problems[0] == "ok" {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "数据库完整性检查失败 {}: {}",
+            path.display(),
+            problems.join("; ")
+        ))
+    }
+}
+
+/// 从快照恢复数据库 (必须在没有打开目标数据库时执行)
+///
+/// 程序或其他连接仍打开目标数据库时拒绝恢复。
+/// 快照和复制后的临时文件都通过完整性检查后才替换目标文件，原数据库连同其日志文件
+/// 保留为 `<目标>.before-restore-<时间戳>`。返回原数据库的备份路径。
+pub fn restore_snapshot(snapshot: &Path, target: &Path) -> Result<Option<PathBuf>> {
+    if target.exists() {
+        ensure_unlocked(target)?;
+    }
+
+    check_integrity(snapshot)?;
+
+    let staging = target.with_extension("restore-tmp");
+    std::fs::copy(snapshot, &staging)?;
+    if let Err(e) = check_integrity(&staging) {
+        let _ = std::fs::remove_file(&staging);
+        return Err(e);
+    }
+
+    let previous = if target.exists() {
+        let previous = PathBuf::from(format!(
+            "{}.before-restore-{}",
+            target.display(),
+            Utc::now().format("%Y%m%d-%H%M%S")
+        ));
+        std::fs::rename(target, &previous)?;
+
+        // 日志文件属于被替换的数据库，随其一起移动，既不会应用到恢复后的文件，也保证原数据库副本完整
+        for suffix in SIDECAR_SUFFIXES {
+            let sidecar = PathBuf::from(format!("{}{}", target.display(), suffix));
+            if sidecar.exists() {
+                std::fs::rename(
+                    &sidecar,
+                    PathBuf::from(format!("{}{}", previous.display(), suffix)),
+                )?;
+            }
+        }
+        Some(previous)
+    } else {
+        None
+    };
+
+    std::fs::rename(&staging, target)?;
+    info!(
+        "数据库已从快照恢复: {} -> {}",
+        snapshot.display(),
+        target.display()
+    );
+
+    Ok(previous)
+}
+
+/// 确认没有其他连接打开目标数据库
+///
+/// WAL模式下打开数据库的连接一直持有数据库文件的共享锁，以独占锁定模式不等待地开启独占事务，
+/// 其他连接存在时立即失败。若存在上次异常退出留下的热日志，打开时SQLite会先将其回滚，
+/// 之后移动的原数据库即为一致的状态。
+fn ensure_unlocked(path: &Path) -> Result<()> {
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(Duration::ZERO)?;
+    conn.execute_batch("PRAGMA locking_mode=EXCLUSIVE; BEGIN EXCLUSIVE; COMMIT;")
+        .map_err(|_| anyhow::anyhow!("数据库{}仍被程序打开，请先停止程序再恢复", path.display()))?;
+    Ok(())
+}
+
+/// 定时生成快照 (首次在一个周期后执行)
+pub async fn run_scheduled_snapshots(
+    db: Arc<Database>,
+    dir: PathBuf,
+    retention: usize,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        if let Err(e) = create_snapshot(&db, &dir, retention).await {
+            warn!("定时快照失败: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use crate::types::CandleData;
+    use chrono::DateTime;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("okx_quant_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn candle(minute: i64) -> CandleData {
+        let price = 100.0 + (minute % 50) as f64;
+        CandleData {
+            timestamp: DateTime::from_timestamp_millis(1_704_067_200_000 + minute * 60_000)
+                .unwrap(),
+            symbol: "SOL-USDT".to_string(),
+            open: price,
+            high: price + 1.0,
+            low: price - 1.0,
+            close: price + 0.5,
+            volume: 10.0,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_snapshot_during_writes() {
+        let dir = temp_dir("backup_writes");
+        let db_path = dir.join("live.db");
+        let db = Arc::new(Database::new(db_path.to_str().unwrap()).await.unwrap());
+        let initial: Vec<CandleData> = (0..5000).map(candle).collect();
+        db.save_candles(&initial).await.unwrap();
+
+        // 备份期间持续写入新K线
+        let writer = {
+            let db = db.clone();
+            tokio::spawn(async move {
+                for minute in 5000..5300 {
+                    db.save_candle(&candle(minute)).await.unwrap();
+                    tokio::task::yield_now().await;
+                }
+            })
+        };
+        let snapshot = create_snapshot(&db, &dir.join("snapshots"), 1)
+            .await
+            .unwrap();
+        writer.await.unwrap();
+
+        // 快照是单个完整文件，包含开始备份时的全部数据
+        assert!(!PathBuf::from(format!("{}-wal", snapshot.display())).exists());
+        check_integrity(&snapshot).unwrap();
+        let copy = Database::new(snapshot.to_str().unwrap()).await.unwrap();
+        let count = copy
+            .get_candles(
+                "SOL-USDT",
+                candle(0).timestamp,
+                candle(6000).timestamp,
+                None,
+            )
+            .await
+            .unwrap()
+            .len();
+        assert!((5000..=5300).contains(&count), "快照K线数{}", count);
+        assert_eq!(
+            db.get_candles(
+                "SOL-USDT",
+                candle(0).timestamp,
+                candle(6000).timestamp,
+                None
+            )
+            .await
+            .unwrap()
+            .len(),
+            5300
+        );
+
+        drop(copy);
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_retention_and_restore() {
+        let dir = temp_dir("backup");
+        let db_path = dir.join("live.db");
+        let db = Database::new(db_path.to_str().unwrap()).await.unwrap();
+
+        let candle = CandleData {
+            timestamp: DateTime::from_timestamp_millis(1_704_067_200_000).unwrap(),
+            symbol: "SOL-USDT".to_string(),
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.5,
+            volume: 10.0,
+        };
+        db.save_candle(&candle).await.unwrap();
+
+        let snapshot_dir = dir.join("snapshots");
+        let first = create_snapshot(&db, &snapshot_dir, 2).await.unwrap();
+        for _ in 0..2 {
+            // 快照名精确到毫秒
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            create_snapshot(&db, &snapshot_dir, 2).await.unwrap();
+        }
+        let snapshots = list_snapshots(&snapshot_dir).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert!(!snapshots.contains(&first));
+
+        // 损坏的快照不能替换目标文件
+        let corrupt = dir.join("corrupt.db");
+        std::fs::write(&corrupt, b"not a database").unwrap();
+        let restored_path = dir.join("restored.db");
+        assert!(restore_snapshot(&corrupt, &restored_path).is_err());
+        assert!(!restored_path.exists());
+
+        restore_snapshot(&snapshots[1], &restored_path).unwrap();
+        let restored = Database::new(restored_path.to_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            restored
+                .get_latest_candles("SOL-USDT", 10)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // 程序仍打开目标数据库时拒绝恢复，原文件保持不动
+        assert!(restore_snapshot(&snapshots[1], &db_path).is_err());
+        assert!(db_path.exists());
+        drop(db);
+
+        // 关闭后可以恢复，原数据库连同日志文件一起保留
+        let journal = PathBuf::from(format!("{}-journal", db_path.display()));
+        std::fs::write(&journal, b"").unwrap();
+        let previous = restore_snapshot(&snapshots[1], &db_path).unwrap().unwrap();
+        assert!(previous.exists());
+        assert!(!journal.exists());
+        assert!(PathBuf::from(format!("{}-journal", previous.display())).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::storage::Storage;
+use crate::types::{
+    CandleData, DataCoverageReport, DataGap, OhlcViolation, OhlcViolationKind, Timeframe,
+    ZeroVolumeStreak,
+};
+
+/// 连续零成交量K线达到该数量才计入报告
+pub const MIN_ZERO_VOLUME_STREAK: usize = 3;
+
+/// 数据库中K线的存储周期
+pub const STORED_TIMEFRAME: Timeframe = Timeframe::M1;
+
+/// 生成已存储全部交易对的数据覆盖报告
+pub async fn coverage_reports(storage: &dyn Storage) -> Result<Vec<DataCoverageReport>> {
+    let mut reports = Vec::new();
+    for symbol in storage.get_candle_symbols().await? {
+        reports.push(coverage_report(storage, &symbol, STORED_TIMEFRAME).await?);
+    }
+    Ok(reports)
+}
+
+/// 生成指定交易对、指定周期的数据覆盖报告
+pub async fn coverage_report(
+    storage: &dyn Storage,
+    symbol: &str,
+    timeframe: Timeframe,
+) -> Result<DataCoverageReport> {
+    if timeframe != STORED_TIMEFRAME {
+        return Err(anyhow::anyhow!(
+            "数据库仅存储{}周期K线，不支持{}周期的覆盖报告",
+            STORED_TIMEFRAME.as_str(),
+            timeframe.as_str()
+        ));
+    }
+
+    let candles = storage
+        .get_candles(
+            symbol,
+            DateTime::<Utc>::MIN_UTC,
+            DateTime::<Utc>::MAX_UTC,
+            None,
+        )
+        .await?;
+
+    // 存储按交易对和时间戳去重并按时间返回，重复与倒序只能取自写入时累计的计数
+    let ingest = storage.get_candle_ingest_stats(symbol).await?;
+    let mut report = analyze_candles(symbol, timeframe, &candles);
+    report.duplicate_count += ingest.duplicate_count;
+    report.out_of_order_count += ingest.out_of_order_count;
+
+    Ok(report)
+}
+
+/// 分析K线序列的覆盖情况与数据质量
+///
+/// 重复和倒序按传入顺序统计 (用于未经存储的K线序列，如待导入的文件)，缺口和覆盖率按排序去重后的时间戳计算。
+pub fn analyze_candles(
+    symbol: &str,
+    timeframe: Timeframe,
+    candles: &[CandleData],
+) -> DataCoverageReport {
+    let interval = timeframe.duration_ms();
+
+    let mut out_of_order_count = 0;
+    let mut misaligned_count = 0;
+    let mut ohlc_violations = Vec::new();
+
+    let mut previous_ms: Option<i64> = None;
+    for candle in candles {
+        let ts = candle.timestamp.timestamp_millis();
+
+        if previous_ms.is_some_and(|prev| ts < prev) {
+            out_of_order_count += 1;
+        }
+        previous_ms = Some(ts);
+
+        if ts.rem_euclid(interval) != 0 {
+            misaligned_count += 1;
+        }
+
+        ohlc_violations.extend(check_ohlc(candle).into_iter().map(|kind| OhlcViolation {
+            timestamp: candle.timestamp,
+            kind,
+        }));
+    }
+
+    // 排序去重后计算缺口
+    let mut sorted: Vec<&CandleData> = candles.iter().collect();
+    sorted.sort_by_key(|c| c.timestamp);
+    let before_dedup = sorted.len();
+    sorted.dedup_by_key(|c| c.timestamp);
+    let duplicate_count = before_dedup - sorted.len();
+
+    let mut gaps = Vec::new();
+    for pair in sorted.windows(2) {
+        let diff = pair[1].timestamp.timestamp_millis() - pair[0].timestamp.timestamp_millis();
+        if diff > interval {
+            gaps.push(DataGap {
+                after: pair[0].timestamp,
+                before: pair[1].timestamp,
+                missing_bars: (diff / interval - 1).max(1) as usize,
+            });
+        }
+    }
+
+    let first_timestamp = sorted.first().map(|c| c.timestamp);
+    let last_timestamp = sorted.last().map(|c| c.timestamp);
+    let expected_bars = match (first_timestamp, last_timestamp) {
+        (Some(first), Some(last)) => {
+            ((last.timestamp_millis() - first.timestamp_millis()) / interval + 1) as usize
+        }
+        _ => 0,
+    };
+    let actual_bars = sorted.len();
+
+    DataCoverageReport {
+        symbol: symbol.to_string(),
+        timeframe,
+        first_timestamp,
+        last_timestamp,
+        expected_bars,
+        actual_bars,
+        coverage: if expected_bars > 0 {
+            actual_bars as f64 / expected_bars as f64
+        } else {
+            0.0
+        },
+        gaps,
+        duplicate_count,
+        out_of_order_count,
+        misaligned_count,
+        ohlc_violations,
+        zero_volume_streaks: find_zero_volume_streaks(&sorted),
+    }
+}
+
+/// 检查单根K线的OHLC合理性
+fn check_ohlc(candle: &CandleData) -> Vec<OhlcViolationKind> {
+    let mut violations = Vec::new();
+
+    if candle.open <= 0.0 || candle.high <= 0.0 || candle.low <= 0.0 || candle.close <= 0.0 {
+        violations.push(OhlcViolationKind::NonPositivePrice);
+    }
+    if candle.high < candle.open.max(candle.close) {
+        violations.push(OhlcViolationKind::HighBelowOpenClose);
+    }
+    if candle.low > candle.open.min(candle.close) {
+        violations.push(OhlcViolationKind::LowAboveOpenClose);
+    }
+    if candle.volume < 0.0 {
+        violations.push(OhlcViolationKind::NegativeVolume);
+    }
+
+    violations
+}
+
+/// 查找连续零成交量区间
+fn find_zero_volume_streaks(sorted: &[&CandleData]) -> Vec<ZeroVolumeStreak> {
+    let mut streaks = Vec::new();
+    let mut current: Option<(DateTime<Utc>, DateTime<Utc>, usize)> = None;
+
+    for candle in sorted {
+        if candle.volume == 0.0 {
+            current = Some(match current {
+                Some((start, _, bars)) => (start, candle.timestamp, bars + 1),
+                None => (candle.timestamp, candle.timestamp, 1),
+            });
+        } else if let Some((start, end, bars)) = current.take()
+            && bars >= MIN_ZERO_VOLUME_STREAK
+        {
+            streaks.push(ZeroVolumeStreak { start, end, bars });
+        }
+    }
+
+    if let Some((start, end, bars)) = current
+        && bars >= MIN_ZERO_VOLUME_STREAK
+    {
+        streaks.push(ZeroVolumeStreak { start, end, bars });
+    }
+
+    streaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::memory_db::MemoryDatabase;
+
+    fn candle(minute: i64, volume: f64) -> CandleData {
+        CandleData {
+            timestamp: DateTime::from_timestamp_millis(minute * 60_000).unwrap(),
+            symbol: "SOL-USDT".to_string(),
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.5,
+            volume,
+        }
+    }
+
+    #[test]
+    fn test_gaps_duplicates_and_streaks() {
+        let mut candles: Vec<CandleData> = (0..10).map(|m| candle(m, 1.0)).collect();
+        candles.retain(|c| c.timestamp.timestamp() / 60 != 4 && c.timestamp.timestamp() / 60 != 5);
+        candles.push(candle(3, 1.0)); // 重复且倒序
+        for m in 10..14 {
+            candles.push(candle(m, 0.0));
+        }
+        candles[0].high = 99.5; // 最高价低于收盘价
+
+        let report = analyze_candles("SOL-USDT", Timeframe::M1, &candles);
+
+        assert_eq!(report.expected_bars, 14);
+        assert_eq!(report.actual_bars, 12);
+        assert_eq!(report.gaps.len(), 1);
+        assert_eq!(report.gaps[0].missing_bars, 2);
+        assert_eq!(report.duplicate_count, 1);
+        assert_eq!(report.out_of_order_count, 1);
+        assert_eq!(report.ohlc_violations.len(), 1);
+        assert_eq!(
+            report.ohlc_violations[0].kind,
+            OhlcViolationKind::HighBelowOpenClose
+        );
+        assert_eq!(report.zero_volume_streaks.len(), 1);
+        assert_eq!(report.zero_volume_streaks[0].bars, 4);
+    }
+
+    #[tokio::test]
+    async fn test_report_counts_duplicates_and_disorder_at_ingest() {
+        let backends: Vec<(&str, Box<dyn Storage>)> = vec![
+            ("sqlite", Box::new(Database::new(":memory:").await.unwrap())),
+            ("memory", Box::new(MemoryDatabase::new())),
+        ];
+
+        for (name, db) in backends {
+            // OKX接口按新到旧返回，整批倒序不计为乱序
+            let page: Vec<CandleData> = (0..5).rev().map(|m| candle(m, 1.0)).collect();
+            db.save_candles(&page).await.unwrap();
+
+            // 相同数据的冗余覆盖和最新一根 (未收盘) K线的更新不计为重复
+            let mut forming = candle(4, 2.0);
+            forming.close = 100.8;
+            db.save_candles(&[candle(3, 1.0), forming]).await.unwrap();
+            let report = coverage_report(db.as_ref(), "SOL-USDT", Timeframe::M1)
+                .await
+                .unwrap();
+            assert_eq!(report.duplicate_count, 0, "{}", name);
+            assert_eq!(report.out_of_order_count, 0, "{}", name);
+
+            // 已收盘K线被不同数据覆盖计为重复，逆着批次方向的K线计为乱序
+            let mut revised = candle(2, 1.0);
+            revised.close = 100.9;
+            db.save_candles(&[
+                candle(5, 1.0),
+                candle(7, 1.0),
+                candle(6, 1.0),
+                candle(8, 1.0),
+            ])
+            .await
+            .unwrap();
+            db.save_candle(&revised).await.unwrap();
+
+            let report = coverage_report(db.as_ref(), "SOL-USDT", Timeframe::M1)
+                .await
+                .unwrap();
+            assert_eq!(report.actual_bars, 9, "{}", name);
+            assert_eq!(report.duplicate_count, 1, "{}", name);
+            assert_eq!(report.out_of_order_count, 1, "{}", name);
+        }
+    }
+}
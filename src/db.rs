@@ -1,23 +1,66 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use log::{info, warn};
-use rusqlite::{Connection, Row, params};
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Row, params};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
-use crate::types::{CandleData, TickerData, Trade, TradingSignal};
+use crate::storage::{Storage, count_out_of_order, filled_state, is_conflicting_rewrite};
+use crate::types::{
+    AccountState, BacktestReport, BacktestRun, CandleData, CandleIngestStats, Fill, FundingPayment,
+    FundingRate, Order, OrderState, PairedPosition, Position, PositionLeg, PositionUnit,
+    SessionBoundary, SignalOutcome, SignalRecord, StrategyInstance, TickerData, TickerSnapshot,
+    Timeframe, Trade, TradingSignal,
+};
+
+/// 订单表查询列
+const ORDER_COLUMNS: &str = "id, client_order_id, exchange_order_id, symbol, side, order_type, 
+     price, size, state, filled_size, avg_fill_price, fee, strategy, created_at, updated_at, 
+     signal_id";
+
+/// 交易表查询列
+const TRADE_COLUMNS: &str =
+    "id, timestamp, symbol, side, price, quantity, strategy, pnl, signal_id, strategy_id";
+
+/// 回测记录表查询列
+const BACKTEST_RUN_COLUMNS: &str = "id, symbol, params, candle_count, data_fingerprint, 
+     code_version, created_at, start_time, end_time, initial_balance, final_balance, 
+     total_return, return_rate, max_drawdown, total_trades, win_rate, avg_return, sharpe_ratio, 
+     strategy";
+
+/// 在线备份每步复制的页面数
+const BACKUP_PAGES_PER_STEP: i32 = 256;
+
+/// 在线备份两步之间的暂停时间
+const BACKUP_STEP_PAUSE: Duration = Duration::from_millis(20);
 
 /// 数据库管理结构
 pub struct Database {
     conn: Arc<Mutex<Connection>>, // 数据库连接
+    path: Option<PathBuf>,        // 数据库文件路径 (内存数据库为None)
 }
 
 impl Database {
     /// 创建新的数据库实例
+    ///
+    /// 文件数据库使用WAL日志模式，读事务不阻塞写入，在线备份期间数据收集可以继续写入。
     pub async fn new(db_path: &str) -> Result<Self> {
         let conn = Connection::open(db_path)?;
+        let path = if db_path == ":memory:" {
+            None
+        } else {
+            conn.query_row("PRAGMA journal_mode=WAL", [], |row| row.get::<_, String>(0))?;
+            Some(PathBuf::from(db_path))
+        };
+
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
+            path,
         };
 
         // 创建必要的表
@@ -47,6 +90,16 @@ impl Database {
             [],
         )?;
 
+        // 创建K线写入质量计数表 (每个交易对一行)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS candle_ingest_stats (
+                symbol TEXT PRIMARY KEY,
+                duplicate_count INTEGER NOT NULL DEFAULT 0,
+                out_of_order_count INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+
         // 创建Ticker数据表
         conn.execute(
             "CREATE TABLE IF NOT EXISTS tickers (
@@ -61,6 +114,17 @@ impl Database {
             [],
         )?;
 
+        // 创建资金费率表 (永续合约每个结算时点一行)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS funding_rates (
+                symbol TEXT NOT NULL,
+                funding_time DATETIME NOT NULL,
+                funding_rate REAL NOT NULL,
+                PRIMARY KEY (symbol, funding_time)
+            )",
+            [],
+        )?;
+
         // 创建交易信号表
         conn.execute(
             "CREATE TABLE IF NOT EXISTS signals (
@@ -71,7 +135,10 @@ impl Database {
                 price REAL NOT NULL,
                 strategy TEXT NOT NULL,
                 reason TEXT NOT NULL,
-                confidence REAL NOT NULL
+                confidence REAL NOT NULL,
+                signal_outcome TEXT,
+                outcome_reason TEXT,
+                atr REAL
             )",
             [],
         )?;
@@ -86,11 +153,206 @@ impl Database {
                 price REAL NOT NULL,
                 quantity REAL NOT NULL,
                 strategy TEXT NOT NULL,
-                pnl REAL
+                pnl REAL,
+                signal_id INTEGER REFERENCES signals(id),
+                strategy_id TEXT NOT NULL DEFAULT 'Turtle'
+            )",
+            [],
+        )?;
+
+        // 创建账户状态表 (单行)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS account_state (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                balance REAL NOT NULL,
+                updated_at DATETIME NOT NULL
+            )",
+            [],
+        )?;
+
+        // 创建持仓表 (每个策略实例的每个交易对一行)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS positions (
+                strategy_id TEXT NOT NULL,
+                symbol TEXT NOT NULL,
+                quantity REAL NOT NULL,
+                avg_price REAL NOT NULL,
+                current_price REAL NOT NULL,
+                unrealized_pnl REAL NOT NULL,
+                timestamp DATETIME NOT NULL,
+                PRIMARY KEY (strategy_id, symbol)
+            )",
+            [],
+        )?;
+
+        // 创建持仓单位表 (分批建仓的每个单位一行)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS position_units (
+                strategy_id TEXT NOT NULL,
+                symbol TEXT NOT NULL,
+                unit_index INTEGER NOT NULL,
+                entry_price REAL NOT NULL,
+                quantity REAL NOT NULL,
+                stop_price REAL,
+                timestamp DATETIME NOT NULL,
+                PRIMARY KEY (strategy_id, symbol, unit_index)
+            )",
+            [],
+        )?;
+
+        // 创建配对持仓表 (多腿策略的每一腿一行，空头数量为负)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS paired_position_legs (
+                strategy_id TEXT NOT NULL,
+                leg_index INTEGER NOT NULL,
+                symbol TEXT NOT NULL,
+                quantity REAL NOT NULL,
+                entry_price REAL NOT NULL,
+                current_price REAL NOT NULL,
+                timestamp DATETIME NOT NULL,
+                margin REAL NOT NULL,
+                PRIMARY KEY (strategy_id, leg_index)
+            )",
+            [],
+        )?;
+
+        // 创建资金费收支表 (配对持仓在每个结算时点收付的资金费)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS funding_payments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                strategy_id TEXT NOT NULL,
+                symbol TEXT NOT NULL,
+                funding_time DATETIME NOT NULL,
+                funding_rate REAL NOT NULL,
+                quantity REAL NOT NULL,
+                price REAL NOT NULL,
+                amount REAL NOT NULL
+            )",
+            [],
+        )?;
+
+        // 创建策略实例定义表 (运行时添加的实例，重启时据此重新创建)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS strategy_instances (
+                strategy_id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                symbol TEXT NOT NULL,
+                params TEXT NOT NULL,
+                allocation REAL NOT NULL
+            )",
+            [],
+        )?;
+
+        // 创建订单表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS orders (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                client_order_id TEXT NOT NULL UNIQUE,
+                exchange_order_id TEXT,
+                symbol TEXT NOT NULL,
+                side TEXT NOT NULL,
+                order_type TEXT NOT NULL,
+                price REAL,
+                size REAL NOT NULL,
+                state TEXT NOT NULL,
+                filled_size REAL NOT NULL DEFAULT 0,
+                avg_fill_price REAL,
+                fee REAL NOT NULL DEFAULT 0,
+                strategy TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                signal_id INTEGER REFERENCES signals(id)
+            )",
+            [],
+        )?;
+
+        // 创建成交明细表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS fills (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                order_id INTEGER NOT NULL REFERENCES orders(id),
+                exchange_trade_id TEXT,
+                price REAL NOT NULL,
+                size REAL NOT NULL,
+                fee REAL NOT NULL,
+                fee_ccy TEXT NOT NULL,
+                timestamp DATETIME NOT NULL,
+                UNIQUE (order_id, exchange_trade_id)
+            )",
+            [],
+        )?;
+
+        // 创建回测记录表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS backtest_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                symbol TEXT NOT NULL,
+                params TEXT NOT NULL,
+                candle_count INTEGER NOT NULL,
+                data_fingerprint TEXT NOT NULL,
+                code_version TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                start_time DATETIME NOT NULL,
+                end_time DATETIME NOT NULL,
+                initial_balance REAL NOT NULL,
+                final_balance REAL NOT NULL,
+                total_return REAL NOT NULL,
+                return_rate REAL NOT NULL,
+                max_drawdown REAL NOT NULL,
+                total_trades INTEGER NOT NULL,
+                win_rate REAL NOT NULL,
+                avg_return REAL NOT NULL,
+                sharpe_ratio REAL NOT NULL,
+                strategy TEXT NOT NULL DEFAULT 'Turtle'
+            )",
+            [],
+        )?;
+
+        // 创建回测交易表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS backtest_trades (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id INTEGER NOT NULL REFERENCES backtest_runs(id),
+                timestamp DATETIME NOT NULL,
+                symbol TEXT NOT NULL,
+                side TEXT NOT NULL,
+                price REAL NOT NULL,
+                quantity REAL NOT NULL,
+                strategy TEXT NOT NULL,
+                pnl REAL,
+                strategy_id TEXT NOT NULL DEFAULT 'Turtle'
+            )",
+            [],
+        )?;
+
+        // 创建回测权益曲线表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS backtest_equity (
+                run_id INTEGER NOT NULL REFERENCES backtest_runs(id),
+                timestamp DATETIME NOT NULL,
+                equity REAL NOT NULL,
+                PRIMARY KEY (run_id, timestamp)
             )",
             [],
         )?;
 
+        // 为旧版本数据库补充新增的列
+        add_column_if_missing(&conn, "signals", "signal_outcome", "TEXT")?;
+        add_column_if_missing(&conn, "signals", "outcome_reason", "TEXT")?;
+        add_column_if_missing(&conn, "signals", "atr", "REAL")?;
+        add_column_if_missing(
+            &conn,
+            "trades",
+            "signal_id",
+            "INTEGER REFERENCES signals(id)",
+        )?;
+        add_column_if_missing(
+            &conn,
+            "trades",
+            "strategy_id",
+            "TEXT NOT NULL DEFAULT 'Turtle'",
+        )?;
+
         // 创建索引以提高查询性能
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_candles_symbol_timestamp 
@@ -104,90 +366,194 @@ impl Database {
             [],
         )?;
 
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_signals_symbol_timestamp 
+             ON signals(symbol, timestamp)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_trades_signal_id 
+             ON trades(signal_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_orders_state 
+             ON orders(state, symbol)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_fills_order_id 
+             ON fills(order_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_tickers_symbol_timestamp 
+             ON tickers(symbol, timestamp)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_backtest_trades_run_id 
+             ON backtest_trades(run_id)",
+            [],
+        )?;
+
         info!("数据库表创建完成");
         Ok(())
     }
 
+    /// 使用SQLite在线备份API将当前数据库复制到目标文件
+    ///
+    /// 备份在独立的只读连接上进行：先开启读事务固定WAL快照，再每步复制 `BACKUP_PAGES_PER_STEP`
+    /// 个页面并暂停，主连接的写入既不被阻塞也不会使备份重新开始，完成时得到开始时刻的一致快照。
+    /// 快照改为回滚日志模式，单个文件即包含全部数据。
+    pub async fn backup_to(&self, dest: &Path) -> Result<()> {
+        let source = self
+            .path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("内存数据库不支持生成快照"))?;
+        let dest: PathBuf = dest.to_path_buf();
+
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let src = Connection::open_with_flags(&source, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+            src.execute_batch("BEGIN; SELECT COUNT(*) FROM sqlite_master;")?;
+
+            let mut dst = Connection::open(&dest)?;
+            Backup::new(&src, &mut dst)?.run_to_completion(
+                BACKUP_PAGES_PER_STEP,
+                BACKUP_STEP_PAUSE,
+                None,
+            )?;
+            dst.query_row("PRAGMA journal_mode=DELETE", [], |row| {
+                row.get::<_, String>(0)
+            })?;
+
+            src.execute_batch("COMMIT")?;
+            Ok(())
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl Storage for Database {
     /// 保存K线数据
-    pub async fn save_candle(&self, candle: &CandleData) -> Result<()> {
-        let conn = self.conn.lock().await;
+    async fn save_candle(&self, candle: &CandleData) -> Result<()> {
+        let mut conn = self.conn.lock().await;
 
-        match conn.execute(
-            "INSERT OR REPLACE INTO candles 
-             (timestamp, symbol, open, high, low, close, volume) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                candle.timestamp.timestamp_millis(),
-                candle.symbol,
-                candle.open,
-                candle.high,
-                candle.low,
-                candle.close,
-                candle.volume,
-            ],
-        ) {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                warn!("保存K线数据失败: {}", e);
-                Err(e.into())
-            }
-        }
+        write_candles(&mut conn, std::slice::from_ref(candle)).inspect_err(|e| {
+            warn!("保存K线数据失败: {}", e);
+        })
     }
 
     /// 批量保存K线数据
-    pub async fn save_candles(&self, candles: &[CandleData]) -> Result<()> {
+    async fn save_candles(&self, candles: &[CandleData]) -> Result<()> {
+        let mut conn = self.conn.lock().await;
+        write_candles(&mut conn, candles)?;
+
+        info!("批量保存{}条K线数据", candles.len());
+        Ok(())
+    }
+
+    /// 获取交易对K线写入时累计的重复与乱序计数
+    async fn get_candle_ingest_stats(&self, symbol: &str) -> Result<CandleIngestStats> {
+        let conn = self.conn.lock().await;
+
+        let stats = conn
+            .query_row(
+                "SELECT duplicate_count, out_of_order_count 
+                 FROM candle_ingest_stats WHERE symbol = ?1",
+                params![symbol],
+                |row| {
+                    Ok(CandleIngestStats {
+                        duplicate_count: row.get(0)?,
+                        out_of_order_count: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(stats.unwrap_or_default())
+    }
+
+    /// 保存Ticker数据
+    async fn save_ticker(&self, ticker: &TickerData) -> Result<()> {
+        self.save_ticker_snapshots(&[TickerSnapshot::from(ticker)])
+            .await
+    }
+
+    /// 批量保存Ticker快照
+    async fn save_ticker_snapshots(&self, tickers: &[TickerSnapshot]) -> Result<()> {
         let conn = self.conn.lock().await;
         let mut stmt = conn.prepare(
-            "INSERT OR REPLACE INTO candles 
-             (timestamp, symbol, open, high, low, close, volume) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            "INSERT INTO tickers 
+             (timestamp, symbol, last_price, bid_price, ask_price, volume_24h) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         )?;
 
-        for candle in candles {
+        for ticker in tickers {
             stmt.execute(params![
-                candle.timestamp.timestamp_millis(),
-                candle.symbol,
-                candle.open,
-                candle.high,
-                candle.low,
-                candle.close,
-                candle.volume,
+                ticker.timestamp.timestamp_millis(),
+                ticker.symbol,
+                ticker.last_price,
+                ticker.bid_price,
+                ticker.ask_price,
+                ticker.volume_24h,
             ])?;
         }
 
-        info!("批量保存{}条K线数据", candles.len());
         Ok(())
     }
 
-    /// 保存Ticker数据
-    pub async fn save_ticker(&self, ticker: &TickerData) -> Result<()> {
+    /// 获取指定时间范围的Ticker快照
+    async fn get_tickers(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<TickerSnapshot>> {
         let conn = self.conn.lock().await;
 
-        let last_price: f64 = ticker.last.parse().unwrap_or(0.0);
-        let bid_price: f64 = ticker.bid_px.parse().unwrap_or(0.0);
-        let ask_price: f64 = ticker.ask_px.parse().unwrap_or(0.0);
-        let volume_24h: f64 = ticker.vol_ccy24h.parse().unwrap_or(0.0);
-        let timestamp_ms: i64 = ticker.ts.parse().unwrap_or(0);
-
-        conn.execute(
-            "INSERT INTO tickers 
-             (timestamp, symbol, last_price, bid_price, ask_price, volume_24h) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, symbol, last_price, bid_price, ask_price, volume_24h 
+             FROM tickers 
+             WHERE symbol = ?1 AND timestamp >= ?2 AND timestamp <= ?3 
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+        let ticker_iter = stmt.query_map(
             params![
-                timestamp_ms,
-                ticker.inst_id,
-                last_price,
-                bid_price,
-                ask_price,
-                volume_24h,
+                symbol,
+                start_time.timestamp_millis(),
+                end_time.timestamp_millis(),
             ],
+            |row| {
+                let timestamp_ms: i64 = row.get(0)?;
+
+                Ok(TickerSnapshot {
+                    timestamp: DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default(),
+                    symbol: row.get(1)?,
+                    last_price: row.get(2)?,
+                    bid_price: row.get(3)?,
+                    ask_price: row.get(4)?,
+                    volume_24h: row.get(5)?,
+                })
+            },
         )?;
 
-        Ok(())
+        let mut tickers = Vec::new();
+        for ticker in ticker_iter {
+            tickers.push(ticker?);
+        }
+
+        Ok(tickers)
     }
 
     /// 获取指定时间范围的K线数据
-    pub async fn get_candles(
+    async fn get_candles(
         &self,
         symbol: &str,
         start_time: DateTime<Utc>,
@@ -196,11 +562,11 @@ impl Database {
     ) -> Result<Vec<CandleData>> {
         let conn = self.conn.lock().await;
 
-        let mut query = format!(
+        let mut query = String::from(
             "SELECT timestamp, symbol, open, high, low, close, volume 
              FROM candles 
              WHERE symbol = ?1 AND timestamp >= ?2 AND timestamp <= ?3 
-             ORDER BY timestamp ASC"
+             ORDER BY timestamp ASC",
         );
 
         if let Some(limit) = limit {
@@ -239,7 +605,7 @@ impl Database {
     }
 
     /// 获取最新的N条K线数据
-    pub async fn get_latest_candles(&self, symbol: &str, count: usize) -> Result<Vec<CandleData>> {
+    async fn get_latest_candles(&self, symbol: &str, count: usize) -> Result<Vec<CandleData>> {
         let conn = self.conn.lock().await;
 
         let mut stmt = conn.prepare(
@@ -275,76 +641,294 @@ impl Database {
         Ok(candles)
     }
 
-    /// 保存交易信号
-    pub async fn save_signal(&self, signal: &TradingSignal) -> Result<()> {
+    /// 在SQL中将1m K线聚合为指定周期
+    async fn get_resampled_candles(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        session: SessionBoundary,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<CandleData>> {
         let conn = self.conn.lock().await;
 
-        conn.execute(
-            "INSERT INTO signals 
-             (timestamp, symbol, signal_type, price, strategy, reason, confidence) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        // 桶起始时间 = ts - ((ts + shift) mod interval)，取模结果保持非负
+        let mut stmt = conn.prepare(
+            "SELECT b.bucket,
+                    (SELECT open FROM candles WHERE symbol = ?1 AND timestamp = b.first_ts),
+                    b.high, b.low,
+                    (SELECT close FROM candles WHERE symbol = ?1 AND timestamp = b.last_ts),
+                    b.volume
+             FROM (
+                 SELECT timestamp - (((timestamp + ?4) % ?5) + ?5) % ?5 AS bucket,
+                        MIN(timestamp) AS first_ts, MAX(timestamp) AS last_ts,
+                        MAX(high) AS high, MIN(low) AS low, SUM(volume) AS volume
+                 FROM candles
+                 WHERE symbol = ?1 AND timestamp >= ?2 AND timestamp <= ?3
+                 GROUP BY bucket
+             ) b
+             ORDER BY b.bucket ASC",
+        )?;
+        let candle_iter = stmt.query_map(
             params![
-                signal.timestamp.timestamp_millis(),
-                signal.symbol,
-                format!("{:?}", signal.signal_type),
-                signal.price,
-                signal.strategy,
-                signal.reason,
-                signal.confidence,
+                symbol,
+                start_time.timestamp_millis(),
+                end_time.timestamp_millis(),
+                timeframe.bucket_shift_ms(session),
+                timeframe.duration_ms(),
             ],
+            |row| {
+                let timestamp_ms: i64 = row.get(0)?;
+                let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+
+                Ok(CandleData {
+                    timestamp,
+                    symbol: symbol.to_string(),
+                    open: row.get(1)?,
+                    high: row.get(2)?,
+                    low: row.get(3)?,
+                    close: row.get(4)?,
+                    volume: row.get(5)?,
+                })
+            },
         )?;
 
-        Ok(())
+        let mut candles = Vec::new();
+        for candle in candle_iter {
+            candles.push(candle?);
+        }
+
+        Ok(candles)
     }
 
-    /// 保存交易记录
-    pub async fn save_trade(&self, trade: &Trade) -> Result<i64> {
+    /// 获取已存储K线的全部交易对
+    async fn get_candle_symbols(&self) -> Result<Vec<String>> {
         let conn = self.conn.lock().await;
 
-        conn.execute(
-            "INSERT INTO trades 
-             (timestamp, symbol, side, price, quantity, strategy, pnl) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                trade.timestamp.timestamp_millis(),
-                trade.symbol,
-                trade.side,
-                trade.price,
-                trade.quantity,
-                trade.strategy,
-                trade.pnl,
-            ],
-        )?;
+        let mut stmt = conn.prepare("SELECT DISTINCT symbol FROM candles ORDER BY symbol ASC")?;
+        let symbol_iter = stmt.query_map([], |row| row.get(0))?;
 
-        Ok(conn.last_insert_rowid())
+        let mut symbols = Vec::new();
+        for symbol in symbol_iter {
+            symbols.push(symbol?);
+        }
+
+        Ok(symbols)
     }
 
-    /// 获取最近的交易记录
-    pub async fn get_recent_trades(&self, limit: usize) -> Result<Vec<Trade>> {
+    /// 批量保存资金费率
+    async fn save_funding_rates(&self, rates: &[FundingRate]) -> Result<()> {
         let conn = self.conn.lock().await;
-
         let mut stmt = conn.prepare(
-            "SELECT id, timestamp, symbol, side, price, quantity, strategy, pnl 
-             FROM trades 
-             ORDER BY timestamp DESC 
-             LIMIT ?1",
+            "INSERT OR REPLACE INTO funding_rates 
+             (symbol, funding_time, funding_rate) 
+             VALUES (?1, ?2, ?3)",
         )?;
 
-        let trade_iter = stmt.query_map(params![limit], |row| {
-            let timestamp_ms: i64 = row.get(1)?;
-            let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
-
-            Ok(Trade {
-                id: Some(row.get(0)?),
-                timestamp,
-                symbol: row.get(2)?,
-                side: row.get(3)?,
-                price: row.get(4)?,
-                quantity: row.get(5)?,
-                strategy: row.get(6)?,
-                pnl: row.get(7)?,
-            })
-        })?;
+        for rate in rates {
+            stmt.execute(params![
+                rate.symbol,
+                rate.funding_time.timestamp_millis(),
+                rate.funding_rate,
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    /// 获取指定时间范围的资金费率
+    async fn get_funding_rates(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<FundingRate>> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn.prepare(
+            "SELECT symbol, funding_time, funding_rate 
+             FROM funding_rates 
+             WHERE symbol = ?1 AND funding_time >= ?2 AND funding_time <= ?3 
+             ORDER BY funding_time ASC",
+        )?;
+        let rate_iter = stmt.query_map(
+            params![
+                symbol,
+                start_time.timestamp_millis(),
+                end_time.timestamp_millis(),
+            ],
+            |row| {
+                let funding_ms: i64 = row.get(1)?;
+
+                Ok(FundingRate {
+                    symbol: row.get(0)?,
+                    funding_time: DateTime::from_timestamp_millis(funding_ms).unwrap_or_default(),
+                    funding_rate: row.get(2)?,
+                })
+            },
+        )?;
+
+        let mut rates = Vec::new();
+        for rate in rate_iter {
+            rates.push(rate?);
+        }
+
+        Ok(rates)
+    }
+
+    /// 保存交易信号，返回信号ID
+    async fn save_signal(&self, signal: &TradingSignal) -> Result<i64> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "INSERT INTO signals 
+             (timestamp, symbol, signal_type, price, strategy, reason, confidence, atr) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                signal.timestamp.timestamp_millis(),
+                signal.symbol,
+                format!("{:?}", signal.signal_type),
+                signal.price,
+                signal.strategy,
+                signal.reason,
+                signal.confidence,
+                signal.atr,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 记录信号的处理结果 (已执行或被跳过的原因)
+    async fn update_signal_outcome(
+        &self,
+        signal_id: i64,
+        outcome: SignalOutcome,
+        reason: &str,
+    ) -> Result<()> {
+        let conn = self.conn.lock().await;
+
+        let updated = conn.execute(
+            "UPDATE signals SET signal_outcome = ?1, outcome_reason = ?2 WHERE id = ?3",
+            params![outcome.as_str(), reason, signal_id],
+        )?;
+
+        if updated == 0 {
+            return Err(anyhow::anyhow!("信号不存在: {}", signal_id));
+        }
+
+        Ok(())
+    }
+
+    /// 查询指定时间范围内的交易信号，可按策略过滤
+    async fn get_signals(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        strategy: Option<&str>,
+    ) -> Result<Vec<SignalRecord>> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, timestamp, symbol, signal_type, price, strategy, reason, confidence, 
+                    signal_outcome, outcome_reason, atr 
+             FROM signals 
+             WHERE symbol = ?1 AND timestamp >= ?2 AND timestamp <= ?3 
+                   AND (?4 IS NULL OR strategy = ?4) 
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+
+        let signal_iter = stmt.query_map(
+            params![
+                symbol,
+                start_time.timestamp_millis(),
+                end_time.timestamp_millis(),
+                strategy,
+            ],
+            |row| {
+                let timestamp_ms: i64 = row.get(1)?;
+                let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+                let signal_type: String = row.get(3)?;
+                let outcome: Option<String> = row.get(8)?;
+
+                Ok(SignalRecord {
+                    signal: TradingSignal {
+                        id: Some(row.get(0)?),
+                        symbol: row.get(2)?,
+                        signal_type: signal_type.parse().map_err(|e: anyhow::Error| {
+                            rusqlite::Error::FromSqlConversionFailure(
+                                3,
+                                rusqlite::types::Type::Text,
+                                e.into(),
+                            )
+                        })?,
+                        price: row.get(4)?,
+                        timestamp,
+                        strategy: row.get(5)?,
+                        reason: row.get(6)?,
+                        confidence: row.get(7)?,
+                        atr: row.get(10)?,
+                    },
+                    outcome: outcome.map(|o| o.parse()).transpose().map_err(
+                        |e: anyhow::Error| {
+                            rusqlite::Error::FromSqlConversionFailure(
+                                8,
+                                rusqlite::types::Type::Text,
+                                e.into(),
+                            )
+                        },
+                    )?,
+                    outcome_reason: row.get(9)?,
+                })
+            },
+        )?;
+
+        let mut signals = Vec::new();
+        for signal in signal_iter {
+            signals.push(signal?);
+        }
+
+        Ok(signals)
+    }
+
+    /// 保存交易记录
+    async fn save_trade(&self, trade: &Trade) -> Result<i64> {
+        let conn = self.conn.lock().await;
+        insert_trade(&conn, trade)
+    }
+
+    /// 在同一事务中保存交易记录与成交后的账户状态
+    async fn save_trades_with_account_state(
+        &self,
+        trades: &[Trade],
+        state: &AccountState,
+    ) -> Result<Vec<i64>> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn.transaction()?;
+
+        let mut trade_ids = Vec::with_capacity(trades.len());
+        for trade in trades {
+            trade_ids.push(insert_trade(&tx, trade)?);
+        }
+        write_account_state(&tx, state)?;
+
+        tx.commit()?;
+        Ok(trade_ids)
+    }
+
+    /// 获取最近的交易记录
+    async fn get_recent_trades(&self, limit: usize) -> Result<Vec<Trade>> {
+        let conn = self.conn.lock().await;
+
+        let query = format!(
+            "SELECT {} FROM trades 
+             ORDER BY timestamp DESC 
+             LIMIT ?1",
+            TRADE_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let trade_iter = stmt.query_map(params![limit], row_to_trade)?;
 
         let mut trades = Vec::new();
         for trade in trade_iter {
@@ -355,39 +939,719 @@ impl Database {
     }
 
     /// 获取指定时间范围内的交易记录
-    pub async fn get_trades_by_time_range(
+    async fn get_trades_by_time_range(
         &self,
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
     ) -> Result<Vec<Trade>> {
         let conn = self.conn.lock().await;
 
-        let mut stmt = conn.prepare(
-            "SELECT id, timestamp, symbol, side, price, quantity, strategy, pnl 
-             FROM trades 
+        let query = format!(
+            "SELECT {} FROM trades 
              WHERE timestamp >= ?1 AND timestamp <= ?2 
              ORDER BY timestamp ASC",
+            TRADE_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let trade_iter = stmt.query_map(
+            params![start_time.timestamp_millis(), end_time.timestamp_millis()],
+            row_to_trade,
         )?;
 
-        let trade_iter = stmt.query_map(
-            params![start_time.timestamp_millis(), end_time.timestamp_millis(),],
+        let mut trades = Vec::new();
+        for trade in trade_iter {
+            trades.push(trade?);
+        }
+
+        Ok(trades)
+    }
+
+    /// 获取由指定信号产生的交易记录
+    async fn get_trades_by_signal(&self, signal_id: i64) -> Result<Vec<Trade>> {
+        let conn = self.conn.lock().await;
+
+        let query = format!(
+            "SELECT {} FROM trades WHERE signal_id = ?1 ORDER BY timestamp ASC",
+            TRADE_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let trade_iter = stmt.query_map(params![signal_id], row_to_trade)?;
+
+        let mut trades = Vec::new();
+        for trade in trade_iter {
+            trades.push(trade?);
+        }
+
+        Ok(trades)
+    }
+
+    /// 保存资金费收支记录
+    async fn save_funding_payment(&self, payment: &FundingPayment) -> Result<i64> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "INSERT INTO funding_payments 
+             (strategy_id, symbol, funding_time, funding_rate, quantity, price, amount) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                payment.strategy_id,
+                payment.symbol,
+                payment.funding_time.timestamp_millis(),
+                payment.funding_rate,
+                payment.quantity,
+                payment.price,
+                payment.amount,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 获取指定结算时间范围内的资金费收支记录
+    async fn get_funding_payments(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<FundingPayment>> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, strategy_id, symbol, funding_time, funding_rate, quantity, price, amount 
+             FROM funding_payments 
+             WHERE funding_time >= ?1 AND funding_time <= ?2 
+             ORDER BY funding_time ASC, id ASC",
+        )?;
+        let payment_iter = stmt.query_map(
+            params![start_time.timestamp_millis(), end_time.timestamp_millis()],
             |row| {
-                let timestamp_ms: i64 = row.get(1)?;
-                let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+                let funding_ms: i64 = row.get(3)?;
 
-                Ok(Trade {
-                    id: Some(row.get(0)?),
-                    timestamp,
+                Ok(FundingPayment {
+                    id: row.get(0)?,
+                    strategy_id: row.get(1)?,
                     symbol: row.get(2)?,
-                    side: row.get(3)?,
-                    price: row.get(4)?,
+                    funding_time: DateTime::from_timestamp_millis(funding_ms).unwrap_or_default(),
+                    funding_rate: row.get(4)?,
                     quantity: row.get(5)?,
-                    strategy: row.get(6)?,
-                    pnl: row.get(7)?,
+                    price: row.get(6)?,
+                    amount: row.get(7)?,
                 })
             },
         )?;
 
+        let mut payments = Vec::new();
+        for payment in payment_iter {
+            payments.push(payment?);
+        }
+
+        Ok(payments)
+    }
+
+    /// 保存账户状态 (余额与全部持仓在同一事务中覆盖写入)
+    async fn save_account_state(&self, state: &AccountState) -> Result<()> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn.transaction()?;
+        write_account_state(&tx, state)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 加载账户状态，从未保存过时返回None
+    async fn load_account_state(&self) -> Result<Option<AccountState>> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt =
+            conn.prepare("SELECT balance, updated_at FROM account_state WHERE id = 1")?;
+        let mut rows = stmt.query([])?;
+        let (balance, updated_at) = match rows.next()? {
+            Some(row) => {
+                let balance: f64 = row.get(0)?;
+                let updated_ms: i64 = row.get(1)?;
+                (
+                    balance,
+                    DateTime::from_timestamp_millis(updated_ms).unwrap_or_default(),
+                )
+            }
+            None => return Ok(None),
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT strategy_id, symbol, quantity, avg_price, current_price, unrealized_pnl, 
+                    timestamp 
+             FROM positions 
+             ORDER BY strategy_id ASC, symbol ASC",
+        )?;
+
+        let position_iter = stmt.query_map([], |row| {
+            let timestamp_ms: i64 = row.get(6)?;
+            let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+
+            Ok(Position {
+                strategy_id: row.get(0)?,
+                symbol: row.get(1)?,
+                quantity: row.get(2)?,
+                avg_price: row.get(3)?,
+                current_price: row.get(4)?,
+                unrealized_pnl: row.get(5)?,
+                timestamp,
+                units: Vec::new(),
+            })
+        })?;
+
+        let mut positions = Vec::new();
+        for position in position_iter {
+            positions.push(position?);
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT strategy_id, symbol, entry_price, quantity, stop_price, timestamp 
+             FROM position_units 
+             ORDER BY strategy_id ASC, symbol ASC, unit_index ASC",
+        )?;
+
+        let unit_iter = stmt.query_map([], |row| {
+            let timestamp_ms: i64 = row.get(5)?;
+            let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                PositionUnit {
+                    entry_price: row.get(2)?,
+                    quantity: row.get(3)?,
+                    stop_price: row.get(4)?,
+                    timestamp,
+                },
+            ))
+        })?;
+
+        for unit in unit_iter {
+            let (strategy_id, symbol, unit) = unit?;
+            if let Some(position) = positions
+                .iter_mut()
+                .find(|p| p.strategy_id == strategy_id && p.symbol == symbol)
+            {
+                position.units.push(unit);
+            }
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT strategy_id, symbol, quantity, entry_price, current_price, timestamp, margin 
+             FROM paired_position_legs 
+             ORDER BY strategy_id ASC, leg_index ASC",
+        )?;
+
+        let leg_iter = stmt.query_map([], |row| {
+            let timestamp_ms: i64 = row.get(5)?;
+            let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+
+            Ok((
+                row.get::<_, String>(0)?,
+                timestamp,
+                PositionLeg {
+                    symbol: row.get(1)?,
+                    quantity: row.get(2)?,
+                    entry_price: row.get(3)?,
+                    current_price: row.get(4)?,
+                    margin: row.get(6)?,
+                },
+            ))
+        })?;
+
+        let mut paired_positions: Vec<PairedPosition> = Vec::new();
+        for leg in leg_iter {
+            let (strategy_id, timestamp, leg) = leg?;
+            match paired_positions.last_mut() {
+                Some(position) if position.strategy_id == strategy_id => position.legs.push(leg),
+                _ => paired_positions.push(PairedPosition {
+                    strategy_id,
+                    legs: vec![leg],
+                    timestamp,
+                }),
+            }
+        }
+
+        Ok(Some(AccountState {
+            balance,
+            positions,
+            paired_positions,
+            updated_at,
+        }))
+    }
+
+    /// 保存策略实例定义 (同一实例ID覆盖写入，保留首次保存的顺序)
+    async fn save_strategy_instance(&self, instance: &StrategyInstance) -> Result<()> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "INSERT INTO strategy_instances (strategy_id, kind, symbol, params, allocation) 
+             VALUES (?1, ?2, ?3, ?4, ?5) 
+             ON CONFLICT(strategy_id) DO UPDATE SET 
+             kind = excluded.kind, symbol = excluded.symbol, params = excluded.params, 
+             allocation = excluded.allocation",
+            params![
+                instance.strategy_id,
+                instance.kind,
+                instance.symbol,
+                serde_json::to_string(&instance.params)?,
+                instance.allocation,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// 获取全部策略实例定义 (按首次保存的顺序)
+    async fn get_strategy_instances(&self) -> Result<Vec<StrategyInstance>> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn.prepare(
+            "SELECT strategy_id, kind, symbol, params, allocation 
+             FROM strategy_instances 
+             ORDER BY rowid ASC",
+        )?;
+        let instance_iter = stmt.query_map([], |row| {
+            let params_json: String = row.get(3)?;
+            let params = serde_json::from_str(&params_json).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, e.into())
+            })?;
+
+            Ok(StrategyInstance {
+                strategy_id: row.get(0)?,
+                kind: row.get(1)?,
+                symbol: row.get(2)?,
+                params,
+                allocation: row.get(4)?,
+            })
+        })?;
+
+        let mut instances = Vec::new();
+        for instance in instance_iter {
+            instances.push(instance?);
+        }
+
+        Ok(instances)
+    }
+
+    /// 保存新订单，返回订单ID
+    async fn save_order(&self, order: &Order) -> Result<i64> {
+        let conn = self.conn.lock().await;
+
+        conn.execute(
+            "INSERT INTO orders 
+             (client_order_id, exchange_order_id, symbol, side, order_type, price, size, 
+              state, filled_size, avg_fill_price, fee, strategy, created_at, updated_at, 
+              signal_id) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                order.client_order_id,
+                order.exchange_order_id,
+                order.symbol,
+                order.side,
+                order.order_type.as_str(),
+                order.price,
+                order.size,
+                order.state.as_str(),
+                order.filled_size,
+                order.avg_fill_price,
+                order.fee,
+                order.strategy,
+                order.created_at.timestamp_millis(),
+                order.updated_at.timestamp_millis(),
+                order.signal_id,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 更新订单状态 (交易所确认、撤单、拒单等)
+    async fn update_order_state(
+        &self,
+        order_id: i64,
+        state: OrderState,
+        exchange_order_id: Option<&str>,
+        updated_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().await;
+
+        let updated = conn.execute(
+            "UPDATE orders 
+             SET state = ?1, exchange_order_id = COALESCE(?2, exchange_order_id), updated_at = ?3 
+             WHERE id = ?4",
+            params![
+                state.as_str(),
+                exchange_order_id,
+                updated_at.timestamp_millis(),
+                order_id,
+            ],
+        )?;
+
+        if updated == 0 {
+            return Err(anyhow::anyhow!("订单不存在: {}", order_id));
+        }
+
+        Ok(())
+    }
+
+    /// 保存成交明细，并在同一事务中更新订单的成交数量、均价、手续费和状态
+    async fn save_fill(&self, fill: &Fill) -> Result<i64> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn.transaction()?;
+
+        let (size, current): (f64, String) = tx
+            .query_row(
+                "SELECT size, state FROM orders WHERE id = ?1",
+                params![fill.order_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| anyhow::anyhow!("订单不存在: {} ({})", fill.order_id, e))?;
+
+        // 重复推送的成交明细被唯一约束忽略，不重复计入成交汇总
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO fills 
+             (order_id, exchange_trade_id, price, size, fee, fee_ccy, timestamp) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                fill.order_id,
+                fill.exchange_trade_id,
+                fill.price,
+                fill.size,
+                fill.fee,
+                fill.fee_ccy,
+                fill.timestamp.timestamp_millis(),
+            ],
+        )?;
+        if inserted == 0 {
+            let fill_id = tx.query_row(
+                "SELECT id FROM fills WHERE order_id = ?1 AND exchange_trade_id = ?2",
+                params![fill.order_id, fill.exchange_trade_id],
+                |row| row.get(0),
+            )?;
+            return Ok(fill_id);
+        }
+        let fill_id = tx.last_insert_rowid();
+
+        let (filled_size, notional, fee): (f64, f64, f64) = tx.query_row(
+            "SELECT COALESCE(SUM(size), 0), COALESCE(SUM(price * size), 0), COALESCE(SUM(fee), 0) 
+             FROM fills WHERE order_id = ?1",
+            params![fill.order_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+
+        let state = filled_state(current.parse()?, filled_size, size);
+        let avg_fill_price = (filled_size > 0.0).then(|| notional / filled_size);
+
+        tx.execute(
+            "UPDATE orders 
+             SET filled_size = ?1, avg_fill_price = ?2, fee = ?3, state = ?4, updated_at = ?5 
+             WHERE id = ?6",
+            params![
+                filled_size,
+                avg_fill_price,
+                fee,
+                state.as_str(),
+                fill.timestamp.timestamp_millis(),
+                fill.order_id,
+            ],
+        )?;
+
+        tx.commit()?;
+        Ok(fill_id)
+    }
+
+    /// 根据订单ID获取订单
+    async fn get_order(&self, order_id: i64) -> Result<Option<Order>> {
+        let conn = self.conn.lock().await;
+
+        let query = format!("SELECT {} FROM orders WHERE id = ?1", ORDER_COLUMNS);
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query(params![order_id])?;
+
+        match rows.next()? {
+            Some(row) => Ok(Some(row_to_order(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 根据客户端订单ID获取订单
+    async fn get_order_by_client_id(&self, client_order_id: &str) -> Result<Option<Order>> {
+        let conn = self.conn.lock().await;
+
+        let query = format!(
+            "SELECT {} FROM orders WHERE client_order_id = ?1",
+            ORDER_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query(params![client_order_id])?;
+
+        match rows.next()? {
+            Some(row) => Ok(Some(row_to_order(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 获取未完结订单，symbol为None时返回全部交易对
+    async fn get_open_orders(&self, symbol: Option<&str>) -> Result<Vec<Order>> {
+        let conn = self.conn.lock().await;
+
+        let query = format!(
+            "SELECT {} FROM orders 
+             WHERE state IN (?1, ?2, ?3) AND (?4 IS NULL OR symbol = ?4) 
+             ORDER BY created_at ASC",
+            ORDER_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let order_iter = stmt.query_map(
+            params![
+                OrderState::Pending.as_str(),
+                OrderState::Live.as_str(),
+                OrderState::PartiallyFilled.as_str(),
+                symbol,
+            ],
+            row_to_order,
+        )?;
+
+        let mut orders = Vec::new();
+        for order in order_iter {
+            orders.push(order?);
+        }
+
+        Ok(orders)
+    }
+
+    /// 获取指定时间范围内创建的订单历史 (含已完结订单)
+    async fn get_order_history(
+        &self,
+        symbol: Option<&str>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<Order>> {
+        let conn = self.conn.lock().await;
+
+        let query = format!(
+            "SELECT {} FROM orders 
+             WHERE created_at >= ?1 AND created_at <= ?2 AND (?3 IS NULL OR symbol = ?3) 
+             ORDER BY created_at ASC",
+            ORDER_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let order_iter = stmt.query_map(
+            params![
+                start_time.timestamp_millis(),
+                end_time.timestamp_millis(),
+                symbol,
+            ],
+            row_to_order,
+        )?;
+
+        let mut orders = Vec::new();
+        for order in order_iter {
+            orders.push(order?);
+        }
+
+        Ok(orders)
+    }
+
+    /// 获取由指定信号产生的订单
+    async fn get_orders_by_signal(&self, signal_id: i64) -> Result<Vec<Order>> {
+        let conn = self.conn.lock().await;
+
+        let query = format!(
+            "SELECT {} FROM orders WHERE signal_id = ?1 ORDER BY created_at ASC",
+            ORDER_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let order_iter = stmt.query_map(params![signal_id], row_to_order)?;
+
+        let mut orders = Vec::new();
+        for order in order_iter {
+            orders.push(order?);
+        }
+
+        Ok(orders)
+    }
+
+    /// 获取订单的全部成交明细
+    async fn get_fills(&self, order_id: i64) -> Result<Vec<Fill>> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, order_id, exchange_trade_id, price, size, fee, fee_ccy, timestamp 
+             FROM fills 
+             WHERE order_id = ?1 
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+
+        let fill_iter = stmt.query_map(params![order_id], |row| {
+            let timestamp_ms: i64 = row.get(7)?;
+            let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+
+            Ok(Fill {
+                id: Some(row.get(0)?),
+                order_id: row.get(1)?,
+                exchange_trade_id: row.get(2)?,
+                price: row.get(3)?,
+                size: row.get(4)?,
+                fee: row.get(5)?,
+                fee_ccy: row.get(6)?,
+                timestamp,
+            })
+        })?;
+
+        let mut fills = Vec::new();
+        for fill in fill_iter {
+            fills.push(fill?);
+        }
+
+        Ok(fills)
+    }
+
+    /// 保存回测记录及其交易和权益曲线，返回回测记录ID
+    async fn save_backtest_run(
+        &self,
+        run: &BacktestRun,
+        trades: &[Trade],
+        equity_curve: &[(DateTime<Utc>, f64)],
+    ) -> Result<i64> {
+        let mut conn = self.conn.lock().await;
+        let tx = conn.transaction()?;
+
+        let report = &run.report;
+        tx.execute(
+            "INSERT INTO backtest_runs 
+             (symbol, params, candle_count, data_fingerprint, code_version, created_at, 
+              start_time, end_time, initial_balance, final_balance, total_return, return_rate, 
+              max_drawdown, total_trades, win_rate, avg_return, sharpe_ratio, strategy) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            params![
+                run.symbol,
+                serde_json::to_string(&run.params)?,
+                run.candle_count,
+                run.data_fingerprint,
+                run.code_version,
+                run.created_at.timestamp_millis(),
+                report.start_time.timestamp_millis(),
+                report.end_time.timestamp_millis(),
+                report.initial_balance,
+                report.final_balance,
+                report.total_return,
+                report.return_rate,
+                report.max_drawdown,
+                report.total_trades,
+                report.win_rate,
+                report.avg_return,
+                report.sharpe_ratio,
+                run.strategy,
+            ],
+        )?;
+        let run_id = tx.last_insert_rowid();
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO backtest_trades 
+                 (run_id, timestamp, symbol, side, price, quantity, strategy, pnl, strategy_id) 
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for trade in trades {
+                stmt.execute(params![
+                    run_id,
+                    trade.timestamp.timestamp_millis(),
+                    trade.symbol,
+                    trade.side,
+                    trade.price,
+                    trade.quantity,
+                    trade.strategy,
+                    trade.pnl,
+                    trade.strategy_id,
+                ])?;
+            }
+
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO backtest_equity (run_id, timestamp, equity) 
+                 VALUES (?1, ?2, ?3)",
+            )?;
+            for (timestamp, equity) in equity_curve {
+                stmt.execute(params![run_id, timestamp.timestamp_millis(), equity])?;
+            }
+        }
+
+        tx.commit()?;
+        info!(
+            "保存回测记录#{}: {}笔交易, {}个权益点",
+            run_id,
+            trades.len(),
+            equity_curve.len()
+        );
+
+        Ok(run_id)
+    }
+
+    /// 获取最近的回测记录
+    async fn get_backtest_runs(&self, limit: usize) -> Result<Vec<BacktestRun>> {
+        let conn = self.conn.lock().await;
+
+        let query = format!(
+            "SELECT {} FROM backtest_runs ORDER BY id DESC LIMIT ?1",
+            BACKTEST_RUN_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let run_iter = stmt.query_map(params![limit], row_to_backtest_run)?;
+
+        let mut runs = Vec::new();
+        for run in run_iter {
+            runs.push(run?);
+        }
+
+        Ok(runs)
+    }
+
+    /// 根据ID获取回测记录
+    async fn get_backtest_run(&self, run_id: i64) -> Result<Option<BacktestRun>> {
+        let conn = self.conn.lock().await;
+
+        let query = format!(
+            "SELECT {} FROM backtest_runs WHERE id = ?1",
+            BACKTEST_RUN_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query(params![run_id])?;
+
+        match rows.next()? {
+            Some(row) => Ok(Some(row_to_backtest_run(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 获取回测记录的交易列表
+    async fn get_backtest_trades(&self, run_id: i64) -> Result<Vec<Trade>> {
+        let conn = self.conn.lock().await;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, timestamp, symbol, side, price, quantity, strategy, pnl, strategy_id 
+             FROM backtest_trades 
+             WHERE run_id = ?1 
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+
+        let trade_iter = stmt.query_map(params![run_id], |row| {
+            let timestamp_ms: i64 = row.get(1)?;
+            let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+
+            Ok(Trade {
+                id: Some(row.get(0)?),
+                timestamp,
+                symbol: row.get(2)?,
+                side: row.get(3)?,
+                price: row.get(4)?,
+                quantity: row.get(5)?,
+                strategy: row.get(6)?,
+                strategy_id: row.get(8)?,
+                pnl: row.get(7)?,
+                signal_id: None,
+            })
+        })?;
+
         let mut trades = Vec::new();
         for trade in trade_iter {
             trades.push(trade?);
@@ -396,28 +1660,321 @@ impl Database {
         Ok(trades)
     }
 
-    /// 获取数据统计信息
-    pub async fn get_stats(&self) -> Result<()> {
+    /// 获取回测记录的权益曲线
+    async fn get_backtest_equity(&self, run_id: i64) -> Result<Vec<(DateTime<Utc>, f64)>> {
         let conn = self.conn.lock().await;
 
-        let candle_count: i64 =
-            conn.query_row("SELECT COUNT(*) FROM candles", [], |row| row.get(0))?;
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, equity 
+             FROM backtest_equity 
+             WHERE run_id = ?1 
+             ORDER BY timestamp ASC",
+        )?;
 
-        let ticker_count: i64 =
-            conn.query_row("SELECT COUNT(*) FROM tickers", [], |row| row.get(0))?;
+        let point_iter = stmt.query_map(params![run_id], |row| {
+            let timestamp_ms: i64 = row.get(0)?;
+            let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+            Ok((timestamp, row.get(1)?))
+        })?;
 
-        let signal_count: i64 =
-            conn.query_row("SELECT COUNT(*) FROM signals", [], |row| row.get(0))?;
+        let mut points = Vec::new();
+        for point in point_iter {
+            points.push(point?);
+        }
 
-        let trade_count: i64 =
-            conn.query_row("SELECT COUNT(*) FROM trades", [], |row| row.get(0))?;
+        Ok(points)
+    }
+}
 
-        info!("数据库统计:");
-        info!("  K线数据: {} 条", candle_count);
-        info!("  Ticker数据: {} 条", ticker_count);
-        info!("  交易信号: {} 条", signal_count);
-        info!("  交易记录: {} 条", trade_count);
+/// 在同一事务中覆盖写入K线，并累计该批次的重复与乱序计数
+fn write_candles(conn: &mut Connection, candles: &[CandleData]) -> Result<()> {
+    let tx = conn.transaction()?;
 
-        Ok(())
+    let mut duplicates: HashMap<String, usize> = HashMap::new();
+    {
+        let mut latest_stmt = tx.prepare("SELECT MAX(timestamp) FROM candles WHERE symbol = ?1")?;
+        let mut existing_stmt = tx.prepare(
+            "SELECT timestamp, symbol, open, high, low, close, volume 
+             FROM candles WHERE symbol = ?1 AND timestamp = ?2",
+        )?;
+        let mut insert_stmt = tx.prepare(
+            "INSERT OR REPLACE INTO candles 
+             (timestamp, symbol, open, high, low, close, volume) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+
+        let mut latest_before: HashMap<String, Option<i64>> = HashMap::new();
+        for candle in candles {
+            let latest = match latest_before.get(&candle.symbol) {
+                Some(latest) => *latest,
+                None => {
+                    let latest: Option<i64> =
+                        latest_stmt.query_row(params![candle.symbol], |row| row.get(0))?;
+                    latest_before.insert(candle.symbol.clone(), latest);
+                    latest
+                }
+            };
+
+            let ts = candle.timestamp.timestamp_millis();
+            let existing = existing_stmt
+                .query_row(params![candle.symbol, ts], row_to_candle)
+                .optional()?;
+            if existing.is_some_and(|existing| is_conflicting_rewrite(&existing, candle, latest)) {
+                *duplicates.entry(candle.symbol.clone()).or_insert(0) += 1;
+            }
+
+            insert_stmt.execute(params![
+                ts,
+                candle.symbol,
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                candle.volume,
+            ])?;
+        }
+    }
+
+    let out_of_order = count_out_of_order(candles);
+    for (symbol, &out_of_order_count) in &out_of_order {
+        let duplicate_count = duplicates.remove(symbol).unwrap_or(0);
+        if duplicate_count == 0 && out_of_order_count == 0 {
+            continue;
+        }
+        tx.execute(
+            "INSERT INTO candle_ingest_stats (symbol, duplicate_count, out_of_order_count) 
+             VALUES (?1, ?2, ?3) 
+             ON CONFLICT(symbol) DO UPDATE SET 
+             duplicate_count = duplicate_count + excluded.duplicate_count, 
+             out_of_order_count = out_of_order_count + excluded.out_of_order_count",
+            params![symbol, duplicate_count, out_of_order_count],
+        )?;
     }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// 将candles表的一行转换为K线 (列顺序为 timestamp, symbol, open, high, low, close, volume)
+fn row_to_candle(row: &Row) -> rusqlite::Result<CandleData> {
+    let timestamp_ms: i64 = row.get(0)?;
+
+    Ok(CandleData {
+        timestamp: DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default(),
+        symbol: row.get(1)?,
+        open: row.get(2)?,
+        high: row.get(3)?,
+        low: row.get(4)?,
+        close: row.get(5)?,
+        volume: row.get(6)?,
+    })
+}
+
+/// 插入一条交易记录，返回交易ID
+fn insert_trade(conn: &Connection, trade: &Trade) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO trades 
+         (timestamp, symbol, side, price, quantity, strategy, pnl, signal_id, strategy_id) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            trade.timestamp.timestamp_millis(),
+            trade.symbol,
+            trade.side,
+            trade.price,
+            trade.quantity,
+            trade.strategy,
+            trade.pnl,
+            trade.signal_id,
+            trade.strategy_id,
+        ],
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// 覆盖写入余额与全部持仓 (由调用方提供事务)
+fn write_account_state(conn: &Connection, state: &AccountState) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO account_state (id, balance, updated_at) 
+         VALUES (1, ?1, ?2)",
+        params![state.balance, state.updated_at.timestamp_millis()],
+    )?;
+
+    conn.execute("DELETE FROM positions", [])?;
+    conn.execute("DELETE FROM position_units", [])?;
+    conn.execute("DELETE FROM paired_position_legs", [])?;
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO positions 
+         (strategy_id, symbol, quantity, avg_price, current_price, unrealized_pnl, 
+          timestamp) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+
+    for position in &state.positions {
+        stmt.execute(params![
+            position.strategy_id,
+            position.symbol,
+            position.quantity,
+            position.avg_price,
+            position.current_price,
+            position.unrealized_pnl,
+            position.timestamp.timestamp_millis(),
+        ])?;
+    }
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO position_units 
+         (strategy_id, symbol, unit_index, entry_price, quantity, stop_price, timestamp) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+
+    for position in &state.positions {
+        for (index, unit) in position.units.iter().enumerate() {
+            stmt.execute(params![
+                position.strategy_id,
+                position.symbol,
+                index as i64,
+                unit.entry_price,
+                unit.quantity,
+                unit.stop_price,
+                unit.timestamp.timestamp_millis(),
+            ])?;
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO paired_position_legs 
+         (strategy_id, leg_index, symbol, quantity, entry_price, current_price, timestamp, margin) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+
+    for position in &state.paired_positions {
+        for (index, leg) in position.legs.iter().enumerate() {
+            stmt.execute(params![
+                position.strategy_id,
+                index as i64,
+                leg.symbol,
+                leg.quantity,
+                leg.entry_price,
+                leg.current_price,
+                position.timestamp.timestamp_millis(),
+                leg.margin,
+            ])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 将trades表的一行转换为交易记录 (列顺序与TRADE_COLUMNS一致)
+fn row_to_trade(row: &Row) -> rusqlite::Result<Trade> {
+    let timestamp_ms: i64 = row.get(1)?;
+    let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+
+    Ok(Trade {
+        id: Some(row.get(0)?),
+        timestamp,
+        symbol: row.get(2)?,
+        side: row.get(3)?,
+        price: row.get(4)?,
+        quantity: row.get(5)?,
+        strategy: row.get(6)?,
+        strategy_id: row.get(9)?,
+        pnl: row.get(7)?,
+        signal_id: row.get(8)?,
+    })
+}
+
+/// 表中是否存在该列
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let mut columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+
+    Ok(columns.any(|name| name.as_ref().is_ok_and(|name| name == column)))
+}
+
+/// 表中不存在该列时追加 (用于旧版本数据库的结构升级)
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    if has_column(conn, table, column)? {
+        return Ok(());
+    }
+
+    conn.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        [],
+    )?;
+    info!("数据表{}新增列: {}", table, column);
+
+    Ok(())
+}
+
+/// 将orders表的一行转换为订单 (列顺序与ORDER_COLUMNS一致)
+fn row_to_order(row: &Row) -> rusqlite::Result<Order> {
+    let order_type: String = row.get(5)?;
+    let state: String = row.get(8)?;
+    let created_ms: i64 = row.get(13)?;
+    let updated_ms: i64 = row.get(14)?;
+
+    Ok(Order {
+        id: Some(row.get(0)?),
+        client_order_id: row.get(1)?,
+        exchange_order_id: row.get(2)?,
+        symbol: row.get(3)?,
+        side: row.get(4)?,
+        order_type: order_type.parse().map_err(|e: anyhow::Error| {
+            rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, e.into())
+        })?,
+        price: row.get(6)?,
+        size: row.get(7)?,
+        state: state.parse().map_err(|e: anyhow::Error| {
+            rusqlite::Error::FromSqlConversionFailure(8, rusqlite::types::Type::Text, e.into())
+        })?,
+        filled_size: row.get(9)?,
+        avg_fill_price: row.get(10)?,
+        fee: row.get(11)?,
+        strategy: row.get(12)?,
+        created_at: DateTime::from_timestamp_millis(created_ms).unwrap_or_default(),
+        updated_at: DateTime::from_timestamp_millis(updated_ms).unwrap_or_default(),
+        signal_id: row.get(15)?,
+    })
+}
+
+/// 将backtest_runs表的一行转换为回测记录 (列顺序与BACKTEST_RUN_COLUMNS一致)
+fn row_to_backtest_run(row: &Row) -> rusqlite::Result<BacktestRun> {
+    let params_json: String = row.get(2)?;
+    let params = serde_json::from_str(&params_json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, e.into())
+    })?;
+
+    let to_datetime = |ms: i64| DateTime::from_timestamp_millis(ms).unwrap_or_default();
+
+    Ok(BacktestRun {
+        id: Some(row.get(0)?),
+        symbol: row.get(1)?,
+        strategy: row.get(18)?,
+        params,
+        candle_count: row.get(3)?,
+        data_fingerprint: row.get(4)?,
+        code_version: row.get(5)?,
+        created_at: to_datetime(row.get(6)?),
+        report: BacktestReport {
+            start_time: to_datetime(row.get(7)?),
+            end_time: to_datetime(row.get(8)?),
+            initial_balance: row.get(9)?,
+            final_balance: row.get(10)?,
+            total_return: row.get(11)?,
+            return_rate: row.get(12)?,
+            max_drawdown: row.get(13)?,
+            total_trades: row.get(14)?,
+            win_rate: row.get(15)?,
+            avg_return: row.get(16)?,
+            sharpe_ratio: row.get(17)?,
+        },
+    })
 }
@@ -1,28 +1,57 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::DateTime;
 use futures_util::{SinkExt, StreamExt};
 use log::{error, info, warn};
 use serde_json::{Value, json};
+use std::collections::BTreeSet;
 use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, watch};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use url::Url;
 
-use crate::db::Database;
-use crate::types::{CandleData, TickerData, WsMessage};
+use crate::storage::Storage;
+use crate::types::{CandleData, FundingRate, InstrumentRules, TickerData, WsMessage};
+
+/// 资金费率回填请求失败后的重试间隔
+const FUNDING_RETRY_DELAY: tokio::time::Duration = tokio::time::Duration::from_secs(10);
+
+/// 资金费率回填每页的最大尝试次数，超过后放弃本次回填，下一分钟重新回填
+const FUNDING_MAX_ATTEMPTS: u32 = 3;
+
+/// 订阅ticker的交易对: SOL-USDT 供策略交易，其余供三角套利检测
+const TICKER_SYMBOLS: &[&str] = &["SOL-USDT", "BTC-USDT", "ETH-BTC", "ETH-USDT"];
 
 /// 数据处理器
 pub struct DataHandler {
-    db: Arc<Database>,                           // 数据库实例
-    price_sender: broadcast::Sender<TickerData>, // 价格数据广播
+    db: Arc<dyn Storage>,                             // 存储实例
+    price_sender: broadcast::Sender<TickerData>,      // 价格数据广播
+    candle_symbols: watch::Sender<BTreeSet<String>>,  // 需要采集K线的交易对
+    funding_symbols: watch::Sender<BTreeSet<String>>, // 需要采集资金费率的永续合约
 }
 
 impl DataHandler {
     /// 创建新的数据处理器
-    pub fn new(db: Arc<Database>) -> Self {
+    pub fn new(db: Arc<dyn Storage>) -> Self {
         let (price_sender, _) = broadcast::channel(1000);
+        let (candle_symbols, _) = watch::channel(BTreeSet::new());
+        let (funding_symbols, _) = watch::channel(BTreeSet::new());
+
+        Self {
+            db,
+            price_sender,
+            candle_symbols,
+            funding_symbols,
+        }
+    }
+
+    /// 设置需要采集K线的交易对 (取自已注册策略实例交易的全部交易对)，新增的交易对在下一分钟开始回填
+    pub fn set_candle_symbols(&self, symbols: BTreeSet<String>) {
+        self.candle_symbols.send_replace(symbols);
+    }
 
-        Self { db, price_sender }
+    /// 设置需要采集资金费率的永续合约 (取自已注册策略实例的永续腿)，新增的交易对在下一分钟开始回填
+    pub fn set_funding_symbols(&self, symbols: BTreeSet<String>) {
+        self.funding_symbols.send_replace(symbols);
     }
 
     /// 开始数据收集
@@ -43,15 +72,27 @@ impl DataHandler {
         // 启动REST API历史数据收集
         let rest_task = {
             let db = self.db.clone();
+            let symbols = self.candle_symbols.subscribe();
             tokio::spawn(async move {
-                if let Err(e) = collect_historical_data(db).await {
+                if let Err(e) = collect_historical_data(db, symbols).await {
                     error!("历史数据收集错误: {}", e);
                 }
             })
         };
 
+        // 启动永续合约资金费率收集
+        let funding_task = {
+            let db = self.db.clone();
+            let symbols = self.funding_symbols.subscribe();
+            tokio::spawn(async move {
+                if let Err(e) = collect_funding_data(db, symbols).await {
+                    error!("资金费率收集错误: {}", e);
+                }
+            })
+        };
+
         // 等待任务完成
-        tokio::try_join!(ws_task, rest_task)?;
+        tokio::try_join!(ws_task, rest_task, funding_task)?;
 
         Ok(())
     }
@@ -64,7 +105,7 @@ impl DataHandler {
 
 /// 通过WebSocket收集实时数据
 async fn collect_websocket_data(
-    db: Arc<Database>,
+    db: Arc<dyn Storage>,
     price_sender: broadcast::Sender<TickerData>,
 ) -> Result<()> {
     let ws_url = "wss://ws.okx.com:8443/ws/v5/public";
@@ -74,26 +115,27 @@ async fn collect_websocket_data(
     let (ws_stream, _) = connect_async(url).await?;
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
-    // 订阅SOL-USDT ticker数据
+    // 订阅ticker数据
+    let args: Vec<Value> = TICKER_SYMBOLS
+        .iter()
+        .map(|symbol| json!({ "channel": "tickers", "instId": symbol }))
+        .collect();
     let subscribe_msg = json!({
         "op": "subscribe",
-        "args": [{
-            "channel": "tickers",
-            "instId": "SOL-USDT"
-        }]
+        "args": args
     });
 
     ws_sender
         .send(Message::Text(subscribe_msg.to_string()))
         .await?;
 
-    info!("已订阅SOL-USDT ticker数据");
+    info!("已订阅 {} ticker数据", TICKER_SYMBOLS.join(", "));
 
     // 处理接收到的消息
     while let Some(msg) = ws_receiver.next().await {
         match msg {
             Ok(Message::Text(text)) => {
-                if let Err(e) = process_ws_message(&text, &db, &price_sender).await {
+                if let Err(e) = process_ws_message(&text, db.as_ref(), &price_sender).await {
                     warn!("处理WebSocket消息失败: {}", e);
                 }
             }
@@ -122,25 +164,25 @@ async fn collect_websocket_data(
 /// 处理WebSocket消息
 async fn process_ws_message(
     text: &str,
-    db: &Database,
+    db: &dyn Storage,
     price_sender: &broadcast::Sender<TickerData>,
 ) -> Result<()> {
     // 解析消息
     let value: Value = serde_json::from_str(text)?;
 
     // 检查是否包含数据
-    if let Some(data_array) = value.get("data") {
-        if let Ok(ws_msg) = serde_json::from_value::<WsMessage>(value) {
-            for ticker in ws_msg.data {
-                // 保存到数据库
-                if let Err(e) = db.save_ticker(&ticker).await {
-                    warn!("保存ticker数据失败: {}", e);
-                }
+    if value.get("data").is_some()
+        && let Ok(ws_msg) = serde_json::from_value::<WsMessage>(value)
+    {
+        for ticker in ws_msg.data {
+            // 保存到数据库
+            if let Err(e) = db.save_ticker(&ticker).await {
+                warn!("保存ticker数据失败: {}", e);
+            }
 
-                // 广播价格更新
-                if price_sender.send(ticker).is_err() {
-                    warn!("广播价格更新失败，可能没有订阅者");
-                }
+            // 广播价格更新
+            if price_sender.send(ticker).is_err() {
+                warn!("广播价格更新失败，可能没有订阅者");
             }
         }
     }
@@ -148,11 +190,42 @@ async fn process_ws_message(
     Ok(())
 }
 
-/// 收集历史K线数据
-async fn collect_historical_data(db: Arc<Database>) -> Result<()> {
+/// 收集已注册策略所需交易对的历史K线数据
+///
+/// 每分钟读取最新的交易对列表，首次出现的交易对先分页回填历史K线，之后与其余交易对一起增量更新；
+/// 回填失败的交易对在下一分钟重新回填。
+async fn collect_historical_data(
+    db: Arc<dyn Storage>,
+    symbols: watch::Receiver<BTreeSet<String>>,
+) -> Result<()> {
     let client = reqwest::Client::new();
-    let symbol = "SOL-USDT";
+    let mut backfilled: BTreeSet<String> = BTreeSet::new();
+
+    // 每分钟增量更新，带冗余覆盖（取最近300条）
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+    loop {
+        interval.tick().await;
 
+        let current = symbols.borrow().clone();
+        for symbol in &current {
+            if !backfilled.contains(symbol) {
+                if let Err(e) = backfill_candles(db.as_ref(), &client, symbol).await {
+                    warn!("{}历史K线回填失败，下一分钟重试: {}", symbol, e);
+                    continue;
+                }
+                backfilled.insert(symbol.clone());
+            }
+
+            info!("定时更新{}历史数据（每分钟）", symbol);
+            if let Err(e) = update_recent_candles(db.as_ref(), &client, symbol).await {
+                warn!("定时更新失败: {}", e);
+            }
+        }
+    }
+}
+
+/// 分页回填交易对最近的历史K线
+async fn backfill_candles(db: &dyn Storage, client: &reqwest::Client, symbol: &str) -> Result<()> {
     info!("开始收集{}的历史K线数据", symbol);
 
     // 分页回填：目标回填数量与时间范围可按需调整
@@ -163,7 +236,7 @@ async fn collect_historical_data(db: Arc<Database>) -> Result<()> {
     let mut before: Option<i64> = None; // 毫秒时间戳，OKX 使用 before 游标
 
     loop {
-        let page = fetch_candles_page(&client, symbol, page_limit, before).await?;
+        let page = fetch_candles_page(client, symbol, page_limit, before).await?;
         if page.is_empty() {
             info!("历史回填结束，未返回更多数据，累计{}条", total_collected);
             break;
@@ -188,18 +261,8 @@ async fn collect_historical_data(db: Arc<Database>) -> Result<()> {
         tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
     }
 
-    info!("历史数据回填完成，共{}条记录", total_collected);
-
-    // 改为每分钟增量更新，带冗余覆盖（取最近300条）
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
-    loop {
-        interval.tick().await;
-
-        info!("定时更新历史数据（每分钟）");
-        if let Err(e) = update_recent_candles(&db, &client, symbol).await {
-            warn!("定时更新失败: {}", e);
-        }
-    }
+    info!("{}历史数据回填完成，共{}条记录", symbol, total_collected);
+    Ok(())
 }
 
 /// 拉取一页 OKX 1m K线
@@ -232,23 +295,23 @@ async fn fetch_candles_page(
     let mut candles = Vec::new();
 
     for candle_data in data {
-        if let Some(candle_array) = candle_data.as_array() {
-            if candle_array.len() >= 6 {
-                let timestamp_ms: i64 = candle_array[0].as_str().unwrap_or("0").parse().unwrap_or(0);
-                let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
-
-                let candle = CandleData {
-                    timestamp,
-                    symbol: symbol.to_string(),
-                    open: candle_array[1].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                    high: candle_array[2].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                    low: candle_array[3].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                    close: candle_array[4].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                    volume: candle_array[5].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                };
-
-                candles.push(candle);
-            }
+        if let Some(candle_array) = candle_data.as_array()
+            && candle_array.len() >= 6
+        {
+            let timestamp_ms: i64 = candle_array[0].as_str().unwrap_or("0").parse().unwrap_or(0);
+            let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+
+            let candle = CandleData {
+                timestamp,
+                symbol: symbol.to_string(),
+                open: candle_array[1].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+                high: candle_array[2].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+                low: candle_array[3].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+                close: candle_array[4].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+                volume: candle_array[5].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+            };
+
+            candles.push(candle);
         }
     }
 
@@ -257,7 +320,7 @@ async fn fetch_candles_page(
 
 /// 更新最近的K线数据（带冗余覆盖）
 async fn update_recent_candles(
-    db: &Database,
+    db: &dyn Storage,
     client: &reqwest::Client,
     symbol: &str,
 ) -> Result<()> {
@@ -279,23 +342,23 @@ async fn update_recent_candles(
     let mut candles = Vec::new();
 
     for candle_data in data {
-        if let Some(candle_array) = candle_data.as_array() {
-            if candle_array.len() >= 6 {
-                let timestamp_ms: i64 = candle_array[0].as_str().unwrap_or("0").parse().unwrap_or(0);
-                let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
-
-                let candle = CandleData {
-                    timestamp,
-                    symbol: symbol.to_string(),
-                    open: candle_array[1].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                    high: candle_array[2].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                    low: candle_array[3].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                    close: candle_array[4].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                    volume: candle_array[5].as_str().unwrap_or("0").parse().unwrap_or(0.0),
-                };
-
-                candles.push(candle);
-            }
+        if let Some(candle_array) = candle_data.as_array()
+            && candle_array.len() >= 6
+        {
+            let timestamp_ms: i64 = candle_array[0].as_str().unwrap_or("0").parse().unwrap_or(0);
+            let timestamp = DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default();
+
+            let candle = CandleData {
+                timestamp,
+                symbol: symbol.to_string(),
+                open: candle_array[1].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+                high: candle_array[2].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+                low: candle_array[3].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+                close: candle_array[4].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+                volume: candle_array[5].as_str().unwrap_or("0").parse().unwrap_or(0.0),
+            };
+
+            candles.push(candle);
         }
     }
 
@@ -306,3 +369,180 @@ async fn update_recent_candles(
 
     Ok(())
 }
+
+/// 收集已注册策略所需永续合约的资金费率历史 (永续K线作为策略的一条腿由 `collect_historical_data` 采集)
+///
+/// 每分钟读取最新的永续合约列表，首次出现的交易对先回填历史费率，之后与其余交易对一起增量更新；
+/// 回填失败的交易对在下一分钟重新回填。
+async fn collect_funding_data(
+    db: Arc<dyn Storage>,
+    symbols: watch::Receiver<BTreeSet<String>>,
+) -> Result<()> {
+    let client = reqwest::Client::new();
+    let mut backfilled: BTreeSet<String> = BTreeSet::new();
+
+    // 每分钟更新最近的资金费率，结算后的费率通常在结算时间后数分钟内可查
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+
+        let current = symbols.borrow().clone();
+        for symbol in &current {
+            if !backfilled.contains(symbol) {
+                if let Err(e) = backfill_funding_rates(db.as_ref(), &client, symbol).await {
+                    warn!("{}资金费率回填失败，下一分钟重试: {}", symbol, e);
+                    continue;
+                }
+                backfilled.insert(symbol.clone());
+            }
+
+            match fetch_funding_rate_page(&client, symbol, None).await {
+                Ok(page) => {
+                    if let Err(e) = db.save_funding_rates(&page).await {
+                        warn!("保存{}资金费率失败: {}", symbol, e);
+                    }
+                }
+                Err(e) => warn!("{}资金费率更新失败: {}", symbol, e),
+            }
+        }
+    }
+}
+
+/// 分页回填永续合约的资金费率历史
+///
+/// 请求或保存失败时记录日志并从同一页重试，同一页连续失败 `FUNDING_MAX_ATTEMPTS` 次后返回错误。
+async fn backfill_funding_rates(
+    db: &dyn Storage,
+    client: &reqwest::Client,
+    symbol: &str,
+) -> Result<()> {
+    info!("开始收集{}的资金费率历史", symbol);
+
+    // 分页回填：OKX 只提供最近约3个月的资金费率历史，after 游标返回更早的记录
+    let mut total_collected: usize = 0;
+    let mut after: Option<i64> = None;
+    let mut attempts: u32 = 0;
+    loop {
+        let result = match fetch_funding_rate_page(client, symbol, after).await {
+            Ok(page) if page.is_empty() => break,
+            Ok(page) => db.save_funding_rates(&page).await.map(|_| page),
+            Err(e) => Err(e),
+        };
+        let page = match result {
+            Ok(page) => page,
+            Err(e) => {
+                attempts += 1;
+                if attempts >= FUNDING_MAX_ATTEMPTS {
+                    return Err(e);
+                }
+                warn!("{}资金费率回填第{}次失败，稍后重试: {}", symbol, attempts, e);
+                tokio::time::sleep(FUNDING_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        attempts = 0;
+        total_collected += page.len();
+        after = page.iter().map(|r| r.funding_time.timestamp_millis()).min();
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    }
+
+    info!("{}资金费率回填完成，共{}条记录", symbol, total_collected);
+    Ok(())
+}
+
+/// 拉取一页 OKX 已结算的资金费率 (最多100条，按结算时间倒序)
+async fn fetch_funding_rate_page(
+    client: &reqwest::Client,
+    symbol: &str,
+    after: Option<i64>,
+) -> Result<Vec<FundingRate>> {
+    // OKX 文档：/api/v5/public/funding-rate-history?instId=...&limit=...&after=...
+    let mut url = format!(
+        "https://www.okx.com/api/v5/public/funding-rate-history?instId={}&limit=100",
+        symbol
+    );
+    if let Some(ts) = after {
+        url.push_str(&format!("&after={}", ts));
+    }
+
+    let response = client.get(&url).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("API请求失败: {}", response.status()));
+    }
+
+    let json_response: Value = response.json().await?;
+    if json_response["code"] != "0" {
+        return Err(anyhow::anyhow!("API返回错误: {}", json_response["msg"]));
+    }
+
+    let data = json_response["data"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("无效的API响应格式"))?;
+    let mut rates = Vec::new();
+
+    for item in data {
+        let funding_ms: i64 = item["fundingTime"]
+            .as_str()
+            .unwrap_or("0")
+            .parse()
+            .unwrap_or(0);
+        // realizedRate 为实际结算费率，缺失时使用 fundingRate
+        let rate = item["realizedRate"]
+            .as_str()
+            .filter(|r| !r.is_empty())
+            .or_else(|| item["fundingRate"].as_str())
+            .and_then(|r| r.parse::<f64>().ok());
+
+        if let Some(funding_rate) = rate
+            && funding_ms > 0
+        {
+            rates.push(FundingRate {
+                symbol: symbol.to_string(),
+                funding_time: DateTime::from_timestamp_millis(funding_ms).unwrap_or_default(),
+                funding_rate,
+            });
+        }
+    }
+
+    Ok(rates)
+}
+
+/// 拉取现货交易对的下单规则 (数量精度与最小下单量)
+pub async fn fetch_instrument_rules(symbols: &[String]) -> Result<Vec<InstrumentRules>> {
+    let client = reqwest::Client::new();
+    let mut instruments = Vec::new();
+
+    for symbol in symbols {
+        // OKX 文档：/api/v5/public/instruments?instType=SPOT&instId=...
+        let url = format!(
+            "https://www.okx.com/api/v5/public/instruments?instType=SPOT&instId={}",
+            symbol
+        );
+
+        let response = client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("API请求失败: {}", response.status()));
+        }
+
+        let json_response: Value = response.json().await?;
+        if json_response["code"] != "0" {
+            return Err(anyhow::anyhow!("API返回错误: {}", json_response["msg"]));
+        }
+
+        let item = json_response["data"]
+            .as_array()
+            .and_then(|data| data.first())
+            .ok_or_else(|| anyhow::anyhow!("未找到交易对 {} 的下单规则", symbol))?;
+        let field = |name: &str| item[name].as_str().unwrap_or("").to_string();
+        instruments.push(InstrumentRules {
+            symbol: symbol.clone(),
+            base_currency: field("baseCcy"),
+            quote_currency: field("quoteCcy"),
+            lot_size: field("lotSz").parse().unwrap_or(0.0),
+            min_size: field("minSz").parse().unwrap_or(0.0),
+        });
+    }
+
+    Ok(instruments)
+}
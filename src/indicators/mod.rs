@@ -0,0 +1,266 @@
+//! 技术指标库
+//!
+//! 每个指标都是保存最小状态的增量实现，每根K线更新一次，复杂度为O(1) (滚动极值为均摊O(1))，
+//! 不需要每次从头扫描历史数据；启动时可用 `warm_up` 以历史数据预热。
+//! 同名的小写函数 (如 `rsi`) 在整段K线上批量计算，返回与K线一一对应的指标值，
+//! 内部复用增量实现，因此批量与增量的结果一致。
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::CandleData;
+
+mod momentum;
+mod moving_average;
+mod rolling;
+mod trend;
+mod volatility;
+mod volume;
+
+pub use momentum::{Macd, MacdValue, Rsi, Stochastic, StochasticValue, macd, rsi, stochastic};
+pub use moving_average::{Ema, MaType, MovingAverage, Sma, Wma, ema, sma, wma};
+pub use rolling::{Donchian, RollingMax, RollingMin, donchian};
+pub use trend::{Adx, AdxValue, ParabolicSar, adx, parabolic_sar};
+pub use volatility::{Atr, AtrSmoothing, Bollinger, Keltner, TrueRange, atr, bollinger, keltner};
+pub use volume::{Obv, Vwap, obv, vwap};
+
+/// 增量指标接口
+pub trait Indicator<I> {
+    /// 指标输出
+    type Output: Copy;
+
+    /// 输入一个新数据，返回更新后的指标值 (数据不足时为None)
+    fn update(&mut self, input: &I) -> Option<Self::Output>;
+
+    /// 当前指标值 (数据不足时为None)
+    fn value(&self) -> Option<Self::Output>;
+
+    /// 清空状态，回到未输入任何数据时的状态
+    fn reset(&mut self);
+
+    /// 依次输入历史数据预热，返回最后的指标值
+    fn warm_up(&mut self, inputs: &[I]) -> Option<Self::Output> {
+        inputs.iter().fold(None, |_, input| self.update(input))
+    }
+
+    /// 批量计算，返回与输入一一对应的指标值
+    fn batch(mut self, inputs: &[I]) -> Vec<Option<Self::Output>>
+    where
+        Self: Sized,
+    {
+        inputs.iter().map(|input| self.update(input)).collect()
+    }
+}
+
+/// 通道类指标的上中下轨 (布林带、唐奇安通道、肯特纳通道)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bands {
+    pub upper: f64,  // 上轨
+    pub middle: f64, // 中轨
+    pub lower: f64,  // 下轨
+}
+
+/// K线收盘价序列
+fn closes(candles: &[CandleData]) -> Vec<f64> {
+    candles.iter().map(|c| c.close).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    /// 40根逐分钟K线: 收盘价为正弦波叠加上升趋势，最高/最低价按不同周期偏离收盘价
+    fn sample_candles() -> Vec<CandleData> {
+        let base_time = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        let mut candles: Vec<CandleData> = Vec::new();
+        for i in 0..40 {
+            let x = i as f64;
+            let close = 100.0 + 5.0 * (x / 3.0).sin() + x * 0.3;
+            candles.push(CandleData {
+                timestamp: base_time + chrono::Duration::minutes(i),
+                symbol: "SOL-USDT".to_string(),
+                open: candles.last().map_or(close, |c| c.close),
+                high: close + 1.0 + (i % 3) as f64 * 0.5,
+                low: close - 1.0 - (i % 4) as f64 * 0.25,
+                close,
+                volume: 1000.0 + (i % 5) as f64 * 100.0,
+            });
+        }
+        candles
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "实际值{}，期望值{}",
+            actual,
+            expected
+        );
+    }
+
+    fn assert_bands(actual: Option<Bands>, expected: (f64, f64, f64)) {
+        let bands = actual.expect("指标应有值");
+        assert_close(bands.upper, expected.0);
+        assert_close(bands.middle, expected.1);
+        assert_close(bands.lower, expected.2);
+    }
+
+    /// 第一个有值的位置
+    fn first_value<T>(values: &[Option<T>]) -> Option<usize> {
+        values.iter().position(Option::is_some)
+    }
+
+    // 期望值由按定义逐窗口计算的独立参考实现得出
+
+    #[test]
+    fn test_moving_averages_match_reference() {
+        let candles = sample_candles();
+
+        let sma = sma(&candles, 5);
+        assert_eq!(first_value(&sma), Some(4));
+        assert_close(sma[25].unwrap(), 111.28364361004205);
+        assert_close(sma[39].unwrap(), 110.06964549223503);
+
+        let ema = ema(&candles, 5);
+        assert_eq!(first_value(&ema), Some(4));
+        assert_close(ema[25].unwrap(), 110.77463187310858);
+        assert_close(ema[39].unwrap(), 110.7163785376218);
+
+        let wma = wma(&candles, 5);
+        assert_eq!(first_value(&wma), Some(4));
+        assert_close(wma[25].unwrap(), 111.6778024086275);
+        assert_close(wma[39].unwrap(), 111.28408571545951);
+    }
+
+    #[test]
+    fn test_momentum_indicators_match_reference() {
+        let candles = sample_candles();
+
+        let rsi = rsi(&candles, 14);
+        assert_eq!(first_value(&rsi), Some(14));
+        assert_close(rsi[25].unwrap(), 77.05281707140459);
+        assert_close(rsi[39].unwrap(), 72.12878797708959);
+
+        let macd = macd(&candles, 5, 10, 4);
+        assert_eq!(first_value(&macd), Some(12));
+        let last = macd[39].unwrap();
+        assert_close(last.macd, 1.4542935441231037);
+        assert_close(last.signal, 0.7634210830687527);
+        assert_close(last.histogram, 0.6908724610543511);
+        assert_close(macd[25].unwrap().histogram, 0.09218285816458627);
+
+        let stochastic = stochastic(&candles, 5, 3);
+        assert_eq!(first_value(&stochastic), Some(6));
+        let last = stochastic[39].unwrap();
+        assert_close(last.k, 89.96643714560584);
+        assert_close(last.d, 83.51965133812814);
+        assert_close(stochastic[25].unwrap().d, 73.64890540131178);
+    }
+
+    #[test]
+    fn test_channels_match_reference() {
+        let candles = sample_candles();
+
+        let bollinger = bollinger(&candles, 10, 2.0);
+        assert_eq!(first_value(&bollinger), Some(9));
+        assert_bands(
+            bollinger[25],
+            (115.82054938866561, 107.71848935949228, 99.61642933031895),
+        );
+        assert_bands(
+            bollinger[39],
+            (113.7103535361863, 107.72356125435905, 101.7367689725318),
+        );
+
+        let donchian = donchian(&candles, 10);
+        assert_eq!(first_value(&donchian), Some(9));
+        assert_bands(
+            donchian[39],
+            (114.8008351841332, 109.20387688650854, 103.60691858888387),
+        );
+
+        let keltner = keltner(&candles, 10, 5, 2.0);
+        assert_eq!(first_value(&keltner), Some(9));
+        assert_bands(
+            keltner[25],
+            (114.4495877790723, 108.57997660485816, 102.71036543064402),
+        );
+        assert_bands(
+            keltner[39],
+            (115.55638647816684, 109.26208499349869, 102.96778350883054),
+        );
+
+        let atr = atr(&candles, 5, AtrSmoothing::Wilder);
+        assert_eq!(first_value(&atr), Some(5));
+        assert_close(atr[39].unwrap(), 3.1471507423340794);
+    }
+
+    #[test]
+    fn test_volume_indicators_match_reference() {
+        let candles = sample_candles();
+
+        let vwap = vwap(&candles);
+        assert_close(vwap[25].unwrap(), 104.75041195437795);
+        assert_close(vwap[39].unwrap(), 106.01388293836817);
+
+        let obv = obv(&candles);
+        assert_eq!(obv[0], Some(0.0));
+        assert_eq!(obv[25], Some(6000.0));
+        assert_eq!(obv[39], Some(6400.0));
+
+        // 跨UTC日后VWAP重新累计
+        let mut next_day = candles[0].clone();
+        next_day.timestamp += chrono::Duration::days(1);
+        let mut indicator = Vwap::new();
+        indicator.warm_up(&candles);
+        assert_close(
+            indicator.update(&next_day).unwrap(),
+            (next_day.high + next_day.low + next_day.close) / 3.0,
+        );
+    }
+
+    #[test]
+    fn test_trend_indicators_match_reference() {
+        let candles = sample_candles();
+
+        let adx = adx(&candles, 5);
+        assert_eq!(first_value(&adx), Some(9));
+        let mid = adx[25].unwrap();
+        assert_close(mid.adx, 72.90781360437131);
+        assert_close(mid.plus_di, 29.873653499955612);
+        assert_close(mid.minus_di, 4.92036720214187);
+        let last = adx[39].unwrap();
+        assert_close(last.adx, 52.75750858675982);
+        assert_close(last.plus_di, 37.06984541836248);
+        assert_close(last.minus_di, 5.379574445246537);
+
+        let sar = parabolic_sar(&candles, 0.02, 0.2);
+        assert_eq!(first_value(&sar), Some(1));
+        assert_close(sar[25].unwrap(), 105.14168712112269);
+        assert_close(sar[39].unwrap(), 104.16916496400475);
+    }
+
+    #[test]
+    fn test_streaming_matches_batch_after_warm_up() {
+        let candles = sample_candles();
+        let (history, live) = candles.split_at(30);
+
+        // 用前30根预热后逐根更新，与整段批量计算的结果一致
+        let mut streaming = Adx::new(5);
+        streaming.warm_up(history);
+        let batch = adx(&candles, 5);
+        for (i, candle) in live.iter().enumerate() {
+            assert_eq!(streaming.update(candle), batch[30 + i]);
+        }
+
+        let mut streaming = Rsi::new(14);
+        streaming.warm_up(&closes(history));
+        let batch = rsi(&candles, 14);
+        for (i, candle) in live.iter().enumerate() {
+            assert_eq!(streaming.update(&candle.close), batch[30 + i]);
+        }
+
+        streaming.reset();
+        assert_eq!(streaming.value(), None);
+    }
+}
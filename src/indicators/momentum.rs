@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Ema, Indicator, RollingMax, RollingMin, Sma, closes};
+use crate::types::CandleData;
+
+/// 相对强弱指数 (Wilder平滑)，需要period+1个数据才有值
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    prev: Option<f64>, // 上一个数据
+    gain: Ema,         // 平均涨幅
+    loss: Ema,         // 平均跌幅
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        let alpha = 1.0 / period.max(1) as f64;
+        Self {
+            prev: None,
+            gain: Ema::with_alpha(period, alpha),
+            loss: Ema::with_alpha(period, alpha),
+        }
+    }
+}
+
+impl Indicator<f64> for Rsi {
+    type Output = f64;
+
+    fn update(&mut self, input: &f64) -> Option<f64> {
+        if let Some(prev) = self.prev.replace(*input) {
+            let change = input - prev;
+            self.gain.update(&change.max(0.0));
+            self.loss.update(&(-change).max(0.0));
+        }
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        let (gain, loss) = (self.gain.value()?, self.loss.value()?);
+        // 窗口内没有波动时取中间值50
+        Some(if gain + loss == 0.0 {
+            50.0
+        } else {
+            100.0 * gain / (gain + loss)
+        })
+    }
+
+    fn reset(&mut self) {
+        self.prev = None;
+        self.gain.reset();
+        self.loss.reset();
+    }
+}
+
+/// MACD指标值
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MacdValue {
+    pub macd: f64,      // 快线EMA - 慢线EMA
+    pub signal: f64,    // MACD的EMA (信号线)
+    pub histogram: f64, // MACD - 信号线
+}
+
+/// MACD: 快慢EMA之差及其信号线，信号线从第一个MACD值开始计算
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Ema,                // 快线
+    slow: Ema,                // 慢线
+    signal: Ema,              // 信号线
+    value: Option<MacdValue>, // 当前值
+}
+
+impl Macd {
+    pub fn new(fast_period: usize, slow_period: usize, signal_period: usize) -> Self {
+        Self {
+            fast: Ema::new(fast_period),
+            slow: Ema::new(slow_period),
+            signal: Ema::new(signal_period),
+            value: None,
+        }
+    }
+}
+
+impl Indicator<f64> for Macd {
+    type Output = MacdValue;
+
+    fn update(&mut self, input: &f64) -> Option<MacdValue> {
+        let fast = self.fast.update(input);
+        let slow = self.slow.update(input);
+        self.value = match (fast, slow) {
+            (Some(fast), Some(slow)) => {
+                let macd = fast - slow;
+                self.signal.update(&macd).map(|signal| MacdValue {
+                    macd,
+                    signal,
+                    histogram: macd - signal,
+                })
+            }
+            _ => None,
+        };
+        self.value
+    }
+
+    fn value(&self) -> Option<MacdValue> {
+        self.value
+    }
+
+    fn reset(&mut self) {
+        self.fast.reset();
+        self.slow.reset();
+        self.signal.reset();
+        self.value = None;
+    }
+}
+
+/// 随机指标值
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StochasticValue {
+    pub k: f64, // %K
+    pub d: f64, // %D (%K的简单平均)
+}
+
+/// 随机指标: %K = (收盘价-最低价)/(最高价-最低价)*100，区间为最近k_period根K线
+#[derive(Debug, Clone)]
+pub struct Stochastic {
+    high: RollingMax,               // 区间最高价
+    low: RollingMin,                // 区间最低价
+    d: Sma,                         // %D
+    value: Option<StochasticValue>, // 当前值
+}
+
+impl Stochastic {
+    pub fn new(k_period: usize, d_period: usize) -> Self {
+        Self {
+            high: RollingMax::new(k_period),
+            low: RollingMin::new(k_period),
+            d: Sma::new(d_period),
+            value: None,
+        }
+    }
+}
+
+impl Indicator<CandleData> for Stochastic {
+    type Output = StochasticValue;
+
+    fn update(&mut self, input: &CandleData) -> Option<StochasticValue> {
+        let high = self.high.update(&input.high);
+        let low = self.low.update(&input.low);
+        self.value = match (high, low) {
+            (Some(high), Some(low)) => {
+                // 区间没有波动时取中间值50
+                let k = if high > low {
+                    100.0 * (input.close - low) / (high - low)
+                } else {
+                    50.0
+                };
+                self.d.update(&k).map(|d| StochasticValue { k, d })
+            }
+            _ => None,
+        };
+        self.value
+    }
+
+    fn value(&self) -> Option<StochasticValue> {
+        self.value
+    }
+
+    fn reset(&mut self) {
+        self.high.reset();
+        self.low.reset();
+        self.d.reset();
+        self.value = None;
+    }
+}
+
+/// 按收盘价批量计算RSI
+pub fn rsi(candles: &[CandleData], period: usize) -> Vec<Option<f64>> {
+    Rsi::new(period).batch(&closes(candles))
+}
+
+/// 按收盘价批量计算MACD
+pub fn macd(
+    candles: &[CandleData],
+    fast_period: usize,
+    slow_period: usize,
+    signal_period: usize,
+) -> Vec<Option<MacdValue>> {
+    Macd::new(fast_period, slow_period, signal_period).batch(&closes(candles))
+}
+
+/// 批量计算随机指标
+pub fn stochastic(
+    candles: &[CandleData],
+    k_period: usize,
+    d_period: usize,
+) -> Vec<Option<StochasticValue>> {
+    Stochastic::new(k_period, d_period).batch(candles)
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::{Indicator, closes};
+use crate::types::CandleData;
+
+/// 简单移动平均
+#[derive(Debug, Clone)]
+pub struct Sma {
+    period: usize,         // 周期
+    window: VecDeque<f64>, // 窗口内的数据
+    sum: f64,              // 窗口内数据之和
+}
+
+impl Sma {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            window: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+        }
+    }
+}
+
+impl Indicator<f64> for Sma {
+    type Output = f64;
+
+    fn update(&mut self, input: &f64) -> Option<f64> {
+        if self.period == 0 {
+            return None;
+        }
+
+        self.window.push_back(*input);
+        self.sum += input;
+        if self.window.len() > self.period
+            && let Some(old) = self.window.pop_front()
+        {
+            self.sum -= old;
+        }
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        (self.period > 0 && self.window.len() == self.period).then(|| self.sum / self.period as f64)
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.sum = 0.0;
+    }
+}
+
+/// 指数移动平均，以前period个数据的简单平均作为初始值
+#[derive(Debug, Clone)]
+pub struct Ema {
+    period: usize,      // 周期
+    alpha: f64,         // 平滑系数 2/(period+1)
+    count: usize,       // 已输入的数据个数
+    seed_sum: f64,      // 初始值累计 (仅前period个数据)
+    value: Option<f64>, // 当前值
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Self {
+        Self::with_alpha(period, 2.0 / (period as f64 + 1.0))
+    }
+
+    /// 指定平滑系数 (如Wilder平滑为1/period)
+    pub fn with_alpha(period: usize, alpha: f64) -> Self {
+        Self {
+            period,
+            alpha,
+            count: 0,
+            seed_sum: 0.0,
+            value: None,
+        }
+    }
+}
+
+impl Indicator<f64> for Ema {
+    type Output = f64;
+
+    fn update(&mut self, input: &f64) -> Option<f64> {
+        if self.period == 0 {
+            return None;
+        }
+
+        self.count += 1;
+        self.value = match self.value {
+            Some(prev) => Some(prev + self.alpha * (input - prev)),
+            None => {
+                self.seed_sum += input;
+                (self.count == self.period).then(|| self.seed_sum / self.period as f64)
+            }
+        };
+        self.value
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.value
+    }
+
+    fn reset(&mut self) {
+        self.count = 0;
+        self.seed_sum = 0.0;
+        self.value = None;
+    }
+}
+
+/// 加权移动平均，权重从最早的1线性增加到最新的period
+#[derive(Debug, Clone)]
+pub struct Wma {
+    period: usize,         // 周期
+    window: VecDeque<f64>, // 窗口内的数据
+    sum: f64,              // 窗口内数据之和
+    weighted_sum: f64,     // 窗口内数据的加权和
+}
+
+impl Wma {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            window: VecDeque::with_capacity(period),
+            sum: 0.0,
+            weighted_sum: 0.0,
+        }
+    }
+}
+
+impl Indicator<f64> for Wma {
+    type Output = f64;
+
+    fn update(&mut self, input: &f64) -> Option<f64> {
+        if self.period == 0 {
+            return None;
+        }
+
+        if self.window.len() == self.period {
+            // 窗口已满: 所有权重减1 (最早的数据权重降为0移出)，新数据权重为period
+            self.weighted_sum += self.period as f64 * input - self.sum;
+            self.sum += input - self.window.pop_front().unwrap_or_default();
+        } else {
+            self.weighted_sum += (self.window.len() + 1) as f64 * input;
+            self.sum += input;
+        }
+        self.window.push_back(*input);
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        let n = self.period as f64;
+        (self.period > 0 && self.window.len() == self.period)
+            .then(|| self.weighted_sum / (n * (n + 1.0) / 2.0))
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.sum = 0.0;
+        self.weighted_sum = 0.0;
+    }
+}
+
+/// 均线类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaType {
+    #[default]
+    Sma, // 简单移动平均
+    Ema, // 指数移动平均
+    Wma, // 加权移动平均
+}
+
+/// 按类型选择的移动平均
+#[derive(Debug, Clone)]
+pub enum MovingAverage {
+    Sma(Sma),
+    Ema(Ema),
+    Wma(Wma),
+}
+
+impl MovingAverage {
+    pub fn new(ma_type: MaType, period: usize) -> Self {
+        match ma_type {
+            MaType::Sma => Self::Sma(Sma::new(period)),
+            MaType::Ema => Self::Ema(Ema::new(period)),
+            MaType::Wma => Self::Wma(Wma::new(period)),
+        }
+    }
+}
+
+impl Indicator<f64> for MovingAverage {
+    type Output = f64;
+
+    fn update(&mut self, input: &f64) -> Option<f64> {
+        match self {
+            Self::Sma(ma) => ma.update(input),
+            Self::Ema(ma) => ma.update(input),
+            Self::Wma(ma) => ma.update(input),
+        }
+    }
+
+    fn value(&self) -> Option<f64> {
+        match self {
+            Self::Sma(ma) => ma.value(),
+            Self::Ema(ma) => ma.value(),
+            Self::Wma(ma) => ma.value(),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Self::Sma(ma) => ma.reset(),
+            Self::Ema(ma) => ma.reset(),
+            Self::Wma(ma) => ma.reset(),
+        }
+    }
+}
+
+/// 按收盘价批量计算简单移动平均
+pub fn sma(candles: &[CandleData], period: usize) -> Vec<Option<f64>> {
+    Sma::new(period).batch(&closes(candles))
+}
+
+/// 按收盘价批量计算指数移动平均
+pub fn ema(candles: &[CandleData], period: usize) -> Vec<Option<f64>> {
+    Ema::new(period).batch(&closes(candles))
+}
+
+/// 按收盘价批量计算加权移动平均
+pub fn wma(candles: &[CandleData], period: usize) -> Vec<Option<f64>> {
+    Wma::new(period).batch(&closes(candles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sma_ema_wma() {
+        let mut sma = Sma::new(3);
+        assert_eq!(sma.warm_up(&[1.0, 2.0]), None);
+        assert_eq!(sma.update(&3.0), Some(2.0));
+        assert_eq!(sma.update(&7.0), Some(4.0));
+
+        // 以前3个数据的平均值2作为初始值，alpha=0.5
+        let mut ema = Ema::new(3);
+        assert_eq!(ema.warm_up(&[1.0, 2.0, 3.0]), Some(2.0));
+        assert_eq!(ema.update(&6.0), Some(4.0));
+        assert_eq!(ema.update(&2.0), Some(3.0));
+
+        // (1*1+2*2+3*3)/6，随后 (1*2+2*3+3*7)/6
+        let mut wma = Wma::new(3);
+        assert_eq!(wma.warm_up(&[1.0, 2.0, 3.0]), Some(14.0 / 6.0));
+        assert_eq!(wma.update(&7.0), Some(29.0 / 6.0));
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+
+use super::{Bands, Indicator};
+use crate::types::CandleData;
+
+/// 单调队列维护的滚动窗口极值
+#[derive(Debug, Clone)]
+struct MonotonicWindow {
+    period: usize,                 // 窗口长度
+    count: usize,                  // 已输入的数据个数
+    deque: VecDeque<(usize, f64)>, // (序号, 数值)，从队首到队尾单调
+    keep_max: bool,                // true维护最大值，false维护最小值
+}
+
+impl MonotonicWindow {
+    fn new(period: usize, keep_max: bool) -> Self {
+        Self {
+            period,
+            count: 0,
+            deque: VecDeque::with_capacity(period + 1),
+            keep_max,
+        }
+    }
+
+    fn push(&mut self, value: f64) -> Option<f64> {
+        if self.period == 0 {
+            return None;
+        }
+
+        // 队尾中不可能再成为极值的元素出队
+        while let Some(&(_, back)) = self.deque.back() {
+            let dominated = if self.keep_max {
+                back <= value
+            } else {
+                back >= value
+            };
+            if !dominated {
+                break;
+            }
+            self.deque.pop_back();
+        }
+        self.deque.push_back((self.count, value));
+        self.count += 1;
+
+        // 移出窗口的元素出队
+        while let Some(&(index, _)) = self.deque.front() {
+            if index + self.period > self.count - 1 {
+                break;
+            }
+            self.deque.pop_front();
+        }
+
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        if self.period == 0 || self.count < self.period {
+            return None;
+        }
+        self.deque.front().map(|&(_, value)| value)
+    }
+
+    fn reset(&mut self) {
+        self.count = 0;
+        self.deque.clear();
+    }
+}
+
+/// 滚动最大值
+#[derive(Debug, Clone)]
+pub struct RollingMax(MonotonicWindow);
+
+impl RollingMax {
+    /// 创建窗口长度为period的滚动最大值，period为0时始终没有值
+    pub fn new(period: usize) -> Self {
+        Self(MonotonicWindow::new(period, true))
+    }
+}
+
+impl Indicator<f64> for RollingMax {
+    type Output = f64;
+
+    fn update(&mut self, input: &f64) -> Option<f64> {
+        self.0.push(*input)
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.0.value()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+/// 滚动最小值
+#[derive(Debug, Clone)]
+pub struct RollingMin(MonotonicWindow);
+
+impl RollingMin {
+    /// 创建窗口长度为period的滚动最小值，period为0时始终没有值
+    pub fn new(period: usize) -> Self {
+        Self(MonotonicWindow::new(period, false))
+    }
+}
+
+impl Indicator<f64> for RollingMin {
+    type Output = f64;
+
+    fn update(&mut self, input: &f64) -> Option<f64> {
+        self.0.push(*input)
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.0.value()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+/// 唐奇安通道: 最近period根K线 (含当前K线) 的最高价、最低价及其中值
+#[derive(Debug, Clone)]
+pub struct Donchian {
+    high: RollingMax, // 上轨
+    low: RollingMin,  // 下轨
+}
+
+impl Donchian {
+    pub fn new(period: usize) -> Self {
+        Self {
+            high: RollingMax::new(period),
+            low: RollingMin::new(period),
+        }
+    }
+}
+
+impl Indicator<CandleData> for Donchian {
+    type Output = Bands;
+
+    fn update(&mut self, input: &CandleData) -> Option<Bands> {
+        self.high.update(&input.high);
+        self.low.update(&input.low);
+        self.value()
+    }
+
+    fn value(&self) -> Option<Bands> {
+        let (upper, lower) = (self.high.value()?, self.low.value()?);
+        Some(Bands {
+            upper,
+            middle: (upper + lower) / 2.0,
+            lower,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.high.reset();
+        self.low.reset();
+    }
+}
+
+/// 批量计算唐奇安通道
+pub fn donchian(candles: &[CandleData], period: usize) -> Vec<Option<Bands>> {
+    Donchian::new(period).batch(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_extremes_match_window_scan() {
+        let values = [5.0, 3.0, 8.0, 1.0, 4.0, 7.0, 7.0, 2.0, 6.0, 0.5];
+        let period = 3;
+        let mut max = RollingMax::new(period);
+        let mut min = RollingMin::new(period);
+
+        for (i, value) in values.iter().enumerate() {
+            let got_max = max.update(value);
+            let got_min = min.update(value);
+            if i + 1 < period {
+                assert_eq!(got_max, None);
+                assert_eq!(got_min, None);
+                continue;
+            }
+            let window = &values[i + 1 - period..=i];
+            assert_eq!(got_max, window.iter().copied().reduce(f64::max));
+            assert_eq!(got_min, window.iter().copied().reduce(f64::min));
+        }
+
+        max.reset();
+        assert_eq!(max.value(), None);
+        assert_eq!(RollingMax::new(0).update(&1.0), None);
+    }
+}
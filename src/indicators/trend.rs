@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Ema, Indicator, TrueRange};
+use crate::types::CandleData;
+
+/// ADX指标值
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdxValue {
+    pub adx: f64,      // 平均趋向指数
+    pub plus_di: f64,  // +DI
+    pub minus_di: f64, // -DI
+}
+
+/// 平均趋向指数 (Wilder)，需要2*period根K线才有值
+#[derive(Debug, Clone)]
+pub struct Adx {
+    prev: Option<(f64, f64)>, // 上一根K线 (最高价, 最低价)
+    true_range: TrueRange,    // 真实波幅
+    plus_dm: Ema,             // +DM的Wilder平滑
+    minus_dm: Ema,            // -DM的Wilder平滑
+    tr: Ema,                  // 真实波幅的Wilder平滑
+    adx: Ema,                 // DX的Wilder平滑
+    value: Option<AdxValue>,  // 当前值
+}
+
+impl Adx {
+    pub fn new(period: usize) -> Self {
+        let wilder = || Ema::with_alpha(period, 1.0 / period.max(1) as f64);
+        Self {
+            prev: None,
+            true_range: TrueRange::new(),
+            plus_dm: wilder(),
+            minus_dm: wilder(),
+            tr: wilder(),
+            adx: wilder(),
+            value: None,
+        }
+    }
+}
+
+impl Indicator<CandleData> for Adx {
+    type Output = AdxValue;
+
+    fn update(&mut self, input: &CandleData) -> Option<AdxValue> {
+        let tr = self.true_range.update(input);
+        let prev = self.prev.replace((input.high, input.low));
+        let (Some(tr), Some((prev_high, prev_low))) = (tr, prev) else {
+            return None;
+        };
+
+        // 趋向变动: 只保留较大且为正的一方
+        let up = input.high - prev_high;
+        let down = prev_low - input.low;
+        let plus_dm = if up > down && up > 0.0 { up } else { 0.0 };
+        let minus_dm = if down > up && down > 0.0 { down } else { 0.0 };
+
+        let plus_dm = self.plus_dm.update(&plus_dm);
+        let minus_dm = self.minus_dm.update(&minus_dm);
+        let tr = self.tr.update(&tr);
+        let (Some(plus_dm), Some(minus_dm), Some(tr)) = (plus_dm, minus_dm, tr) else {
+            return None;
+        };
+
+        let (plus_di, minus_di) = if tr > 0.0 {
+            (100.0 * plus_dm / tr, 100.0 * minus_dm / tr)
+        } else {
+            (0.0, 0.0)
+        };
+        let dx = if plus_di + minus_di > 0.0 {
+            100.0 * (plus_di - minus_di).abs() / (plus_di + minus_di)
+        } else {
+            0.0
+        };
+
+        self.value = self.adx.update(&dx).map(|adx| AdxValue {
+            adx,
+            plus_di,
+            minus_di,
+        });
+        self.value
+    }
+
+    fn value(&self) -> Option<AdxValue> {
+        self.value
+    }
+
+    fn reset(&mut self) {
+        self.prev = None;
+        self.true_range.reset();
+        self.plus_dm.reset();
+        self.minus_dm.reset();
+        self.tr.reset();
+        self.adx.reset();
+        self.value = None;
+    }
+}
+
+/// 抛物线转向指标 (Wilder)
+///
+/// 第二根K线按收盘价涨跌确定初始方向，SAR取前两根K线的极值；
+/// 之后 SAR = 前值 + AF*(EP-前值)，且不进入前两根K线的价格区间，价格穿越SAR时反转。
+#[derive(Debug, Clone)]
+pub struct ParabolicSar {
+    step: f64,                         // 加速因子步长
+    max_step: f64,                     // 加速因子上限
+    bars: [Option<CandleExtremes>; 2], // 前两根K线 (上一根, 上上根)
+    is_long: bool,                     // 当前方向
+    sar: Option<f64>,                  // 当前SAR
+    extreme: f64,                      // 极值点EP
+    af: f64,                           // 加速因子
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CandleExtremes {
+    high: f64,
+    low: f64,
+    close: f64,
+}
+
+impl ParabolicSar {
+    pub fn new(step: f64, max_step: f64) -> Self {
+        Self {
+            step,
+            max_step,
+            bars: [None, None],
+            is_long: true,
+            sar: None,
+            extreme: 0.0,
+            af: step,
+        }
+    }
+}
+
+impl Indicator<CandleData> for ParabolicSar {
+    type Output = f64;
+
+    fn update(&mut self, input: &CandleData) -> Option<f64> {
+        let current = CandleExtremes {
+            high: input.high,
+            low: input.low,
+            close: input.close,
+        };
+
+        let Some(prev) = self.bars[0] else {
+            self.bars = [Some(current), None];
+            return None;
+        };
+
+        let sar = match self.sar {
+            // 第二根K线确定初始方向
+            None => {
+                self.is_long = current.close >= prev.close;
+                self.af = self.step;
+                if self.is_long {
+                    self.extreme = prev.high.max(current.high);
+                    prev.low.min(current.low)
+                } else {
+                    self.extreme = prev.low.min(current.low);
+                    prev.high.max(current.high)
+                }
+            }
+            Some(sar) => {
+                let prev2 = self.bars[1].unwrap_or(prev);
+                let sar = sar + self.af * (self.extreme - sar);
+                if self.is_long {
+                    let sar = sar.min(prev.low).min(prev2.low);
+                    if current.low < sar {
+                        self.is_long = false;
+                        self.af = self.step;
+                        std::mem::replace(&mut self.extreme, current.low)
+                    } else {
+                        if current.high > self.extreme {
+                            self.extreme = current.high;
+                            self.af = (self.af + self.step).min(self.max_step);
+                        }
+                        sar
+                    }
+                } else {
+                    let sar = sar.max(prev.high).max(prev2.high);
+                    if current.high > sar {
+                        self.is_long = true;
+                        self.af = self.step;
+                        std::mem::replace(&mut self.extreme, current.high)
+                    } else {
+                        if current.low < self.extreme {
+                            self.extreme = current.low;
+                            self.af = (self.af + self.step).min(self.max_step);
+                        }
+                        sar
+                    }
+                }
+            }
+        };
+
+        self.sar = Some(sar);
+        self.bars = [Some(current), self.bars[0]];
+        self.sar
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.sar
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.step, self.max_step);
+    }
+}
+
+/// 批量计算ADX
+pub fn adx(candles: &[CandleData], period: usize) -> Vec<Option<AdxValue>> {
+    Adx::new(period).batch(candles)
+}
+
+/// 批量计算抛物线转向指标
+pub fn parabolic_sar(candles: &[CandleData], step: f64, max_step: f64) -> Vec<Option<f64>> {
+    ParabolicSar::new(step, max_step).batch(candles)
+}
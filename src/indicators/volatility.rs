@@ -0,0 +1,284 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::{Bands, Ema, Indicator, Sma, closes};
+use crate::types::CandleData;
+
+/// 真实波幅 = max(高-低, |高-昨收|, |低-昨收|)，第一根K线没有昨收，不产生值
+#[derive(Debug, Clone, Default)]
+pub struct TrueRange {
+    prev_close: Option<f64>, // 上一根K线收盘价
+    value: Option<f64>,      // 当前值
+}
+
+impl TrueRange {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator<CandleData> for TrueRange {
+    type Output = f64;
+
+    fn update(&mut self, input: &CandleData) -> Option<f64> {
+        self.value = self.prev_close.map(|prev_close| {
+            (input.high - input.low)
+                .max((input.high - prev_close).abs())
+                .max((input.low - prev_close).abs())
+        });
+        self.prev_close = Some(input.close);
+        self.value
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.value
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// ATR的平滑方式，EMA与Wilder均以前period个真实波幅的简单平均为初始值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AtrSmoothing {
+    Sma, // 最近period个真实波幅的简单平均
+    Ema, // 指数平滑: alpha = 2/(period+1)
+    #[default]
+    Wilder, // Wilder平滑: ATR = (前值*(period-1) + TR) / period
+}
+
+/// 平均真实波幅
+#[derive(Debug, Clone)]
+pub struct Atr {
+    true_range: TrueRange, // 真实波幅
+    average: AtrAverage,   // 真实波幅的平滑
+}
+
+#[derive(Debug, Clone)]
+enum AtrAverage {
+    Sma(Sma),
+    Ema(Ema), // EMA与Wilder平滑只有平滑系数不同
+}
+
+impl Atr {
+    /// 创建ATR，需要period+1根K线才有值
+    pub fn new(period: usize, smoothing: AtrSmoothing) -> Self {
+        let average = match smoothing {
+            AtrSmoothing::Sma => AtrAverage::Sma(Sma::new(period)),
+            AtrSmoothing::Ema => AtrAverage::Ema(Ema::new(period)),
+            AtrSmoothing::Wilder => {
+                AtrAverage::Ema(Ema::with_alpha(period, 1.0 / period.max(1) as f64))
+            }
+        };
+        Self {
+            true_range: TrueRange::new(),
+            average,
+        }
+    }
+}
+
+impl Indicator<CandleData> for Atr {
+    type Output = f64;
+
+    fn update(&mut self, input: &CandleData) -> Option<f64> {
+        let tr = self.true_range.update(input)?;
+        match &mut self.average {
+            AtrAverage::Sma(sma) => sma.update(&tr),
+            AtrAverage::Ema(ema) => ema.update(&tr),
+        }
+    }
+
+    fn value(&self) -> Option<f64> {
+        match &self.average {
+            AtrAverage::Sma(sma) => sma.value(),
+            AtrAverage::Ema(ema) => ema.value(),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.true_range.reset();
+        match &mut self.average {
+            AtrAverage::Sma(sma) => sma.reset(),
+            AtrAverage::Ema(ema) => ema.reset(),
+        }
+    }
+}
+
+/// 布林带: 中轨为period周期简单平均，上下轨为中轨±k倍总体标准差
+#[derive(Debug, Clone)]
+pub struct Bollinger {
+    period: usize,         // 周期
+    k: f64,                // 标准差倍数
+    window: VecDeque<f64>, // 窗口内的数据
+    sum: f64,              // 窗口内数据之和
+    sum_sq: f64,           // 窗口内数据平方和
+}
+
+impl Bollinger {
+    pub fn new(period: usize, k: f64) -> Self {
+        Self {
+            period,
+            k,
+            window: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+}
+
+impl Indicator<f64> for Bollinger {
+    type Output = Bands;
+
+    fn update(&mut self, input: &f64) -> Option<Bands> {
+        if self.period == 0 {
+            return None;
+        }
+
+        self.window.push_back(*input);
+        self.sum += input;
+        self.sum_sq += input * input;
+        if self.window.len() > self.period
+            && let Some(old) = self.window.pop_front()
+        {
+            self.sum -= old;
+            self.sum_sq -= old * old;
+        }
+        self.value()
+    }
+
+    fn value(&self) -> Option<Bands> {
+        if self.period == 0 || self.window.len() < self.period {
+            return None;
+        }
+
+        let n = self.period as f64;
+        let middle = self.sum / n;
+        // 累计误差可能使方差略小于0
+        let std_dev = (self.sum_sq / n - middle * middle).max(0.0).sqrt();
+        Some(Bands {
+            upper: middle + self.k * std_dev,
+            middle,
+            lower: middle - self.k * std_dev,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.sum = 0.0;
+        self.sum_sq = 0.0;
+    }
+}
+
+/// 肯特纳通道: 中轨为收盘价EMA，上下轨为中轨±multiplier倍Wilder ATR
+#[derive(Debug, Clone)]
+pub struct Keltner {
+    ema: Ema,        // 中轨
+    atr: Atr,        // 通道宽度
+    multiplier: f64, // ATR倍数
+}
+
+impl Keltner {
+    pub fn new(period: usize, atr_period: usize, multiplier: f64) -> Self {
+        Self {
+            ema: Ema::new(period),
+            atr: Atr::new(atr_period, AtrSmoothing::Wilder),
+            multiplier,
+        }
+    }
+}
+
+impl Indicator<CandleData> for Keltner {
+    type Output = Bands;
+
+    fn update(&mut self, input: &CandleData) -> Option<Bands> {
+        self.ema.update(&input.close);
+        self.atr.update(input);
+        self.value()
+    }
+
+    fn value(&self) -> Option<Bands> {
+        let (middle, atr) = (self.ema.value()?, self.atr.value()?);
+        Some(Bands {
+            upper: middle + self.multiplier * atr,
+            middle,
+            lower: middle - self.multiplier * atr,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.ema.reset();
+        self.atr.reset();
+    }
+}
+
+/// 批量计算ATR
+pub fn atr(candles: &[CandleData], period: usize, smoothing: AtrSmoothing) -> Vec<Option<f64>> {
+    Atr::new(period, smoothing).batch(candles)
+}
+
+/// 按收盘价批量计算布林带
+pub fn bollinger(candles: &[CandleData], period: usize, k: f64) -> Vec<Option<Bands>> {
+    Bollinger::new(period, k).batch(&closes(candles))
+}
+
+/// 批量计算肯特纳通道
+pub fn keltner(
+    candles: &[CandleData],
+    period: usize,
+    atr_period: usize,
+    multiplier: f64,
+) -> Vec<Option<Bands>> {
+    Keltner::new(period, atr_period, multiplier).batch(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn candle(high: f64, low: f64, close: f64) -> CandleData {
+        CandleData {
+            timestamp: DateTime::from_timestamp_millis(1_704_067_200_000).unwrap(),
+            symbol: "SOL-USDT".to_string(),
+            open: close,
+            high,
+            low,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_atr_smoothing_modes() {
+        // 真实波幅依次为 2, 4 (跳空高开，|高-昨收|), 3, 6
+        let candles = [
+            candle(11.0, 9.0, 10.0),
+            candle(12.0, 10.0, 11.0),
+            candle(15.0, 13.0, 14.0),
+            candle(15.0, 12.0, 13.0),
+            candle(13.0, 7.0, 8.0),
+        ];
+
+        let mut sma = Atr::new(2, AtrSmoothing::Sma);
+        assert_eq!(sma.update(&candles[0]), None);
+        assert_eq!(sma.update(&candles[1]), None);
+        assert_eq!(sma.update(&candles[2]), Some(3.0));
+        assert_eq!(sma.warm_up(&candles[3..]), Some(4.5));
+
+        // Wilder: 初始值(2+4)/2=3，之后 (3*1+3)/2=3，(3*1+6)/2=4.5
+        let mut wilder = Atr::new(2, AtrSmoothing::Wilder);
+        assert_eq!(wilder.warm_up(&candles[..3]), Some(3.0));
+        assert_eq!(wilder.update(&candles[3]), Some(3.0));
+        assert_eq!(wilder.update(&candles[4]), Some(4.5));
+
+        // EMA (alpha=2/3): 初始值3，之后 3+(3-3)*2/3=3，3+(6-3)*2/3=5
+        let mut ema = Atr::new(2, AtrSmoothing::Ema);
+        assert_eq!(ema.warm_up(&candles[..4]), Some(3.0));
+        assert_eq!(ema.update(&candles[4]), Some(5.0));
+
+        wilder.reset();
+        assert_eq!(wilder.value(), None);
+        assert_eq!(wilder.update(&candles[0]), None);
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::NaiveDate;
+
+use super::Indicator;
+use crate::types::CandleData;
+
+/// 成交量加权平均价: 典型价格 (高+低+收)/3 按成交量加权，每个UTC自然日重新累计
+#[derive(Debug, Clone, Default)]
+pub struct Vwap {
+    session: Option<NaiveDate>, // 当前累计的交易日
+    price_volume: f64,          // 典型价格*成交量 累计
+    volume: f64,                // 成交量累计
+}
+
+impl Vwap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator<CandleData> for Vwap {
+    type Output = f64;
+
+    fn update(&mut self, input: &CandleData) -> Option<f64> {
+        let session = input.timestamp.date_naive();
+        if self.session != Some(session) {
+            self.session = Some(session);
+            self.price_volume = 0.0;
+            self.volume = 0.0;
+        }
+
+        let typical = (input.high + input.low + input.close) / 3.0;
+        self.price_volume += typical * input.volume;
+        self.volume += input.volume;
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        (self.volume > 0.0).then(|| self.price_volume / self.volume)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// 能量潮: 收盘价上涨累加成交量，下跌累减，首根K线为0
+#[derive(Debug, Clone, Default)]
+pub struct Obv {
+    prev_close: Option<f64>, // 上一根K线收盘价
+    value: f64,              // 当前值
+}
+
+impl Obv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator<CandleData> for Obv {
+    type Output = f64;
+
+    fn update(&mut self, input: &CandleData) -> Option<f64> {
+        if let Some(prev_close) = self.prev_close {
+            if input.close > prev_close {
+                self.value += input.volume;
+            } else if input.close < prev_close {
+                self.value -= input.volume;
+            }
+        }
+        self.prev_close = Some(input.close);
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.prev_close.map(|_| self.value)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// 批量计算VWAP
+pub fn vwap(candles: &[CandleData]) -> Vec<Option<f64>> {
+    Vwap::new().batch(candles)
+}
+
+/// 批量计算OBV
+pub fn obv(candles: &[CandleData]) -> Vec<Option<f64>> {
+    Obv::new().batch(candles)
+}
@@ -0,0 +1,12 @@
+pub mod arbitrage;
+pub mod archive;
+pub mod backup;
+pub mod coverage;
+pub mod db;
+pub mod handler;
+pub mod indicators;
+pub mod memory_db;
+pub mod storage;
+pub mod strategy;
+pub mod strategy_manager;
+pub mod types;
@@ -3,31 +3,91 @@ use log::{info, warn};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-mod db;
-mod handler;
-mod strategy;
-mod strategy_manager;
-mod types;
+use okx_quant::arbitrage::{self, TriangularArbitrage};
+use okx_quant::archive;
+use okx_quant::backup;
+use okx_quant::coverage;
+use okx_quant::db::Database;
+use okx_quant::handler::{self, DataHandler};
+use okx_quant::storage::Storage;
+use okx_quant::strategy;
+use okx_quant::strategy_manager::StrategyManager;
+use okx_quant::types::{
+    ArchiveDataset, BookQuote, ExportRequest, SessionBoundary, Timeframe, TriangularArbConfig,
+};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use db::Database;
-use handler::DataHandler;
-use strategy_manager::StrategyManager;
+/// 数据库文件路径
+const DB_PATH: &str = "trading.db";
+
+/// 快照目录
+const SNAPSHOT_DIR: &str = "backups";
+
+/// 保留的快照数量
+const SNAPSHOT_RETENTION: usize = 14;
+
+/// 定时快照间隔
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// 三角套利纸面执行每评估该数量的机会输出一次存活统计
+const ARBITRAGE_REPORT_EVERY: usize = 20;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // 初始化日志
     env_logger::init();
+
+    // 命令行子命令: backup / restore <快照文件>
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("backup") => {
+            let database = Database::new(DB_PATH).await?;
+            let path =
+                backup::create_snapshot(&database, Path::new(SNAPSHOT_DIR), SNAPSHOT_RETENTION)
+                    .await?;
+            println!("快照已生成: {}", path.display());
+            return Ok(());
+        }
+        Some("restore") => {
+            let Some(snapshot) = args.get(1) else {
+                return Err(anyhow::anyhow!("用法: okx_quant restore <快照文件>"));
+            };
+            match backup::restore_snapshot(Path::new(snapshot), Path::new(DB_PATH))? {
+                Some(previous) => println!("恢复完成，原数据库已保存为 {}", previous.display()),
+                None => println!("恢复完成"),
+            }
+            return Ok(());
+        }
+        Some(other) => return Err(anyhow::anyhow!("未知命令: {}", other)),
+        None => {}
+    }
+
     info!("启动量化交易系统");
 
     // 初始化数据库
-    let db = Arc::new(Database::new("trading.db").await?);
+    let database = Arc::new(Database::new(DB_PATH).await?);
+    let db: Arc<dyn Storage> = database.clone();
     info!("数据库初始化完成");
 
+    // 启动定时快照任务
+    let snapshot_task = tokio::spawn(backup::run_scheduled_snapshots(
+        database.clone(),
+        PathBuf::from(SNAPSHOT_DIR),
+        SNAPSHOT_RETENTION,
+        SNAPSHOT_INTERVAL,
+    ));
+
     // 初始化数据处理器
     let data_handler = Arc::new(DataHandler::new(db.clone()));
 
     // 初始化策略管理器
-    let strategy_manager = Arc::new(Mutex::new(StrategyManager::new(db.clone())));
+    let strategy_manager = Arc::new(Mutex::new(StrategyManager::new(db.clone()).await?));
+    {
+        let manager = strategy_manager.lock().await;
+        data_handler.set_candle_symbols(manager.candle_symbols());
+        data_handler.set_funding_symbols(manager.funding_symbols());
+    }
 
     // 启动数据收集任务
     let data_task = {
@@ -49,13 +109,32 @@ async fn main() -> Result<()> {
             }
         })
     };
+
+    // 启动三角套利检测
+    let arbitrage_task = {
+        let handler = data_handler.clone();
+        tokio::spawn(async move {
+            if let Err(e) = run_arbitrage_monitor(handler).await {
+                warn!("三角套利检测出错: {}", e);
+            }
+        })
+    };
     loop {
         println!("\n请选择功能:");
         println!("1. 开始数据收集");
         println!("2. 运行回测");
         println!("3. 实时交易");
         println!("4. 查看交易历史");
-        println!("5. 退出");
+        println!("5. 查看回测记录");
+        println!("6. 对比回测记录");
+        println!("7. 数据质量报告");
+        println!("8. 导出数据");
+        println!("9. 导入数据");
+        println!("10. 立即备份数据库");
+        println!("11. 策略资金概览");
+        println!("12. 添加策略实例");
+        println!("13. 三角套利回放");
+        println!("14. 退出");
 
         let mut input = String::new();
         std::io::stdin().read_line(&mut input)?;
@@ -75,6 +154,42 @@ async fn main() -> Result<()> {
                 show_trading_history(db.clone()).await?;
             }
             "5" => {
+                show_backtest_runs(db.clone()).await?;
+            }
+            "6" => {
+                compare_backtest_runs(db.clone()).await?;
+            }
+            "7" => {
+                show_data_coverage(db.clone()).await?;
+            }
+            "8" => {
+                export_data(db.clone()).await?;
+            }
+            "9" => {
+                import_data(db.clone()).await?;
+            }
+            "10" => {
+                let path =
+                    backup::create_snapshot(&database, Path::new(SNAPSHOT_DIR), SNAPSHOT_RETENTION)
+                        .await?;
+                println!("快照已生成: {}", path.display());
+            }
+            "11" => {
+                show_portfolio(strategy_manager.clone()).await;
+            }
+            "12" => {
+                if let Err(e) =
+                    add_strategy_instance(strategy_manager.clone(), data_handler.clone()).await
+                {
+                    println!("添加策略实例失败: {}", e);
+                }
+            }
+            "13" => {
+                if let Err(e) = replay_arbitrage(db.clone()).await {
+                    println!("三角套利回放失败: {}", e);
+                }
+            }
+            "14" => {
                 info!("退出程序");
                 break;
             }
@@ -84,11 +199,19 @@ async fn main() -> Result<()> {
     // 等待任务完成
     data_task.abort();
     trading_task.abort();
+    arbitrage_task.abort();
+    snapshot_task.abort();
 
     Ok(())
 }
 /// 运行回测分析
 async fn run_backtest(strategy_manager: Arc<Mutex<StrategyManager>>) -> Result<()> {
+    // 选择策略实例 (输入时不持有锁，避免阻塞实时交易)
+    let strategy_ids = strategy_manager.lock().await.strategy_ids();
+    println!("可用策略实例: {}", strategy_ids.join(", "));
+    let default_id = strategy_ids.first().cloned().unwrap_or_default();
+    let strategy_id = prompt("策略实例", &default_id)?;
+
     let mut manager = strategy_manager.lock().await;
     // 设置回测参数
     let start_time = chrono::Utc::now() - chrono::Duration::days(30); // 最近30天
@@ -96,11 +219,15 @@ async fn run_backtest(strategy_manager: Arc<Mutex<StrategyManager>>) -> Result<(
     let initial_balance = 10000.0; // 初始资金10000 USDT
     info!("执行回测: {} 到 {}", start_time, end_time);
     // 执行回测
-    let report = manager
-        .run_backtest(start_time, end_time, initial_balance)
+    let run = manager
+        .run_backtest(&strategy_id, start_time, end_time, initial_balance)
         .await?;
+    let report = &run.report;
     // 打印回测报告
-    println!("\n===== 回测报告 =====");
+    println!(
+        "\n===== 回测报告 (记录#{}) =====",
+        run.id.unwrap_or_default()
+    );
     println!("初始资金: ${:.2}", report.initial_balance);
     println!("最终资金: ${:.2}", report.final_balance);
     println!("总收益: ${:.2}", report.total_return);
@@ -125,7 +252,7 @@ async fn run_real_time_trading(
     while let Ok(ticker_data) = receiver.recv().await {
         let mut manager = strategy_manager.lock().await;
         // 处理实时数据，生成交易信号
-        if let Some(signal) = manager.process_real_time_data(&ticker_data).await? {
+        for signal in manager.process_real_time_data(&ticker_data).await? {
             info!("生成交易信号: {:?}", signal);
 
             // 这里可以添加实际的交易执行逻辑
@@ -135,15 +262,101 @@ async fn run_real_time_trading(
 
     Ok(())
 }
+/// 按实时报价检测三角套利机会，并定期输出纸面执行的延迟存活统计
+async fn run_arbitrage_monitor(data_handler: Arc<DataHandler>) -> Result<()> {
+    let mut detector = create_arbitrage_detector().await?;
+    info!("开始三角套利检测: {}", detector.symbols().join(", "));
+
+    let symbols = detector.symbols();
+    let mut receiver = data_handler.subscribe_price_updates().await;
+    let mut reported = 0;
+    while let Ok(ticker_data) = receiver.recv().await {
+        if !symbols.contains(&ticker_data.inst_id) {
+            continue;
+        }
+        detector.on_quote(BookQuote::from(&ticker_data));
+
+        let survival = detector.survival();
+        if survival.checked >= reported + ARBITRAGE_REPORT_EVERY {
+            reported = survival.checked;
+            info!(
+                "三角套利纸面执行: 检测 {} 次，已评估 {} 次，延迟后存活 {} 次 ({:.1}%)",
+                survival.detected,
+                survival.checked,
+                survival.survived,
+                survival.survival_rate() * 100.0
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// 使用默认配置和交易所下单规则创建三角套利检测器
+async fn create_arbitrage_detector() -> Result<TriangularArbitrage> {
+    let config = TriangularArbConfig::default();
+    let mut symbols: Vec<String> = config.triangles.iter().flatten().cloned().collect();
+    symbols.sort();
+    symbols.dedup();
+    let instruments = handler::fetch_instrument_rules(&symbols).await?;
+    TriangularArbitrage::new(config, instruments)
+}
+
+/// 回放最近24小时存储的Ticker，统计三角套利机会及其延迟存活情况
+async fn replay_arbitrage(db: Arc<dyn Storage>) -> Result<()> {
+    let mut detector = create_arbitrage_detector().await?;
+    let end_time = chrono::Utc::now();
+    let start_time = end_time - chrono::Duration::hours(24);
+    let opportunities =
+        arbitrage::replay_tickers(db.as_ref(), &mut detector, start_time, end_time).await?;
+
+    println!("\n===== 三角套利回放 (最近24小时) =====");
+    for opportunity in &opportunities {
+        println!(
+            "{} | {} | 投入: {:.4} | 换回: {:.4} | 收益率: {:.4}% (理论 {:.4}%)",
+            opportunity.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+            opportunity.path,
+            opportunity.start_amount,
+            opportunity.end_amount,
+            opportunity.edge * 100.0,
+            opportunity.gross_edge * 100.0
+        );
+    }
+
+    let survival = detector.survival();
+    println!("检测到机会: {}", survival.detected);
+    if survival.checked > 0 {
+        println!(
+            "延迟后存活: {} / {} ({:.1}%)",
+            survival.survived,
+            survival.checked,
+            survival.survival_rate() * 100.0
+        );
+        println!(
+            "平均检测收益率: {:.4}%",
+            survival.detected_edge_sum / survival.checked as f64 * 100.0
+        );
+    }
+    if survival.survived > 0 {
+        println!(
+            "存活机会平均收益率: {:.4}%",
+            survival.survived_edge_sum / survival.survived as f64 * 100.0
+        );
+    }
+
+    Ok(())
+}
+
 /// 显示交易历史
-async fn show_trading_history(db: Arc<Database>) -> Result<()> {
+async fn show_trading_history(db: Arc<dyn Storage>) -> Result<()> {
     let trades = db.get_recent_trades(50).await?;
 
     println!("\n===== 最近交易历史 =====");
     for trade in trades {
         println!(
-            "{} | {} | {} | 价格: ${:.4} | 数量: {:.4} | PnL: ${:.2}",
+            "{} | {} | {} | {} | 价格: ${:.4} | 数量: {:.4} | PnL: ${:.2}",
             trade.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            trade.strategy_id,
             trade.symbol,
             trade.side,
             trade.price,
@@ -154,3 +367,361 @@ async fn show_trading_history(db: Arc<Database>) -> Result<()> {
 
     Ok(())
 }
+
+/// 显示K线数据的覆盖情况与质量问题
+async fn show_data_coverage(db: Arc<dyn Storage>) -> Result<()> {
+    let reports = coverage::coverage_reports(db.as_ref()).await?;
+
+    if reports.is_empty() {
+        println!("暂无K线数据");
+        return Ok(());
+    }
+
+    println!("\n===== 数据质量报告 =====");
+    for report in &reports {
+        println!(
+            "{} [{}] | {} ~ {} | 覆盖率: {:.2}% ({}/{})",
+            report.symbol,
+            report.timeframe.as_str(),
+            report
+                .first_timestamp
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            report
+                .last_timestamp
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            report.coverage * 100.0,
+            report.actual_bars,
+            report.expected_bars,
+        );
+        println!(
+            "  缺口: {} 处 (缺失 {} 根) | 重复: {} | 倒序: {} | 未对齐: {} | OHLC异常: {} | 零成交量区间: {}",
+            report.gaps.len(),
+            report.gaps.iter().map(|g| g.missing_bars).sum::<usize>(),
+            report.duplicate_count,
+            report.out_of_order_count,
+            report.misaligned_count,
+            report.ohlc_violations.len(),
+            report.zero_volume_streaks.len(),
+        );
+        for gap in report.gaps.iter().take(5) {
+            println!(
+                "    缺口 {} ~ {} (缺失 {} 根)",
+                gap.after.format("%Y-%m-%d %H:%M"),
+                gap.before.format("%Y-%m-%d %H:%M"),
+                gap.missing_bars
+            );
+        }
+        if report.gaps.len() > 5 {
+            println!("    ... 共 {} 处缺口", report.gaps.len());
+        }
+    }
+
+    println!("\n是否输出JSON? (y/N)");
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    if input.trim().eq_ignore_ascii_case("y") {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    }
+
+    Ok(())
+}
+
+/// 显示各策略实例的资金与盈亏，以及账户合计
+async fn show_portfolio(strategy_manager: Arc<Mutex<StrategyManager>>) {
+    let summary = strategy_manager.lock().await.portfolio_summary();
+
+    println!("\n===== 策略资金概览 =====");
+    for s in &summary.strategies {
+        println!(
+            "{} | {} {} | 分配: ${:.2} | 可用: ${:.2} | 持仓市值: ${:.2} | 已实现: ${:.2} | 未实现: ${:.2} | 权益: ${:.2}",
+            s.strategy_id,
+            s.strategy,
+            s.symbol,
+            s.allocation,
+            s.available,
+            s.position_value,
+            s.realized_pnl,
+            s.unrealized_pnl,
+            s.equity(),
+        );
+    }
+    println!(
+        "账户合计 | 现金: ${:.2} (未分配 ${:.2}) | 持仓市值: ${:.2} | 已实现: ${:.2} | 未实现: ${:.2} | 总权益: ${:.2}",
+        summary.cash,
+        summary.unallocated,
+        summary.position_value,
+        summary.realized_pnl,
+        summary.unrealized_pnl,
+        summary.equity(),
+    );
+}
+
+/// 按策略名称和JSON参数添加策略实例 (实盘与回测共用，保存定义供重启时重新创建)，并更新需要采集资金费率的永续合约
+async fn add_strategy_instance(
+    strategy_manager: Arc<Mutex<StrategyManager>>,
+    data_handler: Arc<DataHandler>,
+) -> Result<()> {
+    println!("可选策略: {}", strategy::STRATEGY_KINDS.join(", "));
+    let kind = prompt("策略", "MaCross")?;
+    let strategy_id = prompt("实例ID", &kind)?;
+    let symbol = prompt("交易对", "SOL-USDT")?;
+    let allocation: f64 = prompt("分配资金", "1000")?.parse()?;
+    // 参数留空时使用默认参数，输入的JSON中省略的可选字段取默认值
+    let params = prompt("参数 (JSON)", "")?;
+    let params = if params.is_empty() {
+        None
+    } else {
+        Some(serde_json::from_str(&params)?)
+    };
+
+    let instance = strategy::create_strategy(&kind, &symbol, params)?;
+    let params = instance.params();
+    let mut manager = strategy_manager.lock().await;
+    manager
+        .register_strategy(strategy_id.clone(), instance, allocation)
+        .await?;
+    data_handler.set_candle_symbols(manager.candle_symbols());
+    data_handler.set_funding_symbols(manager.funding_symbols());
+    println!("已添加策略实例 {}: {}", strategy_id, params);
+
+    Ok(())
+}
+
+/// 读取一行输入，为空时返回默认值
+fn prompt(label: &str, default: &str) -> Result<String> {
+    println!("{} [{}]:", label, default);
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+
+    let input = input.trim();
+    Ok(if input.is_empty() {
+        default.to_string()
+    } else {
+        input.to_string()
+    })
+}
+
+/// 导出数据到CSV/Parquet
+async fn export_data(db: Arc<dyn Storage>) -> Result<()> {
+    println!("选择数据集: 1. K线  2. Ticker  3. 成交记录  4. 回测记录  5. 回测权益曲线");
+    let dataset = match prompt("数据集", "1")?.as_str() {
+        "1" => ArchiveDataset::Candles,
+        "2" => ArchiveDataset::Tickers,
+        "3" => ArchiveDataset::Trades,
+        "4" => ArchiveDataset::BacktestRuns,
+        "5" => ArchiveDataset::BacktestEquity(prompt("回测记录ID", "1")?.parse()?),
+        other => {
+            println!("无效的数据集: {}", other);
+            return Ok(());
+        }
+    };
+
+    let symbol = prompt("交易对", "SOL-USDT")?;
+    let timeframe: Timeframe = prompt("K线周期", "1m")?.parse()?;
+    let session = match prompt("日线会话边界 (utc / utc8)", "utc8")?.as_str() {
+        "utc" => SessionBoundary::Utc,
+        _ => SessionBoundary::Utc8,
+    };
+    let days: i64 = prompt("最近天数", "30")?.parse()?;
+    let path = prompt("输出文件 (.csv / .parquet)", "export.parquet")?;
+
+    let end_time = chrono::Utc::now();
+    let request = ExportRequest {
+        dataset,
+        symbol,
+        timeframe,
+        session,
+        start_time: end_time - chrono::Duration::days(days),
+        end_time,
+    };
+
+    let rows = archive::export(db.as_ref(), &request, Path::new(&path)).await?;
+    println!("已导出 {} 行到 {}", rows, path);
+
+    Ok(())
+}
+
+/// 从CSV/Parquet导入K线或Ticker数据
+async fn import_data(db: Arc<dyn Storage>) -> Result<()> {
+    println!("选择数据类型: 1. K线 (含OKX历史数据CSV)  2. Ticker");
+    let kind = prompt("数据类型", "1")?;
+    let path = prompt("归档文件 (.csv / .parquet)", "import.csv")?;
+    let symbol = prompt("交易对 (留空则读取文件中的交易对列)", "")?;
+    let symbol = (!symbol.is_empty()).then_some(symbol.as_str());
+
+    let summary = match kind.as_str() {
+        "1" => {
+            let timeframe: Timeframe = prompt("文件的K线周期 (仅支持1m)", "1m")?.parse()?;
+            archive::import_candles(db.as_ref(), Path::new(&path), symbol, timeframe).await?
+        }
+        "2" => archive::import_tickers(db.as_ref(), Path::new(&path), symbol).await?,
+        other => {
+            println!("无效的数据类型: {}", other);
+            return Ok(());
+        }
+    };
+
+    println!(
+        "读取 {} 行，导入 {} 行，重复 {} 行，跳过 {} 行",
+        summary.rows_read, summary.imported, summary.duplicates, summary.skipped
+    );
+
+    Ok(())
+}
+
+/// 显示最近的回测记录
+async fn show_backtest_runs(db: Arc<dyn Storage>) -> Result<()> {
+    let runs = db.get_backtest_runs(20).await?;
+
+    println!("\n===== 最近回测记录 =====");
+    for run in runs {
+        println!(
+            "#{} | {} | {} | {} ~ {} | 收益率: {:.2}% | 回撤: {:.2}% | 交易: {} | 夏普: {:.2} | {}",
+            run.id.unwrap_or_default(),
+            run.created_at.format("%Y-%m-%d %H:%M:%S"),
+            run.symbol,
+            run.report.start_time.format("%Y-%m-%d"),
+            run.report.end_time.format("%Y-%m-%d"),
+            run.report.return_rate * 100.0,
+            run.report.max_drawdown * 100.0,
+            run.report.total_trades,
+            run.report.sharpe_ratio,
+            run.code_version,
+        );
+    }
+
+    Ok(())
+}
+
+/// 并排对比多条回测记录
+async fn compare_backtest_runs(db: Arc<dyn Storage>) -> Result<()> {
+    println!("请输入要对比的回测记录ID (逗号分隔):");
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+
+    let mut runs = Vec::new();
+    for id in input.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let Ok(id) = id.parse::<i64>() else {
+            println!("无效的记录ID: {}", id);
+            continue;
+        };
+        match db.get_backtest_run(id).await? {
+            Some(run) => runs.push(run),
+            None => println!("回测记录不存在: #{}", id),
+        }
+    }
+
+    if runs.is_empty() {
+        return Ok(());
+    }
+
+    let row = |label: &str, values: Vec<String>| {
+        let cells: Vec<String> = values.iter().map(|v| format!("{:>20}", v)).collect();
+        println!("{:<12}{}", label, cells.join(""));
+    };
+
+    println!("\n===== 回测对比 =====");
+    row(
+        "记录",
+        runs.iter()
+            .map(|r| format!("#{}", r.id.unwrap_or_default()))
+            .collect(),
+    );
+    row("交易对", runs.iter().map(|r| r.symbol.clone()).collect());
+    row(
+        "开始",
+        runs.iter()
+            .map(|r| r.report.start_time.format("%Y-%m-%d %H:%M").to_string())
+            .collect(),
+    );
+    row(
+        "结束",
+        runs.iter()
+            .map(|r| r.report.end_time.format("%Y-%m-%d %H:%M").to_string())
+            .collect(),
+    );
+    row(
+        "K线数量",
+        runs.iter().map(|r| r.candle_count.to_string()).collect(),
+    );
+    row("策略", runs.iter().map(|r| r.strategy.clone()).collect());
+
+    // 按参数名逐行对比，缺少该参数的记录显示为 "-"
+    let mut param_names: Vec<String> = Vec::new();
+    for run in &runs {
+        if let Some(params) = run.params.as_object() {
+            for name in params.keys() {
+                if !param_names.contains(name) {
+                    param_names.push(name.clone());
+                }
+            }
+        }
+    }
+    for name in &param_names {
+        row(
+            name,
+            runs.iter()
+                .map(|r| {
+                    r.params
+                        .get(name)
+                        .map_or_else(|| "-".to_string(), |v| v.to_string())
+                })
+                .collect(),
+        );
+    }
+    row(
+        "初始资金",
+        runs.iter()
+            .map(|r| format!("{:.2}", r.report.initial_balance))
+            .collect(),
+    );
+    row(
+        "最终资金",
+        runs.iter()
+            .map(|r| format!("{:.2}", r.report.final_balance))
+            .collect(),
+    );
+    row(
+        "收益率",
+        runs.iter()
+            .map(|r| format!("{:.2}%", r.report.return_rate * 100.0))
+            .collect(),
+    );
+    row(
+        "最大回撤",
+        runs.iter()
+            .map(|r| format!("{:.2}%", r.report.max_drawdown * 100.0))
+            .collect(),
+    );
+    row(
+        "交易次数",
+        runs.iter()
+            .map(|r| r.report.total_trades.to_string())
+            .collect(),
+    );
+    row(
+        "胜率",
+        runs.iter()
+            .map(|r| format!("{:.2}%", r.report.win_rate * 100.0))
+            .collect(),
+    );
+    row(
+        "夏普比率",
+        runs.iter()
+            .map(|r| format!("{:.2}", r.report.sharpe_ratio))
+            .collect(),
+    );
+    row(
+        "代码版本",
+        runs.iter().map(|r| r.code_version.clone()).collect(),
+    );
+
+    let fingerprints: Vec<&str> = runs.iter().map(|r| r.data_fingerprint.as_str()).collect();
+    if fingerprints.windows(2).any(|w| w[0] != w[1]) {
+        println!("注意: 以上回测使用的数据不同 (数据指纹不一致)");
+    }
+
+    Ok(())
+}
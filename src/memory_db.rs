@@ -0,0 +1,668 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::Mutex;
+
+use crate::storage::{
+    Storage, count_out_of_order, filled_state, is_conflicting_rewrite, resample_candles,
+};
+use crate::types::{
+    AccountState, BacktestRun, CandleData, CandleIngestStats, Fill, FundingPayment, FundingRate,
+    Order, OrderState, SessionBoundary, SignalOutcome, SignalRecord, StrategyInstance, TickerData,
+    TickerSnapshot, Timeframe, Trade, TradingSignal,
+};
+
+/// 内存存储，语义与SQLite实现一致，进程退出后数据丢失
+pub struct MemoryDatabase {
+    state: Mutex<MemoryState>, // 全部数据
+}
+
+/// 内存存储的数据 (ID从1开始，与SQLite自增主键一致)
+#[derive(Default)]
+struct MemoryState {
+    candles: HashMap<String, BTreeMap<i64, CandleData>>, // 交易对 -> 时间戳 -> K线
+    candle_ingest: HashMap<String, CandleIngestStats>,   // 交易对 -> K线写入质量计数
+    tickers: Vec<TickerSnapshot>,                        // Ticker数据
+    funding_rates: HashMap<String, BTreeMap<i64, FundingRate>>, // 交易对 -> 结算时间 -> 资金费率
+    funding_payments: Vec<FundingPayment>,               // 资金费收支记录
+    signals: Vec<SignalRecord>,                          // 交易信号
+    trades: Vec<Trade>,                                  // 交易记录
+    account_state: Option<AccountState>,                 // 账户状态
+    strategy_instances: Vec<StrategyInstance>,           // 策略实例定义 (按首次保存的顺序)
+    orders: Vec<Order>,                                  // 订单
+    fills: Vec<Fill>,                                    // 成交明细
+    backtest_runs: Vec<BacktestRun>,                     // 回测记录
+    backtest_trades: HashMap<i64, Vec<Trade>>,           // 回测ID -> 交易列表
+    backtest_equity: HashMap<i64, Vec<(DateTime<Utc>, f64)>>, // 回测ID -> 权益曲线
+}
+
+impl MemoryDatabase {
+    /// 创建空的内存存储
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(MemoryState::default()),
+        }
+    }
+}
+
+impl Default for MemoryDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryState {
+    /// 根据订单ID获取可变订单
+    fn order_mut(&mut self, order_id: i64) -> Result<&mut Order> {
+        self.orders
+            .iter_mut()
+            .find(|o| o.id == Some(order_id))
+            .ok_or_else(|| anyhow::anyhow!("订单不存在: {}", order_id))
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryDatabase {
+    async fn save_candle(&self, candle: &CandleData) -> Result<()> {
+        self.save_candles(std::slice::from_ref(candle)).await
+    }
+
+    async fn save_candles(&self, candles: &[CandleData]) -> Result<()> {
+        let mut state = self.state.lock().await;
+
+        let mut latest_before: HashMap<String, Option<i64>> = HashMap::new();
+        for candle in candles {
+            let series = state.candles.entry(candle.symbol.clone()).or_default();
+            let latest = *latest_before
+                .entry(candle.symbol.clone())
+                .or_insert_with(|| series.keys().next_back().copied());
+
+            let duplicate = series
+                .insert(candle.timestamp.timestamp_millis(), candle.clone())
+                .is_some_and(|existing| is_conflicting_rewrite(&existing, candle, latest));
+            if duplicate {
+                state
+                    .candle_ingest
+                    .entry(candle.symbol.clone())
+                    .or_default()
+                    .duplicate_count += 1;
+            }
+        }
+
+        for (symbol, count) in count_out_of_order(candles) {
+            state
+                .candle_ingest
+                .entry(symbol)
+                .or_default()
+                .out_of_order_count += count;
+        }
+
+        Ok(())
+    }
+
+    async fn get_candle_ingest_stats(&self, symbol: &str) -> Result<CandleIngestStats> {
+        let state = self.state.lock().await;
+        Ok(state.candle_ingest.get(symbol).copied().unwrap_or_default())
+    }
+
+    async fn save_ticker(&self, ticker: &TickerData) -> Result<()> {
+        self.state
+            .lock()
+            .await
+            .tickers
+            .push(TickerSnapshot::from(ticker));
+        Ok(())
+    }
+
+    async fn save_ticker_snapshots(&self, tickers: &[TickerSnapshot]) -> Result<()> {
+        self.state
+            .lock()
+            .await
+            .tickers
+            .extend(tickers.iter().cloned());
+        Ok(())
+    }
+
+    async fn get_tickers(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<TickerSnapshot>> {
+        let state = self.state.lock().await;
+
+        let mut tickers: Vec<TickerSnapshot> = state
+            .tickers
+            .iter()
+            .filter(|t| t.symbol == symbol && t.timestamp >= start_time && t.timestamp <= end_time)
+            .cloned()
+            .collect();
+        tickers.sort_by_key(|t| t.timestamp);
+
+        Ok(tickers)
+    }
+
+    async fn get_candles(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        limit: Option<usize>,
+    ) -> Result<Vec<CandleData>> {
+        let state = self.state.lock().await;
+
+        let Some(series) = state.candles.get(symbol) else {
+            return Ok(Vec::new());
+        };
+
+        let range = series
+            .range(start_time.timestamp_millis()..=end_time.timestamp_millis())
+            .map(|(_, c)| c.clone());
+
+        Ok(match limit {
+            Some(limit) => range.take(limit).collect(),
+            None => range.collect(),
+        })
+    }
+
+    async fn get_latest_candles(&self, symbol: &str, count: usize) -> Result<Vec<CandleData>> {
+        let state = self.state.lock().await;
+
+        let Some(series) = state.candles.get(symbol) else {
+            return Ok(Vec::new());
+        };
+
+        let mut candles: Vec<CandleData> = series.values().rev().take(count).cloned().collect();
+        candles.reverse();
+        Ok(candles)
+    }
+
+    async fn get_resampled_candles(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        session: SessionBoundary,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<CandleData>> {
+        let candles = self.get_candles(symbol, start_time, end_time, None).await?;
+        Ok(resample_candles(&candles, timeframe, session))
+    }
+
+    async fn get_candle_symbols(&self) -> Result<Vec<String>> {
+        let state = self.state.lock().await;
+
+        let mut symbols: Vec<String> = state
+            .candles
+            .iter()
+            .filter(|(_, series)| !series.is_empty())
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        symbols.sort();
+
+        Ok(symbols)
+    }
+
+    async fn save_funding_rates(&self, rates: &[FundingRate]) -> Result<()> {
+        let mut state = self.state.lock().await;
+
+        for rate in rates {
+            state
+                .funding_rates
+                .entry(rate.symbol.clone())
+                .or_default()
+                .insert(rate.funding_time.timestamp_millis(), rate.clone());
+        }
+
+        Ok(())
+    }
+
+    async fn get_funding_rates(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<FundingRate>> {
+        let state = self.state.lock().await;
+
+        Ok(state
+            .funding_rates
+            .get(symbol)
+            .map_or_else(Vec::new, |series| {
+                series
+                    .range(start_time.timestamp_millis()..=end_time.timestamp_millis())
+                    .map(|(_, r)| r.clone())
+                    .collect()
+            }))
+    }
+
+    async fn save_signal(&self, signal: &TradingSignal) -> Result<i64> {
+        let mut state = self.state.lock().await;
+
+        let id = state.signals.len() as i64 + 1;
+        let mut signal = signal.clone();
+        signal.id = Some(id);
+        state.signals.push(SignalRecord {
+            signal,
+            outcome: None,
+            outcome_reason: None,
+        });
+
+        Ok(id)
+    }
+
+    async fn update_signal_outcome(
+        &self,
+        signal_id: i64,
+        outcome: SignalOutcome,
+        reason: &str,
+    ) -> Result<()> {
+        let mut state = self.state.lock().await;
+
+        let record = state
+            .signals
+            .iter_mut()
+            .find(|r| r.signal.id == Some(signal_id))
+            .ok_or_else(|| anyhow::anyhow!("信号不存在: {}", signal_id))?;
+        record.outcome = Some(outcome);
+        record.outcome_reason = Some(reason.to_string());
+
+        Ok(())
+    }
+
+    async fn get_signals(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        strategy: Option<&str>,
+    ) -> Result<Vec<SignalRecord>> {
+        let state = self.state.lock().await;
+
+        let mut records: Vec<SignalRecord> = state
+            .signals
+            .iter()
+            .filter(|r| {
+                r.signal.symbol == symbol
+                    && r.signal.timestamp >= start_time
+                    && r.signal.timestamp <= end_time
+                    && strategy.is_none_or(|s| r.signal.strategy == s)
+            })
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.signal.timestamp);
+
+        Ok(records)
+    }
+
+    async fn save_trade(&self, trade: &Trade) -> Result<i64> {
+        let mut state = self.state.lock().await;
+
+        let id = state.trades.len() as i64 + 1;
+        let mut trade = trade.clone();
+        trade.id = Some(id);
+        state.trades.push(trade);
+
+        Ok(id)
+    }
+
+    async fn save_trades_with_account_state(
+        &self,
+        trades: &[Trade],
+        account_state: &AccountState,
+    ) -> Result<Vec<i64>> {
+        let mut state = self.state.lock().await;
+
+        let mut trade_ids = Vec::with_capacity(trades.len());
+        for trade in trades {
+            let id = state.trades.len() as i64 + 1;
+            let mut trade = trade.clone();
+            trade.id = Some(id);
+            state.trades.push(trade);
+            trade_ids.push(id);
+        }
+        state.account_state = Some(account_state.clone());
+
+        Ok(trade_ids)
+    }
+
+    async fn get_recent_trades(&self, limit: usize) -> Result<Vec<Trade>> {
+        let state = self.state.lock().await;
+
+        let mut trades = state.trades.clone();
+        trades.sort_by_key(|t| std::cmp::Reverse(t.timestamp));
+        trades.truncate(limit);
+
+        Ok(trades)
+    }
+
+    async fn get_trades_by_time_range(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<Trade>> {
+        let state = self.state.lock().await;
+
+        let mut trades: Vec<Trade> = state
+            .trades
+            .iter()
+            .filter(|t| t.timestamp >= start_time && t.timestamp <= end_time)
+            .cloned()
+            .collect();
+        trades.sort_by_key(|t| t.timestamp);
+
+        Ok(trades)
+    }
+
+    async fn get_trades_by_signal(&self, signal_id: i64) -> Result<Vec<Trade>> {
+        let state = self.state.lock().await;
+
+        Ok(state
+            .trades
+            .iter()
+            .filter(|t| t.signal_id == Some(signal_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn save_funding_payment(&self, payment: &FundingPayment) -> Result<i64> {
+        let mut state = self.state.lock().await;
+
+        let id = state.funding_payments.len() as i64 + 1;
+        let mut payment = payment.clone();
+        payment.id = Some(id);
+        state.funding_payments.push(payment);
+
+        Ok(id)
+    }
+
+    async fn get_funding_payments(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<FundingPayment>> {
+        let state = self.state.lock().await;
+
+        let mut payments: Vec<FundingPayment> = state
+            .funding_payments
+            .iter()
+            .filter(|p| p.funding_time >= start_time && p.funding_time <= end_time)
+            .cloned()
+            .collect();
+        payments.sort_by_key(|p| p.funding_time);
+
+        Ok(payments)
+    }
+
+    async fn save_account_state(&self, account_state: &AccountState) -> Result<()> {
+        self.state.lock().await.account_state = Some(account_state.clone());
+        Ok(())
+    }
+
+    async fn load_account_state(&self) -> Result<Option<AccountState>> {
+        let state = self.state.lock().await;
+
+        Ok(state.account_state.clone().map(|mut account| {
+            account
+                .positions
+                .sort_by(|a, b| (&a.strategy_id, &a.symbol).cmp(&(&b.strategy_id, &b.symbol)));
+            account
+                .paired_positions
+                .sort_by(|a, b| a.strategy_id.cmp(&b.strategy_id));
+            account
+        }))
+    }
+
+    async fn save_strategy_instance(&self, instance: &StrategyInstance) -> Result<()> {
+        let mut state = self.state.lock().await;
+
+        match state
+            .strategy_instances
+            .iter_mut()
+            .find(|i| i.strategy_id == instance.strategy_id)
+        {
+            Some(existing) => *existing = instance.clone(),
+            None => state.strategy_instances.push(instance.clone()),
+        }
+
+        Ok(())
+    }
+
+    async fn get_strategy_instances(&self) -> Result<Vec<StrategyInstance>> {
+        Ok(self.state.lock().await.strategy_instances.clone())
+    }
+
+    async fn save_order(&self, order: &Order) -> Result<i64> {
+        let mut state = self.state.lock().await;
+
+        if state
+            .orders
+            .iter()
+            .any(|o| o.client_order_id == order.client_order_id)
+        {
+            return Err(anyhow::anyhow!(
+                "客户端订单ID重复: {}",
+                order.client_order_id
+            ));
+        }
+
+        let id = state.orders.len() as i64 + 1;
+        let mut order = order.clone();
+        order.id = Some(id);
+        state.orders.push(order);
+
+        Ok(id)
+    }
+
+    async fn update_order_state(
+        &self,
+        order_id: i64,
+        order_state: OrderState,
+        exchange_order_id: Option<&str>,
+        updated_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let mut state = self.state.lock().await;
+
+        let order = state.order_mut(order_id)?;
+        order.state = order_state;
+        if let Some(exchange_order_id) = exchange_order_id {
+            order.exchange_order_id = Some(exchange_order_id.to_string());
+        }
+        order.updated_at = updated_at;
+
+        Ok(())
+    }
+
+    async fn save_fill(&self, fill: &Fill) -> Result<i64> {
+        let mut state = self.state.lock().await;
+
+        // 先确认订单存在
+        state.order_mut(fill.order_id)?;
+
+        // 重复推送的成交明细只记录一次
+        if let Some(existing) = state.fills.iter().find(|f| {
+            f.order_id == fill.order_id
+                && f.exchange_trade_id.is_some()
+                && f.exchange_trade_id == fill.exchange_trade_id
+        }) {
+            return Ok(existing.id.unwrap_or_default());
+        }
+
+        let id = state.fills.len() as i64 + 1;
+        let mut fill = fill.clone();
+        fill.id = Some(id);
+        state.fills.push(fill.clone());
+
+        let (filled_size, notional, fee) = state
+            .fills
+            .iter()
+            .filter(|f| f.order_id == fill.order_id)
+            .fold((0.0, 0.0, 0.0), |(size, notional, fee), f| {
+                (size + f.size, notional + f.price * f.size, fee + f.fee)
+            });
+
+        let order = state.order_mut(fill.order_id)?;
+        order.filled_size = filled_size;
+        order.avg_fill_price = (filled_size > 0.0).then(|| notional / filled_size);
+        order.fee = fee;
+        order.state = filled_state(order.state, filled_size, order.size);
+        order.updated_at = fill.timestamp;
+
+        Ok(id)
+    }
+
+    async fn get_order(&self, order_id: i64) -> Result<Option<Order>> {
+        let state = self.state.lock().await;
+        Ok(state
+            .orders
+            .iter()
+            .find(|o| o.id == Some(order_id))
+            .cloned())
+    }
+
+    async fn get_order_by_client_id(&self, client_order_id: &str) -> Result<Option<Order>> {
+        let state = self.state.lock().await;
+        Ok(state
+            .orders
+            .iter()
+            .find(|o| o.client_order_id == client_order_id)
+            .cloned())
+    }
+
+    async fn get_open_orders(&self, symbol: Option<&str>) -> Result<Vec<Order>> {
+        let state = self.state.lock().await;
+
+        let mut orders: Vec<Order> = state
+            .orders
+            .iter()
+            .filter(|o| o.state.is_open() && symbol.is_none_or(|s| o.symbol == s))
+            .cloned()
+            .collect();
+        orders.sort_by_key(|o| o.created_at);
+
+        Ok(orders)
+    }
+
+    async fn get_order_history(
+        &self,
+        symbol: Option<&str>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<Order>> {
+        let state = self.state.lock().await;
+
+        let mut orders: Vec<Order> = state
+            .orders
+            .iter()
+            .filter(|o| {
+                o.created_at >= start_time
+                    && o.created_at <= end_time
+                    && symbol.is_none_or(|s| o.symbol == s)
+            })
+            .cloned()
+            .collect();
+        orders.sort_by_key(|o| o.created_at);
+
+        Ok(orders)
+    }
+
+    async fn get_orders_by_signal(&self, signal_id: i64) -> Result<Vec<Order>> {
+        let state = self.state.lock().await;
+
+        let mut orders: Vec<Order> = state
+            .orders
+            .iter()
+            .filter(|o| o.signal_id == Some(signal_id))
+            .cloned()
+            .collect();
+        orders.sort_by_key(|o| o.created_at);
+
+        Ok(orders)
+    }
+
+    async fn get_fills(&self, order_id: i64) -> Result<Vec<Fill>> {
+        let state = self.state.lock().await;
+
+        let mut fills: Vec<Fill> = state
+            .fills
+            .iter()
+            .filter(|f| f.order_id == order_id)
+            .cloned()
+            .collect();
+        fills.sort_by_key(|f| (f.timestamp, f.id));
+
+        Ok(fills)
+    }
+
+    async fn save_backtest_run(
+        &self,
+        run: &BacktestRun,
+        trades: &[Trade],
+        equity_curve: &[(DateTime<Utc>, f64)],
+    ) -> Result<i64> {
+        let mut state = self.state.lock().await;
+
+        let run_id = state.backtest_runs.len() as i64 + 1;
+        let mut run = run.clone();
+        run.id = Some(run_id);
+        state.backtest_runs.push(run);
+
+        let trades = trades
+            .iter()
+            .enumerate()
+            .map(|(i, t)| Trade {
+                id: Some(i as i64 + 1),
+                signal_id: None,
+                ..t.clone()
+            })
+            .collect();
+        state.backtest_trades.insert(run_id, trades);
+
+        // 同一时间戳只保留最后一个权益点，与SQLite主键语义一致
+        let equity: BTreeMap<DateTime<Utc>, f64> = equity_curve.iter().copied().collect();
+        state
+            .backtest_equity
+            .insert(run_id, equity.into_iter().collect());
+
+        Ok(run_id)
+    }
+
+    async fn get_backtest_runs(&self, limit: usize) -> Result<Vec<BacktestRun>> {
+        let state = self.state.lock().await;
+        Ok(state
+            .backtest_runs
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_backtest_run(&self, run_id: i64) -> Result<Option<BacktestRun>> {
+        let state = self.state.lock().await;
+        Ok(state
+            .backtest_runs
+            .iter()
+            .find(|r| r.id == Some(run_id))
+            .cloned())
+    }
+
+    async fn get_backtest_trades(&self, run_id: i64) -> Result<Vec<Trade>> {
+        let state = self.state.lock().await;
+        Ok(state
+            .backtest_trades
+            .get(&run_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn get_backtest_equity(&self, run_id: i64) -> Result<Vec<(DateTime<Utc>, f64)>> {
+        let state = self.state.lock().await;
+        Ok(state
+            .backtest_equity
+            .get(&run_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,900 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::types::{
+    AccountState, BacktestRun, CandleData, CandleIngestStats, Fill, FundingPayment, FundingRate,
+    Order, OrderState, SessionBoundary, SignalOutcome, SignalRecord, StrategyInstance, TickerData,
+    TickerSnapshot, Timeframe, Trade, TradingSignal,
+};
+
+/// 存储接口
+///
+/// 覆盖行情、信号、交易、订单、账户状态和回测记录的读写。
+/// `db::Database` 为SQLite实现，`memory_db::MemoryDatabase` 为内存实现 (用于测试和大批量回测)。
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// 保存K线数据 (同一交易对同一时间戳覆盖写入)
+    async fn save_candle(&self, candle: &CandleData) -> Result<()>;
+
+    /// 批量保存K线数据，并累计该批次的重复与乱序计数 (见 `CandleIngestStats`)
+    async fn save_candles(&self, candles: &[CandleData]) -> Result<()>;
+
+    /// 获取交易对K线写入时累计的重复与乱序计数
+    async fn get_candle_ingest_stats(&self, symbol: &str) -> Result<CandleIngestStats>;
+
+    /// 保存Ticker数据
+    async fn save_ticker(&self, ticker: &TickerData) -> Result<()>;
+
+    /// 批量保存Ticker快照
+    async fn save_ticker_snapshots(&self, tickers: &[TickerSnapshot]) -> Result<()>;
+
+    /// 获取指定时间范围的Ticker快照 (按时间正序)
+    async fn get_tickers(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<TickerSnapshot>>;
+
+    /// 获取指定时间范围的K线数据 (按时间正序)
+    async fn get_candles(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        limit: Option<usize>,
+    ) -> Result<Vec<CandleData>>;
+
+    /// 获取最新的N条K线数据 (按时间正序)
+    async fn get_latest_candles(&self, symbol: &str, count: usize) -> Result<Vec<CandleData>>;
+
+    /// 将已存储的1m K线聚合为更大周期的K线 (按时间正序)
+    ///
+    /// 时间范围作用于原始1m K线，因此首尾两根聚合K线可能不完整；
+    /// 开盘价取桶内最早一根的开盘价，收盘价取最晚一根的收盘价。
+    async fn get_resampled_candles(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        session: SessionBoundary,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<CandleData>>;
+
+    /// 获取已存储K线的全部交易对 (按名称排序)
+    async fn get_candle_symbols(&self) -> Result<Vec<String>>;
+
+    /// 批量保存资金费率 (同一交易对同一结算时间覆盖写入)
+    async fn save_funding_rates(&self, rates: &[FundingRate]) -> Result<()>;
+
+    /// 获取指定时间范围的资金费率 (按结算时间正序)
+    async fn get_funding_rates(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<FundingRate>>;
+
+    /// 保存交易信号，返回信号ID
+    async fn save_signal(&self, signal: &TradingSignal) -> Result<i64>;
+
+    /// 记录信号的处理结果
+    async fn update_signal_outcome(
+        &self,
+        signal_id: i64,
+        outcome: SignalOutcome,
+        reason: &str,
+    ) -> Result<()>;
+
+    /// 查询指定时间范围内的交易信号，可按策略过滤
+    async fn get_signals(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        strategy: Option<&str>,
+    ) -> Result<Vec<SignalRecord>>;
+
+    /// 保存交易记录，返回交易ID
+    async fn save_trade(&self, trade: &Trade) -> Result<i64>;
+
+    /// 在同一事务中保存交易记录与成交后的账户状态，返回各交易ID
+    ///
+    /// 实盘成交必须通过该方法落库，避免进程在两次写入之间退出导致交易历史与余额、持仓不一致。
+    async fn save_trades_with_account_state(
+        &self,
+        trades: &[Trade],
+        state: &AccountState,
+    ) -> Result<Vec<i64>>;
+
+    /// 获取最近的交易记录 (按时间倒序)
+    async fn get_recent_trades(&self, limit: usize) -> Result<Vec<Trade>>;
+
+    /// 获取指定时间范围内的交易记录 (按时间正序)
+    async fn get_trades_by_time_range(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<Trade>>;
+
+    /// 获取由指定信号产生的交易记录
+    async fn get_trades_by_signal(&self, signal_id: i64) -> Result<Vec<Trade>>;
+
+    /// 保存资金费收支记录，返回记录ID
+    async fn save_funding_payment(&self, payment: &FundingPayment) -> Result<i64>;
+
+    /// 获取指定结算时间范围内的资金费收支记录 (按结算时间正序)
+    async fn get_funding_payments(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<FundingPayment>>;
+
+    /// 保存账户状态 (余额与全部持仓整体覆盖)
+    async fn save_account_state(&self, state: &AccountState) -> Result<()>;
+
+    /// 加载账户状态，从未保存过时返回None
+    async fn load_account_state(&self) -> Result<Option<AccountState>>;
+
+    /// 保存策略实例定义 (同一实例ID覆盖写入)
+    async fn save_strategy_instance(&self, instance: &StrategyInstance) -> Result<()>;
+
+    /// 获取全部策略实例定义 (按首次保存的顺序)
+    async fn get_strategy_instances(&self) -> Result<Vec<StrategyInstance>>;
+
+    /// 保存新订单，返回订单ID
+    async fn save_order(&self, order: &Order) -> Result<i64>;
+
+    /// 更新订单状态
+    async fn update_order_state(
+        &self,
+        order_id: i64,
+        state: OrderState,
+        exchange_order_id: Option<&str>,
+        updated_at: DateTime<Utc>,
+    ) -> Result<()>;
+
+    /// 保存成交明细，并更新订单的成交汇总和状态
+    ///
+    /// 同一订单重复推送的成交 (exchange_trade_id相同) 只记录一次，返回已有记录的ID。
+    async fn save_fill(&self, fill: &Fill) -> Result<i64>;
+
+    /// 根据订单ID获取订单
+    async fn get_order(&self, order_id: i64) -> Result<Option<Order>>;
+
+    /// 根据客户端订单ID获取订单
+    async fn get_order_by_client_id(&self, client_order_id: &str) -> Result<Option<Order>>;
+
+    /// 获取未完结订单，symbol为None时返回全部交易对
+    async fn get_open_orders(&self, symbol: Option<&str>) -> Result<Vec<Order>>;
+
+    /// 获取指定时间范围内创建的订单历史
+    async fn get_order_history(
+        &self,
+        symbol: Option<&str>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<Order>>;
+
+    /// 获取由指定信号产生的订单
+    async fn get_orders_by_signal(&self, signal_id: i64) -> Result<Vec<Order>>;
+
+    /// 获取订单的全部成交明细
+    async fn get_fills(&self, order_id: i64) -> Result<Vec<Fill>>;
+
+    /// 保存回测记录及其交易和权益曲线，返回回测记录ID
+    async fn save_backtest_run(
+        &self,
+        run: &BacktestRun,
+        trades: &[Trade],
+        equity_curve: &[(DateTime<Utc>, f64)],
+    ) -> Result<i64>;
+
+    /// 获取最近的回测记录 (按ID倒序)
+    async fn get_backtest_runs(&self, limit: usize) -> Result<Vec<BacktestRun>>;
+
+    /// 根据ID获取回测记录
+    async fn get_backtest_run(&self, run_id: i64) -> Result<Option<BacktestRun>>;
+
+    /// 获取回测记录的交易列表
+    async fn get_backtest_trades(&self, run_id: i64) -> Result<Vec<Trade>>;
+
+    /// 获取回测记录的权益曲线
+    async fn get_backtest_equity(&self, run_id: i64) -> Result<Vec<(DateTime<Utc>, f64)>>;
+}
+
+/// 订单成交量比较的容差
+const FILL_EPSILON: f64 = 1e-12;
+
+/// 根据累计成交量判断订单状态
+///
+/// 已撤销或被拒绝的订单可能在撤单前已部分成交，迟到的成交明细只在全部成交时改为Filled，否则保留原状态。
+pub(crate) fn filled_state(current: OrderState, filled_size: f64, size: f64) -> OrderState {
+    if filled_size + FILL_EPSILON >= size {
+        OrderState::Filled
+    } else if matches!(current, OrderState::Canceled | OrderState::Rejected) {
+        current
+    } else {
+        OrderState::PartiallyFilled
+    }
+}
+
+/// 统计一批写入K线中各交易对的乱序数量
+///
+/// 批次的时间方向由该交易对首尾两根K线决定 (OKX接口按新到旧返回，归档文件按旧到新)，
+/// 逆着该方向的相邻K线计为乱序。
+pub(crate) fn count_out_of_order(candles: &[CandleData]) -> HashMap<String, usize> {
+    let mut by_symbol: HashMap<&str, Vec<i64>> = HashMap::new();
+    for candle in candles {
+        by_symbol
+            .entry(candle.symbol.as_str())
+            .or_default()
+            .push(candle.timestamp.timestamp_millis());
+    }
+
+    by_symbol
+        .into_iter()
+        .map(|(symbol, timestamps)| {
+            let descending = timestamps.first() > timestamps.last();
+            let count = timestamps
+                .windows(2)
+                .filter(|pair| {
+                    if descending {
+                        pair[1] > pair[0]
+                    } else {
+                        pair[1] < pair[0]
+                    }
+                })
+                .count();
+            (symbol.to_string(), count)
+        })
+        .collect()
+}
+
+/// 覆盖写入的K线是否为重复：已存储且数据不同，并且不是写入前最新的一根 (尚未收盘的K线会被反复更新)
+pub(crate) fn is_conflicting_rewrite(
+    existing: &CandleData,
+    candle: &CandleData,
+    latest_before: Option<i64>,
+) -> bool {
+    latest_before != Some(candle.timestamp.timestamp_millis())
+        && (
+            existing.open,
+            existing.high,
+            existing.low,
+            existing.close,
+            existing.volume,
+        ) != (
+            candle.open,
+            candle.high,
+            candle.low,
+            candle.close,
+            candle.volume,
+        )
+}
+
+/// 将按时间正序的K线聚合为指定周期
+pub(crate) fn resample_candles(
+    candles: &[CandleData],
+    timeframe: Timeframe,
+    session: SessionBoundary,
+) -> Vec<CandleData> {
+    let mut resampled: Vec<CandleData> = Vec::new();
+    let mut current_bucket = None;
+
+    for candle in candles {
+        let bucket = timeframe.bucket_start(candle.timestamp.timestamp_millis(), session);
+
+        match resampled.last_mut() {
+            Some(bar) if current_bucket == Some(bucket) => {
+                bar.high = bar.high.max(candle.high);
+                bar.low = bar.low.min(candle.low);
+                bar.close = candle.close;
+                bar.volume += candle.volume;
+            }
+            _ => {
+                current_bucket = Some(bucket);
+                resampled.push(CandleData {
+                    timestamp: DateTime::from_timestamp_millis(bucket).unwrap_or_default(),
+                    ..candle.clone()
+                });
+            }
+        }
+    }
+
+    resampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::memory_db::MemoryDatabase;
+    use crate::types::{
+        BacktestReport, OrderType, PairedPosition, Position, PositionLeg, PositionUnit, SignalType,
+        TurtleParams,
+    };
+    use chrono::Duration;
+
+    /// 需要保持一致语义的全部存储实现
+    async fn backends() -> Vec<(&'static str, Box<dyn Storage>)> {
+        vec![
+            ("sqlite", Box::new(Database::new(":memory:").await.unwrap())),
+            ("memory", Box::new(MemoryDatabase::new())),
+        ]
+    }
+
+    fn limit_order(client_order_id: &str) -> Order {
+        let now = Utc::now();
+        Order {
+            id: None,
+            client_order_id: client_order_id.to_string(),
+            exchange_order_id: None,
+            symbol: "SOL-USDT".to_string(),
+            side: "buy".to_string(),
+            order_type: OrderType::Limit,
+            price: Some(150.0),
+            size: 2.0,
+            state: OrderState::Pending,
+            filled_size: 0.0,
+            avg_fill_price: None,
+            fee: 0.0,
+            strategy: "Turtle".to_string(),
+            signal_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn fill(order_id: i64, price: f64, size: f64) -> Fill {
+        Fill {
+            id: None,
+            order_id,
+            exchange_trade_id: None,
+            price,
+            size,
+            fee: size * price * 0.001,
+            fee_ccy: "USDT".to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resampled_candles_session_boundary() {
+        for (name, db) in backends().await {
+            // 2024-01-01 15:00 UTC 起连续两小时的1m K线，跨越UTC+8零点 (16:00 UTC)
+            let start = DateTime::parse_from_rfc3339("2024-01-01T15:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc);
+            let candles: Vec<CandleData> = (0..120)
+                .map(|i| {
+                    let price = 100.0 + i as f64;
+                    CandleData {
+                        timestamp: start + chrono::Duration::minutes(i),
+                        symbol: "SOL-USDT".to_string(),
+                        open: price,
+                        high: price + 0.5,
+                        low: price - 0.5,
+                        close: price + 0.25,
+                        volume: 1.0,
+                    }
+                })
+                .collect();
+            // 倒序写入，验证开收盘价不依赖写入顺序
+            for candle in candles.iter().rev() {
+                db.save_candle(candle).await.unwrap();
+            }
+            let end = start + chrono::Duration::hours(2);
+
+            let hourly = db
+                .get_resampled_candles("SOL-USDT", Timeframe::H1, SessionBoundary::Utc, start, end)
+                .await
+                .unwrap();
+            assert_eq!(hourly.len(), 2, "{}", name);
+            assert_eq!(hourly[0].timestamp, start);
+            assert_eq!(hourly[0].open, 100.0);
+            assert_eq!(hourly[0].close, 159.25);
+            assert_eq!(hourly[0].high, 159.5);
+            assert_eq!(hourly[0].low, 99.5);
+            assert_eq!(hourly[0].volume, 60.0);
+
+            let utc_daily = db
+                .get_resampled_candles("SOL-USDT", Timeframe::D1, SessionBoundary::Utc, start, end)
+                .await
+                .unwrap();
+            assert_eq!(utc_daily.len(), 1, "{}", name);
+            assert_eq!(utc_daily[0].open, 100.0);
+            assert_eq!(utc_daily[0].close, 219.25);
+
+            let hk_daily = db
+                .get_resampled_candles("SOL-USDT", Timeframe::D1, SessionBoundary::Utc8, start, end)
+                .await
+                .unwrap();
+            assert_eq!(hk_daily.len(), 2, "{}", name);
+            assert_eq!(hk_daily[1].timestamp, start + chrono::Duration::hours(1));
+            assert_eq!(hk_daily[1].open, 160.0);
+        }
+    }
+
+    #[test]
+    fn test_weekly_bucket_starts_on_monday() {
+        // 2024-01-03 为周三，所在周从 2024-01-01 (周一) 开始
+        let ts = DateTime::parse_from_rfc3339("2024-01-03T12:00:00Z")
+            .unwrap()
+            .timestamp_millis();
+        let monday = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .timestamp_millis();
+
+        assert_eq!(Timeframe::W1.bucket_start(ts, SessionBoundary::Utc), monday);
+        assert_eq!(
+            Timeframe::W1.bucket_start(ts, SessionBoundary::Utc8),
+            monday - SessionBoundary::Utc8.offset_ms()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_order_lifecycle() {
+        for (name, db) in backends().await {
+            let order_id = db.save_order(&limit_order("t1")).await.unwrap();
+            db.update_order_state(order_id, OrderState::Live, Some("okx-1"), Utc::now())
+                .await
+                .unwrap();
+            assert_eq!(db.get_open_orders(Some("SOL-USDT")).await.unwrap().len(), 1);
+
+            db.save_fill(&fill(order_id, 149.0, 0.5)).await.unwrap();
+            let order = db.get_order(order_id).await.unwrap().unwrap();
+            assert_eq!(order.state, OrderState::PartiallyFilled, "{}", name);
+            assert_eq!(order.exchange_order_id.as_deref(), Some("okx-1"));
+
+            db.save_fill(&fill(order_id, 150.0, 1.5)).await.unwrap();
+            let order = db.get_order_by_client_id("t1").await.unwrap().unwrap();
+            assert_eq!(order.state, OrderState::Filled, "{}", name);
+            assert!((order.avg_fill_price.unwrap() - 149.75).abs() < 1e-9);
+            assert!(db.get_open_orders(None).await.unwrap().is_empty());
+            assert_eq!(db.get_fills(order_id).await.unwrap().len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_and_late_fills() {
+        for (name, db) in backends().await {
+            let order_id = db.save_order(&limit_order("t2")).await.unwrap();
+            let trade = |trade_id: &str, size: f64| Fill {
+                exchange_trade_id: Some(trade_id.to_string()),
+                ..fill(order_id, 150.0, size)
+            };
+
+            // 同一成交推送两次只计入一次
+            let first = db.save_fill(&trade("tr-1", 0.5)).await.unwrap();
+            assert_eq!(db.save_fill(&trade("tr-1", 0.5)).await.unwrap(), first);
+            let order = db.get_order(order_id).await.unwrap().unwrap();
+            assert_eq!(order.filled_size, 0.5, "{}", name);
+            assert_eq!(db.get_fills(order_id).await.unwrap().len(), 1, "{}", name);
+
+            // 撤单后迟到的部分成交不会把订单改回未完结状态
+            db.update_order_state(order_id, OrderState::Canceled, None, Utc::now())
+                .await
+                .unwrap();
+            db.save_fill(&trade("tr-2", 0.5)).await.unwrap();
+            let order = db.get_order(order_id).await.unwrap().unwrap();
+            assert_eq!(order.state, OrderState::Canceled, "{}", name);
+            assert_eq!(order.filled_size, 1.0, "{}", name);
+            assert!(db.get_open_orders(None).await.unwrap().is_empty());
+
+            // 全部成交时才改为Filled
+            db.save_fill(&trade("tr-3", 1.0)).await.unwrap();
+            let order = db.get_order(order_id).await.unwrap().unwrap();
+            assert_eq!(order.state, OrderState::Filled, "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fills_without_trade_id_all_counted() {
+        for (name, db) in backends().await {
+            let order_id = db.save_order(&limit_order("t3")).await.unwrap();
+
+            // 没有exchange_trade_id的成交无法判重，每条都计入
+            let first = db.save_fill(&fill(order_id, 150.0, 0.5)).await.unwrap();
+            let second = db.save_fill(&fill(order_id, 150.0, 0.5)).await.unwrap();
+            assert_ne!(first, second, "{}", name);
+            assert_eq!(db.get_fills(order_id).await.unwrap().len(), 2, "{}", name);
+            let order = db.get_order(order_id).await.unwrap().unwrap();
+            assert_eq!(order.filled_size, 1.0, "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_account_state_keeps_position_units() {
+        for (name, db) in backends().await {
+            let now = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+            let unit = |entry_price: f64| PositionUnit {
+                entry_price,
+                quantity: 1.0,
+                stop_price: Some(entry_price - 4.0),
+                timestamp: now,
+            };
+            let mut position = Position::open("Turtle", "SOL-USDT", unit(100.0));
+            position.add_unit(unit(101.0));
+            let expected_units = position.units.clone();
+
+            db.save_account_state(&AccountState {
+                balance: 9799.0,
+                positions: vec![position],
+                paired_positions: Vec::new(),
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+
+            let state = db.load_account_state().await.unwrap().unwrap();
+            let loaded = &state.positions[0];
+            assert_eq!(loaded.units, expected_units, "{}", name);
+            // 加仓后首个单位的止损上移到新单位的止损价
+            assert_eq!(loaded.units[0].stop_price, Some(97.0));
+            assert_eq!(loaded.stop_price(), Some(97.0));
+            assert_eq!(loaded.quantity, 2.0);
+            assert!((loaded.avg_price - 100.5).abs() < 1e-9);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_account_state_keeps_positions_per_instance() {
+        for (name, db) in backends().await {
+            let now = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+            let unit = |entry_price: f64, quantity: f64| PositionUnit {
+                entry_price,
+                quantity,
+                stop_price: None,
+                timestamp: now,
+            };
+            // 同一交易对的两个策略实例各自持仓
+            let positions = vec![
+                Position::open("Turtle", "SOL-USDT", unit(100.0, 1.0)),
+                Position::open("ma-fast", "SOL-USDT", unit(102.0, 3.0)),
+            ];
+
+            db.save_account_state(&AccountState {
+                balance: 9594.0,
+                positions,
+                paired_positions: Vec::new(),
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+
+            let mut loaded = db.load_account_state().await.unwrap().unwrap().positions;
+            loaded.sort_by(|a, b| a.strategy_id.cmp(&b.strategy_id));
+            assert_eq!(loaded.len(), 2, "{}", name);
+            assert_eq!(loaded[0].strategy_id, "Turtle", "{}", name);
+            assert_eq!(loaded[0].quantity, 1.0, "{}", name);
+            assert_eq!(loaded[1].strategy_id, "ma-fast", "{}", name);
+            assert_eq!(loaded[1].quantity, 3.0, "{}", name);
+            assert_eq!(loaded[1].avg_price, 102.0, "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_account_state_keeps_paired_positions() {
+        for (name, db) in backends().await {
+            let now = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+            let leg = |symbol: &str, quantity: f64, entry_price: f64| PositionLeg {
+                symbol: symbol.to_string(),
+                quantity,
+                entry_price,
+                current_price: entry_price,
+                margin: quantity.abs() * entry_price,
+            };
+            let paired = vec![
+                PairedPosition {
+                    strategy_id: "pairs-a".to_string(),
+                    legs: vec![leg("BTC-USDT", 0.1, 40000.0), leg("ETH-USDT", -1.5, 2500.0)],
+                    timestamp: now,
+                },
+                PairedPosition {
+                    strategy_id: "pairs-b".to_string(),
+                    legs: vec![leg("SOL-USDT", -10.0, 100.0), leg("ETH-USDT", 0.4, 2500.0)],
+                    timestamp: now,
+                },
+            ];
+
+            db.save_account_state(&AccountState {
+                balance: 10000.0,
+                positions: Vec::new(),
+                paired_positions: paired.clone(),
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+
+            let state = db.load_account_state().await.unwrap().unwrap();
+            assert_eq!(state.paired_positions, paired, "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_strategy_instances_round_trip() {
+        for (name, db) in backends().await {
+            let instance = |id: &str, kind: &str, allocation: f64| StrategyInstance {
+                strategy_id: id.to_string(),
+                kind: kind.to_string(),
+                symbol: "SOL-USDT".to_string(),
+                params: serde_json::json!({"fast_period": 5, "slow_period": 20}),
+                allocation,
+            };
+            db.save_strategy_instance(&instance("ma-b", "MaCross", 2000.0))
+                .await
+                .unwrap();
+            db.save_strategy_instance(&instance("grid-a", "Grid", 1000.0))
+                .await
+                .unwrap();
+            // 同一实例ID覆盖写入，保持首次保存的顺序
+            db.save_strategy_instance(&instance("ma-b", "MaCross", 3000.0))
+                .await
+                .unwrap();
+
+            let instances = db.get_strategy_instances().await.unwrap();
+            assert_eq!(
+                instances,
+                vec![
+                    instance("ma-b", "MaCross", 3000.0),
+                    instance("grid-a", "Grid", 1000.0)
+                ],
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trades_saved_with_account_state() {
+        for (name, db) in backends().await {
+            let now = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+            let trade = |side: &str, price: f64| Trade {
+                id: None,
+                symbol: "SOL-USDT".to_string(),
+                side: side.to_string(),
+                price,
+                quantity: 1.0,
+                timestamp: now,
+                strategy: "Turtle".to_string(),
+                strategy_id: "Turtle".to_string(),
+                pnl: None,
+                signal_id: None,
+            };
+            let unit = PositionUnit {
+                entry_price: 100.0,
+                quantity: 1.0,
+                stop_price: None,
+                timestamp: now,
+            };
+
+            let ids = db
+                .save_trades_with_account_state(
+                    &[trade("buy", 100.0), trade("buy", 101.0)],
+                    &AccountState {
+                        balance: 9799.0,
+                        positions: vec![Position::open("Turtle", "SOL-USDT", unit)],
+                        paired_positions: Vec::new(),
+                        updated_at: now,
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(ids, vec![1, 2], "{}", name);
+
+            let trades = db.get_trades_by_time_range(now, now).await.unwrap();
+            assert_eq!(trades.len(), 2, "{}", name);
+            let state = db.load_account_state().await.unwrap().unwrap();
+            assert_eq!(state.balance, 9799.0, "{}", name);
+            assert_eq!(state.positions.len(), 1, "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_funding_rates_and_payments() {
+        for (name, db) in backends().await {
+            let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+            let rate = |hours: i64, funding_rate: f64| FundingRate {
+                symbol: "SOL-USDT-SWAP".to_string(),
+                funding_time: start + Duration::hours(hours),
+                funding_rate,
+            };
+            db.save_funding_rates(&[rate(16, 0.0003), rate(0, 0.0001), rate(8, 0.0002)])
+                .await
+                .unwrap();
+            // 同一结算时间覆盖写入
+            db.save_funding_rates(&[rate(8, 0.00025)]).await.unwrap();
+
+            let rates = db
+                .get_funding_rates("SOL-USDT-SWAP", start, start + Duration::hours(8))
+                .await
+                .unwrap();
+            let values: Vec<f64> = rates.iter().map(|r| r.funding_rate).collect();
+            assert_eq!(values, vec![0.0001, 0.00025], "{}", name);
+            assert!(
+                db.get_funding_rates("SOL-USDT", start, start + Duration::days(1))
+                    .await
+                    .unwrap()
+                    .is_empty()
+            );
+
+            let payment = |hours: i64, amount: f64| FundingPayment {
+                id: None,
+                strategy_id: "arb".to_string(),
+                symbol: "SOL-USDT-SWAP".to_string(),
+                funding_time: start + Duration::hours(hours),
+                funding_rate: 0.0001,
+                quantity: -10.0,
+                price: 100.0,
+                amount,
+            };
+            db.save_funding_payment(&payment(8, 0.2)).await.unwrap();
+            let id = db.save_funding_payment(&payment(0, 0.1)).await.unwrap();
+
+            let payments = db
+                .get_funding_payments(start, start + Duration::hours(8))
+                .await
+                .unwrap();
+            let amounts: Vec<f64> = payments.iter().map(|p| p.amount).collect();
+            assert_eq!(amounts, vec![0.1, 0.2], "{}", name);
+            assert_eq!(payments[0].id, Some(id), "{}", name);
+            assert_eq!(payments[0].quantity, -10.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_funding_rate_pages_round_trip() {
+        for (name, db) in backends().await {
+            let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+            let rate = |period: i64| FundingRate {
+                symbol: "SOL-USDT-SWAP".to_string(),
+                funding_time: start + Duration::hours(8 * period),
+                funding_rate: 0.0001 * period as f64,
+            };
+            // 回填按结算时间倒序分页写入，相邻页在边界处重叠
+            let newest: Vec<FundingRate> = (5..10).rev().map(rate).collect();
+            let older: Vec<FundingRate> = (0..6).rev().map(rate).collect();
+            db.save_funding_rates(&newest).await.unwrap();
+            db.save_funding_rates(&older).await.unwrap();
+
+            let end = start + Duration::hours(8 * 9);
+            let times = |rates: Vec<FundingRate>| -> Vec<DateTime<Utc>> {
+                rates.iter().map(|r| r.funding_time).collect()
+            };
+            let all = db
+                .get_funding_rates("SOL-USDT-SWAP", start, end)
+                .await
+                .unwrap();
+            assert_eq!(
+                times(all),
+                (0..10).map(|p| rate(p).funding_time).collect::<Vec<_>>(),
+                "{}",
+                name
+            );
+
+            // 按游标增量读取时只返回游标之后到结束时间 (含) 的结算
+            let cursor = start + Duration::hours(8 * 7);
+            let after = db
+                .get_funding_rates("SOL-USDT-SWAP", cursor + Duration::milliseconds(1), end)
+                .await
+                .unwrap();
+            assert_eq!(
+                times(after),
+                vec![rate(8).funding_time, rate(9).funding_time],
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backtest_run_round_trip() {
+        for (name, db) in backends().await {
+            let now = Utc::now();
+
+            let run = BacktestRun {
+                id: None,
+                symbol: "SOL-USDT".to_string(),
+                strategy: "Turtle".to_string(),
+                params: serde_json::to_value(TurtleParams::default()).unwrap(),
+                candle_count: 2,
+                data_fingerprint: "2-0-60000-abc".to_string(),
+                code_version: "0.1.0+test".to_string(),
+                created_at: now,
+                report: BacktestReport {
+                    initial_balance: 10000.0,
+                    final_balance: 10100.0,
+                    total_return: 100.0,
+                    return_rate: 0.01,
+                    max_drawdown: 0.02,
+                    total_trades: 0,
+                    win_rate: 0.0,
+                    avg_return: 0.0,
+                    sharpe_ratio: 1.5,
+                    start_time: now,
+                    end_time: now,
+                },
+            };
+            let equity = vec![
+                (now, 10000.0),
+                (now + chrono::Duration::minutes(1), 10100.0),
+            ];
+
+            let run_id = db.save_backtest_run(&run, &[], &equity).await.unwrap();
+
+            let loaded = db.get_backtest_run(run_id).await.unwrap().unwrap();
+            assert_eq!(loaded.params["entry_period"], 20);
+            assert_eq!(loaded.strategy, "Turtle");
+            assert_eq!(loaded.report.final_balance, 10100.0);
+            assert_eq!(
+                db.get_backtest_equity(run_id).await.unwrap().len(),
+                2,
+                "{}",
+                name
+            );
+            assert_eq!(db.get_backtest_runs(10).await.unwrap().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signal_outcome_and_trade_link() {
+        for (name, db) in backends().await {
+            let now = Utc::now();
+
+            let signal = TradingSignal {
+                id: None,
+                symbol: "SOL-USDT".to_string(),
+                signal_type: SignalType::Buy,
+                price: 150.0,
+                timestamp: now,
+                strategy: "Turtle".to_string(),
+                reason: "突破".to_string(),
+                confidence: 0.7,
+                atr: Some(2.5),
+            };
+            let executed_id = db.save_signal(&signal).await.unwrap();
+            let skipped_id = db.save_signal(&signal).await.unwrap();
+
+            db.save_trade(&Trade {
+                id: None,
+                symbol: "SOL-USDT".to_string(),
+                side: "buy".to_string(),
+                price: 150.0,
+                quantity: 1.0,
+                timestamp: now,
+                strategy: "Turtle".to_string(),
+                strategy_id: "Turtle".to_string(),
+                pnl: None,
+                signal_id: Some(executed_id),
+            })
+            .await
+            .unwrap();
+            db.update_signal_outcome(executed_id, SignalOutcome::Executed, "")
+                .await
+                .unwrap();
+            db.update_signal_outcome(skipped_id, SignalOutcome::AlreadyPositioned, "已持仓")
+                .await
+                .unwrap();
+
+            let records = db
+                .get_signals("SOL-USDT", now, now, Some("Turtle"))
+                .await
+                .unwrap();
+            assert_eq!(records.len(), 2, "{}", name);
+            assert_eq!(records[1].outcome, Some(SignalOutcome::AlreadyPositioned));
+            assert_eq!(records[1].outcome_reason.as_deref(), Some("已持仓"));
+            assert_eq!(records[0].signal.atr, Some(2.5), "{}", name);
+
+            assert_eq!(db.get_trades_by_signal(executed_id).await.unwrap().len(), 1);
+            assert!(
+                db.get_trades_by_signal(skipped_id)
+                    .await
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+    }
+}
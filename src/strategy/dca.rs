@@ -0,0 +1,649 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::info;
+
+use super::{BarReplay, Strategy};
+use crate::indicators::{Indicator, MovingAverage};
+use crate::strategy_manager::MAX_POSITION_FRACTION;
+use crate::types::{CandleData, DcaParams, Position, SignalType, Trade, TradingSignal};
+
+/// 定投买入信号的策略名称
+const DCA_STRATEGY: &str = "Dca";
+
+/// 止盈卖出信号的策略名称
+const DCA_TAKE_PROFIT_STRATEGY: &str = "Dca_TakeProfit";
+
+/// 定投 (DCA) 策略
+///
+/// 按固定周期买入固定金额，不择时；可选在价格低于均线时加倍买入、限制持仓成本上限，
+/// 以及按持仓成本的涨幅分档止盈。成交经由策略管理器按普通交易和持仓记账，可与其他策略的回测结果直接比较。
+pub struct DcaStrategy {
+    params: DcaParams,                  // 策略参数
+    symbol: String,                     // 交易对
+    dip_ma: Option<MovingAverage>,      // 加倍参考均线 (未设置加倍档位时为None)
+    holding_quantity: f64,              // 持仓数量 (由成交回报维护)
+    holding_cost: f64,                  // 持仓成本 (按平均成本，部分卖出时按比例扣减)
+    take_profit_hits: usize,            // 本轮持仓已触发的止盈档位数量
+    pending_take_profit: Option<usize>, // 最新止盈信号对应的档位
+    order_amount: f64,                  // 最新买入信号的金额 (计价货币)
+    last_period: Option<i64>,           // 已处理的最新定投周期序号
+    last_bar: Option<DateTime<Utc>>,    // 已处理的最新K线时间
+}
+
+impl DcaStrategy {
+    /// 创建新的定投策略实例
+    pub fn new(symbol: String, params: Option<DcaParams>) -> Self {
+        let params = params.unwrap_or_default();
+        Self {
+            dip_ma: (!params.dip_multipliers.is_empty())
+                .then(|| MovingAverage::new(params.dip_ma_type, params.dip_ma_period)),
+            params,
+            symbol,
+            holding_quantity: 0.0,
+            holding_cost: 0.0,
+            take_profit_hits: 0,
+            pending_take_profit: None,
+            order_amount: 0.0,
+            last_period: None,
+            last_bar: None,
+        }
+    }
+
+    /// 获取策略参数
+    pub fn get_params(&self) -> &DcaParams {
+        &self.params
+    }
+
+    /// 当前持仓数量
+    pub fn holding_quantity(&self) -> f64 {
+        self.holding_quantity
+    }
+
+    /// 当前持仓成本 (计价货币)
+    pub fn invested(&self) -> f64 {
+        self.holding_cost
+    }
+
+    /// 验证策略参数
+    pub fn validate_params(params: &DcaParams) -> Result<()> {
+        if !params.quote_amount.is_finite() || params.quote_amount <= 0.0 {
+            return Err(anyhow::anyhow!("每期买入金额必须大于0"));
+        }
+
+        if params.interval_minutes <= 0 {
+            return Err(anyhow::anyhow!("定投周期必须大于0分钟"));
+        }
+
+        if !params.dip_multipliers.is_empty() && params.dip_ma_period == 0 {
+            return Err(anyhow::anyhow!("加倍参考均线周期必须大于0"));
+        }
+
+        for dip in &params.dip_multipliers {
+            if !(dip.below_pct > 0.0 && dip.below_pct < 100.0) {
+                return Err(anyhow::anyhow!("加倍档位的均线下方百分比必须在0到100之间"));
+            }
+            if !dip.multiplier.is_finite() || dip.multiplier <= 0.0 {
+                return Err(anyhow::anyhow!("加倍倍数必须大于0"));
+            }
+        }
+
+        if params
+            .max_allocation
+            .is_some_and(|cap| !cap.is_finite() || cap <= 0.0)
+        {
+            return Err(anyhow::anyhow!("持仓成本上限必须大于0"));
+        }
+
+        for (i, level) in params.take_profit.iter().enumerate() {
+            if !level.gain_pct.is_finite() || level.gain_pct <= 0.0 {
+                return Err(anyhow::anyhow!("止盈涨幅必须大于0"));
+            }
+            if !(level.sell_fraction > 0.0 && level.sell_fraction <= 1.0) {
+                return Err(anyhow::anyhow!("止盈卖出比例必须在0到1之间"));
+            }
+            if i > 0 && level.gain_pct <= params.take_profit[i - 1].gain_pct {
+                return Err(anyhow::anyhow!("止盈档位必须按涨幅从低到高排列"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// K线所在的定投周期序号 (按UTC对齐)
+    fn period_of(&self, timestamp: DateTime<Utc>) -> i64 {
+        timestamp
+            .timestamp()
+            .div_euclid(self.params.interval_minutes * 60)
+    }
+
+    /// 处理一根新收盘的K线: 先检查止盈，再在新周期的第一根K线定投
+    fn step(&mut self, latest: &CandleData) -> Vec<TradingSignal> {
+        let ma = self.dip_ma.as_mut().and_then(|ma| ma.update(&latest.close));
+        let period = self.period_of(latest.timestamp);
+
+        if let Some(signal) = self.take_profit_signal(latest) {
+            // 止盈当期不再定投
+            self.last_period = Some(period);
+            return vec![signal];
+        }
+
+        if self.last_period == Some(period) {
+            return vec![];
+        }
+        self.last_period = Some(period);
+
+        let (multiplier, dip) = self.dip_multiplier(latest.close, ma);
+        let mut amount = self.params.quote_amount * multiplier;
+        if let Some(cap) = self.params.max_allocation {
+            let remaining = cap - self.holding_cost;
+            if remaining <= 0.0 {
+                info!(
+                    "{}定投跳过: 持仓成本{:.2}已达上限{:.2}",
+                    self.symbol, self.holding_cost, cap
+                );
+                return vec![];
+            }
+            amount = amount.min(remaining);
+        }
+        self.order_amount = amount;
+
+        let mut reason = format!("定投买入{:.2}", amount);
+        if let Some(dip) = dip {
+            reason.push_str(&format!("，{}", dip));
+        }
+        if amount < self.params.quote_amount * multiplier {
+            reason.push_str("，受持仓成本上限限制");
+        }
+
+        vec![TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type: SignalType::Buy,
+            price: latest.close,
+            timestamp: latest.timestamp,
+            strategy: DCA_STRATEGY.to_string(),
+            reason,
+            confidence: 0.5,
+            atr: None,
+        }]
+    }
+
+    /// 价格低于均线时满足的最大加倍倍数，以及说明
+    fn dip_multiplier(&self, price: f64, ma: Option<f64>) -> (f64, Option<String>) {
+        let Some(ma) = ma.filter(|ma| *ma > 0.0) else {
+            return (1.0, None);
+        };
+        let below_pct = (1.0 - price / ma) * 100.0;
+        self.params
+            .dip_multipliers
+            .iter()
+            .filter(|dip| below_pct >= dip.below_pct)
+            .map(|dip| dip.multiplier)
+            .max_by(f64::total_cmp)
+            .map_or((1.0, None), |multiplier| {
+                (
+                    multiplier,
+                    Some(format!(
+                        "价格{}低于均线{:.4}达{:.2}%，{}倍买入",
+                        price, ma, below_pct, multiplier
+                    )),
+                )
+            })
+    }
+
+    /// 收盘价相对持仓成本的涨幅达到尚未触发的止盈档位时生成卖出信号 (同时达到多档时按最高档)
+    fn take_profit_signal(&mut self, latest: &CandleData) -> Option<TradingSignal> {
+        if self.holding_quantity <= 0.0 {
+            return None;
+        }
+        let avg_price = self.holding_cost / self.holding_quantity;
+        let gain_pct = (latest.close / avg_price - 1.0) * 100.0;
+        let (index, level) = self
+            .params
+            .take_profit
+            .iter()
+            .enumerate()
+            .skip(self.take_profit_hits)
+            .take_while(|(_, level)| gain_pct >= level.gain_pct)
+            .last()?;
+        self.pending_take_profit = Some(index);
+
+        Some(TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type: SignalType::Sell,
+            price: latest.close,
+            timestamp: latest.timestamp,
+            strategy: DCA_TAKE_PROFIT_STRATEGY.to_string(),
+            reason: format!(
+                "第{}档止盈：价格{}高于持仓成本{:.4}达{:.2}%，卖出{:.0}%持仓",
+                index + 1,
+                latest.close,
+                avg_price,
+                gain_pct,
+                level.sell_fraction * 100.0
+            ),
+            confidence: 0.7,
+            atr: None,
+        })
+    }
+}
+
+impl BarReplay for DcaStrategy {
+    fn last_bar_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.last_bar
+    }
+}
+
+impl Strategy for DcaStrategy {
+    fn name(&self) -> &str {
+        "Dca"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn warmup_bars(&self) -> usize {
+        // 设置加倍档位时等均线就绪后开始定投
+        if self.params.dip_multipliers.is_empty() {
+            1
+        } else {
+            self.params.dip_ma_period
+        }
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::to_value(&self.params).unwrap_or_default()
+    }
+
+    fn fresh(&self) -> Box<dyn Strategy> {
+        Box::new(DcaStrategy::new(
+            self.symbol.clone(),
+            Some(self.params.clone()),
+        ))
+    }
+
+    fn on_bar(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        self.replay_bars(candles, |strategy, bars| {
+            Ok(strategy.step(&bars[bars.len() - 1]))
+        })
+    }
+
+    fn on_fill(&mut self, trade: &Trade) {
+        if trade.side == "buy" {
+            self.holding_quantity += trade.quantity;
+            self.holding_cost += trade.quantity * trade.price;
+            return;
+        }
+
+        if let Some(index) = self.pending_take_profit.take() {
+            self.take_profit_hits = index + 1;
+        }
+        if trade.quantity >= self.holding_quantity * (1.0 - 1e-9) {
+            // 清仓后重新开始一轮，止盈档位重置
+            self.holding_quantity = 0.0;
+            self.holding_cost = 0.0;
+            self.take_profit_hits = 0;
+        } else {
+            self.holding_cost *= 1.0 - trade.quantity / self.holding_quantity;
+            self.holding_quantity -= trade.quantity;
+        }
+    }
+
+    fn position_size(
+        &self,
+        signal: &TradingSignal,
+        balance: f64,
+        _candles: &[CandleData],
+    ) -> Result<Option<f64>> {
+        if signal.price <= 0.0 {
+            return Ok(None);
+        }
+        // 资金不足时按风控允许的剩余资金买入
+        Ok(Some(
+            self.order_amount.min(balance * MAX_POSITION_FRACTION) / signal.price,
+        ))
+    }
+
+    fn exit_size(&self, signal: &TradingSignal, position: &Position) -> Option<f64> {
+        if signal.strategy != DCA_TAKE_PROFIT_STRATEGY {
+            return None;
+        }
+        let fraction = self.params.take_profit[self.pending_take_profit?].sell_fraction;
+        (fraction < 1.0).then_some(position.quantity * fraction)
+    }
+
+    fn max_units(&self) -> usize {
+        // 每期买入都是一个持仓单位，数量由持仓成本上限约束
+        usize::MAX
+    }
+
+    fn on_position_restored(&mut self, position: &Position) {
+        if position.quantity <= 0.0 {
+            return;
+        }
+
+        self.holding_quantity = position.quantity;
+        self.holding_cost = position.quantity * position.avg_price;
+        self.take_profit_hits = 0;
+        // 最后一次买入所在的周期不再重复定投
+        let last_buy = position
+            .units
+            .last()
+            .map_or(position.timestamp, |unit| unit.timestamp);
+        self.last_period = Some(self.period_of(last_buy));
+    }
+
+    fn on_trades_restored(&mut self, trades: &[Trade]) {
+        // 按成交顺序重算持仓成本，每笔止盈卖出按当时相对成本的涨幅确定触发到的档位
+        let mut quantity = 0.0;
+        let mut cost = 0.0;
+        let mut hits = 0;
+        for trade in trades {
+            if trade.side == "buy" {
+                quantity += trade.quantity;
+                cost += trade.quantity * trade.price;
+                continue;
+            }
+            if quantity <= 0.0 {
+                continue;
+            }
+            if trade.strategy == DCA_TAKE_PROFIT_STRATEGY {
+                let gain_pct = (trade.price / (cost / quantity) - 1.0) * 100.0;
+                hits += self
+                    .params
+                    .take_profit
+                    .iter()
+                    .skip(hits)
+                    .take_while(|level| gain_pct >= level.gain_pct)
+                    .count();
+            }
+            cost *= (1.0 - trade.quantity / quantity).max(0.0);
+            quantity -= trade.quantity;
+        }
+        self.take_profit_hits = hits;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::test_support::{
+        SYMBOL, backtest, check_params, flat_candles, memory_db, ticker_at,
+    };
+    use crate::strategy_manager::StrategyManager;
+    use crate::types::{DipMultiplier, PositionUnit, TakeProfitLevel};
+
+    /// 逐根K线驱动策略，买入信号按position_size立即成交，返回 (K线序号, 成交)
+    fn run(strategy: &mut DcaStrategy, candles: &[CandleData]) -> Vec<(usize, Trade)> {
+        let mut fills = Vec::new();
+        for i in 0..candles.len() {
+            for signal in strategy.on_bar(&candles[..=i]).unwrap() {
+                let quantity = strategy
+                    .position_size(&signal, 1_000_000.0, &candles[..=i])
+                    .unwrap()
+                    .unwrap();
+                let trade = Trade {
+                    id: None,
+                    symbol: "SOL-USDT".to_string(),
+                    side: "buy".to_string(),
+                    price: signal.price,
+                    quantity,
+                    timestamp: signal.timestamp,
+                    strategy: signal.strategy.clone(),
+                    strategy_id: "dca".to_string(),
+                    pnl: None,
+                    signal_id: None,
+                };
+                strategy.on_fill(&trade);
+                fills.push((i, trade));
+            }
+        }
+        fills
+    }
+
+    #[test]
+    fn test_buys_once_per_period() {
+        let params = DcaParams {
+            quote_amount: 50.0,
+            interval_minutes: 60,
+            ..DcaParams::default()
+        };
+        let mut strategy = DcaStrategy::new("SOL-USDT".to_string(), Some(params));
+        let candles = flat_candles(SYMBOL, &[100.0; 150]);
+
+        let fills = run(&mut strategy, &candles);
+        // 00:00、01:00、02:00各买入一次
+        let bars: Vec<usize> = fills.iter().map(|(i, _)| *i).collect();
+        assert_eq!(bars, vec![0, 60, 120]);
+        assert!(fills.iter().all(|(_, t)| (t.quantity - 0.5).abs() < 1e-12));
+        assert!((strategy.invested() - 150.0).abs() < 1e-9);
+
+        // 重启后恢复持仓，最后一次买入的周期内不再重复买入
+        let position = Position::open(
+            "dca",
+            "SOL-USDT",
+            PositionUnit {
+                entry_price: 100.0,
+                quantity: 1.5,
+                stop_price: None,
+                timestamp: candles[120].timestamp,
+            },
+        );
+        let mut restored = DcaStrategy::new("SOL-USDT".to_string(), Some(strategy.params.clone()));
+        restored.on_position_restored(&position);
+        assert!(restored.on_bar(&candles[121..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dip_multiplier_and_allocation_cap() {
+        let params = DcaParams {
+            quote_amount: 100.0,
+            interval_minutes: 1,
+            dip_ma_period: 3,
+            dip_multipliers: vec![
+                DipMultiplier {
+                    below_pct: 5.0,
+                    multiplier: 2.0,
+                },
+                DipMultiplier {
+                    below_pct: 15.0,
+                    multiplier: 3.0,
+                },
+            ],
+            max_allocation: Some(600.0),
+            ..DcaParams::default()
+        };
+        let mut strategy = DcaStrategy::new("SOL-USDT".to_string(), Some(params));
+        // 3周期均线约96.7时价格90 (低6.9%) 按2倍买入；均线约86.7时价格70 (低19.2%) 按3倍买入，但只剩100的额度
+        let candles = flat_candles(SYMBOL, &[100.0, 100.0, 100.0, 90.0, 70.0, 70.0]);
+
+        let amounts: Vec<f64> = run(&mut strategy, &candles)
+            .iter()
+            .map(|(_, t)| t.quantity * t.price)
+            .collect();
+        let expected = [100.0, 100.0, 100.0, 200.0, 100.0];
+        assert_eq!(amounts.len(), expected.len());
+        for (amount, expected) in amounts.iter().zip(expected) {
+            assert!((amount - expected).abs() < 1e-9);
+        }
+        // 持仓成本达到上限后跳过
+        assert!((strategy.invested() - 600.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_take_profit_levels() {
+        let db = memory_db(&flat_candles(
+            SYMBOL,
+            &[100.0, 100.0, 110.0, 110.0, 130.0, 130.0],
+        ))
+        .await;
+        let params = DcaParams {
+            quote_amount: 100.0,
+            interval_minutes: 2,
+            take_profit: vec![
+                TakeProfitLevel {
+                    gain_pct: 5.0,
+                    sell_fraction: 0.5,
+                },
+                TakeProfitLevel {
+                    gain_pct: 25.0,
+                    sell_fraction: 1.0,
+                },
+            ],
+            ..DcaParams::default()
+        };
+        let strategy = DcaStrategy::new(SYMBOL.to_string(), Some(params));
+        let (_, run, trades) = backtest(&db, Box::new(strategy)).await;
+
+        let summary: Vec<(&str, &str, f64, f64)> = trades
+            .iter()
+            .map(|t| (t.side.as_str(), t.strategy.as_str(), t.price, t.quantity))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("buy", "Dca", 100.0, 1.0),
+                // 涨10%: 第1档卖出一半，当期不定投
+                ("sell", "Dca_TakeProfit", 110.0, 0.5),
+                // 涨30%: 第2档清仓
+                ("sell", "Dca_TakeProfit", 130.0, 0.5),
+            ]
+        );
+        assert_eq!(trades[1].pnl, Some(5.0));
+        assert_eq!(trades[2].pnl, Some(15.0));
+        assert!((run.report.final_balance - 1020.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_take_profit_progress_restored() {
+        let params = DcaParams {
+            quote_amount: 100.0,
+            interval_minutes: 60,
+            take_profit: vec![
+                TakeProfitLevel {
+                    gain_pct: 5.0,
+                    sell_fraction: 0.5,
+                },
+                TakeProfitLevel {
+                    gain_pct: 25.0,
+                    sell_fraction: 1.0,
+                },
+            ],
+            ..DcaParams::default()
+        };
+        let candles = flat_candles(SYMBOL, &[100.0, 110.0, 112.0]);
+        let trade = |side: &str, strategy: &str, price: f64, i: usize| Trade {
+            id: None,
+            symbol: SYMBOL.to_string(),
+            side: side.to_string(),
+            price,
+            quantity: if side == "buy" { 1.0 } else { 0.5 },
+            timestamp: candles[i].timestamp,
+            strategy: strategy.to_string(),
+            strategy_id: "dca".to_string(),
+            pnl: None,
+            signal_id: None,
+        };
+        // 100买入1个，110触发第1档卖出一半后重启
+        let trades = vec![
+            trade("buy", DCA_STRATEGY, 100.0, 0),
+            trade("sell", DCA_TAKE_PROFIT_STRATEGY, 110.0, 1),
+        ];
+        let position = Position::open(
+            "dca",
+            SYMBOL,
+            PositionUnit {
+                entry_price: 100.0,
+                quantity: 0.5,
+                stop_price: None,
+                timestamp: candles[0].timestamp,
+            },
+        );
+
+        let mut restored = DcaStrategy::new(SYMBOL.to_string(), Some(params));
+        restored.on_position_restored(&position);
+        restored.on_trades_restored(&trades);
+        assert_eq!(restored.take_profit_hits, 1);
+        // 涨12%不再重复触发第1档
+        restored.last_bar = Some(candles[1].timestamp);
+        assert!(restored.on_bar(&candles).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_live_restart_mid_ladder() {
+        let params = DcaParams {
+            quote_amount: 100.0,
+            interval_minutes: 2,
+            max_allocation: Some(250.0),
+            ..DcaParams::default()
+        };
+        let candles = flat_candles(SYMBOL, &[100.0; 8]);
+        let db = memory_db(&candles[..2]).await;
+        let register = |manager: &mut StrategyManager| {
+            let strategy = DcaStrategy::new(SYMBOL.to_string(), Some(params.clone()));
+            manager
+                .add_strategy("dca", Box::new(strategy), 1000.0)
+                .unwrap();
+        };
+        // 写入到第minute根K线，以该K线为未收盘K线处理Ticker，返回成交数量
+        let step = async |manager: &mut StrategyManager, minute: usize| {
+            db.save_candles(&candles[..=minute]).await.unwrap();
+            manager
+                .process_real_time_data(&ticker_at(candles[minute].timestamp, 100.0))
+                .await
+                .unwrap()
+                .len()
+        };
+
+        // 第0、1个周期各买入一次后重启
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        register(&mut manager);
+        assert_eq!(step(&mut manager, 1).await, 1);
+        assert_eq!(step(&mut manager, 3).await, 1);
+
+        // 重启后回放历史: 最后一次买入的周期内不重复买入，持仓成本上限按恢复的持仓计算
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        register(&mut manager);
+        assert_eq!(manager.get_positions()["dca"].units.len(), 2);
+        assert_eq!(step(&mut manager, 4).await, 0);
+        assert_eq!(step(&mut manager, 5).await, 1);
+        assert_eq!(step(&mut manager, 7).await, 0);
+
+        let trades = db
+            .get_trades_by_time_range(DateTime::<Utc>::UNIX_EPOCH, Utc::now())
+            .await
+            .unwrap();
+        let amounts: Vec<f64> = trades.iter().map(|t| t.price * t.quantity).collect();
+        assert_eq!(amounts, vec![100.0, 100.0, 50.0]);
+        assert_eq!(trades[2].timestamp, candles[4].timestamp);
+    }
+
+    #[test]
+    fn test_validate_params() {
+        check_params(
+            DcaStrategy::validate_params,
+            &DcaParams::default(),
+            [
+                DcaParams {
+                    interval_minutes: 0,
+                    ..DcaParams::default()
+                },
+                DcaParams {
+                    take_profit: vec![
+                        TakeProfitLevel {
+                            gain_pct: 20.0,
+                            sell_fraction: 0.5,
+                        },
+                        TakeProfitLevel {
+                            gain_pct: 10.0,
+                            sell_fraction: 1.0,
+                        },
+                    ],
+                    ..DcaParams::default()
+                },
+            ],
+        );
+    }
+}
@@ -0,0 +1,785 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+
+use super::{BarReplay, Strategy};
+use crate::strategy_manager::MAX_POSITION_FRACTION;
+use crate::types::{
+    CandleData, FundingArbParams, FundingRate, MultiLegAction, MultiLegSignal, PairedPosition,
+    SignalLeg, SignalType, Trade, TradingSignal,
+};
+
+/// 期现开仓信号的策略名称
+const FUNDING_ARB_STRATEGY: &str = "FundingArb";
+
+/// 期现平仓信号的策略名称
+const FUNDING_ARB_EXIT_STRATEGY: &str = "FundingArb_Exit";
+
+/// 距强平价低于该倍数的阈值时输出预警日志
+const MARGIN_WARNING_MULTIPLE: f64 = 2.0;
+
+/// 永续空头腿的逐仓保证金状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PerpMargin {
+    pub margin: f64,               // 逐仓保证金 (开仓名义价值 / 杠杆)
+    pub maintenance_margin: f64,   // 维持保证金 (当前名义价值 × 维持保证金率)
+    pub margin_ratio: f64,         // 保证金率 (保证金 + 未实现盈亏) / 维持保证金，不高于1时强平
+    pub liquidation_price: f64,    // 强平价
+    pub liquidation_distance: f64, // 当前价距强平价的比例 (强平价 / 当前价 - 1)
+}
+
+impl PerpMargin {
+    /// 按开仓价、数量 (正数) 与当前价计算逐仓空头的保证金状态
+    ///
+    /// 空头权益 = 保证金 + (开仓价 - 当前价) × 数量，降到维持保证金时强平，
+    /// 解得强平价 = 开仓价 × (1 + 1/杠杆) / (1 + 维持保证金率)。
+    pub fn short(
+        entry_price: f64,
+        quantity: f64,
+        price: f64,
+        leverage: f64,
+        maintenance_margin_rate: f64,
+    ) -> Self {
+        let margin = entry_price * quantity / leverage;
+        let maintenance_margin = price * quantity * maintenance_margin_rate;
+        let equity = margin + (entry_price - price) * quantity;
+        let liquidation_price =
+            entry_price * (1.0 + 1.0 / leverage) / (1.0 + maintenance_margin_rate);
+
+        Self {
+            margin,
+            maintenance_margin,
+            margin_ratio: equity / maintenance_margin,
+            liquidation_price,
+            liquidation_distance: liquidation_price / price - 1.0,
+        }
+    }
+}
+
+/// 期现资金费率套利策略
+///
+/// 第一腿为现货 (策略的交易对)，第二腿为USDT本位永续合约 (`perp_symbol`)。
+/// 预测资金费率 (最近 `forecast_periods` 次结算费率的均值) 达到 `entry_funding` 时
+/// 买入现货并做空等量永续，持仓期间空头腿收取资金费；预测费率回落到 `exit_funding` 以下，
+/// 或永续腿距强平价不足 `min_liquidation_distance` 时两腿同时平仓。
+pub struct FundingArbStrategy {
+    params: FundingArbParams,            // 策略参数
+    symbol: String,                      // 现货交易对
+    rates: VecDeque<f64>,                // 最近的结算资金费率
+    last_funding: Option<DateTime<Utc>>, // 已处理的最新结算时间
+    perp_entry: Option<(f64, f64)>,      // 永续空头的 (开仓价, 数量)，由成交回报维护
+    margin: Option<PerpMargin>,          // 最新的永续腿保证金状态
+    last_bar: Option<DateTime<Utc>>,     // 已处理的最新K线时间
+}
+
+impl FundingArbStrategy {
+    /// 创建新的期现套利策略实例
+    pub fn new(symbol: String, params: Option<FundingArbParams>) -> Self {
+        let params = params.unwrap_or_default();
+        Self {
+            rates: VecDeque::with_capacity(params.forecast_periods + 1),
+            params,
+            symbol,
+            last_funding: None,
+            perp_entry: None,
+            margin: None,
+            last_bar: None,
+        }
+    }
+
+    /// 获取策略参数
+    pub fn get_params(&self) -> &FundingArbParams {
+        &self.params
+    }
+
+    /// 预测资金费率 (最近forecast_periods次结算费率的均值)，结算记录不足时为None
+    pub fn predicted_funding(&self) -> Option<f64> {
+        (self.rates.len() == self.params.forecast_periods)
+            .then(|| self.rates.iter().sum::<f64>() / self.rates.len() as f64)
+    }
+
+    /// 最新的永续腿保证金状态，未持仓时为None
+    pub fn perp_margin(&self) -> Option<PerpMargin> {
+        self.margin
+    }
+
+    /// 是否持有期现配对持仓
+    pub fn is_hedged(&self) -> bool {
+        self.perp_entry.is_some()
+    }
+
+    /// 验证策略参数
+    pub fn validate_params(params: &FundingArbParams) -> Result<()> {
+        if params.perp_symbol.is_empty() {
+            return Err(anyhow::anyhow!("永续合约交易对不能为空"));
+        }
+
+        if !params.entry_funding.is_finite() || params.entry_funding <= 0.0 {
+            return Err(anyhow::anyhow!("开仓资金费率必须大于0"));
+        }
+
+        if !(params.exit_funding.is_finite() && params.exit_funding < params.entry_funding) {
+            return Err(anyhow::anyhow!("平仓资金费率必须低于开仓资金费率"));
+        }
+
+        if params.forecast_periods == 0 {
+            return Err(anyhow::anyhow!("预测资金费率至少需要1次结算记录"));
+        }
+
+        if !params.leverage.is_finite() || params.leverage < 1.0 {
+            return Err(anyhow::anyhow!("永续腿杠杆倍数不能小于1"));
+        }
+
+        if !(params.maintenance_margin_rate > 0.0 && params.maintenance_margin_rate < 1.0) {
+            return Err(anyhow::anyhow!("维持保证金率必须在0到1之间"));
+        }
+
+        if !(params.min_liquidation_distance >= 0.0 && params.min_liquidation_distance < 1.0) {
+            return Err(anyhow::anyhow!("强平距离阈值必须在0到1之间"));
+        }
+
+        if params.position_fraction <= 0.0 || params.position_fraction > MAX_POSITION_FRACTION {
+            return Err(anyhow::anyhow!(
+                "开仓资金比例必须在0到{}之间",
+                MAX_POSITION_FRACTION
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 处理两腿同一时间收盘的K线，更新保证金状态并判断开仓/平仓
+    fn step(&mut self, spot_bar: &CandleData, perp_bar: &CandleData) -> Option<MultiLegSignal> {
+        let predicted = self.predicted_funding();
+
+        let Some((entry_price, quantity)) = self.perp_entry else {
+            let predicted = predicted?; // 结算记录不足，无法预测
+            if predicted < self.params.entry_funding {
+                return None;
+            }
+            let reason = format!(
+                "预测资金费率{:.4}%不低于{:.4}%，买入现货并做空永续",
+                predicted * 100.0,
+                self.params.entry_funding * 100.0
+            );
+            return Some(self.signal(
+                MultiLegAction::Open,
+                spot_bar,
+                perp_bar,
+                FUNDING_ARB_STRATEGY,
+                &reason,
+                0.7,
+            ));
+        };
+
+        let margin = PerpMargin::short(
+            entry_price,
+            quantity,
+            perp_bar.close,
+            self.params.leverage,
+            self.params.maintenance_margin_rate,
+        );
+        self.margin = Some(margin);
+
+        let (reason, confidence) =
+            if margin.liquidation_distance < self.params.min_liquidation_distance {
+                (
+                    format!(
+                        "永续腿距强平价{:.4}仅{:.2}%，保证金率{:.2}，平仓避免强平",
+                        margin.liquidation_price,
+                        margin.liquidation_distance * 100.0,
+                        margin.margin_ratio
+                    ),
+                    0.9,
+                )
+            } else if let Some(predicted) = predicted
+                && predicted <= self.params.exit_funding
+            {
+                (
+                    format!(
+                        "预测资金费率{:.4}%回落到{:.4}%以下，平仓",
+                        predicted * 100.0,
+                        self.params.exit_funding * 100.0
+                    ),
+                    0.8,
+                )
+            } else {
+                if margin.liquidation_distance
+                    < self.params.min_liquidation_distance * MARGIN_WARNING_MULTIPLE
+                {
+                    warn!(
+                        "{}距强平价{:.4}仅{:.2}%，保证金率{:.2}",
+                        self.params.perp_symbol,
+                        margin.liquidation_price,
+                        margin.liquidation_distance * 100.0,
+                        margin.margin_ratio
+                    );
+                }
+                return None;
+            };
+
+        Some(self.signal(
+            MultiLegAction::Close,
+            spot_bar,
+            perp_bar,
+            FUNDING_ARB_EXIT_STRATEGY,
+            &reason,
+            confidence,
+        ))
+    }
+
+    /// 生成两腿信号: 开仓买现货、卖永续，平仓反向
+    fn signal(
+        &self,
+        action: MultiLegAction,
+        spot_bar: &CandleData,
+        perp_bar: &CandleData,
+        strategy: &str,
+        reason: &str,
+        confidence: f64,
+    ) -> MultiLegSignal {
+        let (spot_type, perp_type) = match action {
+            MultiLegAction::Open => (SignalType::Buy, SignalType::Sell),
+            MultiLegAction::Close => (SignalType::Sell, SignalType::Buy),
+        };
+        let leg = |bar: &CandleData, signal_type: SignalType| SignalLeg {
+            signal: TradingSignal {
+                id: None,
+                symbol: bar.symbol.clone(),
+                signal_type,
+                price: bar.close,
+                timestamp: bar.timestamp,
+                strategy: strategy.to_string(),
+                reason: reason.to_string(),
+                confidence,
+                atr: None,
+            },
+            ratio: 1.0,
+        };
+
+        MultiLegSignal {
+            action,
+            legs: vec![leg(spot_bar, spot_type), leg(perp_bar, perp_type)],
+        }
+    }
+}
+
+impl BarReplay for FundingArbStrategy {
+    fn last_bar_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.last_bar
+    }
+}
+
+impl Strategy for FundingArbStrategy {
+    fn name(&self) -> &str {
+        "FundingArb"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn warmup_bars(&self) -> usize {
+        1
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::to_value(&self.params).unwrap_or_default()
+    }
+
+    fn fresh(&self) -> Box<dyn Strategy> {
+        Box::new(FundingArbStrategy::new(
+            self.symbol.clone(),
+            Some(self.params.clone()),
+        ))
+    }
+
+    fn on_bar(&mut self, _candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        // 期现套利策略由 on_legs_bar 驱动
+        Ok(Vec::new())
+    }
+
+    fn on_legs_bar(&mut self, legs: &[&[CandleData]]) -> Result<Vec<MultiLegSignal>> {
+        let [spot_bars, perp_bars] = legs else {
+            return Err(anyhow::anyhow!(
+                "期现套利策略需要2个交易对的K线，实际为{}个",
+                legs.len()
+            ));
+        };
+
+        // 资金费率不随K线回放，首次调用只处理最新一根K线
+        if self.last_bar.is_none() {
+            self.last_bar = spot_bars.iter().rev().nth(1).map(|c| c.timestamp);
+        }
+
+        // 只使用两腿时间相同的K线
+        self.replay_bars(spot_bars, |strategy, bars| {
+            let spot_bar = &bars[bars.len() - 1];
+            Ok(
+                match perp_bars.binary_search_by_key(&spot_bar.timestamp, |c| c.timestamp) {
+                    Ok(index) => strategy
+                        .step(spot_bar, &perp_bars[index])
+                        .into_iter()
+                        .collect(),
+                    Err(_) => Vec::new(),
+                },
+            )
+        })
+    }
+
+    fn on_funding(&mut self, rate: &FundingRate) {
+        if rate.symbol != self.params.perp_symbol
+            || self
+                .last_funding
+                .is_some_and(|last| rate.funding_time <= last)
+        {
+            return;
+        }
+        self.last_funding = Some(rate.funding_time);
+        self.rates.push_back(rate.funding_rate);
+        if self.rates.len() > self.params.forecast_periods {
+            self.rates.pop_front();
+        }
+    }
+
+    fn on_fill(&mut self, trade: &Trade) {
+        if trade.symbol != self.params.perp_symbol {
+            return;
+        }
+        match (trade.strategy.as_str(), trade.side.as_str()) {
+            (FUNDING_ARB_STRATEGY, "sell") => {
+                self.perp_entry = Some((trade.price, trade.quantity));
+                self.margin = Some(PerpMargin::short(
+                    trade.price,
+                    trade.quantity,
+                    trade.price,
+                    self.params.leverage,
+                    self.params.maintenance_margin_rate,
+                ));
+                info!(
+                    "{}空头开仓 {:.4} @ {:.4}，保证金{:.2}，强平价{:.4}",
+                    trade.symbol,
+                    trade.quantity,
+                    trade.price,
+                    trade.quantity * trade.price / self.params.leverage,
+                    self.margin.map_or(0.0, |m| m.liquidation_price)
+                );
+            }
+            _ => {
+                self.perp_entry = None;
+                self.margin = None;
+            }
+        }
+    }
+
+    fn position_size(
+        &self,
+        _signal: &TradingSignal,
+        _balance: f64,
+        _candles: &[CandleData],
+    ) -> Result<Option<f64>> {
+        Ok(None)
+    }
+
+    fn leg_symbols(&self) -> Vec<String> {
+        vec![self.symbol.clone(), self.params.perp_symbol.clone()]
+    }
+
+    fn funding_symbols(&self) -> Vec<String> {
+        vec![self.params.perp_symbol.clone()]
+    }
+
+    fn legs_size(&self, signal: &MultiLegSignal, balance: f64) -> Result<Option<f64>> {
+        // 两腿数量相同 (delta中性)，按现货成本加永续保证金分配资金
+        let required: f64 = signal
+            .legs
+            .iter()
+            .map(|l| l.ratio * l.signal.price / self.leg_leverage(&l.signal.symbol))
+            .sum();
+        if required <= 0.0 {
+            return Ok(None);
+        }
+        Ok(Some(balance * self.params.position_fraction / required))
+    }
+
+    fn leg_leverage(&self, symbol: &str) -> f64 {
+        if symbol == self.params.perp_symbol {
+            self.params.leverage
+        } else {
+            1.0
+        }
+    }
+
+    fn on_paired_position_restored(&mut self, position: &PairedPosition) {
+        let Some(leg) = position
+            .legs
+            .iter()
+            .find(|l| l.symbol == self.params.perp_symbol && l.quantity < 0.0)
+        else {
+            return;
+        };
+        self.perp_entry = Some((leg.entry_price, -leg.quantity));
+        self.margin = Some(PerpMargin::short(
+            leg.entry_price,
+            -leg.quantity,
+            leg.current_price,
+            self.params.leverage,
+            self.params.maintenance_margin_rate,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_db::MemoryDatabase;
+    use crate::storage::Storage;
+    use crate::strategy::test_support::{backtest, base_time, check_params, memory_db, ticker_at};
+    use crate::strategy_manager::{DEFAULT_STRATEGY_ID, StrategyManager};
+    use chrono::Duration;
+    use std::sync::Arc;
+
+    const SPOT: &str = "SOL-USDT";
+    const PERP: &str = "SOL-USDT-SWAP";
+
+    fn bar(symbol: &str, time: DateTime<Utc>, close: f64) -> CandleData {
+        CandleData {
+            timestamp: time,
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000.0,
+        }
+    }
+
+    fn rate(time: DateTime<Utc>, funding_rate: f64) -> FundingRate {
+        FundingRate {
+            symbol: PERP.to_string(),
+            funding_time: time,
+            funding_rate,
+        }
+    }
+
+    fn fill(side: &str, strategy: &str, price: f64, quantity: f64) -> Trade {
+        Trade {
+            id: None,
+            symbol: PERP.to_string(),
+            side: side.to_string(),
+            price,
+            quantity,
+            timestamp: base_time(),
+            strategy: strategy.to_string(),
+            strategy_id: "arb".to_string(),
+            pnl: None,
+            signal_id: None,
+        }
+    }
+
+    #[test]
+    fn test_perp_margin_liquidation_price() {
+        let margin = PerpMargin::short(100.0, 2.0, 100.0, 3.0, 0.005);
+        assert!((margin.margin - 200.0 / 3.0).abs() < 1e-9);
+        assert!((margin.liquidation_price - 100.0 * (4.0 / 3.0) / 1.005).abs() < 1e-9);
+        assert!(margin.margin_ratio > 60.0);
+
+        // 价格涨到强平价时权益恰好等于维持保证金
+        let at_liquidation = PerpMargin::short(100.0, 2.0, margin.liquidation_price, 3.0, 0.005);
+        assert!((at_liquidation.margin_ratio - 1.0).abs() < 1e-9);
+        assert!(at_liquidation.liquidation_distance.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_enters_on_funding_and_exits_on_compression_or_liquidation_risk() {
+        let mut strategy = FundingArbStrategy::new(SPOT.to_string(), None);
+        let t = base_time();
+        let step = |strategy: &mut FundingArbStrategy, minutes: i64, perp: f64| {
+            let time = t + Duration::minutes(minutes);
+            let spot_bars = vec![bar(SPOT, time, 100.0)];
+            let perp_bars = vec![bar(PERP, time, perp)];
+            strategy.on_legs_bar(&[&spot_bars, &perp_bars]).unwrap()
+        };
+
+        // 结算记录不足forecast_periods次时不开仓
+        strategy.on_funding(&rate(t - Duration::hours(16), 0.0005));
+        strategy.on_funding(&rate(t - Duration::hours(8), 0.0004));
+        assert!(step(&mut strategy, 0, 100.0).is_empty());
+
+        // 重复推送的结算记录被忽略
+        strategy.on_funding(&rate(t - Duration::hours(8), 0.0004));
+        strategy.on_funding(&rate(t, 0.0003));
+        assert!((strategy.predicted_funding().unwrap() - 0.0004).abs() < 1e-12);
+        let signals = step(&mut strategy, 1, 100.0);
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].action, MultiLegAction::Open);
+        assert_eq!(
+            signals[0]
+                .legs
+                .iter()
+                .map(|l| (l.signal.symbol.as_str(), l.signal.signal_type))
+                .collect::<Vec<_>>(),
+            vec![(SPOT, SignalType::Buy), (PERP, SignalType::Sell)]
+        );
+        // 现货成本加永续保证金 (3倍杠杆) 合计占可用资金的position_fraction
+        let size = strategy.legs_size(&signals[0], 1000.0).unwrap().unwrap();
+        assert!((size - 1000.0 * 0.45 / (100.0 + 100.0 / 3.0)).abs() < 1e-9);
+
+        strategy.on_fill(&fill("sell", FUNDING_ARB_STRATEGY, 100.0, 2.0));
+        assert!(strategy.is_hedged());
+
+        // 费率仍高于平仓阈值时继续持有，永续上涨逼近强平价时平仓
+        assert!(step(&mut strategy, 2, 110.0).is_empty());
+        let signals = step(&mut strategy, 3, 125.0);
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].action, MultiLegAction::Close);
+        assert_eq!(
+            signals[0].legs[0].signal.strategy,
+            FUNDING_ARB_EXIT_STRATEGY
+        );
+        assert!(signals[0].legs[0].signal.reason.contains("强平"));
+        let margin = strategy.perp_margin().unwrap();
+        assert!(margin.liquidation_distance < 0.1);
+
+        // 重新开仓后资金费率回落到平仓阈值以下时平仓
+        strategy.on_fill(&fill("buy", FUNDING_ARB_EXIT_STRATEGY, 125.0, 2.0));
+        assert!(!strategy.is_hedged());
+        strategy.on_fill(&fill("sell", FUNDING_ARB_STRATEGY, 100.0, 2.0));
+        for hours in [8, 16, 24] {
+            strategy.on_funding(&rate(t + Duration::hours(hours), 0.00001));
+        }
+        let signals = step(&mut strategy, 4, 100.0);
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].action, MultiLegAction::Close);
+        assert!(signals[0].legs[0].signal.reason.contains("资金费率"));
+    }
+
+    #[tokio::test]
+    async fn test_backtest_accrues_funding_into_equity() {
+        let t = base_time();
+        let candles: Vec<CandleData> = (0..72)
+            .flat_map(|hour| {
+                let time = t + Duration::hours(hour);
+                [bar(SPOT, time, 100.0), bar(PERP, time, 100.0)]
+            })
+            .collect();
+        let db = memory_db(&candles).await;
+        let rates: Vec<FundingRate> = [0.0005, 0.0005, 0.0005, 0.0005, 0.0005, -0.0002, -0.0002]
+            .iter()
+            .enumerate()
+            .map(|(i, &r)| rate(t + Duration::hours(8 * i as i64), r))
+            .collect();
+        db.save_funding_rates(&rates).await.unwrap();
+
+        let strategy = FundingArbStrategy::new(SPOT.to_string(), None);
+        let (manager, run, trades) = backtest(&db, Box::new(strategy)).await;
+
+        // 第3次结算后开仓，预测费率在第7次结算后回落到平仓阈值以下
+        assert_eq!(
+            trades
+                .iter()
+                .map(|t| (t.symbol.as_str(), t.side.as_str(), t.strategy.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (SPOT, "buy", "FundingArb"),
+                (PERP, "sell", "FundingArb"),
+                (SPOT, "sell", "FundingArb_Exit"),
+                (PERP, "buy", "FundingArb_Exit"),
+            ]
+        );
+        assert_eq!(trades[0].timestamp, t + Duration::hours(16));
+        assert_eq!(trades[2].timestamp, t + Duration::hours(48));
+        assert!((trades[1].quantity - 3.375).abs() < 1e-9);
+
+        // 价格不变，收益全部来自持仓期间4次结算的资金费: 2 × 0.0005 - 2 × 0.0002
+        let funding = 3.375 * 100.0 * (2.0 * 0.0005 - 2.0 * 0.0002);
+        assert!((run.report.final_balance - (1000.0 + funding)).abs() < 1e-9);
+        assert!(manager.get_paired_positions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_live_funding_payments_survive_restart() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let t = base_time();
+        for minute in 0..3 {
+            let time = t + Duration::minutes(minute);
+            db.save_candles(&[bar(SPOT, time, 100.0), bar(PERP, time, 100.0)])
+                .await
+                .unwrap();
+        }
+        db.save_funding_rates(&[
+            rate(t - Duration::hours(16), 0.0005),
+            rate(t - Duration::hours(8), 0.0005),
+            rate(t, 0.0005),
+        ])
+        .await
+        .unwrap();
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        manager
+            .add_strategy(
+                "arb",
+                Box::new(FundingArbStrategy::new(SPOT.to_string(), None)),
+                1000.0,
+            )
+            .unwrap();
+        // 数据收集按已注册实例的永续腿采集资金费率
+        assert_eq!(
+            manager.funding_symbols().into_iter().collect::<Vec<_>>(),
+            vec![PERP.to_string()]
+        );
+
+        // 启动时的历史费率只用于预测，开仓前的结算不记入资金费
+        let executed = manager
+            .process_real_time_data(&ticker_at(t + Duration::minutes(2), 100.0))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 2);
+        // 现货买入3.375个冻结337.5，永续空头冻结保证金112.5
+        assert!((manager.get_balance() - (10000.0 - 450.0)).abs() < 1e-9);
+        assert!(
+            db.get_funding_payments(DateTime::<Utc>::UNIX_EPOCH, Utc::now())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // 持仓期间的结算: 空头3.375个收取 3.375 × 100 × 0.001
+        db.save_candles(&[
+            bar(SPOT, t + Duration::minutes(3), 100.0),
+            bar(PERP, t + Duration::minutes(3), 100.0),
+        ])
+        .await
+        .unwrap();
+        db.save_funding_rates(&[rate(t + Duration::minutes(2), 0.001)])
+            .await
+            .unwrap();
+        let executed = manager
+            .process_real_time_data(&ticker_at(t + Duration::minutes(3), 100.0))
+            .await
+            .unwrap();
+        assert!(executed.is_empty());
+        assert!((manager.get_balance() - (10000.0 - 450.0 + 0.3375)).abs() < 1e-9);
+        let payments = db
+            .get_funding_payments(DateTime::<Utc>::UNIX_EPOCH, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(payments.len(), 1);
+        assert!((payments[0].quantity + 3.375).abs() < 1e-9);
+
+        // 重启后资金费计入已实现盈亏，可用资金扣除现货成本与永续保证金
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        manager
+            .add_strategy(
+                "arb",
+                Box::new(FundingArbStrategy::new(SPOT.to_string(), None)),
+                1000.0,
+            )
+            .unwrap();
+        let summary = manager.portfolio_summary();
+        let arb = &summary.strategies[0];
+        assert!((arb.realized_pnl - 0.3375).abs() < 1e-9);
+        assert!((arb.available - (1000.0 + 0.3375 - 450.0)).abs() < 1e-9);
+        assert!((summary.equity() - (10000.0 + 0.3375)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_restored_hedge_exits_near_liquidation() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let t = base_time();
+        let save_bars = async |minute: i64, perp: f64| {
+            let time = t + Duration::minutes(minute);
+            db.save_candles(&[bar(SPOT, time, 100.0), bar(PERP, time, perp)])
+                .await
+                .unwrap();
+        };
+        for minute in 0..3 {
+            save_bars(minute, 100.0).await;
+        }
+        db.save_funding_rates(&[
+            rate(t - Duration::hours(16), 0.0005),
+            rate(t - Duration::hours(8), 0.0005),
+            rate(t, 0.0005),
+        ])
+        .await
+        .unwrap();
+        let register = |manager: &mut StrategyManager| {
+            manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+            manager
+                .add_strategy(
+                    "arb",
+                    Box::new(FundingArbStrategy::new(SPOT.to_string(), None)),
+                    1000.0,
+                )
+                .unwrap();
+        };
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        register(&mut manager);
+        let executed = manager
+            .process_real_time_data(&ticker_at(t + Duration::minutes(2), 100.0))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 2);
+
+        // 重启后按恢复的永续空头计算强平价 100 × (1 + 1/3) / 1.005 ≈ 132.67
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        register(&mut manager);
+
+        // 永续涨到115: 距强平价约15%，低于阈值两倍只预警，继续持有
+        save_bars(3, 115.0).await;
+        save_bars(4, 125.0).await;
+        assert!(
+            manager
+                .process_real_time_data(&ticker_at(t + Duration::minutes(4), 100.0))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(manager.get_paired_positions().contains_key("arb"));
+
+        // 永续涨到125: 距强平价约6%，预测费率仍高于平仓阈值也两腿同时平仓
+        save_bars(5, 125.0).await;
+        let executed = manager
+            .process_real_time_data(&ticker_at(t + Duration::minutes(5), 100.0))
+            .await
+            .unwrap();
+        assert_eq!(
+            executed
+                .iter()
+                .map(|s| (s.symbol.as_str(), s.signal_type, s.strategy.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (SPOT, SignalType::Sell, FUNDING_ARB_EXIT_STRATEGY),
+                (PERP, SignalType::Buy, FUNDING_ARB_EXIT_STRATEGY),
+            ]
+        );
+        assert!(executed[0].reason.contains("强平"));
+        assert!(manager.get_paired_positions().is_empty());
+        // 永续空头3.375个从100涨到125的亏损
+        assert!((manager.get_balance() - (10000.0 - 3.375 * 25.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_validate_params() {
+        check_params(
+            FundingArbStrategy::validate_params,
+            &FundingArbParams::default(),
+            [
+                FundingArbParams {
+                    exit_funding: 0.001,
+                    ..FundingArbParams::default()
+                },
+                FundingArbParams {
+                    leverage: 0.5,
+                    ..FundingArbParams::default()
+                },
+            ],
+        );
+        assert!(crate::strategy::create_strategy("FundingArb", PERP, None).is_err());
+    }
+}
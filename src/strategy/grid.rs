@@ -0,0 +1,989 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+
+use super::{BarReplay, Strategy};
+use crate::strategy_manager::STOP_LOSS_STRATEGY;
+use crate::types::{
+    CandleData, GridParams, GridSpacing, Position, RestingOrder, SignalType, TickerSnapshot, Trade,
+    TradingSignal,
+};
+
+/// 网格买卖信号的策略名称
+const GRID_STRATEGY: &str = "Grid";
+
+/// 重建网格前清仓信号的策略名称
+const GRID_REBALANCE_STRATEGY: &str = "Grid_Rebalance";
+
+/// 一格当前挂出的订单
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GridSlot {
+    Idle,                         // 未挂单 (下沿不低于当前价，或网格已停止)
+    Buying,                       // 在下沿挂买单
+    Selling { entry_price: f64 }, // 已买入，在上沿挂卖单
+}
+
+/// 网格中的一格 (相邻两个价位之间)
+#[derive(Debug, Clone, Copy)]
+struct GridCell {
+    slot: GridSlot,  // 挂单状态
+    submitted: bool, // 本根K线内已发出成交信号 (避免Ticker与K线重复发出)
+}
+
+/// 网格挂单
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridOrder {
+    pub cell: usize,      // 所在网格序号 (从下往上，从0开始)
+    pub side: SignalType, // 买卖方向
+    pub price: f64,       // 挂单价格
+    pub quantity: f64,    // 挂单数量
+}
+
+/// 网格交易策略
+///
+/// 策略自身维护挂单阶梯: 价格触及挂单价时发出该价位的买卖信号，成交回报到达后把该格换成反向挂单；
+/// 回测按K线最高/最低价撮合，实盘 (模拟成交) 只按Ticker最新价撮合，收盘K线不再重复撮合同一段价格。
+/// 实盘挂单由策略管理器同步到订单表，卖出的盈亏按该格的买入价计算。
+pub struct GridStrategy {
+    params: GridParams,              // 策略参数
+    symbol: String,                  // 交易对
+    levels: Vec<f64>,                // 当前价位 (grid_count+1个，从低到高)
+    cells: Vec<GridCell>,            // 各格挂单
+    stop_price: Option<f64>,         // 当前止损价 (重建网格时随区间平移)
+    stopped: bool,                   // 已止损停止
+    bars_outside: usize,             // 连续收盘在区间外的K线数量
+    grid_profit: f64,                // 已实现网格利润
+    completed_grids: usize,          // 完成的买卖配对次数
+    tick_driven: bool,               // 已收到Ticker (实盘)，挂单只按Ticker撮合
+    last_bar: Option<DateTime<Utc>>, // 已处理的最新K线时间
+}
+
+impl GridStrategy {
+    /// 创建新的网格策略实例
+    pub fn new(symbol: String, params: Option<GridParams>) -> Self {
+        let params = params.unwrap_or_default();
+        let levels = grid_levels(
+            params.lower_price,
+            params.upper_price,
+            params.grid_count,
+            params.spacing,
+        );
+        Self {
+            cells: vec![
+                GridCell {
+                    slot: GridSlot::Idle,
+                    submitted: false,
+                };
+                params.grid_count
+            ],
+            levels,
+            stop_price: params.stop_loss_price,
+            params,
+            symbol,
+            stopped: false,
+            bars_outside: 0,
+            grid_profit: 0.0,
+            completed_grids: 0,
+            tick_driven: false,
+            last_bar: None,
+        }
+    }
+
+    /// 获取策略参数
+    pub fn get_params(&self) -> &GridParams {
+        &self.params
+    }
+
+    /// 当前网格价位 (从低到高)
+    pub fn levels(&self) -> &[f64] {
+        &self.levels
+    }
+
+    /// 当前挂单 (从下往上)
+    pub fn open_orders(&self) -> Vec<GridOrder> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(|(i, cell)| {
+                let (side, price) = match cell.slot {
+                    GridSlot::Idle => return None,
+                    GridSlot::Buying => (SignalType::Buy, self.levels[i]),
+                    GridSlot::Selling { .. } => (SignalType::Sell, self.levels[i + 1]),
+                };
+                Some(GridOrder {
+                    cell: i,
+                    side,
+                    price,
+                    quantity: self.params.quantity_per_grid,
+                })
+            })
+            .collect()
+    }
+
+    /// 已实现网格利润 (每次卖出按该格买入价计算)
+    pub fn grid_profit(&self) -> f64 {
+        self.grid_profit
+    }
+
+    /// 完成的买卖配对次数
+    pub fn completed_grids(&self) -> usize {
+        self.completed_grids
+    }
+
+    /// 是否已止损停止
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// 验证策略参数
+    pub fn validate_params(params: &GridParams) -> Result<()> {
+        if !params.lower_price.is_finite() || params.lower_price <= 0.0 {
+            return Err(anyhow::anyhow!("网格下边界必须大于0"));
+        }
+
+        if !params.upper_price.is_finite() || params.upper_price <= params.lower_price {
+            return Err(anyhow::anyhow!("网格上边界必须大于下边界"));
+        }
+
+        if params.grid_count == 0 {
+            return Err(anyhow::anyhow!("网格数量必须大于0"));
+        }
+
+        if !params.quantity_per_grid.is_finite() || params.quantity_per_grid <= 0.0 {
+            return Err(anyhow::anyhow!("每格数量必须大于0"));
+        }
+
+        if params
+            .stop_loss_price
+            .is_some_and(|stop| stop <= 0.0 || stop >= params.lower_price)
+        {
+            return Err(anyhow::anyhow!("止损价必须在0到网格下边界之间"));
+        }
+
+        if params.rebalance_bars == Some(0) {
+            return Err(anyhow::anyhow!("重建网格的K线数必须大于0"));
+        }
+
+        Ok(())
+    }
+
+    /// 处理一根新收盘的K线: 回测时按最高/最低价撮合挂单，再按收盘价检查区间与补挂买单
+    fn step(&mut self, candle: &CandleData) -> Vec<TradingSignal> {
+        for cell in &mut self.cells {
+            cell.submitted = false;
+        }
+        if self.stopped {
+            return vec![];
+        }
+
+        // 无持仓时跌破止损价不会触发止损离场，直接停止网格
+        if self.stop_price.is_some_and(|stop| candle.low <= stop) && !self.has_inventory() {
+            self.stop("价格跌破止损价");
+            return vec![];
+        }
+
+        // 实盘K线内的价格已由Ticker撮合过；回测时阳线按 开->低->高->收 的路径先买后卖，
+        // 阴线按 开->高->低->收 的路径先卖后买
+        let mut signals = if self.tick_driven {
+            Vec::new()
+        } else {
+            self.match_orders(
+                candle.low,
+                candle.high,
+                candle.close >= candle.open,
+                candle.timestamp,
+            )
+        };
+
+        let inside = (self.levels[0]..=self.levels[self.levels.len() - 1]).contains(&candle.close);
+        self.bars_outside = if inside { 0 } else { self.bars_outside + 1 };
+        if let Some(bars) = self.params.rebalance_bars
+            && self.bars_outside >= bars
+        {
+            // 有持仓 (或本根K线有待成交的买单) 时先清仓，成交后再重建
+            if self.has_inventory() || !signals.is_empty() {
+                signals.push(self.rebalance_signal(candle));
+            } else {
+                self.rebalance(candle.close);
+            }
+            return signals;
+        }
+
+        self.place_buy_orders(candle.close);
+        signals
+    }
+
+    /// 撮合价格区间 [low, high] 内的挂单，buy_first决定同一根K线内买卖的先后
+    fn match_orders(
+        &mut self,
+        low: f64,
+        high: f64,
+        buy_first: bool,
+        timestamp: DateTime<Utc>,
+    ) -> Vec<TradingSignal> {
+        let buys = self.match_buys(low, timestamp);
+        let sells = self.match_sells(high, timestamp);
+        if buy_first {
+            buys.into_iter().chain(sells).collect()
+        } else {
+            sells.into_iter().chain(buys).collect()
+        }
+    }
+
+    /// 价格跌到买单价时成交，按价格从高到低
+    fn match_buys(&mut self, low: f64, timestamp: DateTime<Utc>) -> Vec<TradingSignal> {
+        let mut signals = Vec::new();
+        for i in (0..self.cells.len()).rev() {
+            let level = self.levels[i];
+            let cell = &mut self.cells[i];
+            if cell.slot == GridSlot::Buying && !cell.submitted && low <= level {
+                cell.submitted = true;
+                let reason = format!(
+                    "网格#{}买入：价格触及{:.4} (区间{:.4}-{:.4}，{}格)",
+                    i + 1,
+                    level,
+                    self.levels[0],
+                    self.levels[self.levels.len() - 1],
+                    self.params.grid_count
+                );
+                signals.push(self.grid_signal(SignalType::Buy, level, timestamp, reason));
+            }
+        }
+        signals
+    }
+
+    /// 价格涨到卖单价时成交，按价格从低到高
+    fn match_sells(&mut self, high: f64, timestamp: DateTime<Utc>) -> Vec<TradingSignal> {
+        let mut signals = Vec::new();
+        for i in 0..self.cells.len() {
+            let level = self.levels[i + 1];
+            let cell = &mut self.cells[i];
+            if let GridSlot::Selling { entry_price } = cell.slot
+                && !cell.submitted
+                && high >= level
+            {
+                cell.submitted = true;
+                let reason = format!(
+                    "网格#{}卖出：价格触及{:.4}，本格利润{:.4} (买入价{:.4})",
+                    i + 1,
+                    level,
+                    (level - entry_price) * self.params.quantity_per_grid,
+                    entry_price
+                );
+                signals.push(self.grid_signal(SignalType::Sell, level, timestamp, reason));
+            }
+        }
+        signals
+    }
+
+    fn grid_signal(
+        &self,
+        signal_type: SignalType,
+        price: f64,
+        timestamp: DateTime<Utc>,
+        reason: String,
+    ) -> TradingSignal {
+        TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type,
+            price,
+            timestamp,
+            strategy: GRID_STRATEGY.to_string(),
+            reason,
+            confidence: 0.6,
+            atr: None,
+        }
+    }
+
+    /// 下沿低于当前价的空闲格挂出买单
+    fn place_buy_orders(&mut self, price: f64) {
+        for (i, cell) in self.cells.iter_mut().enumerate() {
+            if cell.slot == GridSlot::Idle && self.levels[i] < price {
+                cell.slot = GridSlot::Buying;
+            }
+        }
+    }
+
+    fn has_inventory(&self) -> bool {
+        self.cells
+            .iter()
+            .any(|cell| matches!(cell.slot, GridSlot::Selling { .. }))
+    }
+
+    /// 价格离开区间过久时清仓的信号
+    fn rebalance_signal(&self, candle: &CandleData) -> TradingSignal {
+        TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type: SignalType::Sell,
+            price: candle.close,
+            timestamp: candle.timestamp,
+            strategy: GRID_REBALANCE_STRATEGY.to_string(),
+            reason: format!(
+                "价格{}连续{}根K线收盘在网格区间{:.4}-{:.4}外，清仓后重建网格",
+                candle.close,
+                self.bars_outside,
+                self.levels[0],
+                self.levels[self.levels.len() - 1]
+            ),
+            confidence: 0.8,
+            atr: None,
+        }
+    }
+
+    /// 以price为中心、保持原区间宽度 (等比网格保持上下边界之比) 重建网格，撤销全部挂单后重新挂买单
+    fn rebalance(&mut self, price: f64) {
+        let (lower, upper) = (self.levels[0], self.levels[self.levels.len() - 1]);
+        let (new_lower, new_upper) = match self.params.spacing {
+            GridSpacing::Arithmetic => {
+                let half = (upper - lower) / 2.0;
+                (price - half, price + half)
+            }
+            GridSpacing::Geometric => {
+                let ratio = (upper / lower).sqrt();
+                (price / ratio, price * ratio)
+            }
+        };
+        if new_lower <= 0.0 {
+            warn!(
+                "{}网格无法以{:.4}为中心重建: 下边界{:.4}不大于0",
+                self.symbol, price, new_lower
+            );
+            return;
+        }
+
+        // 止损价与下边界保持相同比例
+        self.stop_price = self.stop_price.map(|stop| stop * new_lower / lower);
+        self.levels = grid_levels(
+            new_lower,
+            new_upper,
+            self.params.grid_count,
+            self.params.spacing,
+        );
+        for cell in &mut self.cells {
+            *cell = GridCell {
+                slot: GridSlot::Idle,
+                submitted: false,
+            };
+        }
+        self.bars_outside = 0;
+        self.place_buy_orders(price);
+        info!(
+            "{}网格已重建: 区间{:.4}-{:.4} (原区间{:.4}-{:.4})",
+            self.symbol, new_lower, new_upper, lower, upper
+        );
+    }
+
+    /// 撤销全部挂单并停止网格
+    fn stop(&mut self, reason: &str) {
+        self.stopped = true;
+        for cell in &mut self.cells {
+            cell.slot = GridSlot::Idle;
+        }
+        info!(
+            "{}网格已停止 ({})，已实现网格利润{:.4}",
+            self.symbol, reason, self.grid_profit
+        );
+    }
+
+    /// 与成交价最接近的指定状态的格
+    fn find_cell(&self, price: f64, buying: bool) -> Option<usize> {
+        (0..self.cells.len())
+            .filter_map(|i| match self.cells[i].slot {
+                GridSlot::Buying if buying => Some((i, self.levels[i])),
+                GridSlot::Selling { .. } if !buying => Some((i, self.levels[i + 1])),
+                _ => None,
+            })
+            .min_by(|a, b| (a.1 - price).abs().total_cmp(&(b.1 - price).abs()))
+            .map(|(i, _)| i)
+    }
+}
+
+/// 计算区间内grid_count+1个价位 (从低到高)
+fn grid_levels(lower: f64, upper: f64, grid_count: usize, spacing: GridSpacing) -> Vec<f64> {
+    let n = grid_count.max(1) as f64;
+    (0..=grid_count.max(1))
+        .map(|i| {
+            let t = i as f64 / n;
+            match spacing {
+                GridSpacing::Arithmetic => lower + (upper - lower) * t,
+                GridSpacing::Geometric => lower * (upper / lower).powf(t),
+            }
+        })
+        .collect()
+}
+
+impl BarReplay for GridStrategy {
+    fn last_bar_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.last_bar
+    }
+}
+
+impl Strategy for GridStrategy {
+    fn name(&self) -> &str {
+        "Grid"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn warmup_bars(&self) -> usize {
+        1
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::to_value(&self.params).unwrap_or_default()
+    }
+
+    fn fresh(&self) -> Box<dyn Strategy> {
+        Box::new(GridStrategy::new(
+            self.symbol.clone(),
+            Some(self.params.clone()),
+        ))
+    }
+
+    fn on_bar(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        self.replay_bars(candles, |strategy, bars| {
+            Ok(strategy.step(&bars[bars.len() - 1]))
+        })
+    }
+
+    fn on_tick(&mut self, ticker: &TickerSnapshot) -> Result<Vec<TradingSignal>> {
+        self.tick_driven = true;
+        if self.stopped {
+            return Ok(vec![]);
+        }
+        let price = ticker.last_price;
+        Ok(self.match_orders(price, price, true, ticker.timestamp))
+    }
+
+    fn on_fill(&mut self, trade: &Trade) {
+        match (trade.strategy.as_str(), trade.side.as_str()) {
+            (STOP_LOSS_STRATEGY, _) => self.stop("触及止损价清仓"),
+            (GRID_REBALANCE_STRATEGY, _) => self.rebalance(trade.price),
+            (GRID_STRATEGY, "buy") => {
+                if let Some(i) = self.find_cell(trade.price, true) {
+                    self.cells[i] = GridCell {
+                        slot: GridSlot::Selling {
+                            entry_price: trade.price,
+                        },
+                        submitted: false,
+                    };
+                }
+            }
+            (GRID_STRATEGY, "sell") => {
+                if let Some(i) = self.find_cell(trade.price, false)
+                    && let GridSlot::Selling { entry_price } = self.cells[i].slot
+                {
+                    let profit = (trade.price - entry_price) * trade.quantity;
+                    self.grid_profit += profit;
+                    self.completed_grids += 1;
+                    self.cells[i] = GridCell {
+                        slot: GridSlot::Buying,
+                        submitted: false,
+                    };
+                    info!(
+                        "{}网格#{}完成一次买卖: 利润{:.4}，累计网格利润{:.4} ({}次)",
+                        self.symbol,
+                        i + 1,
+                        profit,
+                        self.grid_profit,
+                        self.completed_grids
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn position_size(
+        &self,
+        _signal: &TradingSignal,
+        _balance: f64,
+        _candles: &[CandleData],
+    ) -> Result<Option<f64>> {
+        Ok(Some(self.params.quantity_per_grid))
+    }
+
+    fn exit_size(&self, signal: &TradingSignal, _position: &Position) -> Option<f64> {
+        // 网格卖单只卖出一格，止损与重建时全部清仓
+        (signal.strategy == GRID_STRATEGY).then_some(self.params.quantity_per_grid)
+    }
+
+    fn exit_unit(&self, signal: &TradingSignal, position: &Position) -> Option<usize> {
+        // 网格卖单平掉该格买入的单位
+        if signal.strategy != GRID_STRATEGY {
+            return None;
+        }
+        let cell = self.find_cell(signal.price, false)?;
+        let GridSlot::Selling { entry_price } = self.cells[cell].slot else {
+            return None;
+        };
+        (0..position.units.len()).min_by(|&a, &b| {
+            (position.units[a].entry_price - entry_price)
+                .abs()
+                .total_cmp(&(position.units[b].entry_price - entry_price).abs())
+        })
+    }
+
+    fn resting_orders(&self) -> Vec<RestingOrder> {
+        self.open_orders()
+            .into_iter()
+            .map(|order| RestingOrder {
+                side: order.side,
+                price: order.price,
+                quantity: order.quantity,
+            })
+            .collect()
+    }
+
+    fn max_units(&self) -> usize {
+        self.params.grid_count
+    }
+
+    fn stop_price(&self, _signal: &TradingSignal, _candles: &[CandleData]) -> Result<Option<f64>> {
+        Ok(self.stop_price)
+    }
+
+    fn on_position_restored(&mut self, position: &Position) {
+        // 每个单位按入场价对应到最近的一格，恢复为等待卖出
+        for unit in &position.units {
+            let cell = (0..self.cells.len())
+                .filter(|&i| self.cells[i].slot == GridSlot::Idle)
+                .min_by(|&a, &b| {
+                    (self.levels[a] - unit.entry_price)
+                        .abs()
+                        .total_cmp(&(self.levels[b] - unit.entry_price).abs())
+                });
+            match cell {
+                Some(i) => {
+                    self.cells[i].slot = GridSlot::Selling {
+                        entry_price: unit.entry_price,
+                    }
+                }
+                None => warn!(
+                    "{}网格恢复持仓时没有空闲的格: 入场价{:.4}",
+                    self.symbol, unit.entry_price
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::test_support::{
+        SYMBOL, backtest, check_params, flat_candles, memory_db, ohlc_candles, ticker_at,
+    };
+    use crate::strategy_manager::StrategyManager;
+    use crate::types::{AccountState, Order, OrderState, PositionUnit};
+
+    fn params() -> GridParams {
+        GridParams {
+            lower_price: 90.0,
+            upper_price: 110.0,
+            grid_count: 4,
+            quantity_per_grid: 1.0,
+            ..GridParams::default()
+        }
+    }
+
+    fn fill(signal: &TradingSignal) -> Trade {
+        Trade {
+            id: None,
+            symbol: "SOL-USDT".to_string(),
+            side: match signal.signal_type {
+                SignalType::Buy => "buy".to_string(),
+                _ => "sell".to_string(),
+            },
+            price: signal.price,
+            quantity: 1.0,
+            timestamp: signal.timestamp,
+            strategy: signal.strategy.clone(),
+            strategy_id: "grid".to_string(),
+            pnl: None,
+            signal_id: None,
+        }
+    }
+
+    #[test]
+    fn test_grid_levels() {
+        assert_eq!(
+            grid_levels(90.0, 110.0, 4, GridSpacing::Arithmetic),
+            vec![90.0, 95.0, 100.0, 105.0, 110.0]
+        );
+        let geometric = grid_levels(100.0, 400.0, 2, GridSpacing::Geometric);
+        assert!((geometric[1] - 200.0).abs() < 1e-9);
+        assert!((geometric[2] - 400.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_orders_are_replaced_as_levels_fill() {
+        let mut strategy = GridStrategy::new("SOL-USDT".to_string(), Some(params()));
+        let candles = ohlc_candles(
+            SYMBOL,
+            &[
+                (101.0, 101.0, 101.0, 101.0),
+                // 跌到94: 100和95的买单成交
+                (101.0, 101.0, 94.0, 96.0),
+                // 涨到101: 按100买入的格在105卖出，按95买入的格在100卖出
+                (96.0, 101.0, 96.0, 100.5),
+            ],
+        );
+
+        assert!(strategy.on_bar(&candles[..1]).unwrap().is_empty());
+        // 下沿低于101的3格 (90/95/100) 挂买单
+        let orders = strategy.open_orders();
+        assert_eq!(orders.len(), 3);
+        assert!(orders.iter().all(|o| o.side == SignalType::Buy));
+
+        let signals = strategy.on_bar(&candles[..2]).unwrap();
+        let prices: Vec<f64> = signals.iter().map(|s| s.price).collect();
+        assert_eq!(prices, vec![100.0, 95.0]);
+        for signal in &signals {
+            strategy.on_fill(&fill(signal));
+        }
+        // 成交的格换成上沿卖单
+        let sells: Vec<f64> = strategy
+            .open_orders()
+            .iter()
+            .filter(|o| o.side == SignalType::Sell)
+            .map(|o| o.price)
+            .collect();
+        assert_eq!(sells, vec![100.0, 105.0]);
+
+        let signals = strategy.on_bar(&candles).unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].signal_type, SignalType::Sell);
+        assert_eq!(signals[0].price, 100.0);
+        strategy.on_fill(&fill(&signals[0]));
+        assert_eq!(strategy.completed_grids(), 1);
+        assert_eq!(strategy.grid_profit(), 5.0);
+        // 卖出的格重新在下沿95挂买单
+        assert!(strategy.open_orders().contains(&GridOrder {
+            cell: 1,
+            side: SignalType::Buy,
+            price: 95.0,
+            quantity: 1.0,
+        }));
+
+        // Ticker触及卖单价时同样成交，同一根K线内不重复发出
+        let ticker = TickerSnapshot {
+            timestamp: candles[2].timestamp,
+            symbol: "SOL-USDT".to_string(),
+            last_price: 105.0,
+            bid_price: 105.0,
+            ask_price: 105.0,
+            volume_24h: 0.0,
+        };
+        assert_eq!(strategy.on_tick(&ticker).unwrap().len(), 1);
+        assert!(strategy.on_tick(&ticker).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ticks_are_not_matched_again_on_bar_close() {
+        let mut strategy = GridStrategy::new(SYMBOL.to_string(), Some(params()));
+        let candles = ohlc_candles(
+            SYMBOL,
+            &[(101.0, 101.0, 101.0, 101.0), (101.0, 101.0, 94.0, 96.0)],
+        );
+        assert!(strategy.on_bar(&candles[..1]).unwrap().is_empty());
+
+        // 第2根K线内Ticker跌到94: 100和95的买单成交
+        let snapshot = TickerSnapshot::from(&ticker_at(candles[1].timestamp, 94.0));
+        let signals = strategy.on_tick(&snapshot).unwrap();
+        assert_eq!(
+            signals.iter().map(|s| s.price).collect::<Vec<_>>(),
+            vec![100.0, 95.0]
+        );
+        for signal in &signals {
+            strategy.on_fill(&fill(signal));
+        }
+
+        // K线收盘后最高价101已越过新挂的100卖单，但这段价格已由Ticker撮合，不再成交
+        assert!(strategy.on_bar(&candles).unwrap().is_empty());
+        let sells = strategy
+            .open_orders()
+            .iter()
+            .filter(|o| o.side == SignalType::Sell)
+            .count();
+        assert_eq!(sells, 2);
+    }
+
+    #[test]
+    fn test_bar_crossing_both_ways_follows_bar_path() {
+        // 跌到94后: 100、95两格持有 (卖单105、100)，90格挂买单
+        let holding = || {
+            let mut strategy = GridStrategy::new(SYMBOL.to_string(), Some(params()));
+            let candles = ohlc_candles(
+                SYMBOL,
+                &[(101.0, 101.0, 101.0, 101.0), (101.0, 101.0, 94.0, 96.0)],
+            );
+            for signal in strategy.on_bar(&candles).unwrap() {
+                strategy.on_fill(&fill(&signal));
+            }
+            strategy
+        };
+        let sides = |signals: Vec<TradingSignal>| -> Vec<(SignalType, f64)> {
+            signals.iter().map(|s| (s.signal_type, s.price)).collect()
+        };
+
+        // 同一根K线向下穿过90、向上穿过100: 阳线先买后卖，阴线先卖后买
+        let bullish = ohlc_candles(
+            SYMBOL,
+            &[
+                (101.0, 101.0, 101.0, 101.0),
+                (101.0, 101.0, 94.0, 96.0),
+                (96.0, 100.0, 89.0, 99.0),
+            ],
+        );
+        assert_eq!(
+            sides(holding().on_bar(&bullish).unwrap()),
+            vec![(SignalType::Buy, 90.0), (SignalType::Sell, 100.0)]
+        );
+        let mut bearish = bullish.clone();
+        bearish[2].close = 92.0;
+        let mut strategy = holding();
+        let signals = strategy.on_bar(&bearish).unwrap();
+        assert_eq!(
+            sides(signals.clone()),
+            vec![(SignalType::Sell, 100.0), (SignalType::Buy, 90.0)]
+        );
+
+        // 两个方向的成交各自换成反向挂单
+        for signal in &signals {
+            strategy.on_fill(&fill(signal));
+        }
+        assert_eq!(strategy.completed_grids(), 1);
+        assert_eq!(
+            strategy
+                .open_orders()
+                .iter()
+                .map(|o| (o.cell, o.side, o.price))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, SignalType::Sell, 95.0),
+                (1, SignalType::Buy, 95.0),
+                (2, SignalType::Sell, 105.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ticks_round_trip_a_cell_repeatedly() {
+        let mut strategy = GridStrategy::new(SYMBOL.to_string(), Some(params()));
+        let candles = flat_candles(SYMBOL, &[101.0, 101.0]);
+        assert!(strategy.on_bar(&candles[..1]).unwrap().is_empty());
+
+        // 同一根K线内价格在100与105之间往返两次，100格每次成交后换成反向挂单
+        let time = candles[1].timestamp;
+        let mut traded = Vec::new();
+        for price in [99.5, 104.0, 105.0, 100.5, 100.0, 105.5] {
+            let snapshot = TickerSnapshot::from(&ticker_at(time, price));
+            for signal in strategy.on_tick(&snapshot).unwrap() {
+                strategy.on_fill(&fill(&signal));
+                traded.push((signal.signal_type, signal.price));
+            }
+        }
+        assert_eq!(
+            traded,
+            vec![
+                (SignalType::Buy, 100.0),
+                (SignalType::Sell, 105.0),
+                (SignalType::Buy, 100.0),
+                (SignalType::Sell, 105.0),
+            ]
+        );
+        assert_eq!(strategy.completed_grids(), 2);
+        assert_eq!(strategy.grid_profit(), 10.0);
+    }
+
+    #[tokio::test]
+    async fn test_live_ladder_is_persisted_and_exits_at_cell_cost() {
+        let candles = flat_candles(SYMBOL, &[99.0, 99.0]);
+        let db = memory_db(&candles).await;
+
+        // 重启前持有两格，单位顺序与按价格后进先出的顺序相反
+        let unit = |entry_price: f64| PositionUnit {
+            entry_price,
+            quantity: 1.0,
+            stop_price: None,
+            timestamp: candles[0].timestamp,
+        };
+        let mut position = Position::open("grid", SYMBOL, unit(95.0));
+        position.add_unit(unit(100.0));
+        db.save_account_state(&AccountState {
+            balance: 10000.0 - 195.0,
+            positions: vec![position],
+            paired_positions: Vec::new(),
+            updated_at: candles[0].timestamp,
+        })
+        .await
+        .unwrap();
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager
+            .add_strategy(
+                "grid",
+                Box::new(GridStrategy::new(SYMBOL.to_string(), Some(params()))),
+                1000.0,
+            )
+            .unwrap();
+
+        // 两格在100和105挂卖单，空闲的90格挂买单
+        let time = candles[1].timestamp;
+        assert!(
+            manager
+                .process_real_time_data(&ticker_at(time, 99.0))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let prices = |orders: &[Order]| {
+            let mut prices: Vec<(String, f64)> = orders
+                .iter()
+                .map(|o| (o.side.clone(), o.price.unwrap()))
+                .collect();
+            prices.sort_by(|a, b| a.1.total_cmp(&b.1));
+            prices
+        };
+        let open = db.get_open_orders(Some(SYMBOL)).await.unwrap();
+        assert_eq!(
+            prices(&open),
+            vec![
+                ("buy".to_string(), 90.0),
+                ("sell".to_string(), 100.0),
+                ("sell".to_string(), 105.0)
+            ]
+        );
+        let sell_order = open.iter().find(|o| o.price == Some(100.0)).unwrap();
+
+        // 100的卖单成交: 按该格的买入价95计算盈亏，订单记为成交，该格改挂95买单
+        let executed = manager
+            .process_real_time_data(&ticker_at(time, 100.0))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 1);
+        let trades = db
+            .get_trades_by_time_range(DateTime::<Utc>::UNIX_EPOCH, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].pnl, Some(5.0));
+        assert_eq!(manager.get_positions()["grid"].units[0].entry_price, 100.0);
+
+        let order = db.get_order(sell_order.id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(order.state, OrderState::Filled);
+        assert_eq!(db.get_fills(order.id.unwrap()).await.unwrap().len(), 1);
+        assert_eq!(
+            prices(&db.get_open_orders(Some(SYMBOL)).await.unwrap()),
+            vec![
+                ("buy".to_string(), 90.0),
+                ("buy".to_string(), 95.0),
+                ("sell".to_string(), 105.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rebalance_when_price_leaves_range() {
+        let params = GridParams {
+            rebalance_bars: Some(2),
+            ..params()
+        };
+        let mut strategy = GridStrategy::new("SOL-USDT".to_string(), Some(params));
+        let candles = ohlc_candles(
+            SYMBOL,
+            &[
+                (101.0, 101.0, 101.0, 101.0),
+                (101.0, 101.0, 99.0, 99.0),
+                (99.0, 115.0, 99.0, 115.0),
+                (115.0, 121.0, 115.0, 120.0),
+            ],
+        );
+
+        let signals = strategy.on_bar(&candles[..2]).unwrap();
+        assert_eq!(signals[0].price, 100.0);
+        strategy.on_fill(&fill(&signals[0]));
+        let signals = strategy.on_bar(&candles[..3]).unwrap();
+        assert_eq!(signals[0].price, 105.0);
+        strategy.on_fill(&fill(&signals[0]));
+
+        // 连续2根K线收盘在区间上方，没有持仓，以120为中心重建
+        assert!(strategy.on_bar(&candles).unwrap().is_empty());
+        assert_eq!(strategy.levels(), &[110.0, 115.0, 120.0, 125.0, 130.0]);
+        assert_eq!(strategy.open_orders().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_round_trips_and_stop_out() {
+        let candles = ohlc_candles(
+            SYMBOL,
+            &[
+                (101.0, 101.0, 101.0, 101.0),
+                (101.0, 101.0, 94.0, 96.0),
+                (96.0, 106.0, 96.0, 104.0),
+                (104.0, 104.0, 89.0, 89.5),
+                (89.5, 89.5, 84.0, 85.0),
+                (85.0, 120.0, 85.0, 100.0),
+            ],
+        );
+        let db = memory_db(&candles).await;
+
+        let params = GridParams {
+            stop_loss_price: Some(85.0),
+            ..params()
+        };
+        let strategy = GridStrategy::new(SYMBOL.to_string(), Some(params));
+        let (_, run, trades) = backtest(&db, Box::new(strategy)).await;
+
+        let summary: Vec<(&str, f64, f64)> = trades
+            .iter()
+            .map(|t| (t.side.as_str(), t.price, t.quantity))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                // 跌到94: 100和95买入
+                ("buy", 100.0, 1.0),
+                ("buy", 95.0, 1.0),
+                // 涨到106: 逐格卖出一个单位
+                ("sell", 100.0, 1.0),
+                ("sell", 105.0, 1.0),
+                // 跌到89: 100、95、90依次买入
+                ("buy", 100.0, 1.0),
+                ("buy", 95.0, 1.0),
+                ("buy", 90.0, 1.0),
+                // 跌破85: 止损清仓，之后网格停止
+                ("sell", 85.0, 3.0),
+            ]
+        );
+        // 卖出按该格的买入价计算盈亏
+        assert_eq!(trades[2].pnl, Some(5.0));
+        assert_eq!(trades[3].pnl, Some(5.0));
+        assert_eq!(trades[7].strategy, STOP_LOSS_STRATEGY);
+        assert!((trades[7].pnl.unwrap() - (-30.0)).abs() < 1e-9);
+        assert!((run.report.final_balance - 980.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_validate_params() {
+        check_params(
+            GridStrategy::validate_params,
+            &params(),
+            [
+                GridParams {
+                    upper_price: 90.0,
+                    ..params()
+                },
+                GridParams {
+                    stop_loss_price: Some(95.0),
+                    ..params()
+                },
+            ],
+        );
+    }
+}
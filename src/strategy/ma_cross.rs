@@ -0,0 +1,532 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::info;
+
+use super::{BarReplay, Strategy};
+use crate::indicators::{Adx, Indicator, MaType, MovingAverage, Sma};
+use crate::strategy_manager::MAX_POSITION_FRACTION;
+use crate::types::{CandleData, MaCrossParams, Position, SignalType, Trade, TradingSignal};
+
+/// 逐根K线增量维护的指标
+#[derive(Debug, Clone)]
+struct MaCrossIndicators {
+    bars: usize,                  // 已输入的K线数量 (含当前K线)
+    fast: MovingAverage,          // 快线
+    slow: MovingAverage,          // 慢线
+    trend: Option<MovingAverage>, // 趋势线 (三均线模式)
+    adx: Option<Adx>,             // 趋势强度
+    volume: Option<Sma>,          // 之前K线的平均成交量
+}
+
+impl MaCrossIndicators {
+    fn new(params: &MaCrossParams) -> Self {
+        Self {
+            bars: 0,
+            fast: MovingAverage::new(params.ma_type, params.fast_period),
+            slow: MovingAverage::new(params.ma_type, params.slow_period),
+            trend: params
+                .trend_period
+                .map(|period| MovingAverage::new(params.ma_type, period)),
+            adx: params.adx_period.map(Adx::new),
+            volume: params.volume_period.map(Sma::new),
+        }
+    }
+}
+
+/// 入场过滤条件的读数
+#[derive(Debug, Clone, Copy, Default)]
+struct FilterReadings {
+    trend: Option<f64>,        // 趋势线
+    adx: Option<f64>,          // ADX
+    volume_ratio: Option<f64>, // 成交量/均量
+}
+
+/// 均线交叉趋势策略
+pub struct MaCrossStrategy {
+    params: MaCrossParams,           // 策略参数
+    symbol: String,                  // 交易对
+    prev_spread: Option<f64>,        // 上一根K线的快线-慢线
+    holding: bool,                   // 是否持有多头 (由成交回报维护)
+    last_bar: Option<DateTime<Utc>>, // 已处理的最新K线时间
+    indicators: MaCrossIndicators,   // 增量指标
+}
+
+impl MaCrossStrategy {
+    /// 创建新的均线交叉策略实例
+    pub fn new(symbol: String, params: Option<MaCrossParams>) -> Self {
+        let params = params.unwrap_or_default();
+        Self {
+            indicators: MaCrossIndicators::new(&params),
+            params,
+            symbol,
+            prev_spread: None,
+            holding: false,
+            last_bar: None,
+        }
+    }
+
+    /// 获取策略参数
+    pub fn get_params(&self) -> &MaCrossParams {
+        &self.params
+    }
+
+    /// 验证策略参数
+    pub fn validate_params(params: &MaCrossParams) -> Result<()> {
+        if params.fast_period == 0 {
+            return Err(anyhow::anyhow!("快线周期必须大于0"));
+        }
+
+        if params.slow_period <= params.fast_period {
+            return Err(anyhow::anyhow!("慢线周期必须大于快线周期"));
+        }
+
+        if params
+            .trend_period
+            .is_some_and(|period| period <= params.slow_period)
+        {
+            return Err(anyhow::anyhow!("趋势线周期必须大于慢线周期"));
+        }
+
+        if params.adx_period == Some(0) {
+            return Err(anyhow::anyhow!("ADX周期必须大于0"));
+        }
+
+        if !(0.0..=100.0).contains(&params.adx_threshold) {
+            return Err(anyhow::anyhow!("ADX阈值必须在0到100之间"));
+        }
+
+        if params.volume_period == Some(0) {
+            return Err(anyhow::anyhow!("均量周期必须大于0"));
+        }
+
+        if !params.volume_multiplier.is_finite() || params.volume_multiplier <= 0.0 {
+            return Err(anyhow::anyhow!("成交量倍数必须大于0"));
+        }
+
+        if params.position_fraction <= 0.0 || params.position_fraction > MAX_POSITION_FRACTION {
+            return Err(anyhow::anyhow!(
+                "入场资金比例必须在0到{}之间",
+                MAX_POSITION_FRACTION
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 处理一根新收盘的K线，更新指标并判断交叉
+    fn step(&mut self, latest: &CandleData) -> Vec<TradingSignal> {
+        let indicators = &mut self.indicators;
+        indicators.bars += 1;
+        let fast = indicators.fast.update(&latest.close);
+        let slow = indicators.slow.update(&latest.close);
+        let readings = FilterReadings {
+            trend: indicators
+                .trend
+                .as_mut()
+                .and_then(|trend| trend.update(&latest.close)),
+            adx: indicators
+                .adx
+                .as_mut()
+                .and_then(|adx| adx.update(latest))
+                .map(|value| value.adx),
+            // 均量取当前K线之前的数据，放量K线不抬高自身的比较基准
+            volume_ratio: indicators.volume.as_mut().and_then(|volume| {
+                let average = volume.value();
+                volume.update(&latest.volume);
+                average
+                    .filter(|&average| average > 0.0)
+                    .map(|average| latest.volume / average)
+            }),
+        };
+
+        let (Some(fast), Some(slow)) = (fast, slow) else {
+            return vec![];
+        };
+        let spread = fast - slow;
+        let prev_spread = self.prev_spread.replace(spread);
+        if indicators.bars < self.warmup_bars() {
+            return vec![]; // 数据不足，无法分析
+        }
+        let Some(prev_spread) = prev_spread else {
+            return vec![];
+        };
+
+        if !self.holding && prev_spread <= 0.0 && spread > 0.0 {
+            self.entry_signal(latest, fast, slow, readings)
+                .into_iter()
+                .collect()
+        } else if self.holding && prev_spread >= 0.0 && spread < 0.0 {
+            vec![self.exit_signal(latest, fast, slow)]
+        } else {
+            vec![]
+        }
+    }
+
+    /// 金叉时按过滤条件生成入场信号，被过滤时返回None
+    fn entry_signal(
+        &self,
+        latest: &CandleData,
+        fast: f64,
+        slow: f64,
+        readings: FilterReadings,
+    ) -> Option<TradingSignal> {
+        let mut reason = format!(
+            "快线{}={:.4}上穿慢线{}={:.4}",
+            self.ma_label(self.params.fast_period),
+            fast,
+            self.ma_label(self.params.slow_period),
+            slow
+        );
+        let mut confidence: f64 = 0.5;
+        let mut rejected = Vec::new();
+
+        if let (Some(period), Some(trend)) = (self.params.trend_period, readings.trend) {
+            if slow > trend {
+                confidence += 0.1;
+            } else {
+                rejected.push(format!(
+                    "慢线未位于趋势线{}={:.4}上方",
+                    self.ma_label(period),
+                    trend
+                ));
+            }
+            reason.push_str(&format!("，趋势线{}={:.4}", self.ma_label(period), trend));
+        }
+
+        if let Some(adx) = readings.adx {
+            if adx >= self.params.adx_threshold {
+                confidence += 0.1;
+            } else {
+                rejected.push(format!("ADX={:.1}低于{}", adx, self.params.adx_threshold));
+            }
+            reason.push_str(&format!("，ADX={:.1}", adx));
+        }
+
+        if let Some(ratio) = readings.volume_ratio {
+            if ratio >= self.params.volume_multiplier {
+                confidence += 0.1;
+            } else {
+                rejected.push(format!(
+                    "成交量为均量的{:.2}倍，低于{}倍",
+                    ratio, self.params.volume_multiplier
+                ));
+            }
+            reason.push_str(&format!("，成交量为均量的{:.2}倍", ratio));
+        }
+
+        if !rejected.is_empty() {
+            info!(
+                "{}均线金叉被过滤: {} ({})",
+                self.symbol,
+                reason,
+                rejected.join("；")
+            );
+            return None;
+        }
+
+        Some(TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type: SignalType::Buy,
+            price: latest.close,
+            timestamp: latest.timestamp,
+            strategy: "MaCross".to_string(),
+            reason,
+            confidence: confidence.clamp(0.1, 0.9),
+            atr: None,
+        })
+    }
+
+    /// 死叉离场信号
+    fn exit_signal(&self, latest: &CandleData, fast: f64, slow: f64) -> TradingSignal {
+        TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type: SignalType::Sell,
+            price: latest.close,
+            timestamp: latest.timestamp,
+            strategy: "MaCross_Exit".to_string(),
+            reason: format!(
+                "多头离场：快线{}={:.4}下穿慢线{}={:.4}",
+                self.ma_label(self.params.fast_period),
+                fast,
+                self.ma_label(self.params.slow_period),
+                slow
+            ),
+            confidence: 0.8, // 离场信号置信度较高
+            atr: None,
+        }
+    }
+
+    /// 均线名称，如 EMA(10)
+    fn ma_label(&self, period: usize) -> String {
+        let name = match self.params.ma_type {
+            MaType::Sma => "SMA",
+            MaType::Ema => "EMA",
+            MaType::Wma => "WMA",
+        };
+        format!("{}({})", name, period)
+    }
+}
+
+impl BarReplay for MaCrossStrategy {
+    fn last_bar_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.last_bar
+    }
+}
+
+impl Strategy for MaCrossStrategy {
+    fn name(&self) -> &str {
+        "MaCross"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn warmup_bars(&self) -> usize {
+        // 判断交叉需要前一根K线的均线，ADX需要2倍周期，均量不含当前K线
+        let params = &self.params;
+        [
+            params.slow_period,
+            params.trend_period.unwrap_or(0),
+            params.adx_period.map_or(0, |period| 2 * period - 1),
+            params.volume_period.unwrap_or(0),
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+            + 1
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::to_value(&self.params).unwrap_or_default()
+    }
+
+    fn fresh(&self) -> Box<dyn Strategy> {
+        Box::new(MaCrossStrategy::new(
+            self.symbol.clone(),
+            Some(self.params.clone()),
+        ))
+    }
+
+    fn on_bar(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        self.replay_bars(candles, |strategy, bars| {
+            Ok(strategy.step(&bars[bars.len() - 1]))
+        })
+    }
+
+    fn on_fill(&mut self, trade: &Trade) {
+        self.holding = trade.side == "buy";
+    }
+
+    fn position_size(
+        &self,
+        signal: &TradingSignal,
+        balance: f64,
+        _candles: &[CandleData],
+    ) -> Result<Option<f64>> {
+        if signal.price <= 0.0 {
+            return Ok(None);
+        }
+        Ok(Some(balance * self.params.position_fraction / signal.price))
+    }
+
+    fn on_position_restored(&mut self, position: &Position) {
+        self.holding = position.quantity > 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::test_support::{SYMBOL, backtest, check_params, memory_db, ohlc_candles};
+
+    /// 按 (收盘价, 成交量) 生成逐分钟K线，最高/最低价为收盘价±0.5
+    fn candles_from(bars: &[(f64, f64)]) -> Vec<CandleData> {
+        let ranges: Vec<_> = bars
+            .iter()
+            .map(|&(close, _)| (close, close + 0.5, close - 0.5, close))
+            .collect();
+        ohlc_candles(SYMBOL, &ranges)
+            .into_iter()
+            .zip(bars)
+            .map(|(candle, &(_, volume))| CandleData { volume, ..candle })
+            .collect()
+    }
+
+    /// 下跌20根后上涨20根再下跌20根，快慢线先金叉后死叉
+    fn down_up_down() -> Vec<(f64, f64)> {
+        let mut bars = Vec::new();
+        for i in 0..20 {
+            bars.push((120.0 - i as f64, 1000.0));
+        }
+        for i in 0..20 {
+            bars.push((101.0 + i as f64, 1000.0));
+        }
+        for i in 0..20 {
+            bars.push((119.0 - i as f64, 1000.0));
+        }
+        bars
+    }
+
+    fn fill(side: &str, price: f64) -> Trade {
+        Trade {
+            id: None,
+            symbol: "SOL-USDT".to_string(),
+            side: side.to_string(),
+            price,
+            quantity: 1.0,
+            timestamp: Utc::now(),
+            strategy: "MaCross".to_string(),
+            strategy_id: "ma".to_string(),
+            pnl: None,
+            signal_id: None,
+        }
+    }
+
+    /// 逐根K线驱动策略，买卖信号视为立即成交，返回 (K线序号, 信号)
+    fn run(strategy: &mut MaCrossStrategy, candles: &[CandleData]) -> Vec<(usize, TradingSignal)> {
+        let mut signals = Vec::new();
+        for i in 0..candles.len() {
+            for signal in strategy.on_bar(&candles[..=i]).unwrap() {
+                let side = match signal.signal_type {
+                    SignalType::Buy => "buy",
+                    _ => "sell",
+                };
+                strategy.on_fill(&fill(side, signal.price));
+                signals.push((i, signal));
+            }
+        }
+        signals
+    }
+
+    fn params(fast_period: usize, slow_period: usize) -> MaCrossParams {
+        MaCrossParams {
+            fast_period,
+            slow_period,
+            ..MaCrossParams::default()
+        }
+    }
+
+    #[test]
+    fn test_crossovers_emit_entry_and_exit() {
+        let candles = candles_from(&down_up_down());
+
+        for ma_type in [MaType::Sma, MaType::Ema, MaType::Wma] {
+            let params = MaCrossParams {
+                ma_type,
+                ..params(3, 8)
+            };
+            let mut strategy = MaCrossStrategy::new("SOL-USDT".to_string(), Some(params));
+            let signals = run(&mut strategy, &candles);
+
+            assert_eq!(signals.len(), 2, "{:?}", ma_type);
+            let (buy_at, buy) = &signals[0];
+            assert_eq!(buy.signal_type, SignalType::Buy);
+            assert_eq!(buy.strategy, "MaCross");
+            assert!((20..30).contains(buy_at));
+            assert!(buy.reason.contains("上穿"));
+            let (sell_at, sell) = &signals[1];
+            assert_eq!(sell.signal_type, SignalType::Sell);
+            assert_eq!(sell.strategy, "MaCross_Exit");
+            assert!((40..50).contains(sell_at));
+        }
+
+        // 增量回放与一次传入全部K线的结果一致
+        let mut strategy = MaCrossStrategy::new("SOL-USDT".to_string(), Some(params(3, 8)));
+        let mut batch = MaCrossStrategy::new("SOL-USDT".to_string(), Some(params(3, 8)));
+        let signals = run(&mut strategy, &candles);
+        let (buy_at, _) = signals[0];
+        assert_eq!(
+            batch.on_bar(&candles[..=buy_at]).unwrap()[0].price,
+            candles[buy_at].close
+        );
+    }
+
+    #[test]
+    fn test_filters_block_weak_crosses() {
+        let candles = candles_from(&down_up_down());
+
+        // 成交量不变，放量要求过滤所有金叉
+        let mut strategy = MaCrossStrategy::new(
+            "SOL-USDT".to_string(),
+            Some(MaCrossParams {
+                volume_period: Some(5),
+                ..params(3, 8)
+            }),
+        );
+        assert!(run(&mut strategy, &candles).is_empty());
+
+        // 金叉K线放量时入场
+        let mut bars = down_up_down();
+        let mut probe = MaCrossStrategy::new("SOL-USDT".to_string(), Some(params(3, 8)));
+        let cross_at = run(&mut probe, &candles)[0].0;
+        bars[cross_at].1 = 2000.0;
+        let mut strategy = MaCrossStrategy::new(
+            "SOL-USDT".to_string(),
+            Some(MaCrossParams {
+                volume_period: Some(5),
+                ..params(3, 8)
+            }),
+        );
+        let signals = run(&mut strategy, &candles_from(&bars));
+        assert_eq!(signals[0].0, cross_at);
+        assert!(signals[0].1.reason.contains("成交量为均量的2.00倍"));
+
+        // 三均线模式: 金叉时慢线仍在长期下跌的趋势线下方，不入场
+        let mut strategy = MaCrossStrategy::new(
+            "SOL-USDT".to_string(),
+            Some(MaCrossParams {
+                trend_period: Some(20),
+                ..params(3, 8)
+            }),
+        );
+        assert!(run(&mut strategy, &candles).is_empty());
+
+        // 单边行情ADX较高，通过趋势强度过滤
+        let mut strategy = MaCrossStrategy::new(
+            "SOL-USDT".to_string(),
+            Some(MaCrossParams {
+                adx_period: Some(5),
+                ..params(3, 8)
+            }),
+        );
+        let signals = run(&mut strategy, &candles);
+        assert_eq!(signals[0].0, cross_at);
+        assert!(signals[0].1.reason.contains("ADX="));
+    }
+
+    #[test]
+    fn test_validate_params() {
+        check_params(
+            MaCrossStrategy::validate_params,
+            &MaCrossParams::default(),
+            [
+                params(10, 10),
+                MaCrossParams {
+                    trend_period: Some(20),
+                    ..params(10, 30)
+                },
+                MaCrossParams {
+                    position_fraction: 1.0,
+                    ..MaCrossParams::default()
+                },
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backtest_runs_through_manager() {
+        let db = memory_db(&candles_from(&down_up_down())).await;
+        let strategy = MaCrossStrategy::new(SYMBOL.to_string(), Some(params(3, 8)));
+        let (_, run, trades) = backtest(&db, Box::new(strategy)).await;
+
+        assert_eq!(run.strategy, "MaCross");
+        assert_eq!(run.params["slow_period"], 8);
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].side, "buy");
+        assert_eq!(trades[1].strategy, "MaCross_Exit");
+        assert!(trades[1].pnl.unwrap() > 0.0);
+    }
+}
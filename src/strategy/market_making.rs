@@ -0,0 +1,784 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::info;
+
+use super::{BarReplay, Strategy};
+use crate::types::{
+    BookQuote, CandleData, FairPrice, MarketMakingParams, Position, RestingOrder, SignalType,
+    TickerSnapshot, Trade, TradingSignal,
+};
+
+/// 做市成交信号的策略名称
+const MARKET_MAKING_STRATEGY: &str = "MarketMaking";
+
+/// 库存比较的容差
+const INVENTORY_EPSILON: f64 = 1e-9;
+
+/// 做市挂单
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MakerQuote {
+    pub side: SignalType, // 买卖方向
+    pub price: f64,       // 挂单价格
+    pub quantity: f64,    // 挂单数量
+}
+
+/// 挂出的报价及其撮合状态
+#[derive(Debug, Clone, Copy)]
+struct RestingQuote {
+    quote: MakerQuote, // 报价
+    submitted: bool,   // 已发出成交信号 (重新报价前不再撮合)
+}
+
+/// 做市策略
+///
+/// 以盘口的中间价或微价格为公允价格双边挂单，半价差随K线波动率放大，报价中心按库存下移，
+/// 库存达到上限时撤下买单。盘口变化超过阈值或库存变化时重新报价。
+/// 按Ticker的买一/卖一撮合挂单: 实盘使用推送的Ticker，回测回放Ticker存储，K线只用于计算波动率。
+pub struct MarketMakingStrategy {
+    params: MarketMakingParams,      // 策略参数
+    symbol: String,                  // 交易对
+    closes: VecDeque<f64>,           // 最近的收盘价 (volatility_window+1个)
+    volatility: Option<f64>,         // 每根K线对数收益率的标准差
+    book: Option<BookQuote>,         // 最新盘口
+    inventory: f64,                  // 当前库存 (基础货币)
+    bid: Option<RestingQuote>,       // 当前买单
+    ask: Option<RestingQuote>,       // 当前卖单
+    quoted_fair: Option<f64>,        // 当前报价对应的公允价格
+    quoted_inventory: f64,           // 当前报价对应的库存
+    refreshes: usize,                // 重新报价次数
+    last_bar: Option<DateTime<Utc>>, // 已处理的最新K线时间
+}
+
+impl MarketMakingStrategy {
+    /// 创建新的做市策略实例
+    pub fn new(symbol: String, params: Option<MarketMakingParams>) -> Self {
+        let params = params.unwrap_or_default();
+        Self {
+            closes: VecDeque::with_capacity(params.volatility_window + 1),
+            params,
+            symbol,
+            volatility: None,
+            book: None,
+            inventory: 0.0,
+            bid: None,
+            ask: None,
+            quoted_fair: None,
+            quoted_inventory: 0.0,
+            refreshes: 0,
+            last_bar: None,
+        }
+    }
+
+    /// 获取策略参数
+    pub fn get_params(&self) -> &MarketMakingParams {
+        &self.params
+    }
+
+    /// 当前挂单 (买单在前)
+    pub fn quotes(&self) -> Vec<MakerQuote> {
+        [self.bid, self.ask]
+            .into_iter()
+            .flatten()
+            .map(|resting| resting.quote)
+            .collect()
+    }
+
+    /// 当前库存
+    pub fn inventory(&self) -> f64 {
+        self.inventory
+    }
+
+    /// 当前报价对应的公允价格
+    pub fn fair_price(&self) -> Option<f64> {
+        self.quoted_fair
+    }
+
+    /// 每根K线对数收益率的标准差，K线不足时为None
+    pub fn volatility(&self) -> Option<f64> {
+        self.volatility
+    }
+
+    /// 重新报价次数
+    pub fn refreshes(&self) -> usize {
+        self.refreshes
+    }
+
+    /// 验证策略参数
+    pub fn validate_params(params: &MarketMakingParams) -> Result<()> {
+        if !params.half_spread_bps.is_finite() || params.half_spread_bps < 0.0 {
+            return Err(anyhow::anyhow!("基础半价差不能为负数"));
+        }
+
+        if params.volatility_window < 2 {
+            return Err(anyhow::anyhow!("波动率窗口至少为2"));
+        }
+
+        if !params.volatility_multiplier.is_finite() || params.volatility_multiplier < 0.0 {
+            return Err(anyhow::anyhow!("波动率倍数不能为负数"));
+        }
+
+        if !params.inventory_skew.is_finite() || params.inventory_skew < 0.0 {
+            return Err(anyhow::anyhow!("库存偏移系数不能为负数"));
+        }
+
+        if !params.order_size.is_finite() || params.order_size <= 0.0 {
+            return Err(anyhow::anyhow!("挂单数量必须大于0"));
+        }
+
+        if !params.max_inventory.is_finite() || params.max_inventory < params.order_size {
+            return Err(anyhow::anyhow!("库存上限不能小于挂单数量"));
+        }
+
+        if !params.refresh_threshold_bps.is_finite() || params.refresh_threshold_bps < 0.0 {
+            return Err(anyhow::anyhow!("重新报价阈值不能为负数"));
+        }
+
+        Ok(())
+    }
+
+    /// 按参数选择的方式计算盘口的公允价格，盘口无效时返回None
+    fn book_fair(&self, book: &BookQuote) -> Option<f64> {
+        if book.bid_price <= 0.0 || book.ask_price < book.bid_price {
+            return None;
+        }
+        let mid = (book.bid_price + book.ask_price) / 2.0;
+        match (self.params.fair_price, book.bid_size, book.ask_size) {
+            (FairPrice::Microprice, Some(bid_size), Some(ask_size))
+                if bid_size + ask_size > 0.0 =>
+            {
+                // 买一数量越大，下一笔成交越可能向卖一移动
+                Some(
+                    (book.bid_price * ask_size + book.ask_price * bid_size) / (bid_size + ask_size),
+                )
+            }
+            _ => Some(mid),
+        }
+    }
+
+    /// 处理一根新收盘的K线: 更新波动率并重新报价 (撮合只由Ticker驱动，K线不再撮合同一段行情)
+    fn step(&mut self, candle: &CandleData) {
+        self.closes.push_back(candle.close);
+        while self.closes.len() > self.params.volatility_window + 1 {
+            self.closes.pop_front();
+        }
+        self.volatility = (self.closes.len() > self.params.volatility_window)
+            .then(|| log_return_std(&self.closes));
+
+        // 没有盘口 (回测) 时以收盘价为公允价格
+        let fair = self
+            .book
+            .as_ref()
+            .and_then(|book| self.book_fair(book))
+            .unwrap_or(candle.close);
+        self.refresh(fair, true);
+    }
+
+    /// 对手价严格穿过挂单价时视为成交: 卖一低于买单价时买单成交，买一高于卖单价时卖单成交
+    /// (排队位置未知，触及挂单价不算成交)
+    fn match_quotes(&mut self, ticker: &TickerSnapshot) -> Vec<TradingSignal> {
+        let mut signals = Vec::new();
+        if let Some(resting) = &mut self.bid
+            && !resting.submitted
+            && ticker.ask_price > 0.0
+            && ticker.ask_price < resting.quote.price
+        {
+            resting.submitted = true;
+            let reason = format!(
+                "做市买单成交：卖一价{:.4}低于买价{:.4}，库存{:.4}",
+                ticker.ask_price, resting.quote.price, self.inventory
+            );
+            let price = resting.quote.price;
+            signals.push(self.maker_signal(SignalType::Buy, price, ticker.timestamp, reason));
+        }
+
+        if let Some(resting) = &mut self.ask
+            && !resting.submitted
+            && ticker.bid_price > resting.quote.price
+        {
+            resting.submitted = true;
+            let reason = format!(
+                "做市卖单成交：买一价{:.4}高于卖价{:.4}，库存{:.4}",
+                ticker.bid_price, resting.quote.price, self.inventory
+            );
+            let price = resting.quote.price;
+            signals.push(self.maker_signal(SignalType::Sell, price, ticker.timestamp, reason));
+        }
+
+        signals
+    }
+
+    fn maker_signal(
+        &self,
+        signal_type: SignalType,
+        price: f64,
+        timestamp: DateTime<Utc>,
+        reason: String,
+    ) -> TradingSignal {
+        TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type,
+            price,
+            timestamp,
+            strategy: MARKET_MAKING_STRATEGY.to_string(),
+            reason,
+            confidence: 0.6,
+            atr: None,
+        }
+    }
+
+    /// 按公允价格、波动率和库存重新计算双边报价，返回是否重新报价
+    ///
+    /// 非强制时，只有公允价格变化达到阈值或库存变化才重新报价；波动率就绪前不报价。
+    fn refresh(&mut self, fair: f64, force: bool) -> bool {
+        let Some(volatility) = self.volatility else {
+            return false;
+        };
+        let moved = self.quoted_fair.is_none_or(|quoted| {
+            ((fair - quoted) / quoted).abs() * 10000.0 >= self.params.refresh_threshold_bps
+        });
+        let inventory_changed = (self.inventory - self.quoted_inventory).abs() > INVENTORY_EPSILON;
+        if !force && !moved && !inventory_changed {
+            return false;
+        }
+
+        let half_spread = fair
+            * (self.params.half_spread_bps / 10000.0
+                + self.params.volatility_multiplier * volatility);
+        let inventory_ratio = (self.inventory / self.params.max_inventory).clamp(0.0, 1.0);
+        let center = fair - self.params.inventory_skew * inventory_ratio * half_spread;
+        let mut bid_price = center - half_spread;
+        let mut ask_price = center + half_spread;
+
+        // 只挂被动单: 穿过对手价的报价退到本方最优价
+        if let Some(book) = &self.book {
+            if bid_price >= book.ask_price {
+                bid_price = book.bid_price;
+            }
+            if ask_price <= book.bid_price {
+                ask_price = book.ask_price;
+            }
+        }
+
+        let size = self.params.order_size;
+        let can_buy = self.inventory + size <= self.params.max_inventory + INVENTORY_EPSILON;
+        let can_sell = self.inventory + INVENTORY_EPSILON >= size;
+        self.bid = (can_buy && bid_price > 0.0).then_some(RestingQuote {
+            quote: MakerQuote {
+                side: SignalType::Buy,
+                price: bid_price,
+                quantity: size,
+            },
+            submitted: false,
+        });
+        self.ask = can_sell.then_some(RestingQuote {
+            quote: MakerQuote {
+                side: SignalType::Sell,
+                price: ask_price,
+                quantity: size,
+            },
+            submitted: false,
+        });
+        self.quoted_fair = Some(fair);
+        self.quoted_inventory = self.inventory;
+        self.refreshes += 1;
+        true
+    }
+}
+
+/// 价格序列对数收益率的标准差
+fn log_return_std(prices: &VecDeque<f64>) -> f64 {
+    let returns: Vec<f64> = prices
+        .iter()
+        .zip(prices.iter().skip(1))
+        .filter(|(prev, _)| **prev > 0.0)
+        .map(|(prev, next)| (next / prev).ln())
+        .collect();
+    if returns.is_empty() {
+        return 0.0;
+    }
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64;
+    variance.sqrt()
+}
+
+impl BarReplay for MarketMakingStrategy {
+    fn last_bar_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.last_bar
+    }
+}
+
+impl Strategy for MarketMakingStrategy {
+    fn name(&self) -> &str {
+        "MarketMaking"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn warmup_bars(&self) -> usize {
+        self.params.volatility_window + 1
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::to_value(&self.params).unwrap_or_default()
+    }
+
+    fn fresh(&self) -> Box<dyn Strategy> {
+        Box::new(MarketMakingStrategy::new(
+            self.symbol.clone(),
+            Some(self.params.clone()),
+        ))
+    }
+
+    fn on_bar(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        self.replay_bars(candles, |strategy, bars| {
+            strategy.step(&bars[bars.len() - 1]);
+            Ok(Vec::new())
+        })
+    }
+
+    fn on_tick(&mut self, ticker: &TickerSnapshot) -> Result<Vec<TradingSignal>> {
+        Ok(self.match_quotes(ticker))
+    }
+
+    fn replays_ticks(&self) -> bool {
+        true
+    }
+
+    fn resting_orders(&self) -> Vec<RestingOrder> {
+        // 已发出成交信号的报价等待成交回报，不再作为挂单保留
+        [self.bid, self.ask]
+            .into_iter()
+            .flatten()
+            .filter(|resting| !resting.submitted)
+            .map(|resting| RestingOrder {
+                side: resting.quote.side,
+                price: resting.quote.price,
+                quantity: resting.quote.quantity,
+            })
+            .collect()
+    }
+
+    fn on_book(&mut self, book: &BookQuote) {
+        self.book = Some(book.clone());
+        if let Some(fair) = self.book_fair(book) {
+            self.refresh(fair, false);
+        }
+    }
+
+    fn on_fill(&mut self, trade: &Trade) {
+        match trade.side.as_str() {
+            "buy" => self.inventory += trade.quantity,
+            "sell" => self.inventory = (self.inventory - trade.quantity).max(0.0),
+            _ => return,
+        }
+        info!(
+            "{}做市{}成交: {:.4} @ {:.4}，库存{:.4}",
+            self.symbol, trade.side, trade.quantity, trade.price, self.inventory
+        );
+        if let Some(fair) = self.quoted_fair {
+            self.refresh(fair, false);
+        }
+    }
+
+    fn position_size(
+        &self,
+        _signal: &TradingSignal,
+        _balance: f64,
+        _candles: &[CandleData],
+    ) -> Result<Option<f64>> {
+        Ok(Some(self.params.order_size))
+    }
+
+    fn exit_size(&self, signal: &TradingSignal, _position: &Position) -> Option<f64> {
+        (signal.strategy == MARKET_MAKING_STRATEGY).then_some(self.params.order_size)
+    }
+
+    fn max_units(&self) -> usize {
+        ((self.params.max_inventory / self.params.order_size + INVENTORY_EPSILON).floor() as usize)
+            .max(1)
+    }
+
+    fn on_position_restored(&mut self, position: &Position) {
+        self.inventory = position.quantity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::test_support::{
+        SYMBOL, backtest, base_time, check_params, flat_candles, memory_db, ohlc_candles, ticker_at,
+    };
+    use crate::strategy_manager::StrategyManager;
+    use crate::types::{Order, OrderState, TickerData};
+
+    fn params() -> MarketMakingParams {
+        MarketMakingParams {
+            fair_price: FairPrice::Microprice,
+            volatility_window: 3,
+            ..MarketMakingParams::default()
+        }
+    }
+
+    fn book(bid: f64, ask: f64, bid_size: f64, ask_size: f64) -> BookQuote {
+        BookQuote {
+            symbol: SYMBOL.to_string(),
+            timestamp: base_time() + chrono::Duration::minutes(5),
+            bid_price: bid,
+            bid_size: Some(bid_size),
+            ask_price: ask,
+            ask_size: Some(ask_size),
+        }
+    }
+
+    fn fill(side: &str, price: f64, quantity: f64) -> Trade {
+        Trade {
+            id: None,
+            symbol: SYMBOL.to_string(),
+            side: side.to_string(),
+            price,
+            quantity,
+            timestamp: base_time() + chrono::Duration::minutes(5),
+            strategy: MARKET_MAKING_STRATEGY.to_string(),
+            strategy_id: "mm".to_string(),
+            pnl: None,
+            signal_id: None,
+        }
+    }
+
+    /// 指定时间、买一和卖一的Ticker，最新成交价取中间价
+    fn ticker(time: DateTime<Utc>, bid: f64, ask: f64) -> TickerSnapshot {
+        TickerSnapshot {
+            timestamp: time,
+            symbol: SYMBOL.to_string(),
+            last_price: (bid + ask) / 2.0,
+            bid_price: bid,
+            ask_price: ask,
+            volume_24h: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_quotes_follow_microprice_and_inventory_skew() {
+        let mut strategy = MarketMakingStrategy::new(SYMBOL.to_string(), Some(params()));
+        // 波动率就绪前不报价
+        strategy.on_bar(&flat_candles(SYMBOL, &[100.0; 3])).unwrap();
+        assert!(strategy.quotes().is_empty());
+        strategy.on_bar(&flat_candles(SYMBOL, &[100.0; 4])).unwrap();
+        assert_eq!(strategy.volatility(), Some(0.0));
+
+        // 买一数量3、卖一数量1: 微价格 = (99.9×1 + 100.1×3) / 4 = 100.05
+        strategy.on_book(&book(99.9, 100.1, 3.0, 1.0));
+        let fair = 100.05;
+        let half = fair * 0.001;
+        assert!((strategy.fair_price().unwrap() - fair).abs() < 1e-9);
+        // 没有库存时只挂买单
+        let quotes = strategy.quotes();
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].side, SignalType::Buy);
+        assert!((quotes[0].price - (fair - half)).abs() < 1e-9);
+
+        // 库存达到上限一半: 报价中心下移半个半价差
+        strategy.on_fill(&fill("buy", 99.95, 0.5));
+        let quotes = strategy.quotes();
+        assert_eq!(quotes.len(), 2);
+        assert!((quotes[0].price - (fair - 1.5 * half)).abs() < 1e-9);
+        assert!((quotes[1].price - (fair + 0.5 * half)).abs() < 1e-9);
+
+        // 库存达到上限时撤下买单
+        strategy.on_fill(&fill("buy", 99.9, 0.5));
+        let quotes = strategy.quotes();
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].side, SignalType::Sell);
+    }
+
+    #[test]
+    fn test_volatility_widens_spread() {
+        let params = MarketMakingParams {
+            fair_price: FairPrice::Mid,
+            ..params()
+        };
+        let mut calm = MarketMakingStrategy::new(SYMBOL.to_string(), Some(params.clone()));
+        calm.on_bar(&flat_candles(SYMBOL, &[100.0; 4])).unwrap();
+        calm.on_book(&book(99.9, 100.1, 1.0, 1.0));
+
+        let mut volatile = MarketMakingStrategy::new(SYMBOL.to_string(), Some(params));
+        volatile
+            .on_bar(&flat_candles(SYMBOL, &[100.0, 101.0, 100.0, 101.0]))
+            .unwrap();
+        volatile.on_book(&book(99.9, 100.1, 1.0, 1.0));
+
+        let sigma = volatile.volatility().unwrap();
+        assert!(sigma > 0.0);
+        let calm_bid = calm.quotes()[0].price;
+        let volatile_bid = volatile.quotes()[0].price;
+        assert!((calm_bid - 99.9).abs() < 1e-9);
+        assert!((volatile_bid - (100.0 - 100.0 * (0.001 + sigma))).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_refresh_threshold_and_tick_fills() {
+        let mut strategy = MarketMakingStrategy::new(SYMBOL.to_string(), Some(params()));
+        strategy.on_bar(&flat_candles(SYMBOL, &[100.0; 4])).unwrap();
+        strategy.on_book(&book(99.9, 100.1, 1.0, 1.0));
+        let refreshes = strategy.refreshes();
+        let bid = strategy.quotes()[0].price;
+
+        // 公允价格变化不足2个基点时保持原报价
+        strategy.on_book(&book(99.91, 100.11, 1.0, 1.0));
+        assert_eq!(strategy.refreshes(), refreshes);
+        assert_eq!(strategy.quotes()[0].price, bid);
+        strategy.on_book(&book(100.0, 100.2, 1.0, 1.0));
+        assert_eq!(strategy.refreshes(), refreshes + 1);
+        let bid = strategy.quotes()[0].price;
+
+        // 卖一触及买价不算成交，严格低于买价时按买价成交，成交回报前不重复撮合
+        let time = base_time() + chrono::Duration::minutes(5);
+        assert!(
+            strategy
+                .on_tick(&ticker(time, bid - 0.1, bid))
+                .unwrap()
+                .is_empty()
+        );
+        let signals = strategy
+            .on_tick(&ticker(time, bid - 0.1, bid - 0.05))
+            .unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].signal_type, SignalType::Buy);
+        assert_eq!(signals[0].price, bid);
+        assert!(
+            strategy
+                .on_tick(&ticker(time, bid - 0.2, bid - 0.1))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(strategy.resting_orders().is_empty());
+    }
+
+    fn tick_params() -> MarketMakingParams {
+        MarketMakingParams {
+            fair_price: FairPrice::Mid,
+            half_spread_bps: 50.0,
+            volatility_window: 2,
+            volatility_multiplier: 0.0,
+            inventory_skew: 0.0,
+            order_size: 1.0,
+            max_inventory: 2.0,
+            refresh_threshold_bps: 2.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backtest_fills_from_stored_tickers() {
+        let candles = ohlc_candles(
+            SYMBOL,
+            &[
+                (100.0, 100.0, 100.0, 100.0),
+                (100.0, 100.0, 100.0, 100.0),
+                (100.0, 100.0, 100.0, 100.0),
+                (100.0, 100.0, 99.3, 99.9),
+                // 没有Ticker的K线即使价格区间穿过挂单也不成交
+                (99.9, 110.0, 90.0, 99.9),
+            ],
+        );
+        let db = memory_db(&candles).await;
+        // 第3根K线收盘后以收盘价100挂出买单99.5
+        let during = |minute: usize, seconds: i64| {
+            candles[minute].timestamp + chrono::Duration::seconds(seconds)
+        };
+        db.save_ticker_snapshots(&[
+            // 卖一99.4穿过买价99.5: 买入，之后按盘口中间价99.35重新报价
+            ticker(during(3, 10), 99.3, 99.4),
+            // 买一99.9穿过卖价 99.35×1.005: 卖出
+            ticker(during(3, 30), 99.9, 100.0),
+        ])
+        .await
+        .unwrap();
+
+        let strategy = MarketMakingStrategy::new(SYMBOL.to_string(), Some(tick_params()));
+        let (_, _, trades) = backtest(&db, Box::new(strategy)).await;
+
+        let expected = [("buy", 99.5), ("sell", 99.35 * 1.005)];
+        assert_eq!(trades.len(), expected.len());
+        for (trade, (side, price)) in trades.iter().zip(expected) {
+            assert_eq!(trade.side, side);
+            assert!((trade.price - price).abs() < 1e-9);
+            assert_eq!(trade.quantity, 1.0);
+        }
+        // 按Ticker时间成交
+        assert_eq!(trades[0].timestamp, during(3, 10));
+        assert_eq!(trades[1].timestamp, during(3, 30));
+        assert!((trades[1].pnl.unwrap() - (99.35 * 1.005 - 99.5)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_live_quotes_are_orders_and_bars_do_not_fill() {
+        let candles = ohlc_candles(
+            SYMBOL,
+            &[
+                (100.0, 100.0, 100.0, 100.0),
+                (100.0, 100.0, 100.0, 100.0),
+                (100.0, 100.0, 100.0, 100.0),
+                (100.0, 100.0, 100.0, 100.0),
+                (100.0, 110.0, 90.0, 99.35),
+            ],
+        );
+        let db = memory_db(&candles).await;
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager
+            .add_strategy(
+                "mm",
+                Box::new(MarketMakingStrategy::new(
+                    SYMBOL.to_string(),
+                    Some(tick_params()),
+                )),
+                1000.0,
+            )
+            .unwrap();
+        let book_ticker = |minute: i64, bid: f64, ask: f64| TickerData {
+            bid_px: bid.to_string(),
+            ask_px: ask.to_string(),
+            ..ticker_at(
+                base_time() + chrono::Duration::minutes(minute),
+                (bid + ask) / 2.0,
+            )
+        };
+        let quotes = |orders: &[Order]| {
+            let mut quotes: Vec<(String, f64)> = orders
+                .iter()
+                .map(|o| (o.side.clone(), o.price.unwrap()))
+                .collect();
+            quotes.sort_by(|a, b| a.1.total_cmp(&b.1));
+            quotes
+        };
+
+        // 前4根K线收盘后按盘口中间价100挂出买单，报价写入订单表
+        assert!(
+            manager
+                .process_real_time_data(&book_ticker(4, 99.9, 100.1))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let open = db.get_open_orders(Some(SYMBOL)).await.unwrap();
+        assert_eq!(quotes(&open), vec![("buy".to_string(), 99.5)]);
+
+        // 卖一穿过买价: 买单成交并记为订单成交，按新盘口挂出双边报价
+        let executed = manager
+            .process_real_time_data(&book_ticker(4, 99.3, 99.4))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 1);
+        let order = db.get_order(open[0].id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(order.state, OrderState::Filled);
+        let open = db.get_open_orders(Some(SYMBOL)).await.unwrap();
+        let expected = [("buy", 99.35 * 0.995), ("sell", 99.35 * 1.005)];
+        assert_eq!(open.len(), expected.len());
+        for ((side, price), (expected_side, expected_price)) in quotes(&open).iter().zip(expected) {
+            assert_eq!(side, expected_side);
+            assert!((price - expected_price).abs() < 1e-9);
+        }
+
+        // 第5根K线的价格区间穿过两边报价，收盘时不再按K线撮合
+        db.save_candles(&flat_candles(SYMBOL, &[99.35; 6])[5..])
+            .await
+            .unwrap();
+        assert!(
+            manager
+                .process_real_time_data(&book_ticker(5, 99.3, 99.4))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            quotes(&db.get_open_orders(Some(SYMBOL)).await.unwrap()),
+            quotes(&open)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_live_bid_withdrawn_at_max_inventory() {
+        let db = memory_db(&flat_candles(SYMBOL, &[100.0; 5])).await;
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager
+            .add_strategy(
+                "mm",
+                Box::new(MarketMakingStrategy::new(
+                    SYMBOL.to_string(),
+                    Some(tick_params()),
+                )),
+                1000.0,
+            )
+            .unwrap();
+        let mut trade = async |bid: f64, ask: f64| {
+            let ticker = TickerData {
+                bid_px: bid.to_string(),
+                ask_px: ask.to_string(),
+                ..ticker_at(
+                    base_time() + chrono::Duration::minutes(4),
+                    (bid + ask) / 2.0,
+                )
+            };
+            let executed = manager.process_real_time_data(&ticker).await.unwrap();
+            let mut open: Vec<String> = db
+                .get_open_orders(Some(SYMBOL))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|o| o.side)
+                .collect();
+            open.sort();
+            (
+                executed
+                    .iter()
+                    .map(|s| s.signal_type)
+                    .collect::<Vec<SignalType>>(),
+                open,
+            )
+        };
+
+        // 没有库存时只挂买单
+        assert_eq!(trade(99.9, 100.1).await, (vec![], vec!["buy".to_string()]));
+
+        // 两次买单成交后库存达到上限2: 撤下买单，只保留卖单
+        let both = vec!["buy".to_string(), "sell".to_string()];
+        assert_eq!(
+            trade(99.3, 99.4).await,
+            (vec![SignalType::Buy], both.clone())
+        );
+        assert_eq!(
+            trade(98.7, 98.8).await,
+            (vec![SignalType::Buy], vec!["sell".to_string()])
+        );
+
+        // 库存满时价格继续下跌也不再买入，只按新盘口调整卖单
+        assert_eq!(trade(97.0, 97.1).await, (vec![], vec!["sell".to_string()]));
+
+        // 卖单成交、库存回落后重新挂出买单
+        assert_eq!(trade(98.0, 98.1).await, (vec![SignalType::Sell], both));
+        assert_eq!(manager.get_positions()["mm"].quantity, 1.0);
+    }
+
+    #[test]
+    fn test_validate_params() {
+        check_params(
+            MarketMakingStrategy::validate_params,
+            &params(),
+            [
+                MarketMakingParams {
+                    max_inventory: 0.05,
+                    ..params()
+                },
+                MarketMakingParams {
+                    volatility_window: 1,
+                    ..params()
+                },
+            ],
+        );
+    }
+}
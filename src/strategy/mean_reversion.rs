@@ -0,0 +1,550 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::info;
+
+use super::{BarReplay, Strategy};
+use crate::indicators::{Bands, Bollinger, Indicator, Rsi};
+use crate::strategy_manager::MAX_POSITION_FRACTION;
+use crate::types::{
+    CandleData, MeanReversionParams, MeanReversionTrigger, Position, SignalType, Trade,
+    TradingSignal,
+};
+
+/// 逐根K线增量维护的指标
+#[derive(Debug, Clone)]
+struct MeanReversionIndicators {
+    bars: usize,      // 已输入的K线数量 (含当前K线)
+    stats: Bollinger, // 1倍标准差的布林带，提供均值和标准差
+    rsi: Option<Rsi>, // RSI确认
+}
+
+impl MeanReversionIndicators {
+    fn new(params: &MeanReversionParams) -> Self {
+        Self {
+            bars: 0,
+            stats: Bollinger::new(params.period, 1.0),
+            rsi: params.rsi_period.map(Rsi::new),
+        }
+    }
+}
+
+/// 窗口的均值与标准差
+#[derive(Debug, Clone, Copy)]
+struct Stats {
+    mean: f64,    // 均值
+    std_dev: f64, // 标准差
+}
+
+impl Stats {
+    fn from_bands(bands: Bands) -> Self {
+        Self {
+            mean: bands.middle,
+            std_dev: bands.upper - bands.middle,
+        }
+    }
+
+    /// 价格偏离均值的标准差倍数，窗口内没有波动时为0
+    fn z_score(&self, price: f64) -> f64 {
+        if self.std_dev > 0.0 {
+            (price - self.mean) / self.std_dev
+        } else {
+            0.0
+        }
+    }
+}
+
+/// 布林带/RSI均值回归策略
+pub struct MeanReversionStrategy {
+    params: MeanReversionParams,         // 策略参数
+    symbol: String,                      // 交易对
+    holding: bool,                       // 是否持有多头 (由成交回报维护)
+    entry_time: Option<DateTime<Utc>>,   // 入场K线时间
+    bars_held: usize,                    // 入场后已收盘的K线数量
+    last_bar: Option<DateTime<Utc>>,     // 已处理的最新K线时间
+    indicators: MeanReversionIndicators, // 增量指标
+}
+
+impl MeanReversionStrategy {
+    /// 创建新的均值回归策略实例
+    pub fn new(symbol: String, params: Option<MeanReversionParams>) -> Self {
+        let params = params.unwrap_or_default();
+        Self {
+            indicators: MeanReversionIndicators::new(&params),
+            params,
+            symbol,
+            holding: false,
+            entry_time: None,
+            bars_held: 0,
+            last_bar: None,
+        }
+    }
+
+    /// 获取策略参数
+    pub fn get_params(&self) -> &MeanReversionParams {
+        &self.params
+    }
+
+    /// 验证策略参数
+    pub fn validate_params(params: &MeanReversionParams) -> Result<()> {
+        if params.period < 2 {
+            return Err(anyhow::anyhow!("均值周期必须大于1"));
+        }
+
+        if !params.entry_std.is_finite() || params.entry_std <= 0.0 {
+            return Err(anyhow::anyhow!("入场偏离必须大于0"));
+        }
+
+        if !params.exit_std.is_finite() || params.exit_std <= -params.entry_std {
+            return Err(anyhow::anyhow!("离场位置必须高于入场偏离"));
+        }
+
+        if params.rsi_period == Some(0) {
+            return Err(anyhow::anyhow!("RSI周期必须大于0"));
+        }
+
+        if !(0.0..=100.0).contains(&params.rsi_oversold) {
+            return Err(anyhow::anyhow!("RSI超卖阈值必须在0到100之间"));
+        }
+
+        if params.max_holding_bars == Some(0) {
+            return Err(anyhow::anyhow!("最长持仓K线数必须大于0"));
+        }
+
+        if params.position_fraction <= 0.0 || params.position_fraction > MAX_POSITION_FRACTION {
+            return Err(anyhow::anyhow!(
+                "入场资金比例必须在0到{}之间",
+                MAX_POSITION_FRACTION
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 处理一根新收盘的K线，更新指标并判断入场/离场
+    fn step(&mut self, latest: &CandleData) -> Vec<TradingSignal> {
+        let indicators = &mut self.indicators;
+        indicators.bars += 1;
+        let previous = indicators.stats.value();
+        let current = indicators.stats.update(&latest.close);
+        let rsi = indicators
+            .rsi
+            .as_mut()
+            .and_then(|rsi| rsi.update(&latest.close));
+        let bars = indicators.bars;
+
+        if self.holding
+            && self
+                .entry_time
+                .is_some_and(|entry| latest.timestamp > entry)
+        {
+            self.bars_held += 1;
+        }
+        if bars < self.warmup_bars() {
+            return vec![]; // 数据不足，无法分析
+        }
+
+        let stats = match self.params.trigger {
+            MeanReversionTrigger::Band => current,
+            MeanReversionTrigger::ZScore => previous,
+        };
+        let Some(stats) = stats.map(Stats::from_bands) else {
+            return vec![];
+        };
+
+        let signal = if self.holding {
+            self.exit_signal(latest, stats)
+        } else {
+            self.entry_signal(latest, stats, rsi)
+        };
+        signal.into_iter().collect()
+    }
+
+    /// 价格偏离到极值时生成入场信号，RSI未确认时返回None
+    fn entry_signal(
+        &self,
+        latest: &CandleData,
+        stats: Stats,
+        rsi: Option<f64>,
+    ) -> Option<TradingSignal> {
+        let z = stats.z_score(latest.close);
+        if z > -self.params.entry_std {
+            return None;
+        }
+
+        let mut reason = match self.params.trigger {
+            MeanReversionTrigger::Band => format!(
+                "价格{}跌破布林带下轨{:.4} (均值{:.4}，z={:.2})",
+                latest.close,
+                stats.mean - self.params.entry_std * stats.std_dev,
+                stats.mean,
+                z
+            ),
+            MeanReversionTrigger::ZScore => format!(
+                "价格{}的z-score={:.2}低于-{} (前{}根K线均值{:.4})",
+                latest.close, z, self.params.entry_std, self.params.period, stats.mean
+            ),
+        };
+        let mut confidence: f64 = 0.5;
+
+        if self.params.rsi_period.is_some() {
+            match rsi {
+                Some(rsi) if rsi <= self.params.rsi_oversold => {
+                    confidence += 0.1;
+                    reason.push_str(&format!("，RSI={:.1}", rsi));
+                }
+                _ => {
+                    info!(
+                        "{}均值回归入场未获RSI确认: {} (RSI={:?}，要求不高于{})",
+                        self.symbol, reason, rsi, self.params.rsi_oversold
+                    );
+                    return None;
+                }
+            }
+        }
+
+        // 偏离越深，回归空间越大
+        if z <= -(self.params.entry_std + 1.0) {
+            confidence += 0.1;
+        }
+
+        Some(TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type: SignalType::Buy,
+            price: latest.close,
+            timestamp: latest.timestamp,
+            strategy: "MeanReversion".to_string(),
+            reason,
+            confidence: confidence.clamp(0.1, 0.9),
+            atr: None,
+        })
+    }
+
+    /// 回归均值或持仓超时时生成离场信号
+    fn exit_signal(&self, latest: &CandleData, stats: Stats) -> Option<TradingSignal> {
+        let target = stats.mean + self.params.exit_std * stats.std_dev;
+        let (reason, confidence) = if latest.close >= target {
+            (
+                format!(
+                    "回归均值离场：价格{}回到目标价{:.4} (均值{:.4}，z={:.2})",
+                    latest.close,
+                    target,
+                    stats.mean,
+                    stats.z_score(latest.close)
+                ),
+                0.8,
+            )
+        } else if let Some(max_bars) = self.params.max_holding_bars
+            && self.bars_held >= max_bars
+        {
+            (
+                format!(
+                    "时间止损离场：持仓{}根K线未回归目标价{:.4} (价格{}，z={:.2})",
+                    self.bars_held,
+                    target,
+                    latest.close,
+                    stats.z_score(latest.close)
+                ),
+                0.7,
+            )
+        } else {
+            return None;
+        };
+
+        Some(TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type: SignalType::Sell,
+            price: latest.close,
+            timestamp: latest.timestamp,
+            strategy: "MeanReversion_Exit".to_string(),
+            reason,
+            confidence,
+            atr: None,
+        })
+    }
+}
+
+impl BarReplay for MeanReversionStrategy {
+    fn last_bar_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.last_bar
+    }
+}
+
+impl Strategy for MeanReversionStrategy {
+    fn name(&self) -> &str {
+        "MeanReversion"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn warmup_bars(&self) -> usize {
+        // z-score模式使用之前period根K线的统计量，RSI需要period+1个收盘价
+        self.params.period.max(self.params.rsi_period.unwrap_or(0)) + 1
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::to_value(&self.params).unwrap_or_default()
+    }
+
+    fn fresh(&self) -> Box<dyn Strategy> {
+        Box::new(MeanReversionStrategy::new(
+            self.symbol.clone(),
+            Some(self.params.clone()),
+        ))
+    }
+
+    fn on_bar(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        self.replay_bars(candles, |strategy, bars| {
+            Ok(strategy.step(&bars[bars.len() - 1]))
+        })
+    }
+
+    fn on_fill(&mut self, trade: &Trade) {
+        self.holding = trade.side == "buy";
+        // 从产生信号的K线之后开始计算持仓时间
+        self.entry_time = if self.holding {
+            self.last_bar.or(Some(trade.timestamp))
+        } else {
+            None
+        };
+        self.bars_held = 0;
+    }
+
+    fn position_size(
+        &self,
+        signal: &TradingSignal,
+        balance: f64,
+        _candles: &[CandleData],
+    ) -> Result<Option<f64>> {
+        if signal.price <= 0.0 {
+            return Ok(None);
+        }
+        Ok(Some(balance * self.params.position_fraction / signal.price))
+    }
+
+    fn on_position_restored(&mut self, position: &Position) {
+        if position.quantity <= 0.0 {
+            return;
+        }
+
+        // 下次 on_bar 从头回放时按入场时间重新累计持仓K线数
+        self.holding = true;
+        self.entry_time = Some(
+            position
+                .units
+                .first()
+                .map_or(position.timestamp, |unit| unit.timestamp),
+        );
+        self.bars_held = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::test_support::{SYMBOL, check_params, flat_candles, ohlc_candles};
+    use crate::types::PositionUnit;
+
+    /// 按收盘价生成逐分钟K线，最高/最低价为收盘价±0.5
+    fn candles_from(closes: &[f64]) -> Vec<CandleData> {
+        let bars: Vec<_> = closes
+            .iter()
+            .map(|&close| (close, close + 0.5, close - 0.5, close))
+            .collect();
+        ohlc_candles(SYMBOL, &bars)
+    }
+
+    /// 在100附近小幅震荡的收盘价
+    fn ranging(count: usize) -> Vec<f64> {
+        (0..count)
+            .map(|i| 100.0 + if i % 2 == 0 { 0.5 } else { -0.5 })
+            .collect()
+    }
+
+    fn fill(side: &str, price: f64) -> Trade {
+        Trade {
+            id: None,
+            symbol: "SOL-USDT".to_string(),
+            side: side.to_string(),
+            price,
+            quantity: 1.0,
+            timestamp: Utc::now(),
+            strategy: "MeanReversion".to_string(),
+            strategy_id: "mr".to_string(),
+            pnl: None,
+            signal_id: None,
+        }
+    }
+
+    /// 逐根K线驱动策略，买卖信号视为立即成交，返回 (K线序号, 信号)
+    fn run(
+        strategy: &mut MeanReversionStrategy,
+        candles: &[CandleData],
+    ) -> Vec<(usize, TradingSignal)> {
+        let mut signals = Vec::new();
+        for i in 0..candles.len() {
+            for signal in strategy.on_bar(&candles[..=i]).unwrap() {
+                let side = match signal.signal_type {
+                    SignalType::Buy => "buy",
+                    _ => "sell",
+                };
+                strategy.on_fill(&fill(side, signal.price));
+                signals.push((i, signal));
+            }
+        }
+        signals
+    }
+
+    #[test]
+    fn test_enters_on_extreme_and_exits_at_mean() {
+        // 第30根急跌到95，之后两根逐步回到100上方
+        let mut closes = ranging(30);
+        closes.extend([95.0, 97.0, 100.6, 100.0]);
+        let candles = candles_from(&closes);
+
+        for trigger in [MeanReversionTrigger::Band, MeanReversionTrigger::ZScore] {
+            let params = MeanReversionParams {
+                trigger,
+                ..MeanReversionParams::default()
+            };
+            let mut strategy = MeanReversionStrategy::new("SOL-USDT".to_string(), Some(params));
+            let signals = run(&mut strategy, &candles);
+
+            assert_eq!(signals.len(), 2, "{:?}", trigger);
+            assert_eq!(signals[0].0, 30);
+            assert_eq!(signals[0].1.signal_type, SignalType::Buy);
+            assert_eq!(signals[1].0, 32);
+            assert_eq!(signals[1].1.strategy, "MeanReversion_Exit");
+            assert!(signals[1].1.reason.contains("回归均值"));
+        }
+    }
+
+    #[test]
+    fn test_rsi_confirmation_and_time_stop() {
+        // 急跌后一直停留在低位，均值逐渐下移但收盘价始终低于均值
+        let mut closes = ranging(30);
+        closes.push(95.0);
+        closes.extend((1..=10).map(|i| 95.0 - i as f64 * 0.1));
+        let candles = candles_from(&closes);
+
+        let params = MeanReversionParams {
+            max_holding_bars: Some(5),
+            ..MeanReversionParams::default()
+        };
+        let mut strategy = MeanReversionStrategy::new("SOL-USDT".to_string(), Some(params));
+        let signals = run(&mut strategy, &candles);
+        assert_eq!(signals[1].0, 35);
+        assert!(signals[1].1.reason.contains("时间止损"));
+
+        // 震荡中的一次急跌RSI不够低，要求RSI不高于10时不入场
+        let params = MeanReversionParams {
+            rsi_period: Some(14),
+            rsi_oversold: 10.0,
+            ..MeanReversionParams::default()
+        };
+        let mut strategy = MeanReversionStrategy::new("SOL-USDT".to_string(), Some(params));
+        assert!(run(&mut strategy, &candles[..31]).is_empty());
+
+        let params = MeanReversionParams {
+            rsi_period: Some(14),
+            rsi_oversold: 40.0,
+            ..MeanReversionParams::default()
+        };
+        let mut strategy = MeanReversionStrategy::new("SOL-USDT".to_string(), Some(params));
+        let signals = run(&mut strategy, &candles[..31]);
+        assert!(signals[0].1.reason.contains("RSI="));
+    }
+
+    #[test]
+    fn test_crash_after_flat_window() {
+        // 横盘20根后急跌到90
+        let mut closes = vec![100.0; 20];
+        closes.push(90.0);
+        let candles = flat_candles(SYMBOL, &closes);
+
+        // 布林带包含当前K线，急跌本身带来波动，照常入场
+        let mut strategy = MeanReversionStrategy::new(SYMBOL.to_string(), None);
+        let signals = run(&mut strategy, &candles);
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].0, 20);
+
+        // z-score的比较基准没有波动，不产生信号 (不会除以0)
+        let params = MeanReversionParams {
+            trigger: MeanReversionTrigger::ZScore,
+            ..MeanReversionParams::default()
+        };
+        let mut strategy = MeanReversionStrategy::new(SYMBOL.to_string(), Some(params));
+        assert!(run(&mut strategy, &candles).is_empty());
+    }
+
+    #[test]
+    fn test_time_stop_counts_from_restored_entry() {
+        let mut closes = ranging(30);
+        closes.push(95.0);
+        closes.extend((1..=10).map(|i| 95.0 - i as f64 * 0.1));
+        let candles = candles_from(&closes);
+        let params = MeanReversionParams {
+            max_holding_bars: Some(5),
+            ..MeanReversionParams::default()
+        };
+
+        // 第30根K线入场后重启，首次 on_bar 回放全部历史，持仓K线数从入场时间起算
+        let position = Position::open(
+            "mr",
+            SYMBOL,
+            PositionUnit {
+                entry_price: 95.0,
+                quantity: 1.0,
+                stop_price: None,
+                timestamp: candles[30].timestamp,
+            },
+        );
+        let mut strategy = MeanReversionStrategy::new(SYMBOL.to_string(), Some(params.clone()));
+        strategy.on_position_restored(&position);
+        assert!(strategy.on_bar(&candles[..35]).unwrap().is_empty());
+        let signals = strategy.on_bar(&candles[..36]).unwrap();
+        assert_eq!(signals.len(), 1);
+        assert!(signals[0].reason.contains("持仓5根K线"));
+
+        // 在时间止损的K线上重启同样立即离场
+        let mut strategy = MeanReversionStrategy::new(SYMBOL.to_string(), Some(params));
+        strategy.on_position_restored(&position);
+        let signals = strategy.on_bar(&candles[..36]).unwrap();
+        assert_eq!(signals[0].strategy, "MeanReversion_Exit");
+    }
+
+    #[test]
+    fn test_validate_params() {
+        check_params(
+            MeanReversionStrategy::validate_params,
+            &MeanReversionParams::default(),
+            [
+                MeanReversionParams {
+                    period: 1,
+                    ..MeanReversionParams::default()
+                },
+                MeanReversionParams {
+                    exit_std: -2.0,
+                    ..MeanReversionParams::default()
+                },
+                MeanReversionParams {
+                    max_holding_bars: Some(0),
+                    ..MeanReversionParams::default()
+                },
+            ],
+        );
+
+        // 省略的可选字段取默认值
+        let params: MeanReversionParams = serde_json::from_value(serde_json::json!({
+            "period": 20,
+            "entry_std": 2.5,
+            "position_fraction": 0.5
+        }))
+        .unwrap();
+        assert_eq!(params.max_holding_bars, Some(60));
+        assert_eq!(params.trigger, MeanReversionTrigger::Band);
+    }
+}
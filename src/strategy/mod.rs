@@ -0,0 +1,288 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+
+use crate::types::{
+    BookQuote, CandleData, DcaParams, FundingArbParams, FundingRate, GridParams, MaCrossParams,
+    MarketMakingParams, MeanReversionParams, MultiLegSignal, PairedPosition, PairsParams, Position,
+    RestingOrder, TickerSnapshot, Trade, TradingSignal, TurtleParams,
+};
+
+mod dca;
+mod funding_arb;
+mod grid;
+mod ma_cross;
+mod market_making;
+mod mean_reversion;
+mod pairs;
+#[cfg(test)]
+mod test_support;
+mod turtle;
+
+pub use dca::DcaStrategy;
+pub use funding_arb::{FundingArbStrategy, PerpMargin};
+pub use grid::{GridOrder, GridStrategy};
+pub use ma_cross::MaCrossStrategy;
+pub use market_making::{MakerQuote, MarketMakingStrategy};
+pub use mean_reversion::MeanReversionStrategy;
+pub use pairs::{PairsStrategy, SpreadEstimate, SpreadSide, adf_statistic};
+pub use turtle::{BreakoutOutcome, IndicatorSummary, TurtleStrategy};
+
+/// 可按名称创建的策略
+pub const STRATEGY_KINDS: &[&str] = &[
+    "Turtle",
+    "MaCross",
+    "MeanReversion",
+    "Grid",
+    "Dca",
+    "Pairs",
+    "FundingArb",
+    "MarketMaking",
+];
+
+/// 按策略名称创建策略实例，params为JSON格式的策略参数 (None时使用默认参数)，创建前校验参数
+pub fn create_strategy(
+    kind: &str,
+    symbol: &str,
+    params: Option<serde_json::Value>,
+) -> Result<Box<dyn Strategy>> {
+    match kind {
+        "Turtle" => {
+            let params: TurtleParams = parse_params(params)?;
+            TurtleStrategy::validate_params(&params)?;
+            Ok(Box::new(TurtleStrategy::new(
+                symbol.to_string(),
+                Some(params),
+            )))
+        }
+        "MaCross" => {
+            let params: MaCrossParams = parse_params(params)?;
+            MaCrossStrategy::validate_params(&params)?;
+            Ok(Box::new(MaCrossStrategy::new(
+                symbol.to_string(),
+                Some(params),
+            )))
+        }
+        "MeanReversion" => {
+            let params: MeanReversionParams = parse_params(params)?;
+            MeanReversionStrategy::validate_params(&params)?;
+            Ok(Box::new(MeanReversionStrategy::new(
+                symbol.to_string(),
+                Some(params),
+            )))
+        }
+        "Grid" => {
+            let params: GridParams = parse_params(params)?;
+            GridStrategy::validate_params(&params)?;
+            Ok(Box::new(GridStrategy::new(
+                symbol.to_string(),
+                Some(params),
+            )))
+        }
+        "Dca" => {
+            let params: DcaParams = parse_params(params)?;
+            DcaStrategy::validate_params(&params)?;
+            Ok(Box::new(DcaStrategy::new(symbol.to_string(), Some(params))))
+        }
+        "Pairs" => {
+            let params: PairsParams = parse_params(params)?;
+            PairsStrategy::validate_params(&params)?;
+            if params.hedge_symbol == symbol {
+                return Err(anyhow::anyhow!(
+                    "配对策略的两腿不能是同一个交易对: {}",
+                    symbol
+                ));
+            }
+            Ok(Box::new(PairsStrategy::new(
+                symbol.to_string(),
+                Some(params),
+            )))
+        }
+        "FundingArb" => {
+            let params: FundingArbParams = parse_params(params)?;
+            FundingArbStrategy::validate_params(&params)?;
+            if params.perp_symbol == symbol {
+                return Err(anyhow::anyhow!(
+                    "期现套利的现货腿与永续腿不能是同一个交易对: {}",
+                    symbol
+                ));
+            }
+            Ok(Box::new(FundingArbStrategy::new(
+                symbol.to_string(),
+                Some(params),
+            )))
+        }
+        "MarketMaking" => {
+            let params: MarketMakingParams = parse_params(params)?;
+            MarketMakingStrategy::validate_params(&params)?;
+            Ok(Box::new(MarketMakingStrategy::new(
+                symbol.to_string(),
+                Some(params),
+            )))
+        }
+        other => Err(anyhow::anyhow!(
+            "未知的策略: {} (可选: {})",
+            other,
+            STRATEGY_KINDS.join(", ")
+        )),
+    }
+}
+
+/// 解析JSON格式的策略参数，None时使用默认参数
+fn parse_params<P: DeserializeOwned + Default>(params: Option<serde_json::Value>) -> Result<P> {
+    match params {
+        Some(value) => {
+            serde_json::from_value(value).map_err(|e| anyhow::anyhow!("策略参数无效: {}", e))
+        }
+        None => Ok(P::default()),
+    }
+}
+
+/// 按K线逐根推进内部状态的策略
+///
+/// `on_bar` 收到截至最新K线的全部历史，实现该接口后由 `replay_bars` 只把上次处理之后的新K线逐根交给各策略的单根K线逻辑。
+trait BarReplay {
+    /// 已处理的最新K线时间
+    fn last_bar_mut(&mut self) -> &mut Option<DateTime<Utc>>;
+
+    /// 对上次之后的每根K线以截至该K线的历史调用 `step`，只返回最新K线的信号
+    ///
+    /// 首次调用时回放全部历史，实盘启动后的内部状态与回测一致。
+    fn replay_bars<S>(
+        &mut self,
+        candles: &[CandleData],
+        mut step: impl FnMut(&mut Self, &[CandleData]) -> Result<Vec<S>>,
+    ) -> Result<Vec<S>>
+    where
+        Self: Sized,
+    {
+        let start = match *self.last_bar_mut() {
+            Some(last) => candles.partition_point(|c| c.timestamp <= last),
+            None => 0,
+        };
+
+        let mut signals = Vec::new();
+        for end in start..candles.len() {
+            signals = step(self, &candles[..=end])?;
+        }
+
+        if let Some(latest) = candles.last() {
+            *self.last_bar_mut() = Some(latest.timestamp);
+        }
+
+        Ok(signals)
+    }
+}
+
+/// 交易策略接口
+///
+/// `StrategyManager` 只通过该接口驱动策略：实盘与回测都在K线收盘时调用 `on_bar`，
+/// 实盘收到Ticker时调用 `on_tick` 与 `on_book`，交易成交后调用 `on_fill`，开仓数量由 `position_size` 决定。
+/// 同时交易多个交易对的策略通过 `leg_symbols` 声明各腿，改由 `on_legs_bar` 产生多腿信号，
+/// 各腿作为配对持仓同时开平仓；永续合约腿的资金费结算通过 `on_funding` 通知策略。
+pub trait Strategy: Send + Sync {
+    /// 策略名称
+    fn name(&self) -> &str;
+
+    /// 交易对
+    fn symbol(&self) -> &str;
+
+    /// 产生有效信号前需要的K线数量
+    fn warmup_bars(&self) -> usize;
+
+    /// 策略参数 (随回测记录保存)
+    fn params(&self) -> serde_json::Value;
+
+    /// 以相同参数创建一个未处理过任何数据的新实例，用于回测等隔离运行
+    fn fresh(&self) -> Box<dyn Strategy>;
+
+    /// K线收盘时调用，candles为截至最新一根已收盘K线的历史 (按时间正序)
+    fn on_bar(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>>;
+
+    /// 收到实时Ticker时调用
+    fn on_tick(&mut self, _ticker: &TickerSnapshot) -> Result<Vec<TradingSignal>> {
+        Ok(Vec::new())
+    }
+
+    /// 实盘收到Ticker时在 `on_tick` 之后调用，传入该交易对的最优买卖报价
+    fn on_book(&mut self, _book: &BookQuote) {}
+
+    /// 由本策略信号产生的交易成交后调用
+    fn on_fill(&mut self, _trade: &Trade) {}
+
+    /// 计算开仓数量 (加仓时为单个单位的数量)，数据不足以计算时返回None
+    fn position_size(
+        &self,
+        signal: &TradingSignal,
+        balance: f64,
+        candles: &[CandleData],
+    ) -> Result<Option<f64>>;
+
+    /// 卖出信号的平仓数量 (部分平仓)，None表示平掉全部持仓
+    fn exit_size(&self, _signal: &TradingSignal, _position: &Position) -> Option<f64> {
+        None
+    }
+
+    /// 部分平仓时优先扣减的持仓单位序号 (盈亏按该单位的入场价计算)，None表示后进先出
+    fn exit_unit(&self, _signal: &TradingSignal, _position: &Position) -> Option<usize> {
+        None
+    }
+
+    /// 回测时是否回放Ticker存储中的行情：每条Ticker在所属K线的 `on_bar` 之前依次调用 `on_tick` 和 `on_book`
+    fn replays_ticks(&self) -> bool {
+        false
+    }
+
+    /// 策略自身维护的限价挂单，实盘时由策略管理器同步到订单表 (撤销不再挂出的订单，为新挂单创建订单)
+    fn resting_orders(&self) -> Vec<RestingOrder> {
+        Vec::new()
+    }
+
+    /// 单个持仓最多的单位数，持仓期间的买入信号在该数量以内视为加仓
+    fn max_units(&self) -> usize {
+        1
+    }
+
+    /// 新单位的止损价，None表示不设止损
+    fn stop_price(&self, _signal: &TradingSignal, _candles: &[CandleData]) -> Result<Option<f64>> {
+        Ok(None)
+    }
+
+    /// 程序重启后恢复到该策略实例的持仓，用于同步策略内部的持仓状态
+    fn on_position_restored(&mut self, _position: &Position) {}
+
+    /// 程序重启后传入该策略实例本轮持仓 (上次清仓之后) 的成交记录，在 `on_position_restored` 之后调用，
+    /// 用于恢复依赖成交历史的内部状态
+    fn on_trades_restored(&mut self, _trades: &[Trade]) {}
+
+    /// 多腿策略交易的全部交易对 (第一个为 `symbol`)，单品种策略返回空
+    fn leg_symbols(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// 多腿策略在主交易对K线收盘时调用 (代替 `on_bar`)，legs按 `leg_symbols` 的顺序给出各交易对已收盘的K线
+    fn on_legs_bar(&mut self, _legs: &[&[CandleData]]) -> Result<Vec<MultiLegSignal>> {
+        Ok(Vec::new())
+    }
+
+    /// 需要采集资金费率与K线的永续合约交易对 (如期现套利的永续腿)
+    fn funding_symbols(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// 多腿策略某条腿的资金费率结算时调用 (按结算时间正序)，结算时的配对持仓已由策略管理器记入资金费
+    fn on_funding(&mut self, _rate: &FundingRate) {}
+
+    /// 多腿开仓时第一腿的数量 (其余腿按各自的ratio换算)，balance为实例可用资金
+    fn legs_size(&self, _signal: &MultiLegSignal, _balance: f64) -> Result<Option<f64>> {
+        Ok(None)
+    }
+
+    /// 多腿开仓时该交易对一腿的杠杆倍数，开仓冻结 名义价值 / 杠杆 的现金 (默认1倍，即全额)
+    fn leg_leverage(&self, _symbol: &str) -> f64 {
+        1.0
+    }
+
+    /// 程序重启后恢复到该策略实例的配对持仓
+    fn on_paired_position_restored(&mut self, _position: &PairedPosition) {}
+}
@@ -0,0 +1,835 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use statrs::statistics::Statistics;
+
+use super::{BarReplay, Strategy};
+use crate::strategy_manager::MAX_POSITION_FRACTION;
+use crate::types::{
+    CandleData, HedgeRatioMethod, MultiLegAction, MultiLegSignal, PairedPosition, PairsParams,
+    SignalLeg, SignalType, Trade, TradingSignal,
+};
+
+/// 配对开仓信号的策略名称
+const PAIRS_STRATEGY: &str = "Pairs";
+
+/// 配对平仓信号的策略名称
+const PAIRS_EXIT_STRATEGY: &str = "Pairs_Exit";
+
+/// ADF检验中价差差分的滞后阶数
+const ADF_LAGS: usize = 1;
+
+/// 价差持仓方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpreadSide {
+    Long,  // 做多价差: 买入第一腿、卖出第二腿
+    Short, // 做空价差: 卖出第一腿、买入第二腿
+}
+
+/// 最新一根K线的价差估计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpreadEstimate {
+    pub alpha: f64,    // 截距
+    pub beta: f64,     // 对冲比例 (每单位第一腿对应的第二腿数量)
+    pub spread: f64,   // 价差 y - (alpha + beta * x)
+    pub z_score: f64,  // 价差z-score
+    pub adf_stat: f64, // 窗口内回归残差的ADF统计量
+}
+
+/// 卡尔曼滤波估计的动态截距与对冲比例
+///
+/// 状态 [alpha, beta] 按随机游走演化，观测为 y = alpha + beta * x。
+#[derive(Debug, Clone)]
+struct KalmanHedge {
+    state: [f64; 2],           // [alpha, beta]
+    covariance: [[f64; 2]; 2], // 状态协方差
+    state_noise: f64,          // 状态噪声方差 delta / (1 - delta)
+    observation_var: f64,      // 观测噪声方差
+}
+
+impl KalmanHedge {
+    fn new(delta: f64, observation_var: f64) -> Self {
+        Self {
+            state: [0.0, 0.0],
+            covariance: [[0.0; 2]; 2],
+            state_noise: delta / (1.0 - delta),
+            observation_var,
+        }
+    }
+
+    /// 输入一对价格，返回 (预测误差, 预测误差方差)，之后更新状态
+    fn update(&mut self, y: f64, x: f64) -> (f64, f64) {
+        let h = [1.0, x];
+        let mut r = self.covariance;
+        r[0][0] += self.state_noise;
+        r[1][1] += self.state_noise;
+
+        let rh = [
+            r[0][0] * h[0] + r[0][1] * h[1],
+            r[1][0] * h[0] + r[1][1] * h[1],
+        ];
+        let q = h[0] * rh[0] + h[1] * rh[1] + self.observation_var;
+        let error = y - (self.state[0] + self.state[1] * x);
+        let gain = [rh[0] / q, rh[1] / q];
+
+        self.state[0] += gain[0] * error;
+        self.state[1] += gain[1] * error;
+        for i in 0..2 {
+            for j in 0..2 {
+                self.covariance[i][j] = r[i][j] - gain[i] * rh[j];
+            }
+        }
+
+        (error, q)
+    }
+}
+
+/// 配对交易 (统计套利) 策略
+///
+/// 第一腿为策略的交易对 (y)，第二腿为 `hedge_symbol` (x)。按滚动OLS或卡尔曼滤波估计对冲比例，
+/// 窗口内回归残差通过ADF协整检验时，在价差z-score偏离到 `entry_z` 时两腿同时反向开仓，
+/// z-score回到 `exit_z` 以内 (或扩大到 `stop_z`) 时两腿同时平仓。
+pub struct PairsStrategy {
+    params: PairsParams,              // 策略参数
+    symbol: String,                   // 第一腿交易对
+    window: VecDeque<(f64, f64)>,     // 最近lookback根K线的 (y, x) 收盘价
+    kalman: Option<KalmanHedge>,      // 卡尔曼滤波 (仅Kalman模式)
+    side: Option<SpreadSide>,         // 当前价差持仓 (由成交回报维护)
+    estimate: Option<SpreadEstimate>, // 最新价差估计
+    last_bar: Option<DateTime<Utc>>,  // 已处理的最新K线时间
+}
+
+impl PairsStrategy {
+    /// 创建新的配对交易策略实例
+    pub fn new(symbol: String, params: Option<PairsParams>) -> Self {
+        let params = params.unwrap_or_default();
+        Self {
+            kalman: (params.method == HedgeRatioMethod::Kalman)
+                .then(|| KalmanHedge::new(params.kalman_delta, params.kalman_observation_var)),
+            window: VecDeque::with_capacity(params.lookback + 1),
+            params,
+            symbol,
+            side: None,
+            estimate: None,
+            last_bar: None,
+        }
+    }
+
+    /// 获取策略参数
+    pub fn get_params(&self) -> &PairsParams {
+        &self.params
+    }
+
+    /// 最新一根K线的价差估计，窗口未满时为None
+    pub fn latest_estimate(&self) -> Option<SpreadEstimate> {
+        self.estimate
+    }
+
+    /// 当前价差持仓方向
+    pub fn side(&self) -> Option<SpreadSide> {
+        self.side
+    }
+
+    /// 验证策略参数
+    pub fn validate_params(params: &PairsParams) -> Result<()> {
+        if params.hedge_symbol.is_empty() {
+            return Err(anyhow::anyhow!("第二腿交易对不能为空"));
+        }
+
+        if params.lookback < 20 {
+            return Err(anyhow::anyhow!("回归窗口至少需要20根K线"));
+        }
+
+        if !params.entry_z.is_finite() || params.entry_z <= 0.0 {
+            return Err(anyhow::anyhow!("开仓z-score必须大于0"));
+        }
+
+        if !(params.exit_z >= 0.0 && params.exit_z < params.entry_z) {
+            return Err(anyhow::anyhow!("平仓z-score必须在0到开仓z-score之间"));
+        }
+
+        if params.stop_z.is_some_and(|stop| stop <= params.entry_z) {
+            return Err(anyhow::anyhow!("止损z-score必须大于开仓z-score"));
+        }
+
+        if !(params.kalman_delta > 0.0 && params.kalman_delta < 1.0) {
+            return Err(anyhow::anyhow!("卡尔曼状态噪声必须在0到1之间"));
+        }
+
+        if !params.kalman_observation_var.is_finite() || params.kalman_observation_var <= 0.0 {
+            return Err(anyhow::anyhow!("卡尔曼观测噪声方差必须大于0"));
+        }
+
+        if params.position_fraction <= 0.0 || params.position_fraction > MAX_POSITION_FRACTION {
+            return Err(anyhow::anyhow!(
+                "开仓资金比例必须在0到{}之间",
+                MAX_POSITION_FRACTION
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 处理两腿同一时间收盘的K线，更新价差估计并判断开仓/平仓
+    fn step(&mut self, y_bar: &CandleData, x_bar: &CandleData) -> Option<MultiLegSignal> {
+        let (y, x) = (y_bar.close, x_bar.close);
+        let kalman = self.kalman.as_mut().map(|kalman| kalman.update(y, x));
+
+        // 回归与协整检验使用之前lookback根K线，当前K线的价差按样本外计算，
+        // 价差突然偏离的K线不会拉偏自身的比较基准
+        let estimate = if self.window.len() == self.params.lookback {
+            self.estimate(y, x, kalman)
+        } else {
+            None
+        };
+        self.window.push_back((y, x));
+        if self.window.len() > self.params.lookback {
+            self.window.pop_front();
+        }
+
+        let estimate = estimate?; // 数据不足，无法分析
+        self.estimate = Some(estimate);
+
+        match self.side {
+            Some(side) => self.exit_signal(side, y_bar, x_bar, estimate),
+            None => self.entry_signal(y_bar, x_bar, estimate),
+        }
+    }
+
+    /// 按窗口内的回归估计当前价格的价差，kalman为卡尔曼滤波的 (预测误差, 预测误差方差)
+    fn estimate(&self, y: f64, x: f64, kalman: Option<(f64, f64)>) -> Option<SpreadEstimate> {
+        let ys: Vec<f64> = self.window.iter().map(|(y, _)| *y).collect();
+        let xs: Vec<f64> = self.window.iter().map(|(_, x)| *x).collect();
+        let (alpha, beta) = hedge_regression(&ys, &xs)?;
+        let residuals: Vec<f64> = ys
+            .iter()
+            .zip(&xs)
+            .map(|(y, x)| y - alpha - beta * x)
+            .collect();
+        let adf_stat = adf_statistic(&residuals, ADF_LAGS).unwrap_or(f64::NAN);
+
+        Some(match (kalman, &self.kalman) {
+            (Some((error, variance)), Some(filter)) => SpreadEstimate {
+                alpha: filter.state[0],
+                beta: filter.state[1],
+                spread: error,
+                z_score: error / variance.sqrt(),
+                adf_stat,
+            },
+            _ => {
+                let spread = y - alpha - beta * x;
+                let std_dev = residuals.iter().std_dev();
+                SpreadEstimate {
+                    alpha,
+                    beta,
+                    spread,
+                    z_score: if std_dev > 0.0 {
+                        (spread - residuals.iter().mean()) / std_dev
+                    } else {
+                        0.0
+                    },
+                    adf_stat,
+                }
+            }
+        })
+    }
+
+    /// 价差偏离到开仓阈值且通过协整检验时生成开仓信号
+    fn entry_signal(
+        &self,
+        y_bar: &CandleData,
+        x_bar: &CandleData,
+        estimate: SpreadEstimate,
+    ) -> Option<MultiLegSignal> {
+        let z = estimate.z_score;
+        let side = if z >= self.params.entry_z {
+            SpreadSide::Short
+        } else if z <= -self.params.entry_z {
+            SpreadSide::Long
+        } else {
+            return None;
+        };
+
+        // ADF统计量为NaN (窗口内残差无波动) 时比较结果为false，视为未通过
+        let passed = estimate.adf_stat < self.params.adf_critical;
+        if !passed {
+            info!(
+                "{}/{}价差z={:.2}未通过协整检验: ADF={:.2} (临界值{})",
+                self.symbol,
+                self.params.hedge_symbol,
+                z,
+                estimate.adf_stat,
+                self.params.adf_critical
+            );
+            return None;
+        }
+        if estimate.beta <= 0.0 {
+            info!(
+                "{}/{}对冲比例{:.4}不为正，两腿无法反向对冲",
+                self.symbol, self.params.hedge_symbol, estimate.beta
+            );
+            return None;
+        }
+
+        let (y_type, x_type) = match side {
+            SpreadSide::Long => (SignalType::Buy, SignalType::Sell),
+            SpreadSide::Short => (SignalType::Sell, SignalType::Buy),
+        };
+        let reason = format!(
+            "价差z={:.2}{}，对冲比例{:.4}，ADF={:.2}",
+            z,
+            match side {
+                SpreadSide::Long => format!("低于-{}，做多价差", self.params.entry_z),
+                SpreadSide::Short => format!("高于{}，做空价差", self.params.entry_z),
+            },
+            estimate.beta,
+            estimate.adf_stat
+        );
+        let confidence = if estimate.adf_stat < self.params.adf_critical - 1.0 {
+            0.7
+        } else {
+            0.6
+        };
+
+        Some(MultiLegSignal {
+            action: MultiLegAction::Open,
+            legs: vec![
+                SignalLeg {
+                    signal: self.leg_signal(y_bar, y_type, PAIRS_STRATEGY, &reason, confidence),
+                    ratio: 1.0,
+                },
+                SignalLeg {
+                    signal: self.leg_signal(x_bar, x_type, PAIRS_STRATEGY, &reason, confidence),
+                    ratio: estimate.beta,
+                },
+            ],
+        })
+    }
+
+    /// 价差回归或继续扩大到止损阈值时生成平仓信号
+    fn exit_signal(
+        &self,
+        side: SpreadSide,
+        y_bar: &CandleData,
+        x_bar: &CandleData,
+        estimate: SpreadEstimate,
+    ) -> Option<MultiLegSignal> {
+        // 统一为做多价差的方向: 价差为负时持有，回升时平仓
+        let z = match side {
+            SpreadSide::Long => estimate.z_score,
+            SpreadSide::Short => -estimate.z_score,
+        };
+
+        let (reason, confidence) = if z >= -self.params.exit_z {
+            (format!("价差回归平仓：z={:.2}", estimate.z_score), 0.8)
+        } else if let Some(stop) = self.params.stop_z
+            && z <= -stop
+        {
+            (
+                format!(
+                    "价差止损平仓：z={:.2}超过{}，ADF={:.2}",
+                    estimate.z_score, stop, estimate.adf_stat
+                ),
+                0.9,
+            )
+        } else {
+            return None;
+        };
+
+        let (y_type, x_type) = match side {
+            SpreadSide::Long => (SignalType::Sell, SignalType::Buy),
+            SpreadSide::Short => (SignalType::Buy, SignalType::Sell),
+        };
+        Some(MultiLegSignal {
+            action: MultiLegAction::Close,
+            legs: vec![
+                SignalLeg {
+                    signal: self.leg_signal(
+                        y_bar,
+                        y_type,
+                        PAIRS_EXIT_STRATEGY,
+                        &reason,
+                        confidence,
+                    ),
+                    ratio: 1.0,
+                },
+                SignalLeg {
+                    signal: self.leg_signal(
+                        x_bar,
+                        x_type,
+                        PAIRS_EXIT_STRATEGY,
+                        &reason,
+                        confidence,
+                    ),
+                    ratio: estimate.beta,
+                },
+            ],
+        })
+    }
+
+    fn leg_signal(
+        &self,
+        bar: &CandleData,
+        signal_type: SignalType,
+        strategy: &str,
+        reason: &str,
+        confidence: f64,
+    ) -> TradingSignal {
+        TradingSignal {
+            id: None,
+            symbol: bar.symbol.clone(),
+            signal_type,
+            price: bar.close,
+            timestamp: bar.timestamp,
+            strategy: strategy.to_string(),
+            reason: reason.to_string(),
+            confidence,
+            atr: None,
+        }
+    }
+}
+
+/// 以x解释y的最小二乘回归，返回 (截距, 斜率)，x没有波动时返回None
+fn hedge_regression(ys: &[f64], xs: &[f64]) -> Option<(f64, f64)> {
+    let variance = xs.iter().variance();
+    if variance.is_nan() || variance <= 0.0 {
+        return None;
+    }
+    let beta = xs.iter().covariance(ys.iter()) / variance;
+    Some((ys.iter().mean() - beta * xs.iter().mean(), beta))
+}
+
+/// 序列的增广Dickey-Fuller统计量 (不含常数项与趋势项，lags阶差分滞后)
+///
+/// 回归 Δe_t = γ·e_{t-1} + Σφ_i·Δe_{t-i} + ε，返回γ的t统计量；用于协整回归残差时，
+/// 应与Engle-Granger临界值比较 (两变量时1%、5%、10%水平约为-3.90、-3.34、-3.04)。
+/// 样本不足或回归矩阵奇异时返回None。
+pub fn adf_statistic(series: &[f64], lags: usize) -> Option<f64> {
+    let diffs: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+    let k = lags + 1;
+    let rows = diffs.len().checked_sub(lags)?;
+    if rows <= k + 1 {
+        return None;
+    }
+
+    // 第t行: 因变量Δe_t，自变量 [e_{t-1}, Δe_{t-1}, ..., Δe_{t-lags}]
+    let mut xtx = vec![vec![0.0; k]; k];
+    let mut xty = vec![0.0; k];
+    let mut regressors = Vec::with_capacity(rows);
+    for t in lags..diffs.len() {
+        let row: Vec<f64> = std::iter::once(series[t])
+            .chain((1..=lags).map(|i| diffs[t - i]))
+            .collect();
+        for i in 0..k {
+            xty[i] += row[i] * diffs[t];
+            for j in 0..k {
+                xtx[i][j] += row[i] * row[j];
+            }
+        }
+        regressors.push((row, diffs[t]));
+    }
+
+    let inverse = invert(xtx)?;
+    let coefficients: Vec<f64> = (0..k)
+        .map(|i| (0..k).map(|j| inverse[i][j] * xty[j]).sum())
+        .collect();
+    let rss: f64 = regressors
+        .iter()
+        .map(|(row, y)| {
+            let fitted: f64 = row.iter().zip(&coefficients).map(|(x, c)| x * c).sum();
+            (y - fitted).powi(2)
+        })
+        .sum();
+    let sigma2 = rss / (rows - k) as f64;
+    let se = (sigma2 * inverse[0][0]).sqrt();
+    (se > 0.0).then(|| coefficients[0] / se)
+}
+
+/// 高斯-约当消元求逆矩阵，奇异时返回None
+fn invert(mut matrix: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        if matrix[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let scale = matrix[col][col];
+        for j in 0..n {
+            matrix[col][j] /= scale;
+            inverse[col][j] /= scale;
+        }
+        for row in 0..n {
+            if row != col {
+                let factor = matrix[row][col];
+                for j in 0..n {
+                    matrix[row][j] -= factor * matrix[col][j];
+                    inverse[row][j] -= factor * inverse[col][j];
+                }
+            }
+        }
+    }
+
+    Some(inverse)
+}
+
+impl BarReplay for PairsStrategy {
+    fn last_bar_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.last_bar
+    }
+}
+
+impl Strategy for PairsStrategy {
+    fn name(&self) -> &str {
+        "Pairs"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn warmup_bars(&self) -> usize {
+        self.params.lookback + 1
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::to_value(&self.params).unwrap_or_default()
+    }
+
+    fn fresh(&self) -> Box<dyn Strategy> {
+        Box::new(PairsStrategy::new(
+            self.symbol.clone(),
+            Some(self.params.clone()),
+        ))
+    }
+
+    fn on_bar(&mut self, _candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        // 配对策略由 on_legs_bar 驱动
+        Ok(Vec::new())
+    }
+
+    fn on_legs_bar(&mut self, legs: &[&[CandleData]]) -> Result<Vec<MultiLegSignal>> {
+        let [y_bars, x_bars] = legs else {
+            return Err(anyhow::anyhow!(
+                "配对策略需要2个交易对的K线，实际为{}个",
+                legs.len()
+            ));
+        };
+
+        // 只使用两腿时间相同的K线
+        self.replay_bars(y_bars, |strategy, bars| {
+            let y_bar = &bars[bars.len() - 1];
+            Ok(
+                match x_bars.binary_search_by_key(&y_bar.timestamp, |c| c.timestamp) {
+                    Ok(index) => strategy.step(y_bar, &x_bars[index]).into_iter().collect(),
+                    Err(_) => Vec::new(),
+                },
+            )
+        })
+    }
+
+    fn on_fill(&mut self, trade: &Trade) {
+        if trade.symbol != self.symbol {
+            return;
+        }
+        self.side = match (trade.strategy.as_str(), trade.side.as_str()) {
+            (PAIRS_STRATEGY, "buy") => Some(SpreadSide::Long),
+            (PAIRS_STRATEGY, _) => Some(SpreadSide::Short),
+            _ => None,
+        };
+    }
+
+    fn position_size(
+        &self,
+        _signal: &TradingSignal,
+        _balance: f64,
+        _candles: &[CandleData],
+    ) -> Result<Option<f64>> {
+        Ok(None)
+    }
+
+    fn leg_symbols(&self) -> Vec<String> {
+        vec![self.symbol.clone(), self.params.hedge_symbol.clone()]
+    }
+
+    fn legs_size(&self, signal: &MultiLegSignal, balance: f64) -> Result<Option<f64>> {
+        // 两腿金额合计为可用资金的position_fraction
+        let unit_value: f64 = signal
+            .legs
+            .iter()
+            .map(|leg| leg.ratio * leg.signal.price)
+            .sum();
+        if unit_value <= 0.0 {
+            return Ok(None);
+        }
+        Ok(Some(balance * self.params.position_fraction / unit_value))
+    }
+
+    fn on_paired_position_restored(&mut self, position: &PairedPosition) {
+        self.side = match position.legs.iter().find(|leg| leg.symbol == self.symbol) {
+            Some(leg) if leg.quantity > 0.0 => Some(SpreadSide::Long),
+            Some(_) => Some(SpreadSide::Short),
+            None => {
+                warn!(
+                    "{}配对持仓中没有第一腿{}，忽略",
+                    position.strategy_id, self.symbol
+                );
+                None
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::test_support::{backtest, check_params, flat_candles, memory_db};
+
+    /// 可复现的[-0.5, 0.5)均匀噪声
+    fn noise(count: usize, seed: u64) -> Vec<f64> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect()
+    }
+
+    /// x为随机游走，y = 10 + 2x + 均值回归的价差，spike处价差额外偏离
+    fn cointegrated(count: usize, spike: Option<(usize, f64)>) -> (Vec<f64>, Vec<f64>) {
+        let steps = noise(count, 7);
+        let shocks = noise(count, 11);
+        let mut x = 100.0;
+        let mut spread = 0.0;
+        let mut ys = Vec::new();
+        let mut xs = Vec::new();
+        for i in 0..count {
+            x += steps[i];
+            spread = 0.3 * spread + shocks[i];
+            let extra = match spike {
+                Some((at, size)) if i == at || i == at + 1 => size,
+                _ => 0.0,
+            };
+            xs.push(x);
+            ys.push(10.0 + 2.0 * x + spread + extra);
+        }
+        (ys, xs)
+    }
+
+    fn params(method: HedgeRatioMethod) -> PairsParams {
+        PairsParams {
+            hedge_symbol: "ETH-USDT".to_string(),
+            method,
+            lookback: 60,
+            entry_z: 2.5,
+            exit_z: 0.5,
+            stop_z: None,
+            ..PairsParams::default()
+        }
+    }
+
+    #[test]
+    fn test_adf_separates_mean_reversion_from_random_walk() {
+        let shocks = noise(300, 3);
+        let mut level = 0.0;
+        let mut reverting = 0.0;
+        let mut walk = Vec::new();
+        let mut stationary = Vec::new();
+        for shock in shocks {
+            level += shock;
+            reverting = 0.3 * reverting + shock;
+            walk.push(level);
+            stationary.push(reverting);
+        }
+
+        assert!(adf_statistic(&stationary, ADF_LAGS).unwrap() < -3.9);
+        assert!(adf_statistic(&walk, ADF_LAGS).unwrap() > -3.04);
+        assert!(adf_statistic(&[1.0, 2.0, 3.0], ADF_LAGS).is_none());
+    }
+
+    #[test]
+    fn test_hedge_ratio_estimates() {
+        let (ys, xs) = cointegrated(300, None);
+        let (alpha, beta) = hedge_regression(&ys[..60], &xs[..60]).unwrap();
+        assert!((beta - 2.0).abs() < 0.3, "beta={}", beta);
+        assert!((alpha - 10.0).abs() < 30.0);
+
+        // 卡尔曼滤波在足够多的观测后收敛到相同的关系
+        let mut strategy = PairsStrategy::new(
+            "BTC-USDT".to_string(),
+            Some(PairsParams {
+                kalman_delta: 1e-5,
+                ..params(HedgeRatioMethod::Kalman)
+            }),
+        );
+        let y_bars = flat_candles("BTC-USDT", &ys);
+        let x_bars = flat_candles("ETH-USDT", &xs);
+        strategy.on_legs_bar(&[&y_bars, &x_bars]).unwrap();
+        let estimate = strategy.latest_estimate().unwrap();
+        assert!((estimate.beta - 2.0).abs() < 0.2, "beta={}", estimate.beta);
+        assert!(estimate.adf_stat < -3.34);
+    }
+
+    /// 逐根第一腿K线驱动策略，开仓信号的第一腿视为立即成交，返回 (K线序号, 信号)
+    fn run(
+        strategy: &mut PairsStrategy,
+        y_bars: &[CandleData],
+        x_bars: &[CandleData],
+    ) -> Vec<(usize, MultiLegSignal)> {
+        let mut signals = Vec::new();
+        for i in 0..y_bars.len() {
+            for signal in strategy.on_legs_bar(&[&y_bars[..=i], x_bars]).unwrap() {
+                let leg = &signal.legs[0].signal;
+                strategy.on_fill(&Trade {
+                    id: None,
+                    symbol: leg.symbol.clone(),
+                    side: match leg.signal_type {
+                        SignalType::Buy => "buy".to_string(),
+                        _ => "sell".to_string(),
+                    },
+                    price: leg.price,
+                    quantity: 1.0,
+                    timestamp: leg.timestamp,
+                    strategy: leg.strategy.clone(),
+                    strategy_id: "pairs".to_string(),
+                    pnl: None,
+                    signal_id: None,
+                });
+                signals.push((i, signal));
+            }
+        }
+        signals
+    }
+
+    #[test]
+    fn test_bars_without_hedge_leg_are_skipped() {
+        let (ys, xs) = cointegrated(200, Some((120, 4.0)));
+        let y_bars = flat_candles("BTC-USDT", &ys);
+        let x_bars = flat_candles("ETH-USDT", &xs);
+
+        let mut strategy = PairsStrategy::new(
+            "BTC-USDT".to_string(),
+            Some(params(HedgeRatioMethod::RollingOls)),
+        );
+        let signals = run(&mut strategy, &y_bars[..=125], &x_bars);
+        assert_eq!(signals[0].0, 120);
+
+        // 第二腿缺少价差偏离的两根K线时，这两根K线不参与估计也不开仓
+        let gapped: Vec<CandleData> = x_bars
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !(120..=121).contains(i))
+            .map(|(_, bar)| bar.clone())
+            .collect();
+        let mut strategy = PairsStrategy::new(
+            "BTC-USDT".to_string(),
+            Some(params(HedgeRatioMethod::RollingOls)),
+        );
+        assert!(run(&mut strategy, &y_bars[..=125], &gapped).is_empty());
+        assert!(strategy.latest_estimate().unwrap().z_score.abs() < 2.5);
+    }
+
+    #[test]
+    fn test_stop_z_closes_widening_spread() {
+        let (mut ys, xs) = cointegrated(200, None);
+        // 价差在第120根向上偏离，下一根继续扩大
+        ys[120] += 4.0;
+        ys[121] += 12.0;
+        let y_bars = flat_candles("BTC-USDT", &ys);
+        let x_bars = flat_candles("ETH-USDT", &xs);
+
+        let mut strategy = PairsStrategy::new(
+            "BTC-USDT".to_string(),
+            Some(PairsParams {
+                stop_z: Some(6.0),
+                ..params(HedgeRatioMethod::RollingOls)
+            }),
+        );
+        let signals = run(&mut strategy, &y_bars[..=121], &x_bars);
+        assert_eq!(
+            signals
+                .iter()
+                .map(|(i, s)| (*i, s.action, s.legs[0].signal.signal_type))
+                .collect::<Vec<_>>(),
+            vec![
+                (120, MultiLegAction::Open, SignalType::Sell),
+                (121, MultiLegAction::Close, SignalType::Buy),
+            ]
+        );
+        assert!(signals[1].1.legs[0].signal.reason.contains("止损"));
+        assert_eq!(strategy.side(), None);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_opens_and_closes_both_legs() {
+        let (ys, xs) = cointegrated(200, Some((120, 4.0)));
+        let mut candles = flat_candles("BTC-USDT", &ys);
+        candles.extend(flat_candles("ETH-USDT", &xs));
+        let db = memory_db(&candles).await;
+
+        let strategy = PairsStrategy::new(
+            "BTC-USDT".to_string(),
+            Some(params(HedgeRatioMethod::RollingOls)),
+        );
+        let (manager, run, trades) = backtest(&db, Box::new(strategy)).await;
+
+        // 价差向上偏离: 卖出第一腿、按对冲比例买入第二腿，回归后两腿同时平仓
+        assert!(trades.len() >= 4, "{:?}", trades);
+        let (open, close) = (&trades[..2], &trades[2..4]);
+        assert_eq!(
+            open.iter()
+                .map(|t| (t.symbol.as_str(), t.side.as_str(), t.strategy.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("BTC-USDT", "sell", "Pairs"), ("ETH-USDT", "buy", "Pairs")]
+        );
+        assert_eq!(open[0].timestamp, candles[120].timestamp);
+        let ratio = open[1].quantity / open[0].quantity;
+        assert!((ratio - 2.0).abs() < 0.3, "ratio={}", ratio);
+        assert_eq!(
+            close
+                .iter()
+                .map(|t| (t.symbol.as_str(), t.side.as_str(), t.strategy.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("BTC-USDT", "buy", "Pairs_Exit"),
+                ("ETH-USDT", "sell", "Pairs_Exit")
+            ]
+        );
+        assert!(close.iter().all(|t| t.quantity > 0.0));
+        assert!(close.iter().filter_map(|t| t.pnl).sum::<f64>() > 0.0);
+        assert!(run.report.final_balance > 1000.0);
+
+        // 回测不影响实盘账户的配对持仓
+        assert!(manager.get_paired_positions().is_empty());
+    }
+
+    #[test]
+    fn test_validate_params() {
+        check_params(
+            PairsStrategy::validate_params,
+            &PairsParams::default(),
+            [
+                PairsParams {
+                    exit_z: 3.0,
+                    ..PairsParams::default()
+                },
+                PairsParams {
+                    stop_z: Some(1.0),
+                    ..PairsParams::default()
+                },
+            ],
+        );
+    }
+}
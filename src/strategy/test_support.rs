@@ -0,0 +1,117 @@
+//! 策略单元测试共用的K线构造与回测辅助
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use super::Strategy;
+use crate::memory_db::MemoryDatabase;
+use crate::storage::Storage;
+use crate::strategy_manager::StrategyManager;
+use crate::types::{BacktestRun, CandleData, TickerData, Trade};
+
+/// 测试默认交易对
+pub const SYMBOL: &str = "SOL-USDT";
+
+/// 回测使用的策略实例ID
+const BACKTEST_ID: &str = "test";
+
+/// 回测的分配资金与初始资金
+const BACKTEST_BALANCE: f64 = 1000.0;
+
+/// 测试K线的起始时间 (2024-01-01 00:00 UTC)
+pub fn base_time() -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(1_704_067_200_000).unwrap()
+}
+
+/// 按 (开盘价, 最高价, 最低价, 收盘价) 生成从 `base_time` 开始的逐分钟K线，成交量固定为1000
+pub fn ohlc_candles(symbol: &str, bars: &[(f64, f64, f64, f64)]) -> Vec<CandleData> {
+    bars.iter()
+        .enumerate()
+        .map(|(i, &(open, high, low, close))| CandleData {
+            timestamp: base_time() + chrono::Duration::minutes(i as i64),
+            symbol: symbol.to_string(),
+            open,
+            high,
+            low,
+            close,
+            volume: 1000.0,
+        })
+        .collect()
+}
+
+/// 按收盘价生成开高低收相同的逐分钟K线
+pub fn flat_candles(symbol: &str, closes: &[f64]) -> Vec<CandleData> {
+    let bars: Vec<_> = closes.iter().map(|&c| (c, c, c, c)).collect();
+    ohlc_candles(symbol, &bars)
+}
+
+/// 默认交易对在指定时间、以last为最新价和买卖一价的Ticker
+pub fn ticker_at(time: DateTime<Utc>, last: f64) -> TickerData {
+    let last = last.to_string();
+    TickerData {
+        inst_id: SYMBOL.to_string(),
+        last: last.clone(),
+        last_sz: "1".to_string(),
+        ask_px: last.clone(),
+        ask_sz: "1".to_string(),
+        bid_px: last.clone(),
+        bid_sz: "1".to_string(),
+        open24h: last.clone(),
+        high24h: last.clone(),
+        low24h: last,
+        vol_ccy24h: "0".to_string(),
+        vol24h: "0".to_string(),
+        ts: time.timestamp_millis().to_string(),
+    }
+}
+
+/// 校验默认参数通过，且每组无效参数都被拒绝
+pub fn check_params<P>(
+    validate: fn(&P) -> Result<()>,
+    valid: &P,
+    invalid: impl IntoIterator<Item = P>,
+) {
+    assert!(validate(valid).is_ok());
+    for (i, params) in invalid.into_iter().enumerate() {
+        assert!(validate(&params).is_err(), "第{}组无效参数通过了校验", i);
+    }
+}
+
+/// 保存K线到内存数据库
+pub async fn memory_db(candles: &[CandleData]) -> Arc<dyn Storage> {
+    let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+    db.save_candles(candles).await.unwrap();
+    db
+}
+
+/// 注册给定策略实例，回测数据库中该交易对的全部K线，返回策略管理器、回测记录和回测成交
+pub async fn backtest(
+    db: &Arc<dyn Storage>,
+    strategy: Box<dyn Strategy>,
+) -> (StrategyManager, BacktestRun, Vec<Trade>) {
+    let candles = db
+        .get_candles(
+            strategy.symbol(),
+            DateTime::<Utc>::UNIX_EPOCH,
+            Utc::now(),
+            None,
+        )
+        .await
+        .unwrap();
+    let start = candles[0].timestamp;
+    let end = candles[candles.len() - 1].timestamp;
+
+    let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+    manager
+        .add_strategy(BACKTEST_ID, strategy, BACKTEST_BALANCE)
+        .unwrap();
+    let run = manager
+        .run_backtest(BACKTEST_ID, start, end, BACKTEST_BALANCE)
+        .await
+        .unwrap();
+    let trades = db.get_backtest_trades(run.id.unwrap()).await.unwrap();
+
+    (manager, run, trades)
+}
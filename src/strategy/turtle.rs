@@ -0,0 +1,1087 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::info;
+
+use super::{BarReplay, Strategy};
+use crate::indicators::{Atr, AtrSmoothing, Indicator, RollingMax, RollingMin};
+use crate::types::{
+    CandleData, Position, SignalType, TickerSnapshot, Trade, TradingSignal, TurtleParams,
+    TurtleSystem,
+};
+
+/// 加仓间距 (单位N)
+const PYRAMID_SPACING_N: f64 = 0.5;
+
+/// 突破假想交易的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakoutOutcome {
+    Winner, // 按离场通道平仓且盈利
+    Loser,  // 触及2N止损或平仓亏损
+}
+
+/// 最近一次突破的假想交易 (无论是否实际入场)，用于System 1的过滤规则
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Breakout {
+    is_long: bool,                    // 突破方向
+    entry_price: f64,                 // 假想入场价
+    stop_price: f64,                  // 假想止损价 (入场价反向2N)
+    outcome: Option<BreakoutOutcome>, // 结果 (None表示假想交易尚未结束)
+}
+
+/// 唐奇安通道: 当前K线之前 period-1 根K线的最高价和最低价
+#[derive(Debug, Clone)]
+struct Channel {
+    high: RollingMax, // 上轨
+    low: RollingMin,  // 下轨
+}
+
+impl Channel {
+    fn new(period: usize) -> Self {
+        let lookback = period.saturating_sub(1);
+        Self {
+            high: RollingMax::new(lookback),
+            low: RollingMin::new(lookback),
+        }
+    }
+
+    /// 上轨 (窗口为空时为负无穷，任何价格都在其上方)
+    fn upper(&self) -> f64 {
+        self.high.value().unwrap_or(f64::NEG_INFINITY)
+    }
+
+    /// 下轨 (窗口为空时为正无穷)
+    fn lower(&self) -> f64 {
+        self.low.value().unwrap_or(f64::INFINITY)
+    }
+
+    fn push(&mut self, candle: &CandleData) {
+        self.high.update(&candle.high);
+        self.low.update(&candle.low);
+    }
+}
+
+/// 逐根K线增量维护的指标，通道在处理完一根K线后才加入该K线
+#[derive(Debug, Clone)]
+struct TurtleIndicators {
+    bars: usize,       // 已输入的K线数量 (含当前K线)
+    entry: Channel,    // 入场通道
+    exit: Channel,     // 离场通道
+    fallback: Channel, // System 1保底入场通道
+    atr: Atr,          // N
+}
+
+impl TurtleIndicators {
+    fn new(params: &TurtleParams) -> Self {
+        Self {
+            bars: 0,
+            entry: Channel::new(params.entry_period),
+            exit: Channel::new(params.exit_period),
+            fallback: Channel::new(params.fallback_entry_period),
+            atr: Atr::new(params.atr_period, params.atr_smoothing),
+        }
+    }
+
+    fn push_channels(&mut self, candle: &CandleData) {
+        self.entry.push(candle);
+        self.exit.push(candle);
+        self.fallback.push(candle);
+    }
+}
+
+/// 策略视角的持仓方向 (由成交回报维护)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Flat,
+    Long,
+    Short,
+}
+
+/// 海龟交易策略
+pub struct TurtleStrategy {
+    params: TurtleParams,            // 策略参数
+    symbol: String,                  // 交易对
+    last_breakout: Option<Breakout>, // 最近一次突破的假想交易
+    fallback_pending: bool,          // System 1跳过突破后等待保底周期突破
+    side: Side,                      // 当前持仓方向
+    unit_entries: Vec<f64>,          // 已成交单位的入场价
+    unit_n: Option<f64>,             // 首个单位入场时的N，决定加仓间距
+    current_n: Option<f64>,          // 最近一根K线的N
+    pending_add: Option<f64>,        // 已发出尚未成交的加仓价位 (避免逐笔重复发出)
+    last_bar: Option<DateTime<Utc>>, // 已处理的最新K线时间
+    indicators: TurtleIndicators,    // 增量指标
+}
+
+impl TurtleStrategy {
+    /// 创建新的海龟策略实例
+    pub fn new(symbol: String, params: Option<TurtleParams>) -> Self {
+        let params = params.unwrap_or_default();
+        Self {
+            indicators: TurtleIndicators::new(&params),
+            params,
+            symbol,
+            last_breakout: None,
+            fallback_pending: false,
+            side: Side::Flat,
+            unit_entries: Vec::new(),
+            unit_n: None,
+            current_n: None,
+            pending_add: None,
+            last_bar: None,
+        }
+    }
+
+    /// 最近一次已结束的突破假想交易的结果
+    pub fn last_breakout_outcome(&self) -> Option<BreakoutOutcome> {
+        self.last_breakout.and_then(|b| b.outcome)
+    }
+
+    /// 处理一根新收盘的K线 (candles为截至该K线的历史)，更新突破状态并生成交易信号
+    ///
+    /// 指标按K线增量更新，candles只用于读取最新K线和计算置信度。
+    fn step(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        let Some(latest) = candles.last() else {
+            return Ok(vec![]);
+        };
+
+        self.indicators.bars += 1;
+        self.indicators.atr.update(latest);
+        let signals = self.evaluate(candles);
+        self.indicators.push_channels(latest);
+        signals
+    }
+
+    /// 用更新后的指标判断最新K线上的信号
+    fn evaluate(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        if self.indicators.bars < self.warmup_bars() {
+            return Ok(vec![]); // 数据不足，无法分析
+        }
+
+        self.current_n = self.indicators.atr.value();
+        self.pending_add = None;
+
+        // 先用最新K线结算进行中的假想交易，再判断新的突破
+        self.update_breakout(candles)?;
+
+        let mut signals = Vec::new();
+
+        // 计算入场信号 (持有多头时的新突破不重复入场，加仓按N间距进行)
+        if let Some(entry_signal) = self.check_entry_signal(candles)?
+            && !(self.side == Side::Long && entry_signal.signal_type == SignalType::Buy)
+        {
+            signals.push(entry_signal);
+        }
+
+        // 计算离场信号，离场时不再加仓
+        if let Some(exit_signal) = self.check_exit_signal(candles)? {
+            signals.push(exit_signal);
+        } else {
+            let latest = &candles[candles.len() - 1];
+            signals.extend(self.check_pyramid(latest.open, latest.high, latest.timestamp));
+        }
+
+        Ok(signals)
+    }
+
+    /// 多头持仓未满时，价格每较上次入场有利变动0.5N加仓一个单位
+    ///
+    /// open/high为本次可成交的价格区间：K线的开盘价与最高价，或Ticker的最新价。
+    fn check_pyramid(&self, open: f64, high: f64, timestamp: DateTime<Utc>) -> Vec<TradingSignal> {
+        let (Some(&last_entry), Some(n)) =
+            (self.unit_entries.last(), self.unit_n.or(self.current_n))
+        else {
+            return Vec::new();
+        };
+        if self.side != Side::Long || n <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut signals = Vec::new();
+        let mut units = self.unit_entries.len();
+        let mut level = last_entry + PYRAMID_SPACING_N * n;
+        while units < self.params.max_units && high >= level {
+            if self.pending_add.is_none_or(|pending| level > pending) {
+                // 跳空高开时按开盘价成交
+                let price = level.max(open);
+                signals.push(TradingSignal {
+                    id: None,
+                    symbol: self.symbol.clone(),
+                    signal_type: SignalType::Buy,
+                    price,
+                    timestamp,
+                    strategy: "Turtle".to_string(),
+                    reason: format!(
+                        "加仓第{}个单位：价格{:.4}达到加仓价{:.4} (上次入场{:.4}+{}N，N={:.4})",
+                        units + 1,
+                        price,
+                        level,
+                        last_entry,
+                        PYRAMID_SPACING_N,
+                        n
+                    ),
+                    confidence: 0.7,
+                    atr: Some(n),
+                });
+            }
+            units += 1;
+            level += PYRAMID_SPACING_N * n;
+        }
+
+        signals
+    }
+
+    /// 用最新K线结算进行中的假想交易: 触及2N止损为亏损，跌破/突破离场通道时按盈亏判定
+    fn update_breakout(&mut self, candles: &[CandleData]) -> Result<()> {
+        if self.indicators.bars < self.params.exit_period + 1 {
+            return Ok(());
+        }
+
+        let latest = &candles[candles.len() - 1];
+        let exit_high = self.indicators.exit.upper();
+        let exit_low = self.indicators.exit.lower();
+
+        let Some(breakout) = self.last_breakout.as_mut().filter(|b| b.outcome.is_none()) else {
+            return Ok(());
+        };
+
+        breakout.outcome = if breakout.is_long {
+            if latest.low <= breakout.stop_price {
+                Some(BreakoutOutcome::Loser)
+            } else if latest.close < exit_low {
+                Some(if latest.close > breakout.entry_price {
+                    BreakoutOutcome::Winner
+                } else {
+                    BreakoutOutcome::Loser
+                })
+            } else {
+                None
+            }
+        } else if latest.high >= breakout.stop_price {
+            Some(BreakoutOutcome::Loser)
+        } else if latest.close > exit_high {
+            Some(if latest.close < breakout.entry_price {
+                BreakoutOutcome::Winner
+            } else {
+                BreakoutOutcome::Loser
+            })
+        } else {
+            None
+        };
+
+        // 行情结束后不再等待保底突破
+        if breakout.outcome.is_some() {
+            self.fallback_pending = false;
+        }
+
+        Ok(())
+    }
+
+    /// 检查入场信号
+    fn check_entry_signal(&mut self, candles: &[CandleData]) -> Result<Option<TradingSignal>> {
+        if self.indicators.bars < self.params.entry_period + 1 {
+            return Ok(None);
+        }
+
+        let latest_candle = &candles[candles.len() - 1];
+
+        // 入场周期内的最高价和最低价
+        let entry_high = self.indicators.entry.upper();
+        let entry_low = self.indicators.entry.lower();
+
+        // 突破入场条件
+        // 多头入场: 当前价格突破N日最高价; 空头入场: 当前价格突破N日最低价
+        let (is_long, level) = if latest_candle.close > entry_high {
+            (true, entry_high)
+        } else if latest_candle.close < entry_low {
+            (false, entry_low)
+        } else {
+            return self.check_fallback_entry(candles);
+        };
+
+        // 假想交易尚未结束时的后续突破属于同一波行情
+        if self.last_breakout.is_some_and(|b| b.outcome.is_none()) {
+            return self.check_fallback_entry(candles);
+        }
+
+        let atr = self.current_n.unwrap_or_default();
+        let previous_winner = self.last_breakout_outcome() == Some(BreakoutOutcome::Winner);
+
+        // 无论是否入场，本次突破都作为下一次突破的参照
+        self.last_breakout = Some(Breakout {
+            is_long,
+            entry_price: latest_candle.close,
+            stop_price: if is_long {
+                latest_candle.close - 2.0 * atr
+            } else {
+                latest_candle.close + 2.0 * atr
+            },
+            outcome: None,
+        });
+
+        if self.params.system == TurtleSystem::System1 && previous_winner {
+            info!(
+                "System 1 跳过{}突破: 上次突破为盈利交易，等待{}日保底突破",
+                self.symbol, self.params.fallback_entry_period
+            );
+            self.fallback_pending = true;
+            return self.check_fallback_entry(candles);
+        }
+
+        self.fallback_pending = false;
+        let reason = format!(
+            "价格{}{}{}日{}{:.4}，ATR={:.4}",
+            latest_candle.close,
+            if is_long { "突破" } else { "跌破" },
+            self.params.entry_period,
+            if is_long { "最高价" } else { "最低价" },
+            level,
+            atr
+        );
+        Ok(Some(self.entry_signal(candles, is_long, reason)?))
+    }
+
+    /// System 1跳过突破后，价格突破保底周期通道时入场
+    fn check_fallback_entry(&mut self, candles: &[CandleData]) -> Result<Option<TradingSignal>> {
+        let period = self.params.fallback_entry_period;
+        if !self.fallback_pending || self.indicators.bars < period + 1 {
+            return Ok(None);
+        }
+        let Some(breakout) = self.last_breakout else {
+            return Ok(None);
+        };
+
+        let latest_candle = &candles[candles.len() - 1];
+        let level = if breakout.is_long {
+            self.indicators.fallback.upper()
+        } else {
+            self.indicators.fallback.lower()
+        };
+        let triggered = if breakout.is_long {
+            latest_candle.close > level
+        } else {
+            latest_candle.close < level
+        };
+        if !triggered {
+            return Ok(None);
+        }
+
+        self.fallback_pending = false;
+        let atr = self.current_n.unwrap_or_default();
+        let reason = format!(
+            "System 1保底入场：价格{}{}{}日{}{:.4}，ATR={:.4}",
+            latest_candle.close,
+            if breakout.is_long { "突破" } else { "跌破" },
+            period,
+            if breakout.is_long {
+                "最高价"
+            } else {
+                "最低价"
+            },
+            level,
+            atr
+        );
+        Ok(Some(self.entry_signal(
+            candles,
+            breakout.is_long,
+            reason,
+        )?))
+    }
+
+    /// 生成突破入场信号
+    fn entry_signal(
+        &self,
+        candles: &[CandleData],
+        is_long: bool,
+        reason: String,
+    ) -> Result<TradingSignal> {
+        let latest_candle = &candles[candles.len() - 1];
+        let confidence = self.calculate_confidence(candles, is_long)?;
+
+        Ok(TradingSignal {
+            id: None,
+            symbol: self.symbol.clone(),
+            signal_type: if is_long {
+                SignalType::Buy
+            } else {
+                SignalType::Sell
+            },
+            price: latest_candle.close,
+            timestamp: latest_candle.timestamp,
+            strategy: "Turtle".to_string(),
+            reason,
+            confidence,
+            atr: self.current_n,
+        })
+    }
+
+    /// 检查离场信号
+    fn check_exit_signal(&self, candles: &[CandleData]) -> Result<Option<TradingSignal>> {
+        if self.indicators.bars < self.params.exit_period + 1 {
+            return Ok(None);
+        }
+
+        let latest_candle = &candles[candles.len() - 1];
+
+        // 离场周期内的最高价和最低价
+        let exit_high = self.indicators.exit.upper();
+        let exit_low = self.indicators.exit.lower();
+
+        // 多头离场: 当前价格跌破N日最低价 (重启后策略不知道已恢复的持仓，因此不按方向过滤)
+        if latest_candle.close < exit_low {
+            let confidence = 0.8; // 离场信号置信度较高
+
+            return Ok(Some(TradingSignal {
+                id: None,
+                symbol: self.symbol.clone(),
+                signal_type: SignalType::Sell,
+                price: latest_candle.close,
+                timestamp: latest_candle.timestamp,
+                strategy: "Turtle_Exit".to_string(),
+                reason: format!(
+                    "多头离场：价格{}跌破{}日最低价{:.4}",
+                    latest_candle.close, self.params.exit_period, exit_low
+                ),
+                confidence,
+                atr: self.current_n,
+            }));
+        }
+
+        // 空头离场: 当前价格突破N日最高价 (仅在持有空头时发出，避免被当作开多信号)
+        if self.side == Side::Short && latest_candle.close > exit_high {
+            let confidence = 0.8; // 离场信号置信度较高
+
+            return Ok(Some(TradingSignal {
+                id: None,
+                symbol: self.symbol.clone(),
+                signal_type: SignalType::Buy,
+                price: latest_candle.close,
+                timestamp: latest_candle.timestamp,
+                strategy: "Turtle_Exit".to_string(),
+                reason: format!(
+                    "空头离场：价格{}突破{}日最高价{:.4}",
+                    latest_candle.close, self.params.exit_period, exit_high
+                ),
+                confidence,
+                atr: self.current_n,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// 计算指定周期内的最高价
+    fn calculate_highest_high(&self, candles: &[CandleData], period: usize) -> Result<f64> {
+        let len = candles.len();
+        if len < period {
+            return Err(anyhow::anyhow!("数据不足以计算最高价"));
+        }
+
+        let start_idx = len - period;
+        let high_prices: Vec<f64> = candles[start_idx..len - 1].iter().map(|c| c.high).collect();
+
+        Ok(high_prices.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b)))
+    }
+
+    /// 计算指定周期内的最低价
+    fn calculate_lowest_low(&self, candles: &[CandleData], period: usize) -> Result<f64> {
+        let len = candles.len();
+        if len < period {
+            return Err(anyhow::anyhow!("数据不足以计算最低价"));
+        }
+
+        let start_idx = len - period;
+        let low_prices: Vec<f64> = candles[start_idx..len - 1].iter().map(|c| c.low).collect();
+
+        Ok(low_prices.iter().fold(f64::INFINITY, |a, &b| a.min(b)))
+    }
+
+    /// 计算ATR (Average True Range) 平均真实波幅
+    fn calculate_atr(&self, candles: &[CandleData], period: usize) -> Result<f64> {
+        let len = candles.len();
+        if len < period + 1 {
+            return Err(anyhow::anyhow!("数据不足以计算ATR"));
+        }
+
+        // 简单平均只依赖最近period+1根K线，指数平滑需要全部历史
+        let history = match self.params.atr_smoothing {
+            AtrSmoothing::Sma => &candles[len - period - 1..],
+            AtrSmoothing::Ema | AtrSmoothing::Wilder => candles,
+        };
+        Atr::new(period, self.params.atr_smoothing)
+            .warm_up(history)
+            .ok_or_else(|| anyhow::anyhow!("真实波幅数据不足"))
+    }
+
+    /// 开仓使用的N: 优先取逐根K线增量维护的N (与信号记录的N一致)，
+    /// 策略尚未处理过K线时按传入的K线计算
+    fn signal_n(&self, candles: &[CandleData]) -> Result<Option<f64>> {
+        if self.current_n.is_some() {
+            return Ok(self.current_n);
+        }
+        Ok(self.calculate_indicators(candles)?.atr)
+    }
+
+    /// 计算信号置信度
+    fn calculate_confidence(&self, candles: &[CandleData], is_long: bool) -> Result<f64> {
+        let len = candles.len();
+        if len < 10 {
+            return Ok(0.5); // 默认置信度
+        }
+
+        // 基于成交量和价格动量计算置信度
+        let recent_candles = &candles[len - 10..];
+        let avg_volume =
+            recent_candles.iter().map(|c| c.volume).sum::<f64>() / recent_candles.len() as f64;
+
+        let latest_volume = recent_candles.last().unwrap().volume;
+        let volume_ratio = latest_volume / avg_volume;
+
+        // 计算价格动量
+        let price_change = if recent_candles.len() >= 2 {
+            let latest_price = recent_candles.last().unwrap().close;
+            let prev_price = recent_candles[recent_candles.len() - 2].close;
+            (latest_price - prev_price) / prev_price
+        } else {
+            0.0
+        };
+
+        // 根据成交量放大和价格动量计算置信度
+        let mut confidence: f64 = 0.5;
+
+        // 成交量放大增加置信度
+        if volume_ratio > 1.5 {
+            confidence += 0.2;
+        } else if volume_ratio > 1.2 {
+            confidence += 0.1;
+        }
+
+        // 价格动量与信号方向一致增加置信度
+        if (is_long && price_change > 0.0) || (!is_long && price_change < 0.0) {
+            confidence += 0.1;
+        }
+
+        // 限制置信度范围在0.1到0.9之间
+        Ok(confidence.clamp(0.1, 0.9))
+    }
+
+    /// 计算仓位大小
+    pub fn calculate_position_size(&self, balance: f64, _price: f64, atr: f64) -> f64 {
+        // 海龟交易法则的仓位计算
+        // 风险资金 = 总资金 * 风险比例
+        let risk_capital = balance * self.params.risk_per_trade;
+
+        // 单位风险 = ATR * 价格系数 (通常为1)
+        let unit_risk = atr * 1.0;
+
+        // 仓位大小 = 风险资金 / 单位风险
+        if unit_risk > 0.0 {
+            risk_capital / unit_risk
+        } else {
+            0.0
+        }
+    }
+
+    /// 获取策略参数
+    pub fn get_params(&self) -> &TurtleParams {
+        &self.params
+    }
+
+    /// 更新策略参数，指标和突破状态在下次 `on_bar` 时按新参数重新回放
+    pub fn update_params(&mut self, params: TurtleParams) {
+        self.indicators = TurtleIndicators::new(&params);
+        self.last_breakout = None;
+        self.fallback_pending = false;
+        self.current_n = None;
+        self.last_bar = None;
+        self.params = params;
+        info!("海龟策略参数已更新: {:?}", self.params);
+    }
+
+    /// 验证策略参数
+    pub fn validate_params(params: &TurtleParams) -> Result<()> {
+        if params.entry_period == 0 {
+            return Err(anyhow::anyhow!("入场周期必须大于0"));
+        }
+
+        if params.exit_period == 0 {
+            return Err(anyhow::anyhow!("离场周期必须大于0"));
+        }
+
+        if params.atr_period == 0 {
+            return Err(anyhow::anyhow!("ATR周期必须大于0"));
+        }
+
+        if params.risk_per_trade <= 0.0 || params.risk_per_trade > 1.0 {
+            return Err(anyhow::anyhow!("每笔交易风险必须在0到1之间"));
+        }
+
+        if params.max_units == 0 {
+            return Err(anyhow::anyhow!("最大仓位单位必须大于0"));
+        }
+
+        if !params.stop_n.is_finite() || params.stop_n <= 0.0 {
+            return Err(anyhow::anyhow!("止损距离必须大于0"));
+        }
+
+        if params.system == TurtleSystem::System1
+            && params.fallback_entry_period <= params.entry_period
+        {
+            return Err(anyhow::anyhow!("保底入场周期必须大于入场周期"));
+        }
+
+        Ok(())
+    }
+
+    /// 计算技术指标摘要
+    pub fn calculate_indicators(&self, candles: &[CandleData]) -> Result<IndicatorSummary> {
+        if candles.is_empty() {
+            return Err(anyhow::anyhow!("没有K线数据"));
+        }
+
+        let latest = candles.last().unwrap();
+
+        let entry_high = if candles.len() >= self.params.entry_period {
+            Some(self.calculate_highest_high(candles, self.params.entry_period)?)
+        } else {
+            None
+        };
+
+        let entry_low = if candles.len() >= self.params.entry_period {
+            Some(self.calculate_lowest_low(candles, self.params.entry_period)?)
+        } else {
+            None
+        };
+
+        let exit_high = if candles.len() >= self.params.exit_period {
+            Some(self.calculate_highest_high(candles, self.params.exit_period)?)
+        } else {
+            None
+        };
+
+        let exit_low = if candles.len() >= self.params.exit_period {
+            Some(self.calculate_lowest_low(candles, self.params.exit_period)?)
+        } else {
+            None
+        };
+
+        let atr = if candles.len() > self.params.atr_period {
+            Some(self.calculate_atr(candles, self.params.atr_period)?)
+        } else {
+            None
+        };
+
+        Ok(IndicatorSummary {
+            current_price: latest.close,
+            entry_high,
+            entry_low,
+            exit_high,
+            exit_low,
+            atr,
+            timestamp: latest.timestamp,
+        })
+    }
+}
+
+impl BarReplay for TurtleStrategy {
+    fn last_bar_mut(&mut self) -> &mut Option<DateTime<Utc>> {
+        &mut self.last_bar
+    }
+}
+
+impl Strategy for TurtleStrategy {
+    fn name(&self) -> &str {
+        "Turtle"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn warmup_bars(&self) -> usize {
+        // 入场突破和ATR都需要额外一根K线作为比较基准
+        self.params.entry_period.max(self.params.atr_period) + 1
+    }
+
+    fn params(&self) -> serde_json::Value {
+        serde_json::to_value(&self.params).unwrap_or_default()
+    }
+
+    fn fresh(&self) -> Box<dyn Strategy> {
+        Box::new(TurtleStrategy::new(
+            self.symbol.clone(),
+            Some(self.params.clone()),
+        ))
+    }
+
+    fn on_bar(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+        self.replay_bars(candles, |strategy, bars| strategy.step(bars))
+    }
+
+    fn on_tick(&mut self, ticker: &TickerSnapshot) -> Result<Vec<TradingSignal>> {
+        let signals = self.check_pyramid(ticker.last_price, ticker.last_price, ticker.timestamp);
+        if let Some(last) = signals.last() {
+            self.pending_add = Some(last.price);
+        }
+        Ok(signals)
+    }
+
+    fn on_fill(&mut self, trade: &Trade) {
+        self.pending_add = None;
+        self.side = match (self.side, trade.side.as_str()) {
+            (Side::Flat, "buy") => {
+                self.unit_entries = vec![trade.price];
+                self.unit_n = self.current_n;
+                Side::Long
+            }
+            (Side::Long, "buy") => {
+                self.unit_entries.push(trade.price);
+                Side::Long
+            }
+            (Side::Flat, "sell") => Side::Short,
+            (Side::Long, "sell") | (Side::Short, "buy") => {
+                self.unit_entries.clear();
+                self.unit_n = None;
+                Side::Flat
+            }
+            (side, _) => side,
+        };
+    }
+
+    fn max_units(&self) -> usize {
+        self.params.max_units
+    }
+
+    fn stop_price(&self, signal: &TradingSignal, candles: &[CandleData]) -> Result<Option<f64>> {
+        if signal.signal_type != SignalType::Buy {
+            return Ok(None);
+        }
+
+        // 加仓单位沿用首个单位入场时的N
+        let n = match self.unit_n {
+            Some(n) if self.side == Side::Long => n,
+            _ => match self.signal_n(candles)? {
+                Some(n) => n,
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(signal.price - self.params.stop_n * n))
+    }
+
+    fn on_position_restored(&mut self, position: &Position) {
+        if position.quantity <= 0.0 {
+            return;
+        }
+
+        self.side = Side::Long;
+        self.unit_entries = position.units.iter().map(|u| u.entry_price).collect();
+        // 加仓会上移之前单位的止损，最后一个单位仍保持原始的止损距离
+        self.unit_n = position.units.last().and_then(|u| {
+            u.stop_price
+                .map(|stop| (u.entry_price - stop) / self.params.stop_n)
+        });
+    }
+
+    fn position_size(
+        &self,
+        signal: &TradingSignal,
+        balance: f64,
+        candles: &[CandleData],
+    ) -> Result<Option<f64>> {
+        if candles.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(self
+            .signal_n(candles)?
+            .map(|n| self.calculate_position_size(balance, signal.price, n)))
+    }
+}
+
+/// 技术指标摘要
+#[derive(Debug, Clone)]
+pub struct IndicatorSummary {
+    pub current_price: f64,       // 当前价格
+    pub entry_high: Option<f64>,  // 入场最高价
+    pub entry_low: Option<f64>,   // 入场最低价
+    pub exit_high: Option<f64>,   // 离场最高价
+    pub exit_low: Option<f64>,    // 离场最低价
+    pub atr: Option<f64>,         // 平均真实波幅
+    pub timestamp: DateTime<Utc>, // 时间戳
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::test_support::{SYMBOL, ohlc_candles};
+    use chrono::Utc;
+
+    fn create_test_candles() -> Vec<CandleData> {
+        let mut candles = Vec::new();
+        let base_time = Utc::now();
+
+        // 创建30根测试K线数据
+        for i in 0..30 {
+            candles.push(CandleData {
+                timestamp: base_time + chrono::Duration::minutes(i),
+                symbol: "SOL-USDT".to_string(),
+                open: 100.0 + (i as f64 * 0.1),
+                high: 102.0 + (i as f64 * 0.1),
+                low: 98.0 + (i as f64 * 0.1),
+                close: 101.0 + (i as f64 * 0.1),
+                volume: 1000.0,
+            });
+        }
+
+        candles
+    }
+
+    #[test]
+    fn test_turtle_strategy_creation() {
+        let strategy = TurtleStrategy::new("SOL-USDT".to_string(), None);
+        assert_eq!(strategy.symbol, "SOL-USDT");
+        assert_eq!(strategy.params.entry_period, 20);
+    }
+
+    #[test]
+    fn test_atr_calculation() {
+        let strategy = TurtleStrategy::new("SOL-USDT".to_string(), None);
+        let candles = create_test_candles();
+
+        let atr = strategy.calculate_atr(&candles, 10).unwrap();
+        assert!(atr > 0.0);
+    }
+
+    #[test]
+    fn test_highest_high_calculation() {
+        let strategy = TurtleStrategy::new("SOL-USDT".to_string(), None);
+        let candles = create_test_candles();
+
+        let high = strategy.calculate_highest_high(&candles, 10).unwrap();
+        assert!(high > 100.0);
+    }
+
+    /// 按 (收盘价, 最高价, 最低价) 生成逐分钟K线
+    fn candles_from(bars: &[(f64, f64, f64)]) -> Vec<CandleData> {
+        let bars: Vec<_> = bars
+            .iter()
+            .map(|&(close, high, low)| (close, high, low, close))
+            .collect();
+        ohlc_candles(SYMBOL, &bars)
+    }
+
+    /// 盈利的突破行情之后再次出现20日突破，最后价格突破55日高点
+    fn winner_then_breakouts() -> Vec<(f64, f64, f64)> {
+        let mut bars = vec![(100.0, 101.0, 99.0); 60];
+        // 第60根突破20日高点，随后稳步上涨
+        bars.push((105.0, 105.5, 100.0));
+        for i in 1..=15 {
+            let close = 105.0 + i as f64 * 0.5;
+            bars.push((close, close + 0.5, close - 0.5));
+        }
+        // 第76根跌破10日低点，以盈利离场
+        bars.push((107.0, 107.2, 106.8));
+        bars.extend(vec![(107.0, 107.5, 106.5); 20]);
+        // 第97根再次突破20日高点，第98根突破55日高点
+        bars.push((109.0, 109.2, 107.0));
+        bars.push((114.0, 114.2, 108.8));
+        bars
+    }
+
+    /// 逐根K线驱动策略，返回每根K线的买入信号原因
+    fn buy_signals(strategy: &mut TurtleStrategy, candles: &[CandleData]) -> Vec<(usize, String)> {
+        let mut buys = Vec::new();
+        for i in 0..candles.len() {
+            for signal in strategy.on_bar(&candles[..=i]).unwrap() {
+                if signal.signal_type == SignalType::Buy {
+                    buys.push((i, signal.reason));
+                }
+            }
+        }
+        buys
+    }
+
+    #[test]
+    fn test_atr_smoothing_applies_to_signal_sizing_and_stop() {
+        // 突破前的K线波幅不等，不同平滑方式得到不同的N
+        let mut bars = winner_then_breakouts();
+        for (i, bar) in bars.iter_mut().take(60).enumerate() {
+            bar.1 = 101.0 + (i % 4) as f64 * 0.5;
+            bar.2 = 99.0 - (i % 3) as f64 * 0.5;
+        }
+        let candles = candles_from(&bars);
+        let mut entry_n = Vec::new();
+
+        for smoothing in [AtrSmoothing::Sma, AtrSmoothing::Ema, AtrSmoothing::Wilder] {
+            let params = TurtleParams {
+                atr_smoothing: smoothing,
+                ..TurtleParams::default()
+            };
+            let mut strategy = TurtleStrategy::new("SOL-USDT".to_string(), Some(params));
+            let mut entry = None;
+            for i in 0..candles.len() {
+                if let Some(signal) = strategy
+                    .on_bar(&candles[..=i])
+                    .unwrap()
+                    .into_iter()
+                    .find(|s| s.signal_type == SignalType::Buy)
+                {
+                    entry = Some((i, signal));
+                    break;
+                }
+            }
+            let (i, signal) = entry.unwrap();
+
+            // 信号记录的N与整段K线按同一平滑方式计算的ATR一致
+            let n = signal.atr.unwrap();
+            let expected = crate::indicators::atr(&candles[..=i], 20, smoothing)[i].unwrap();
+            assert!((n - expected).abs() < 1e-9, "{:?}", smoothing);
+
+            let history = &candles[..=i];
+            let stop = strategy.stop_price(&signal, history).unwrap().unwrap();
+            assert!((stop - (signal.price - 2.0 * n)).abs() < 1e-9);
+            let size = strategy
+                .position_size(&signal, 10000.0, history)
+                .unwrap()
+                .unwrap();
+            assert!((size - 10000.0 * 0.02 / n).abs() < 1e-9);
+            entry_n.push(n);
+        }
+
+        assert!((entry_n[0] - entry_n[2]).abs() > 1e-6);
+    }
+
+    #[test]
+    fn test_incremental_state_needs_only_new_bars() {
+        let candles = candles_from(&winner_then_breakouts());
+        let mut full = TurtleStrategy::new("SOL-USDT".to_string(), None);
+        let expected = buy_signals(&mut full, &candles);
+
+        // 实盘只传入最近的K线窗口，指标状态与传入全部历史时一致
+        let mut windowed = TurtleStrategy::new("SOL-USDT".to_string(), None);
+        let mut buys = Vec::new();
+        for i in 0..candles.len() {
+            let window = &candles[i.saturating_sub(10)..=i];
+            for signal in windowed.on_bar(window).unwrap() {
+                if signal.signal_type == SignalType::Buy {
+                    buys.push((i, signal.reason));
+                }
+            }
+        }
+        assert_eq!(buys, expected);
+        assert_eq!(windowed.current_n, full.current_n);
+    }
+
+    #[test]
+    fn test_system1_skips_breakout_after_winner_and_takes_fallback() {
+        let candles = candles_from(&winner_then_breakouts());
+        let mut strategy = TurtleStrategy::new("SOL-USDT".to_string(), None);
+
+        let buys = buy_signals(&mut strategy, &candles);
+        let bars: Vec<usize> = buys.iter().map(|(i, _)| *i).collect();
+        assert_eq!(bars, vec![60, 98]);
+        assert!(buys[1].1.contains("保底入场"));
+
+        // 一次性传入全部历史 (实盘首次调用) 得到相同的突破状态
+        let mut replayed = TurtleStrategy::new("SOL-USDT".to_string(), None);
+        replayed.on_bar(&candles[..97]).unwrap();
+        assert_eq!(
+            replayed.last_breakout_outcome(),
+            Some(BreakoutOutcome::Winner)
+        );
+        let signals = replayed.on_bar(&candles[..98]).unwrap();
+        assert!(signals.iter().all(|s| s.signal_type != SignalType::Buy));
+    }
+
+    #[test]
+    fn test_windows_follow_system_when_omitted() {
+        let parse = |value| serde_json::from_value::<TurtleParams>(value).unwrap();
+        let base = serde_json::json!({"atr_period": 20, "risk_per_trade": 0.02, "max_units": 4});
+
+        let mut system2 = base.clone();
+        system2["system"] = serde_json::json!("System2");
+        let params = parse(system2.clone());
+        assert_eq!((params.entry_period, params.exit_period), (55, 20));
+
+        let params = parse(base);
+        assert_eq!((params.entry_period, params.exit_period), (20, 10));
+
+        // 显式给出的周期优先
+        system2["entry_period"] = serde_json::json!(40);
+        let params = parse(system2);
+        assert_eq!((params.entry_period, params.exit_period), (40, 20));
+    }
+
+    #[test]
+    fn test_system2_takes_every_breakout() {
+        let candles = candles_from(&winner_then_breakouts());
+        let params = TurtleParams {
+            entry_period: 20,
+            exit_period: 10,
+            ..TurtleParams::for_system(TurtleSystem::System2)
+        };
+        let mut strategy = TurtleStrategy::new("SOL-USDT".to_string(), Some(params));
+
+        let bars: Vec<usize> = buy_signals(&mut strategy, &candles)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(bars, vec![60, 97]);
+    }
+
+    #[test]
+    fn test_pyramids_every_half_n_up_to_max_units() {
+        // 平盘后突破，随后每根K线上涨0.5
+        let mut bars = vec![(100.0, 101.0, 99.0); 60];
+        bars.push((105.0, 105.5, 100.0));
+        for i in 1..=12 {
+            let close = 105.0 + i as f64 * 0.5;
+            bars.push((close, close + 0.5, close - 0.5));
+        }
+        let candles = candles_from(&bars);
+        let mut strategy = TurtleStrategy::new("SOL-USDT".to_string(), None);
+
+        let mut fills = Vec::new();
+        for i in 0..candles.len() {
+            for signal in strategy.on_bar(&candles[..=i]).unwrap() {
+                if signal.signal_type != SignalType::Buy {
+                    continue;
+                }
+                let stop = strategy
+                    .stop_price(&signal, &candles[..=i])
+                    .unwrap()
+                    .unwrap();
+                strategy.on_fill(&Trade {
+                    id: None,
+                    symbol: "SOL-USDT".to_string(),
+                    side: "buy".to_string(),
+                    price: signal.price,
+                    quantity: 1.0,
+                    timestamp: signal.timestamp,
+                    strategy: signal.strategy.clone(),
+                    strategy_id: "Turtle".to_string(),
+                    pnl: None,
+                    signal_id: None,
+                });
+                fills.push((signal.price, stop));
+            }
+        }
+
+        // 入场后每0.5N加仓一次，共4个单位，止损均为入场时N的2倍
+        assert_eq!(fills.len(), 4);
+        let n = (fills[0].0 - fills[0].1) / 2.0;
+        for pair in fills.windows(2) {
+            assert!((pair[1].0 - pair[0].0 - 0.5 * n).abs() < 1e-9);
+            assert!((pair[1].0 - pair[1].1 - 2.0 * n).abs() < 1e-9);
+        }
+
+        // 持仓已满，Ticker继续上涨也不再加仓
+        let ticker = TickerSnapshot {
+            timestamp: candles[candles.len() - 1].timestamp,
+            symbol: "SOL-USDT".to_string(),
+            last_price: 200.0,
+            bid_price: 200.0,
+            ask_price: 200.0,
+            volume_24h: 0.0,
+        };
+        assert!(strategy.on_tick(&ticker).unwrap().is_empty());
+    }
+}
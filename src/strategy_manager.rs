@@ -1,281 +1,1525 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use log::{error, info, warn};
-use std::collections::HashMap;
+use log::{info, warn};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
 
-use crate::db::Database;
-use crate::strategy::{IndicatorSummary, TurtleStrategy};
+use crate::storage::Storage;
+use crate::strategy::{self, Strategy, TurtleStrategy};
 use crate::types::{
-    BacktestReport, CandleData, Position, SignalType, TickerData, Trade, TradingSignal,
-    TurtleParams,
+    AccountState, BacktestReport, BacktestRun, BookQuote, CandleData, Fill, FundingPayment,
+    FundingRate, MultiLegAction, MultiLegSignal, Order, OrderState, OrderType, PairedPosition,
+    PortfolioSummary, Position, PositionLeg, PositionMismatch, PositionUnit, SignalOutcome,
+    SignalType, StrategyInstance, StrategySummary, TickerData, TickerSnapshot, Trade,
+    TradingSignal,
 };
 
+/// 默认初始资金 (USDT)
+const DEFAULT_BALANCE: f64 = 10000.0;
+
+/// 默认海龟策略实例ID (旧版本数据中的交易和持仓均归属该实例)
+pub const DEFAULT_STRATEGY_ID: &str = "Turtle";
+
+/// 代码版本 (包版本+git提交)，随回测记录保存
+const CODE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+", env!("GIT_HASH"));
+
+/// 单次开仓金额占策略可用资金的上限，超过时拒绝开仓 (策略校验资金比例参数时也以此为上限)
+pub const MAX_POSITION_FRACTION: f64 = 0.95;
+
+/// 持仓数量比较的容差
+const QUANTITY_EPSILON: f64 = 1e-8;
+
+/// 实盘分析时至少加载的K线数量
+const MIN_LOOKBACK_BARS: usize = 100;
+
+/// 多腿策略首次运行 (实盘启动或回测开始) 时加载的历史资金费率天数
+const FUNDING_WARMUP_DAYS: i64 = 7;
+
+/// 止损离场信号和交易记录使用的策略名称
+pub const STOP_LOSS_STRATEGY: &str = "Stop_Loss";
+
+/// 实盘信号执行结果
+enum Execution {
+    Executed(Trade),                // 已成交
+    Skipped(SignalOutcome, String), // 未执行及原因
+}
+
+/// 多腿信号的执行计划
+enum LegPlan {
+    Trades(Vec<Trade>, Vec<f64>), // 各腿交易 (平仓时含盈亏) 与开仓时各腿冻结的现金
+    Skipped(SignalOutcome, String), // 未执行及原因
+}
+
+/// 策略实例及其分配资金
+struct StrategySlot {
+    strategy: Box<dyn Strategy>, // 策略实例
+    allocation: f64,             // 分配资金
+}
+
 /// 策略管理器
+///
+/// 同一交易对可以运行多个策略实例，每个实例以ID区分，拥有独立的分配资金、持仓和盈亏；
+/// 账户现金 `balance` 为所有实例共享的实际余额。
 pub struct StrategyManager {
-    db: Arc<Database>,                           // 数据库实例
-    strategies: HashMap<String, TurtleStrategy>, // 策略实例映射
-    positions: HashMap<String, Position>,        // 当前持仓
-    balance: f64,                                // 账户余额
+    db: Arc<dyn Storage>,                              // 存储实例
+    strategies: BTreeMap<String, StrategySlot>,        // 策略实例 (按实例ID)
+    last_bars: HashMap<String, DateTime<Utc>>,         // 各交易对已处理的最新K线时间
+    bar_cache: HashMap<String, Vec<CandleData>>,       // 各交易对最近的已收盘K线 (实盘增量追加)
+    positions: HashMap<String, Position>,              // 当前持仓 (按策略实例ID)
+    paired_positions: HashMap<String, PairedPosition>, // 多腿策略的配对持仓 (按策略实例ID)
+    realized_pnl: HashMap<String, f64>,                // 各策略实例已实现盈亏
+    // 各多腿策略实例每条腿已处理的最新资金费结算时间 (按实例ID、交易对)
+    funding_cursor: HashMap<String, HashMap<String, DateTime<Utc>>>,
+    resting_orders: HashMap<String, Vec<Order>>, // 各策略实例在订单表中的未完结挂单
+    order_seq: i64,                              // 挂单客户端订单ID的序号
+    restored_trades: HashMap<String, Vec<Trade>>, // 重启时持仓实例本轮的成交记录 (等待实例注册后交给策略)
+    balance: f64,                                 // 账户现金余额
 }
 
 impl StrategyManager {
-    /// 创建新的策略管理器
-    pub fn new(db: Arc<Database>) -> Self {
-        let mut strategies = HashMap::new();
+    /// 创建新的策略管理器，并从数据库恢复账户状态
+    pub async fn new(db: Arc<dyn Storage>) -> Result<Self> {
+        let mut manager = Self {
+            db,
+            strategies: BTreeMap::new(),
+            last_bars: HashMap::new(),
+            bar_cache: HashMap::new(),
+            positions: HashMap::new(),
+            paired_positions: HashMap::new(),
+            realized_pnl: HashMap::new(),
+            funding_cursor: HashMap::new(),
+            resting_orders: HashMap::new(),
+            // 以启动时间为起点，重启后生成的客户端订单ID不会与已有订单重复
+            order_seq: Utc::now().timestamp_millis(),
+            restored_trades: HashMap::new(),
+            balance: DEFAULT_BALANCE,
+        };
 
-        // 初始化SOL-USDT的海龟策略
+        // 初始化SOL-USDT的海龟策略，默认分配全部初始资金，添加其他实例时让出所需的部分
         let turtle_strategy = TurtleStrategy::new("SOL-USDT".to_string(), None);
-        strategies.insert("SOL-USDT".to_string(), turtle_strategy);
+        manager.add_strategy(
+            DEFAULT_STRATEGY_ID,
+            Box::new(turtle_strategy),
+            DEFAULT_BALANCE,
+        )?;
 
-        Self {
-            db,
-            strategies,
-            positions: HashMap::new(),
-            balance: 10000.0, // 默认10000 USDT
+        // 运行时添加的策略实例在恢复账户状态前重新创建，恢复的持仓和成交记录交给对应实例
+        let instances = manager.db.get_strategy_instances().await?;
+        for instance in &instances {
+            let strategy = strategy::create_strategy(
+                &instance.kind,
+                &instance.symbol,
+                Some(instance.params.clone()),
+            )
+            .map_err(|e| anyhow::anyhow!("无法重建策略实例{}: {}", instance.strategy_id, e))?;
+            manager.strategies.insert(
+                instance.strategy_id.clone(),
+                StrategySlot {
+                    strategy,
+                    allocation: instance.allocation,
+                },
+            );
+        }
+
+        manager.restore_account_state().await?;
+
+        // 账户权益恢复后再为这些实例预留资金，默认实例只保留剩余部分
+        for instance in &instances {
+            if let Err(e) = manager.reserve_allocation(&instance.strategy_id, instance.allocation) {
+                warn!(
+                    "策略实例{}的分配资金超出账户权益: {}",
+                    instance.strategy_id, e
+                );
+            }
+        }
+
+        Ok(manager)
+    }
+
+    /// 从数据库恢复余额与持仓，按交易历史与资金费收支汇总各策略实例的已实现盈亏，并做一致性校验
+    async fn restore_account_state(&mut self) -> Result<()> {
+        let trades = self
+            .db
+            .get_trades_by_time_range(DateTime::<Utc>::UNIX_EPOCH, Utc::now())
+            .await?;
+        for trade in &trades {
+            if let Some(pnl) = trade.pnl {
+                *self
+                    .realized_pnl
+                    .entry(trade.strategy_id.clone())
+                    .or_insert(0.0) += pnl;
+            }
+        }
+        let payments = self
+            .db
+            .get_funding_payments(DateTime::<Utc>::UNIX_EPOCH, Utc::now())
+            .await?;
+        for payment in &payments {
+            *self
+                .realized_pnl
+                .entry(payment.strategy_id.clone())
+                .or_insert(0.0) += payment.amount;
+        }
+
+        let Some(state) = self.db.load_account_state().await? else {
+            info!("未找到已保存的账户状态，使用默认余额: {:.2}", self.balance);
+            return Ok(());
+        };
+
+        self.balance = state.balance;
+        self.positions = state
+            .positions
+            .into_iter()
+            .map(|mut p| {
+                // 旧版本持仓没有单位记录，视为按成本价一次建仓
+                if p.units.is_empty() && p.quantity > 0.0 {
+                    p.units.push(PositionUnit {
+                        entry_price: p.avg_price,
+                        quantity: p.quantity,
+                        stop_price: None,
+                        timestamp: p.timestamp,
+                    });
+                }
+                (p.strategy_id.clone(), p)
+            })
+            .collect();
+        self.paired_positions = state
+            .paired_positions
+            .into_iter()
+            .map(|p| (p.strategy_id.clone(), p))
+            .collect();
+
+        self.restored_trades = self.trades_since_flat(&trades);
+        for (id, slot) in self.strategies.iter_mut() {
+            if let Some(position) = self.positions.get(id) {
+                slot.strategy.on_position_restored(position);
+            }
+            if let Some(trades) = self.restored_trades.remove(id) {
+                slot.strategy.on_trades_restored(&trades);
+            }
+            if let Some(position) = self.paired_positions.get(id) {
+                slot.strategy.on_paired_position_restored(position);
+            }
+        }
+
+        info!(
+            "恢复账户状态: 余额 {:.2}, 持仓 {} 个, 配对持仓 {} 个 (保存于 {})",
+            self.balance,
+            self.positions.len(),
+            self.paired_positions.len(),
+            state.updated_at
+        );
+
+        for mismatch in self.check_position_consistency().await? {
+            warn!(
+                "持仓与交易历史不一致: {} ({}) 恢复数量 {:.8}, 交易历史推算数量 {:.8}",
+                mismatch.strategy_id,
+                mismatch.symbol,
+                mismatch.restored_quantity,
+                mismatch.expected_quantity
+            );
+        }
+
+        Ok(())
+    }
+
+    /// 按时间顺序的交易历史中，各持仓实例在该持仓交易对上最近一次清仓之后的成交
+    fn trades_since_flat(&self, trades: &[Trade]) -> HashMap<String, Vec<Trade>> {
+        let mut rounds: HashMap<String, (f64, Vec<Trade>)> = HashMap::new();
+        for trade in trades {
+            let Some(position) = self.positions.get(&trade.strategy_id) else {
+                continue;
+            };
+            if trade.symbol != position.symbol {
+                continue;
+            }
+            let (quantity, round) = rounds.entry(trade.strategy_id.clone()).or_default();
+            round.push(trade.clone());
+            match trade.side.as_str() {
+                "buy" => *quantity += trade.quantity,
+                _ => *quantity -= trade.quantity,
+            }
+            if *quantity <= QUANTITY_EPSILON {
+                *quantity = 0.0;
+                round.clear();
+            }
+        }
+
+        rounds
+            .into_iter()
+            .filter(|(_, (_, round))| !round.is_empty())
+            .map(|(id, (_, round))| (id, round))
+            .collect()
+    }
+
+    /// 当前余额与持仓
+    fn account_state(&self) -> AccountState {
+        AccountState {
+            balance: self.balance,
+            positions: self.positions.values().cloned().collect(),
+            paired_positions: self.paired_positions.values().cloned().collect(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// 将当前余额与持仓写入数据库
+    async fn persist_account_state(&self) -> Result<()> {
+        self.db.save_account_state(&self.account_state()).await
+    }
+
+    /// 将交易记录与已更新的余额、持仓在同一事务中写入数据库，返回各交易ID
+    async fn persist_trades(&self, trades: &[Trade]) -> Result<Vec<i64>> {
+        self.db
+            .save_trades_with_account_state(trades, &self.account_state())
+            .await
+    }
+
+    /// 按交易历史重放推算各策略实例的持仓数量，并与当前持仓比较
+    pub async fn check_position_consistency(&self) -> Result<Vec<PositionMismatch>> {
+        let trades = self
+            .db
+            .get_trades_by_time_range(DateTime::<Utc>::UNIX_EPOCH, Utc::now())
+            .await?;
+
+        let mut expected: BTreeMap<(String, String), f64> = BTreeMap::new();
+        for trade in &trades {
+            let quantity = expected
+                .entry((trade.strategy_id.clone(), trade.symbol.clone()))
+                .or_insert(0.0);
+            match trade.side.as_str() {
+                "buy" => *quantity += trade.quantity,
+                "sell" => *quantity -= trade.quantity,
+                other => warn!("未知的交易方向: {} (交易ID {:?})", other, trade.id),
+            }
+        }
+
+        for position in self.positions.values() {
+            expected
+                .entry((position.strategy_id.clone(), position.symbol.clone()))
+                .or_insert(0.0);
+        }
+        for position in self.paired_positions.values() {
+            for leg in &position.legs {
+                expected
+                    .entry((position.strategy_id.clone(), leg.symbol.clone()))
+                    .or_insert(0.0);
+            }
         }
+
+        let mut mismatches = Vec::new();
+        for ((strategy_id, symbol), expected_quantity) in expected {
+            // 配对持仓的空头腿数量为负，与交易历史中先卖后买的推算结果一致
+            let restored_quantity = self
+                .positions
+                .get(&strategy_id)
+                .filter(|p| p.symbol == symbol)
+                .map_or(0.0, |p| p.quantity)
+                + self.paired_positions.get(&strategy_id).map_or(0.0, |p| {
+                    p.legs
+                        .iter()
+                        .filter(|l| l.symbol == symbol)
+                        .map(|l| l.quantity)
+                        .sum()
+                });
+
+            if (restored_quantity - expected_quantity).abs() > QUANTITY_EPSILON {
+                mismatches.push(PositionMismatch {
+                    strategy_id,
+                    symbol,
+                    restored_quantity,
+                    expected_quantity,
+                });
+            }
+        }
+
+        Ok(mismatches)
     }
 
-    /// 处理实时数据并生成交易信号
+    /// 处理实时数据并执行交易信号，返回已成交的信号
+    ///
+    /// 先按最新价检查各持仓的止损，再让交易对上的每个策略实例处理 `on_tick`；
+    /// 数据库中出现新K线时，对已收盘的K线调用 `on_bar` (多腿策略在主交易对出现新K线时调用 `on_legs_bar`)。
     pub async fn process_real_time_data(
         &mut self,
         ticker: &TickerData,
-    ) -> Result<Option<TradingSignal>> {
-        let symbol = &ticker.inst_id;
+    ) -> Result<Vec<TradingSignal>> {
+        let symbol = ticker.inst_id.clone();
+        let snapshot = TickerSnapshot::from(ticker);
+        let book = BookQuote::from(ticker);
 
-        // 获取最近的K线数据用于分析
-        let candles = self.db.get_latest_candles(symbol, 100).await?;
+        // 配对持仓的其他腿不是任何策略的主交易对，先更新价格
+        self.update_positions_price(&symbol, snapshot.last_price);
 
-        if candles.is_empty() {
-            warn!("没有找到{}的K线数据", symbol);
-            return Ok(None);
+        // 获取该交易对上的策略实例
+        let strategy_ids: Vec<String> = self
+            .strategies
+            .iter()
+            .filter(|(_, slot)| slot.strategy.symbol() == symbol)
+            .map(|(id, _)| id.clone())
+            .collect();
+        if strategy_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut executed = Vec::new();
+
+        // 最新价触及止损时按最新价离场
+        for (id, stop_price) in self.triggered_stops(&symbol, snapshot.last_price) {
+            let signal =
+                stop_loss_signal(&symbol, snapshot.last_price, stop_price, snapshot.timestamp);
+            if let Some(signal) = self.handle_signal(&id, signal).await? {
+                executed.push(signal);
+            }
+        }
+
+        let mut pending = Vec::new();
+        let mut pending_legs = Vec::new();
+        for id in &strategy_ids {
+            let slot = self.strategies.get_mut(id).expect("策略实例存在");
+            for signal in slot.strategy.on_tick(&snapshot)? {
+                pending.push((id.clone(), signal));
+            }
+            slot.strategy.on_book(&book);
         }
 
-        // 获取对应的策略
-        if let Some(strategy) = self.strategies.get(symbol) {
-            let signals = strategy.analyze(&candles)?;
+        // 每个Ticker只读取最新一根 (尚未收盘的) K线，出现新K线时才增量加载已收盘K线
+        let latest = self.db.get_latest_candles(&symbol, 1).await?;
+        match latest.last() {
+            None => warn!("没有找到{}的K线数据", symbol),
+            Some(forming) if self.last_bars.get(&symbol) != Some(&forming.timestamp) => {
+                self.last_bars.insert(symbol.clone(), forming.timestamp);
+
+                let warmup = strategy_ids
+                    .iter()
+                    .map(|id| self.strategies[id].strategy.warmup_bars())
+                    .max()
+                    .unwrap_or_default();
+                let lookback = warmup.max(MIN_LOOKBACK_BARS);
+                self.refresh_bar_cache(&symbol, forming.timestamp, lookback)
+                    .await?;
+
+                // 多腿策略的其他腿按需加载最近的已收盘K线
+                let mut leg_bars: HashMap<String, Vec<CandleData>> = HashMap::new();
+                for id in &strategy_ids {
+                    for leg in self.strategies[id]
+                        .strategy
+                        .leg_symbols()
+                        .into_iter()
+                        .skip(1)
+                    {
+                        if let std::collections::hash_map::Entry::Vacant(entry) =
+                            leg_bars.entry(leg)
+                        {
+                            let bars = self.closed_candles(entry.key(), lookback).await?;
+                            entry.insert(bars);
+                        }
+                    }
+                }
 
-            for signal in signals {
-                // 保存信号到数据库
-                self.db.save_signal(&signal).await?;
+                // 其他腿通常没有Ticker推送，按最新已收盘K线更新配对持仓的价格
+                for (leg, bars) in &leg_bars {
+                    if let Some(last) = bars.last() {
+                        self.update_positions_price(leg, last.close);
+                    }
+                }
 
-                // 执行交易逻辑
-                if let Some(trade) = self.execute_signal(&signal).await? {
-                    info!("执行交易: {:?}", trade);
-                    return Ok(Some(signal));
+                // 多腿策略先处理截至最新已收盘K线的资金费结算，再处理K线
+                if let Some(cutoff) = self.bar_cache[&symbol].last().map(|c| c.timestamp) {
+                    for id in &strategy_ids {
+                        self.settle_funding(id, cutoff).await?;
+                    }
+                }
+
+                let closed = &self.bar_cache[&symbol];
+                if !closed.is_empty() {
+                    for id in &strategy_ids {
+                        let slot = self.strategies.get_mut(id).expect("策略实例存在");
+                        let legs = slot.strategy.leg_symbols();
+                        if legs.is_empty() {
+                            for signal in slot.strategy.on_bar(closed)? {
+                                pending.push((id.clone(), signal));
+                            }
+                        } else {
+                            // 其他腿只取不晚于主交易对最新已收盘K线的部分
+                            let cutoff = closed[closed.len() - 1].timestamp;
+                            let bars: Vec<&[CandleData]> = std::iter::once(closed.as_slice())
+                                .chain(legs[1..].iter().map(|leg| {
+                                    let bars = &leg_bars[leg];
+                                    &bars[..bars.partition_point(|c| c.timestamp <= cutoff)]
+                                }))
+                                .collect();
+                            for signal in slot.strategy.on_legs_bar(&bars)? {
+                                pending_legs.push((id.clone(), signal));
+                            }
+                        }
+                    }
                 }
             }
+            Some(_) => {}
         }
 
-        Ok(None)
+        for (id, signal) in pending {
+            if let Some(signal) = self.handle_signal(&id, signal).await? {
+                executed.push(signal);
+            }
+        }
+
+        for (id, signal) in pending_legs {
+            executed.extend(self.handle_multi_leg_signal(&id, signal).await?);
+        }
+
+        for id in &strategy_ids {
+            self.sync_resting_orders(id).await?;
+        }
+
+        Ok(executed)
+    }
+
+    /// 加载策略实例在订单表中的未完结挂单 (客户端订单ID以实例ID为前缀)，已加载时直接返回
+    async fn load_resting_orders(&mut self, strategy_id: &str) -> Result<()> {
+        if self.resting_orders.contains_key(strategy_id) {
+            return Ok(());
+        }
+        let Some(slot) = self.strategies.get(strategy_id) else {
+            return Ok(());
+        };
+
+        let prefix = format!("{}-", strategy_id);
+        let orders = self
+            .db
+            .get_open_orders(Some(slot.strategy.symbol()))
+            .await?
+            .into_iter()
+            .filter(|o| o.client_order_id.starts_with(&prefix))
+            .collect();
+        self.resting_orders.insert(strategy_id.to_string(), orders);
+
+        Ok(())
+    }
+
+    /// 把策略实例维护的挂单同步到订单表: 撤销策略不再挂出的订单，为新的挂单创建订单
+    ///
+    /// 重启前遗留的未完结订单与策略恢复后的挂单不一致时同样撤销。
+    async fn sync_resting_orders(&mut self, strategy_id: &str) -> Result<()> {
+        self.load_resting_orders(strategy_id).await?;
+        let Some(slot) = self.strategies.get(strategy_id) else {
+            return Ok(());
+        };
+        let desired = slot.strategy.resting_orders();
+        let symbol = slot.strategy.symbol().to_string();
+        let name = slot.strategy.name().to_string();
+
+        let current = self.resting_orders.remove(strategy_id).unwrap_or_default();
+        let (mut orders, stale): (Vec<Order>, Vec<Order>) = current
+            .into_iter()
+            .partition(|order| desired.iter().any(|d| d.matches(order)));
+
+        let now = Utc::now();
+        for order in &stale {
+            if let Some(order_id) = order.id {
+                self.db
+                    .update_order_state(order_id, OrderState::Canceled, None, now)
+                    .await?;
+            }
+        }
+
+        for resting in &desired {
+            if orders.iter().any(|o| resting.matches(o)) {
+                continue;
+            }
+            self.order_seq += 1;
+            let mut order = Order {
+                id: None,
+                client_order_id: format!("{}-{}", strategy_id, self.order_seq),
+                exchange_order_id: None,
+                symbol: symbol.clone(),
+                side: resting.side_str().to_string(),
+                order_type: OrderType::Limit,
+                price: Some(resting.price),
+                size: resting.quantity,
+                state: OrderState::Live,
+                filled_size: 0.0,
+                avg_fill_price: None,
+                fee: 0.0,
+                strategy: name.clone(),
+                signal_id: None,
+                created_at: now,
+                updated_at: now,
+            };
+            order.id = Some(self.db.save_order(&order).await?);
+            orders.push(order);
+        }
+
+        self.resting_orders.insert(strategy_id.to_string(), orders);
+        Ok(())
+    }
+
+    /// 成交价与策略实例的某个挂单相同时，把该成交记为该订单的成交明细
+    async fn fill_resting_order(&mut self, strategy_id: &str, trade: &Trade) -> Result<()> {
+        self.load_resting_orders(strategy_id).await?;
+        let Some(orders) = self.resting_orders.get_mut(strategy_id) else {
+            return Ok(());
+        };
+        let Some(index) = orders.iter().position(|o| {
+            o.side == trade.side
+                && o.price
+                    .is_some_and(|price| (price - trade.price).abs() <= f64::EPSILON * price.abs())
+        }) else {
+            return Ok(());
+        };
+
+        let order = &mut orders[index];
+        let fill = Fill {
+            id: None,
+            order_id: order.id.unwrap_or_default(),
+            exchange_trade_id: trade.id.map(|id| id.to_string()),
+            price: trade.price,
+            size: trade.quantity,
+            fee: 0.0,
+            fee_ccy: String::new(),
+            timestamp: trade.timestamp,
+        };
+        order.filled_size += trade.quantity;
+        if order.filled_size + QUANTITY_EPSILON >= order.size {
+            orders.remove(index);
+        }
+        self.db.save_fill(&fill).await?;
+
+        Ok(())
+    }
+
+    /// 向多腿策略推送截至cutoff的资金费率结算，并向其配对持仓记入资金费
+    ///
+    /// 实例首次运行时推送最近 `FUNDING_WARMUP_DAYS` 天的历史费率，只用于策略预测，不记入资金费
+    /// (程序停止期间结算的资金费不会补记)；之后每个结算时点的资金费保存为收支记录并计入已实现盈亏。
+    async fn settle_funding(&mut self, strategy_id: &str, cutoff: DateTime<Utc>) -> Result<()> {
+        let Some(slot) = self.strategies.get(strategy_id) else {
+            return Ok(());
+        };
+        let legs = slot.strategy.leg_symbols();
+        if legs.is_empty() {
+            return Ok(());
+        }
+
+        // 各腿分别维护游标: 结算记录可能晚于结算时间才写入，游标只推进到该腿已处理的最新结算时间，
+        // 没有新记录时保持不变 (首次运行时记为预热区间的起点)，因此晚到的费率在下一根K线仍会结算
+        let mut rates = Vec::new();
+        for leg in &legs {
+            let cursor = self
+                .funding_cursor
+                .get(strategy_id)
+                .and_then(|cursors| cursors.get(leg))
+                .copied();
+            let start = cursor.map_or(cutoff - chrono::Duration::days(FUNDING_WARMUP_DAYS), |t| {
+                t + chrono::Duration::milliseconds(1)
+            });
+            let leg_rates = self.db.get_funding_rates(leg, start, cutoff).await?;
+
+            let latest = leg_rates
+                .iter()
+                .map(|r| r.funding_time)
+                .max()
+                .unwrap_or(start - chrono::Duration::milliseconds(1));
+            self.funding_cursor
+                .entry(strategy_id.to_string())
+                .or_default()
+                .insert(leg.clone(), latest);
+            // 首次运行推送的历史费率只用于预测
+            rates.extend(leg_rates.into_iter().map(|rate| (rate, cursor.is_some())));
+        }
+        rates.sort_by_key(|(rate, _)| rate.funding_time);
+
+        let mut accrued = false;
+        for (rate, settle) in &rates {
+            if *settle && let Some(mut payment) = self.accrue_funding(strategy_id, rate) {
+                payment.id = Some(self.db.save_funding_payment(&payment).await?);
+                *self
+                    .realized_pnl
+                    .entry(strategy_id.to_string())
+                    .or_insert(0.0) += payment.amount;
+                info!(
+                    "资金费结算 [{}]: {} 费率{:.4}% 持仓{:.4} 收支{:.4}",
+                    strategy_id,
+                    payment.symbol,
+                    payment.funding_rate * 100.0,
+                    payment.quantity,
+                    payment.amount
+                );
+                accrued = true;
+            }
+            if let Some(slot) = self.strategies.get_mut(strategy_id) {
+                slot.strategy.on_funding(rate);
+            }
+        }
+
+        if accrued {
+            self.persist_account_state().await?;
+        }
+
+        Ok(())
+    }
+
+    /// 按结算费率向配对持仓的该腿记入资金费 (计入现金)，结算时未持有该腿则返回None
+    ///
+    /// 资金费 = -持仓数量 × 最新价 × 费率，费率为正时空头收取、多头支付。
+    fn accrue_funding(&mut self, strategy_id: &str, rate: &FundingRate) -> Option<FundingPayment> {
+        let position = self.paired_positions.get(strategy_id)?;
+        if rate.funding_time < position.timestamp {
+            return None;
+        }
+        let leg = position.legs.iter().find(|l| l.symbol == rate.symbol)?;
+        let amount = -leg.quantity * leg.current_price * rate.funding_rate;
+
+        self.balance += amount;
+        Some(FundingPayment {
+            id: None,
+            strategy_id: strategy_id.to_string(),
+            symbol: rate.symbol.clone(),
+            funding_time: rate.funding_time,
+            funding_rate: rate.funding_rate,
+            quantity: leg.quantity,
+            price: leg.current_price,
+            amount,
+        })
+    }
+
+    /// 保存多腿信号的各腿并整体执行，记录处理结果；成交时逐腿通知策略并返回各腿信号
+    async fn handle_multi_leg_signal(
+        &mut self,
+        strategy_id: &str,
+        mut signal: MultiLegSignal,
+    ) -> Result<Vec<TradingSignal>> {
+        for leg in &mut signal.legs {
+            leg.signal.id = Some(self.db.save_signal(&leg.signal).await?);
+        }
+        let signal_ids: Vec<i64> = signal.legs.iter().filter_map(|l| l.signal.id).collect();
+
+        let plan = match self.strategies.get(strategy_id) {
+            Some(slot) => self.plan_multi_leg(
+                strategy_id,
+                slot.strategy.as_ref(),
+                &signal,
+                self.available_capital(strategy_id),
+            )?,
+            None => LegPlan::Skipped(
+                SignalOutcome::Ignored,
+                format!("未找到策略实例{}", strategy_id),
+            ),
+        };
+
+        match plan {
+            LegPlan::Trades(mut trades, margins) => {
+                self.apply_multi_leg(strategy_id, signal.action, &trades, &margins);
+                let pnl: f64 = trades.iter().filter_map(|t| t.pnl).sum();
+                if signal.action == MultiLegAction::Close {
+                    *self
+                        .realized_pnl
+                        .entry(strategy_id.to_string())
+                        .or_insert(0.0) += pnl;
+                }
+                let trade_ids = self.persist_trades(&trades).await?;
+                for (trade, id) in trades.iter_mut().zip(trade_ids) {
+                    trade.id = Some(id);
+                }
+
+                for (leg, trade) in signal.legs.iter().zip(&trades) {
+                    if let Some(signal_id) = leg.signal.id {
+                        self.db
+                            .update_signal_outcome(
+                                signal_id,
+                                SignalOutcome::Executed,
+                                &format!("交易ID {}", trade.id.unwrap_or_default()),
+                            )
+                            .await?;
+                    }
+                }
+                if let Some(slot) = self.strategies.get_mut(strategy_id) {
+                    for trade in &trades {
+                        slot.strategy.on_fill(trade);
+                    }
+                }
+                info!(
+                    "{}成功 [{}]: {}",
+                    match signal.action {
+                        MultiLegAction::Open => "配对开仓",
+                        MultiLegAction::Close => "配对平仓",
+                    },
+                    strategy_id,
+                    trades
+                        .iter()
+                        .map(|t| format!(
+                            "{} {} {:.4} @ {:.4}",
+                            t.side, t.symbol, t.quantity, t.price
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                Ok(signal.legs.into_iter().map(|l| l.signal).collect())
+            }
+            LegPlan::Skipped(outcome, reason) => {
+                info!(
+                    "多腿信号{:?} [{}] 未执行 ({}): {}",
+                    signal_ids,
+                    strategy_id,
+                    outcome.as_str(),
+                    reason
+                );
+                for signal_id in signal_ids {
+                    self.db
+                        .update_signal_outcome(signal_id, outcome, &reason)
+                        .await?;
+                }
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// 按多腿信号生成各腿交易 (未保存，不修改账户)，开仓数量由策略的 `legs_size` 决定
+    fn plan_multi_leg(
+        &self,
+        strategy_id: &str,
+        strategy: &dyn Strategy,
+        signal: &MultiLegSignal,
+        available: f64,
+    ) -> Result<LegPlan> {
+        if signal.legs.is_empty() {
+            return Ok(LegPlan::Skipped(
+                SignalOutcome::Ignored,
+                "多腿信号没有任何腿".to_string(),
+            ));
+        }
+
+        let leg_trade = |leg: &TradingSignal, side: &str, quantity: f64, pnl: Option<f64>| Trade {
+            id: None,
+            symbol: leg.symbol.clone(),
+            side: side.to_string(),
+            price: leg.price,
+            quantity,
+            timestamp: leg.timestamp,
+            strategy: leg.strategy.clone(),
+            strategy_id: strategy_id.to_string(),
+            pnl,
+            signal_id: leg.id,
+        };
+
+        match signal.action {
+            MultiLegAction::Open => {
+                if self.paired_positions.contains_key(strategy_id) {
+                    return Ok(LegPlan::Skipped(
+                        SignalOutcome::AlreadyPositioned,
+                        format!("策略实例{}已有配对持仓", strategy_id),
+                    ));
+                }
+                if signal.legs.iter().any(|l| {
+                    l.signal.signal_type == SignalType::Hold
+                        || !(l.ratio.is_finite() && l.ratio > 0.0)
+                }) {
+                    return Ok(LegPlan::Skipped(
+                        SignalOutcome::Ignored,
+                        "多腿开仓信号的方向或比例无效".to_string(),
+                    ));
+                }
+
+                let Some(size) = strategy.legs_size(signal, available)? else {
+                    return Ok(LegPlan::Skipped(
+                        SignalOutcome::InsufficientData,
+                        "无法计算多腿仓位".to_string(),
+                    ));
+                };
+                if size <= 0.0 {
+                    return Ok(LegPlan::Skipped(
+                        SignalOutcome::SizeTooSmall,
+                        format!("计算仓位为{:.8}", size),
+                    ));
+                }
+
+                // 做空一腿同样占用资金，按各腿冻结的现金 (名义价值 / 杠杆) 之和风控
+                let margins: Vec<f64> = signal
+                    .legs
+                    .iter()
+                    .map(|l| {
+                        size * l.ratio * l.signal.price / strategy.leg_leverage(&l.signal.symbol)
+                    })
+                    .collect();
+                let required: f64 = margins.iter().sum();
+                if required > available * MAX_POSITION_FRACTION {
+                    return Ok(LegPlan::Skipped(
+                        SignalOutcome::RiskRejected,
+                        format!(
+                            "多腿仓位占用资金{:.2}超过策略{}可用资金{:.2}的{:.0}%",
+                            required,
+                            strategy_id,
+                            available,
+                            MAX_POSITION_FRACTION * 100.0
+                        ),
+                    ));
+                }
+
+                Ok(LegPlan::Trades(
+                    signal
+                        .legs
+                        .iter()
+                        .map(|l| {
+                            let side = match l.signal.signal_type {
+                                SignalType::Buy => "buy",
+                                _ => "sell",
+                            };
+                            leg_trade(&l.signal, side, size * l.ratio, None)
+                        })
+                        .collect(),
+                    margins,
+                ))
+            }
+            MultiLegAction::Close => {
+                let Some(position) = self.paired_positions.get(strategy_id) else {
+                    return Ok(LegPlan::Skipped(
+                        SignalOutcome::NoPosition,
+                        format!("策略实例{}没有配对持仓可平", strategy_id),
+                    ));
+                };
+
+                let mut trades = Vec::new();
+                for leg in &position.legs {
+                    let Some(exit) = signal.legs.iter().find(|l| l.signal.symbol == leg.symbol)
+                    else {
+                        return Ok(LegPlan::Skipped(
+                            SignalOutcome::Ignored,
+                            format!("平仓信号缺少{}腿", leg.symbol),
+                        ));
+                    };
+                    let side = if leg.quantity > 0.0 { "sell" } else { "buy" };
+                    let pnl = (exit.signal.price - leg.entry_price) * leg.quantity;
+                    trades.push(leg_trade(&exit.signal, side, leg.quantity.abs(), Some(pnl)));
+                }
+                Ok(LegPlan::Trades(trades, Vec::new()))
+            }
+        }
+    }
+
+    /// 按各腿交易更新现金与配对持仓
+    ///
+    /// 开仓时从现金冻结各腿的 `margins` (做空一腿的卖出所得不计入现金，另冻结保证金)，
+    /// 平仓时退回冻结的现金并计入各腿盈亏。
+    fn apply_multi_leg(
+        &mut self,
+        strategy_id: &str,
+        action: MultiLegAction,
+        trades: &[Trade],
+        margins: &[f64],
+    ) {
+        match action {
+            MultiLegAction::Open => {
+                let legs: Vec<PositionLeg> = trades
+                    .iter()
+                    .zip(margins)
+                    .map(|(t, &margin)| PositionLeg {
+                        symbol: t.symbol.clone(),
+                        quantity: if t.side == "buy" {
+                            t.quantity
+                        } else {
+                            -t.quantity
+                        },
+                        entry_price: t.price,
+                        current_price: t.price,
+                        margin,
+                    })
+                    .collect();
+                self.balance -= legs.iter().map(|l| l.margin).sum::<f64>();
+                self.paired_positions.insert(
+                    strategy_id.to_string(),
+                    PairedPosition {
+                        strategy_id: strategy_id.to_string(),
+                        legs,
+                        timestamp: trades.first().map_or_else(Utc::now, |t| t.timestamp),
+                    },
+                );
+            }
+            MultiLegAction::Close => {
+                if let Some(position) = self.paired_positions.remove(strategy_id) {
+                    let pnl: f64 = trades.iter().filter_map(|t| t.pnl).sum();
+                    self.balance += position.margin() + pnl;
+                }
+            }
+        }
+    }
+
+    /// 保存信号并执行，记录处理结果；成交时通知策略并返回该信号
+    async fn handle_signal(
+        &mut self,
+        strategy_id: &str,
+        mut signal: TradingSignal,
+    ) -> Result<Option<TradingSignal>> {
+        // 保存信号到数据库
+        let signal_id = self.db.save_signal(&signal).await?;
+        signal.id = Some(signal_id);
+
+        // 执行交易逻辑，并记录信号处理结果
+        match self.execute_signal(strategy_id, &signal).await? {
+            Execution::Executed(trade) => {
+                self.db
+                    .update_signal_outcome(
+                        signal_id,
+                        SignalOutcome::Executed,
+                        &format!("交易ID {}", trade.id.unwrap_or_default()),
+                    )
+                    .await?;
+                self.fill_resting_order(strategy_id, &trade).await?;
+                if let Some(slot) = self.strategies.get_mut(strategy_id) {
+                    slot.strategy.on_fill(&trade);
+                }
+                info!("执行交易 [{}]: {:?}", strategy_id, trade);
+                Ok(Some(signal))
+            }
+            Execution::Skipped(outcome, reason) => {
+                info!(
+                    "信号#{} [{}] 未执行 ({}): {}",
+                    signal_id,
+                    strategy_id,
+                    outcome.as_str(),
+                    reason
+                );
+                self.db
+                    .update_signal_outcome(signal_id, outcome, &reason)
+                    .await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// 该交易对上价格低于或等于止损价的多头持仓，返回 (策略实例ID, 止损价)
+    fn triggered_stops(&self, symbol: &str, low: f64) -> Vec<(String, f64)> {
+        let mut triggered: Vec<(String, f64)> = self
+            .positions
+            .values()
+            .filter(|p| p.symbol == symbol && p.quantity > 0.0)
+            .filter_map(|p| p.stop_price().map(|stop| (p.strategy_id.clone(), stop)))
+            .filter(|(_, stop)| low <= *stop)
+            .collect();
+        triggered.sort_by(|a, b| a.0.cmp(&b.0));
+        triggered
+    }
+
+    /// 将新收盘的K线追加到缓存，缓存保留最近lookback根 (超过两倍时整体裁剪)
+    async fn refresh_bar_cache(
+        &mut self,
+        symbol: &str,
+        forming: DateTime<Utc>,
+        lookback: usize,
+    ) -> Result<()> {
+        let last_closed = self
+            .bar_cache
+            .get(symbol)
+            .and_then(|bars| bars.last())
+            .map(|c| c.timestamp);
+
+        let new_bars = match last_closed {
+            Some(last) if last < forming => {
+                self.db
+                    .get_candles(
+                        symbol,
+                        last + chrono::Duration::milliseconds(1),
+                        forming - chrono::Duration::milliseconds(1),
+                        None,
+                    )
+                    .await?
+            }
+            _ => {
+                self.bar_cache.remove(symbol);
+                self.closed_candles(symbol, lookback).await?
+            }
+        };
+
+        let bars = self.bar_cache.entry(symbol.to_string()).or_default();
+        bars.extend(new_bars);
+        if bars.len() > lookback * 2 {
+            bars.drain(..bars.len() - lookback);
+        }
+
+        Ok(())
+    }
+
+    /// 获取最近N根已收盘的K线 (去掉最新一根未收盘K线)
+    async fn closed_candles(&self, symbol: &str, count: usize) -> Result<Vec<CandleData>> {
+        let mut candles = self.db.get_latest_candles(symbol, count + 1).await?;
+        candles.pop();
+        Ok(candles)
+    }
+
+    /// 策略实例的可用资金 (分配资金+已实现盈亏-持仓成本)，不超过账户现金
+    fn available_capital(&self, strategy_id: &str) -> f64 {
+        let Some(slot) = self.strategies.get(strategy_id) else {
+            return 0.0;
+        };
+
+        let realized = self.realized_pnl.get(strategy_id).copied().unwrap_or(0.0);
+        let cost = self
+            .positions
+            .get(strategy_id)
+            .map_or(0.0, |p| p.quantity * p.avg_price)
+            + self
+                .paired_positions
+                .get(strategy_id)
+                .map_or(0.0, PairedPosition::margin);
+
+        (slot.allocation + realized - cost)
+            .min(self.balance)
+            .max(0.0)
     }
 
     /// 执行交易信号
-    async fn execute_signal(&mut self, signal: &TradingSignal) -> Result<Option<Trade>> {
+    async fn execute_signal(
+        &mut self,
+        strategy_id: &str,
+        signal: &TradingSignal,
+    ) -> Result<Execution> {
         let symbol = &signal.symbol;
 
-        // 获取当前持仓
-        let current_position = self.positions.get(symbol).cloned();
-
         match signal.signal_type {
             SignalType::Buy => {
-                if current_position.is_none() || current_position.as_ref().unwrap().quantity <= 0.0
-                {
-                    // 开多仓或平空仓
-                    return self.open_long_position(signal).await;
+                // 检查该策略实例是否已有持仓，未达到单位上限时加仓
+                let (current_quantity, units) = self
+                    .positions
+                    .get(strategy_id)
+                    .filter(|p| p.quantity > 0.0)
+                    .map_or((0.0, 0), |p| (p.quantity, p.units.len()));
+                let max_units = self
+                    .strategies
+                    .get(strategy_id)
+                    .map_or(1, |slot| slot.strategy.max_units());
+
+                if units < max_units {
+                    // 开多仓或加仓
+                    return self.open_long_position(strategy_id, signal).await;
                 }
+                Ok(Execution::Skipped(
+                    SignalOutcome::AlreadyPositioned,
+                    format!(
+                        "已持有{}多仓 {:.4} ({}个单位，上限{})",
+                        symbol, current_quantity, units, max_units
+                    ),
+                ))
             }
             SignalType::Sell => {
-                if current_position.is_none() || current_position.as_ref().unwrap().quantity >= 0.0
-                {
-                    // 开空仓或平多仓
-                    return self.open_short_position(signal).await;
-                }
+                // 开空仓或平多仓
+                self.open_short_position(strategy_id, signal).await
             }
             SignalType::Hold => {
                 // 持有信号，暂不处理
-                return Ok(None);
+                Ok(Execution::Skipped(
+                    SignalOutcome::Ignored,
+                    "持有信号无需交易".to_string(),
+                ))
             }
         }
-
-        Ok(None)
     }
 
-    /// 开多仓
-    async fn open_long_position(&mut self, signal: &TradingSignal) -> Result<Option<Trade>> {
+    /// 开多仓，已有多仓时加仓一个单位
+    async fn open_long_position(
+        &mut self,
+        strategy_id: &str,
+        signal: &TradingSignal,
+    ) -> Result<Execution> {
         let symbol = &signal.symbol;
 
-        // 获取策略和ATR计算仓位大小
-        if let Some(strategy) = self.strategies.get(symbol) {
-            let candles = self.db.get_latest_candles(symbol, 50).await?;
-
-            if !candles.is_empty() {
-                let indicators = strategy.calculate_indicators(&candles)?;
-
-                if let Some(atr) = indicators.atr {
-                    // 计算仓位大小
-                    let position_size =
-                        strategy.calculate_position_size(self.balance, signal.price, atr);
-
-                    if position_size > 0.0 && position_size * signal.price <= self.balance * 0.95 {
-                        // 创建交易记录
-                        let trade = Trade {
-                            id: None,
-                            symbol: symbol.clone(),
-                            side: "buy".to_string(),
-                            price: signal.price,
-                            quantity: position_size,
-                            timestamp: signal.timestamp,
-                            strategy: signal.strategy.clone(),
-                            pnl: None,
-                        };
-
-                        // 保存交易到数据库
-                        let trade_id = self.db.save_trade(&trade).await?;
-
-                        // 更新持仓
-                        let position = Position {
-                            symbol: symbol.clone(),
-                            quantity: position_size,
-                            avg_price: signal.price,
-                            current_price: signal.price,
-                            unrealized_pnl: 0.0,
-                            timestamp: signal.timestamp,
-                        };
-
-                        self.positions.insert(symbol.clone(), position);
-
-                        // 更新账户余额
-                        self.balance -= position_size * signal.price;
-
-                        info!(
-                            "开多仓成功: {} @ {:.4}, 数量: {:.4}",
-                            symbol, signal.price, position_size
-                        );
-
-                        let mut executed_trade = trade;
-                        executed_trade.id = Some(trade_id);
-                        return Ok(Some(executed_trade));
-                    }
-                }
-            }
+        // 由策略根据已收盘K线和该实例的可用资金计算仓位大小
+        let Some(slot) = self.strategies.get(strategy_id) else {
+            return Ok(Execution::Skipped(
+                SignalOutcome::Ignored,
+                format!("未找到策略实例{}", strategy_id),
+            ));
+        };
+
+        let candles = self
+            .closed_candles(symbol, slot.strategy.warmup_bars().max(MIN_LOOKBACK_BARS))
+            .await?;
+        if candles.is_empty() {
+            return Ok(Execution::Skipped(
+                SignalOutcome::InsufficientData,
+                format!("没有{}的K线数据", symbol),
+            ));
         }
 
-        Ok(None)
+        let available = self.available_capital(strategy_id);
+        let Some(position_size) = slot.strategy.position_size(signal, available, &candles)? else {
+            return Ok(Execution::Skipped(
+                SignalOutcome::InsufficientData,
+                format!("K线数量{}不足以计算仓位", candles.len()),
+            ));
+        };
+
+        if position_size <= 0.0 {
+            return Ok(Execution::Skipped(
+                SignalOutcome::SizeTooSmall,
+                format!("计算仓位为{:.8}", position_size),
+            ));
+        }
+
+        if position_size * signal.price > available * MAX_POSITION_FRACTION {
+            return Ok(Execution::Skipped(
+                SignalOutcome::RiskRejected,
+                format!(
+                    "仓位价值{:.2}超过策略{}可用资金{:.2}的{:.0}%",
+                    position_size * signal.price,
+                    strategy_id,
+                    available,
+                    MAX_POSITION_FRACTION * 100.0
+                ),
+            ));
+        }
+
+        let stop_price = slot.strategy.stop_price(signal, &candles)?;
+
+        // 创建交易记录
+        let trade = Trade {
+            id: None,
+            symbol: symbol.clone(),
+            side: "buy".to_string(),
+            price: signal.price,
+            quantity: position_size,
+            timestamp: signal.timestamp,
+            strategy: signal.strategy.clone(),
+            strategy_id: strategy_id.to_string(),
+            pnl: None,
+            signal_id: signal.id,
+        };
+
+        // 更新持仓
+        let unit = PositionUnit {
+            entry_price: signal.price,
+            quantity: position_size,
+            stop_price,
+            timestamp: signal.timestamp,
+        };
+        let units = self.add_position_unit(strategy_id, symbol, unit);
+
+        // 更新账户余额，与交易记录一起保存到数据库
+        self.balance -= position_size * signal.price;
+        let trade_id = self.persist_trades(std::slice::from_ref(&trade)).await?[0];
+
+        info!(
+            "{}成功 [{}]: {} @ {:.4}, 数量: {:.4}, 当前{}个单位",
+            if units > 1 { "加仓" } else { "开多仓" },
+            strategy_id,
+            symbol,
+            signal.price,
+            position_size,
+            units
+        );
+
+        let mut executed_trade = trade;
+        executed_trade.id = Some(trade_id);
+        Ok(Execution::Executed(executed_trade))
+    }
+
+    /// 为策略实例新增一个持仓单位 (无持仓时开仓)，返回加仓后的单位数
+    fn add_position_unit(&mut self, strategy_id: &str, symbol: &str, unit: PositionUnit) -> usize {
+        match self.positions.get_mut(strategy_id) {
+            Some(position) if position.quantity > 0.0 => {
+                position.add_unit(unit);
+                position.units.len()
+            }
+            _ => {
+                self.positions.insert(
+                    strategy_id.to_string(),
+                    Position::open(strategy_id, symbol, unit),
+                );
+                1
+            }
+        }
     }
 
     /// 开空仓
-    async fn open_short_position(&mut self, signal: &TradingSignal) -> Result<Option<Trade>> {
+    async fn open_short_position(
+        &mut self,
+        strategy_id: &str,
+        signal: &TradingSignal,
+    ) -> Result<Execution> {
         let symbol = &signal.symbol;
 
-        // 获取当前持仓，如果是多仓则平仓
-        if let Some(position) = self.positions.get(symbol) {
-            if position.quantity > 0.0 {
-                return self.close_long_position(signal).await;
-            }
+        // 获取该策略实例的持仓，如果是多仓则平仓
+        if let Some(position) = self.positions.get(strategy_id)
+            && position.quantity > 0.0
+        {
+            return self.close_long_position(strategy_id, signal).await;
         }
 
         // 这里可以添加开空仓逻辑，现货交易通常不支持做空
         // 暂时只实现平多仓逻辑
-        Ok(None)
+        Ok(Execution::Skipped(
+            SignalOutcome::NoPosition,
+            format!("没有{}多仓可平，现货不支持开空", symbol),
+        ))
     }
 
     /// 平多仓
-    async fn close_long_position(&mut self, signal: &TradingSignal) -> Result<Option<Trade>> {
+    async fn close_long_position(
+        &mut self,
+        strategy_id: &str,
+        signal: &TradingSignal,
+    ) -> Result<Execution> {
         let symbol = &signal.symbol;
 
-        if let Some(position) = self.positions.get(symbol).cloned() {
-            if position.quantity > 0.0 {
-                // 计算盈亏
-                let pnl = (signal.price - position.avg_price) * position.quantity;
-
-                // 创建平仓交易记录
-                let trade = Trade {
-                    id: None,
-                    symbol: symbol.clone(),
-                    side: "sell".to_string(),
-                    price: signal.price,
-                    quantity: position.quantity,
-                    timestamp: signal.timestamp,
-                    strategy: signal.strategy.clone(),
-                    pnl: Some(pnl),
-                };
-
-                // 保存交易到数据库
-                let trade_id = self.db.save_trade(&trade).await?;
-
-                // 更新账户余额
-                self.balance += position.quantity * signal.price;
-
-                // 清除持仓
-                self.positions.remove(symbol);
+        let Some(position) = self
+            .positions
+            .get(strategy_id)
+            .filter(|p| p.quantity > 0.0)
+            .cloned()
+        else {
+            return Ok(Execution::Skipped(
+                SignalOutcome::NoPosition,
+                format!("没有{}多仓可平", symbol),
+            ));
+        };
 
-                info!(
-                    "平多仓成功: {} @ {:.4}, 盈亏: {:.2}",
-                    symbol, signal.price, pnl
-                );
+        // 由策略决定平仓数量，并计算盈亏
+        let strategy = self
+            .strategies
+            .get(strategy_id)
+            .map(|s| s.strategy.as_ref());
+        let (quantity, pnl, remaining) = plan_exit(strategy, signal, &position);
+
+        // 创建平仓交易记录
+        let trade = Trade {
+            id: None,
+            symbol: symbol.clone(),
+            side: "sell".to_string(),
+            price: signal.price,
+            quantity,
+            timestamp: signal.timestamp,
+            strategy: signal.strategy.clone(),
+            strategy_id: strategy_id.to_string(),
+            pnl: Some(pnl),
+            signal_id: signal.id,
+        };
 
-                let mut executed_trade = trade;
-                executed_trade.id = Some(trade_id);
-                return Ok(Some(executed_trade));
-            }
-        }
+        // 更新账户余额与策略盈亏
+        self.balance += quantity * signal.price;
+        *self
+            .realized_pnl
+            .entry(strategy_id.to_string())
+            .or_insert(0.0) += pnl;
+
+        // 更新或清除持仓
+        let partial = remaining.is_some();
+        match remaining {
+            Some(remaining) => self.positions.insert(strategy_id.to_string(), remaining),
+            None => self.positions.remove(strategy_id),
+        };
 
-        Ok(None)
+        // 与更新后的余额和持仓一起保存交易到数据库
+        let trade_id = self.persist_trades(std::slice::from_ref(&trade)).await?[0];
+
+        info!(
+            "{}成功 [{}]: {} @ {:.4}, 数量: {:.4}, 盈亏: {:.2}",
+            if partial { "减仓" } else { "平多仓" },
+            strategy_id,
+            symbol,
+            signal.price,
+            quantity,
+            pnl
+        );
+
+        let mut executed_trade = trade;
+        executed_trade.id = Some(trade_id);
+        Ok(Execution::Executed(executed_trade))
     }
 
-    /// 运行回测
+    /// 对指定策略实例运行回测，并将参数、交易和权益曲线保存为回测记录
+    ///
+    /// 回测账户只包含该实例，初始资金为 `initial_balance`。
     pub async fn run_backtest(
         &mut self,
+        strategy_id: &str,
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
         initial_balance: f64,
-    ) -> Result<BacktestReport> {
-        info!("开始回测: {} 到 {}", start_time, end_time);
-
-        // 重置回测环境
-        self.balance = initial_balance;
-        self.positions.clear();
+    ) -> Result<BacktestRun> {
+        info!("开始回测 [{}]: {} 到 {}", strategy_id, start_time, end_time);
+
+        // 暂存实盘账户状态，回测结束后恢复，避免回测覆盖实盘余额与持仓
+        let live_balance = std::mem::replace(&mut self.balance, initial_balance);
+        let live_positions = std::mem::take(&mut self.positions);
+        let live_paired_positions = std::mem::take(&mut self.paired_positions);
+
+        let result = self
+            .run_backtest_inner(strategy_id, start_time, end_time, initial_balance)
+            .await;
+
+        self.balance = live_balance;
+        self.positions = live_positions;
+        self.paired_positions = live_paired_positions;
+
+        let (mut run, trades, equity_curve) = result?;
+        run.id = Some(
+            self.db
+                .save_backtest_run(&run, &trades, &equity_curve)
+                .await?,
+        );
+
+        Ok(run)
+    }
 
+    /// 在已重置的账户上逐根K线执行回测，返回回测记录、交易列表和权益曲线
+    async fn run_backtest_inner(
+        &mut self,
+        strategy_id: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        initial_balance: f64,
+    ) -> Result<(BacktestRun, Vec<Trade>, Vec<(DateTime<Utc>, f64)>)> {
         let mut trades = Vec::new();
         let mut equity_curve = Vec::new();
 
+        // 使用新的策略实例回测，不影响实盘策略的内部状态
+        let mut strategy = self
+            .strategies
+            .get(strategy_id)
+            .ok_or_else(|| anyhow::anyhow!("未找到策略实例{}", strategy_id))?
+            .strategy
+            .fresh();
+        let symbol = strategy.symbol().to_string();
+        let leg_symbols = strategy.leg_symbols();
+        let warmup = strategy.warmup_bars().max(1);
+
         // 获取回测期间的K线数据
-        let symbol = "SOL-USDT";
         let candles = self
             .db
-            .get_candles(symbol, start_time, end_time, None)
+            .get_candles(&symbol, start_time, end_time, None)
             .await?;
 
-        if candles.len() < 50 {
-            return Err(anyhow::anyhow!("回测数据不足，需要至少50根K线"));
+        // 多腿策略的其他腿按时间与主交易对的K线对齐
+        let mut leg_candles = Vec::new();
+        for leg in leg_symbols.iter().skip(1) {
+            leg_candles.push(self.db.get_candles(leg, start_time, end_time, None).await?);
+        }
+
+        if candles.len() < warmup {
+            return Err(anyhow::anyhow!("回测数据不足，需要至少{}根K线", warmup));
         }
 
-        info!("回测数据: {} 根K线", candles.len());
+        // 各腿的资金费率 (含回测开始前的历史，供策略预测)，按结算时间逐根K线推送
+        let mut funding_rates = Vec::new();
+        for leg in &leg_symbols {
+            funding_rates.extend(
+                self.db
+                    .get_funding_rates(
+                        leg,
+                        start_time - chrono::Duration::days(FUNDING_WARMUP_DAYS),
+                        end_time,
+                    )
+                    .await?,
+            );
+        }
+        funding_rates.sort_by_key(|r| r.funding_time);
+        let mut next_funding = 0;
+        let mut funding_total = 0.0;
+
+        // 按Ticker撮合的策略回放回测期间存储的Ticker，预热K线之前的Ticker跳过
+        let tickers = if strategy.replays_ticks() {
+            self.db.get_tickers(&symbol, start_time, end_time).await?
+        } else {
+            Vec::new()
+        };
+        let mut next_ticker =
+            tickers.partition_point(|t| t.timestamp < candles[warmup - 1].timestamp);
+
+        info!(
+            "回测数据: {} 根K线, {} 条Ticker",
+            candles.len(),
+            tickers.len()
+        );
 
         // 逐根K线进行回测
-        for i in 50..candles.len() {
+        for i in (warmup - 1)..candles.len() {
             let current_candles = &candles[0..=i];
             let current_candle = &candles[i];
 
-            // 分析当前数据（在短生命周期内获取策略引用）
-            let signals = {
-                let strategy = self
-                    .strategies
-                    .get(symbol)
-                    .ok_or_else(|| anyhow::anyhow!("未找到{}的策略", symbol))?;
-                strategy.analyze(current_candles)?
-            };
+            // 该K线期间的Ticker先于K线收盘处理，仓位计算只使用此前已收盘的K线
+            while let Some(ticker) = tickers.get(next_ticker)
+                && candles
+                    .get(i + 1)
+                    .is_none_or(|next| ticker.timestamp < next.timestamp)
+            {
+                for signal in strategy.on_tick(ticker)? {
+                    if let Some(trade) = self
+                        .simulate_trade(strategy_id, strategy.as_ref(), &signal, &candles[..i])
+                        .await?
+                    {
+                        strategy.on_fill(&trade);
+                        trades.push(trade);
+                    }
+                }
+                strategy.on_book(&BookQuote::from(ticker));
+                next_ticker += 1;
+            }
 
-            // 处理生成的信号（此处可对 self 进行可变借用）
-            for signal in signals {
-                if let Some(trade) = self.simulate_trade(&signal, current_candles).await? {
+            // 先按K线最低价检查止损，跳空低开时按开盘价成交
+            for (_, stop_price) in self.triggered_stops(&symbol, current_candle.low) {
+                let signal = stop_loss_signal(
+                    &symbol,
+                    stop_price.min(current_candle.open),
+                    stop_price,
+                    current_candle.timestamp,
+                );
+                if let Some(trade) = self
+                    .simulate_long_exit(strategy_id, strategy.as_ref(), &signal)
+                    .await?
+                {
+                    strategy.on_fill(&trade);
                     trades.push(trade);
                 }
             }
 
+            if leg_symbols.is_empty() {
+                let signals = strategy.on_bar(current_candles)?;
+
+                for signal in signals {
+                    if let Some(trade) = self
+                        .simulate_trade(strategy_id, strategy.as_ref(), &signal, current_candles)
+                        .await?
+                    {
+                        strategy.on_fill(&trade);
+                        trades.push(trade);
+                    }
+                }
+            } else {
+                while let Some(rate) = funding_rates.get(next_funding)
+                    && rate.funding_time <= current_candle.timestamp
+                {
+                    if let Some(payment) = self.accrue_funding(strategy_id, rate) {
+                        funding_total += payment.amount;
+                    }
+                    strategy.on_funding(rate);
+                    next_funding += 1;
+                }
+
+                let legs: Vec<&[CandleData]> = std::iter::once(current_candles)
+                    .chain(leg_candles.iter().map(|bars| {
+                        &bars[..bars.partition_point(|c| c.timestamp <= current_candle.timestamp)]
+                    }))
+                    .collect();
+
+                for signal in strategy.on_legs_bar(&legs)? {
+                    if let LegPlan::Trades(leg_trades, margins) =
+                        self.plan_multi_leg(strategy_id, strategy.as_ref(), &signal, self.balance)?
+                    {
+                        self.apply_multi_leg(strategy_id, signal.action, &leg_trades, &margins);
+                        for trade in &leg_trades {
+                            strategy.on_fill(trade);
+                        }
+                        trades.extend(leg_trades);
+                    }
+                }
+
+                for (leg, bars) in leg_symbols[1..].iter().zip(&legs[1..]) {
+                    if let Some(last) = bars.last() {
+                        self.update_positions_price(leg, last.close);
+                    }
+                }
+            }
+
             // 更新持仓的当前价格
-            self.update_positions_price(symbol, current_candle.close);
+            self.update_positions_price(&symbol, current_candle.close);
 
             // 记录权益曲线
             let total_equity = self.calculate_total_equity();
@@ -292,125 +1536,155 @@ impl StrategyManager {
         )?;
 
         info!("回测完成，共执行{}笔交易", trades.len());
+        if next_funding > 0 {
+            info!("回测资金费收支合计: {:.4}", funding_total);
+        }
+
+        let run = BacktestRun {
+            id: None,
+            symbol,
+            strategy: strategy.name().to_string(),
+            params: strategy.params(),
+            candle_count: candles.len(),
+            data_fingerprint: if leg_candles.is_empty() {
+                data_fingerprint(&candles)
+            } else {
+                let all: Vec<CandleData> = candles
+                    .iter()
+                    .chain(leg_candles.iter().flatten())
+                    .cloned()
+                    .collect();
+                data_fingerprint(&all)
+            },
+            code_version: CODE_VERSION.to_string(),
+            created_at: Utc::now(),
+            report,
+        };
 
-        Ok(report)
+        Ok((run, trades, equity_curve))
     }
 
     /// 模拟交易执行
     async fn simulate_trade(
         &mut self,
+        strategy_id: &str,
+        strategy: &dyn Strategy,
         signal: &TradingSignal,
         current_candles: &[CandleData],
     ) -> Result<Option<Trade>> {
-        let symbol = &signal.symbol;
-        let current_position = self.positions.get(symbol).cloned();
+        let current_quantity = self.positions.get(strategy_id).map_or(0.0, |p| p.quantity);
+
+        let units = self.positions.get(strategy_id).map_or(0, |p| p.units.len());
 
         match signal.signal_type {
-            SignalType::Buy => {
-                if current_position.is_none() || current_position.as_ref().unwrap().quantity <= 0.0
-                {
-                    return self.simulate_long_entry(signal, current_candles).await;
-                }
+            SignalType::Buy if current_quantity <= 0.0 || units < strategy.max_units() => {
+                self.simulate_long_entry(strategy_id, strategy, signal, current_candles)
+                    .await
             }
-            SignalType::Sell => {
-                if let Some(position) = current_position {
-                    if position.quantity > 0.0 {
-                        return self.simulate_long_exit(signal).await;
-                    }
-                }
+            SignalType::Sell if current_quantity > 0.0 => {
+                self.simulate_long_exit(strategy_id, strategy, signal).await
             }
-            SignalType::Hold => {}
+            _ => Ok(None),
         }
-
-        Ok(None)
     }
 
-    /// 模拟开多仓
+    /// 模拟开多仓或加仓
     async fn simulate_long_entry(
         &mut self,
+        strategy_id: &str,
+        strategy: &dyn Strategy,
         signal: &TradingSignal,
         current_candles: &[CandleData],
     ) -> Result<Option<Trade>> {
         let symbol = &signal.symbol;
 
-        if let Some(strategy) = self.strategies.get(symbol) {
-            let indicators = strategy.calculate_indicators(current_candles)?;
-
-            if let Some(atr) = indicators.atr {
-                let position_size =
-                    strategy.calculate_position_size(self.balance, signal.price, atr);
-                let trade_value = position_size * signal.price;
-
-                if position_size > 0.0 && trade_value <= self.balance * 0.95 {
-                    let trade = Trade {
-                        id: None,
-                        symbol: symbol.clone(),
-                        side: "buy".to_string(),
-                        price: signal.price,
-                        quantity: position_size,
-                        timestamp: signal.timestamp,
-                        strategy: signal.strategy.clone(),
-                        pnl: None,
-                    };
-
-                    // 更新持仓
-                    let position = Position {
-                        symbol: symbol.clone(),
-                        quantity: position_size,
-                        avg_price: signal.price,
-                        current_price: signal.price,
-                        unrealized_pnl: 0.0,
-                        timestamp: signal.timestamp,
-                    };
+        let Some(position_size) = strategy.position_size(signal, self.balance, current_candles)?
+        else {
+            return Ok(None);
+        };
+        let trade_value = position_size * signal.price;
+
+        if position_size > 0.0 && trade_value <= self.balance * MAX_POSITION_FRACTION {
+            let trade = Trade {
+                id: None,
+                symbol: symbol.clone(),
+                side: "buy".to_string(),
+                price: signal.price,
+                quantity: position_size,
+                timestamp: signal.timestamp,
+                strategy: signal.strategy.clone(),
+                strategy_id: strategy_id.to_string(),
+                pnl: None,
+                signal_id: signal.id,
+            };
 
-                    self.positions.insert(symbol.clone(), position);
-                    self.balance -= trade_value;
+            // 更新持仓
+            let unit = PositionUnit {
+                entry_price: signal.price,
+                quantity: position_size,
+                stop_price: strategy.stop_price(signal, current_candles)?,
+                timestamp: signal.timestamp,
+            };
+            self.add_position_unit(strategy_id, symbol, unit);
+            self.balance -= trade_value;
 
-                    return Ok(Some(trade));
-                }
-            }
+            return Ok(Some(trade));
         }
 
         Ok(None)
     }
 
-    /// 模拟平多仓
+    /// 模拟平多仓 (按策略的平仓数量部分平仓)
     async fn simulate_long_exit(
         &mut self,
+        strategy_id: &str,
+        strategy: &dyn Strategy,
         signal: &TradingSignal,
     ) -> Result<Option<Trade>> {
         let symbol = &signal.symbol;
 
-        if let Some(position) = self.positions.get(symbol).cloned() {
-            if position.quantity > 0.0 {
-                let pnl = (signal.price - position.avg_price) * position.quantity;
-
-                let trade = Trade {
-                    id: None,
-                    symbol: symbol.clone(),
-                    side: "sell".to_string(),
-                    price: signal.price,
-                    quantity: position.quantity,
-                    timestamp: signal.timestamp,
-                    strategy: signal.strategy.clone(),
-                    pnl: Some(pnl),
-                };
+        if let Some(position) = self.positions.get(strategy_id).cloned()
+            && position.quantity > 0.0
+        {
+            let (quantity, pnl, remaining) = plan_exit(Some(strategy), signal, &position);
+
+            let trade = Trade {
+                id: None,
+                symbol: symbol.clone(),
+                side: "sell".to_string(),
+                price: signal.price,
+                quantity,
+                timestamp: signal.timestamp,
+                strategy: signal.strategy.clone(),
+                strategy_id: strategy_id.to_string(),
+                pnl: Some(pnl),
+                signal_id: signal.id,
+            };
 
-                self.balance += position.quantity * signal.price;
-                self.positions.remove(symbol);
+            self.balance += quantity * signal.price;
+            match remaining {
+                Some(remaining) => self.positions.insert(strategy_id.to_string(), remaining),
+                None => self.positions.remove(strategy_id),
+            };
 
-                return Ok(Some(trade));
-            }
+            return Ok(Some(trade));
         }
 
         Ok(None)
     }
 
-    /// 更新持仓价格
+    /// 更新该交易对上所有持仓 (含配对持仓的各腿) 的价格
     fn update_positions_price(&mut self, symbol: &str, current_price: f64) {
-        if let Some(position) = self.positions.get_mut(symbol) {
-            position.current_price = current_price;
-            position.unrealized_pnl = (current_price - position.avg_price) * position.quantity;
+        for position in self.positions.values_mut() {
+            if position.symbol == symbol {
+                position.current_price = current_price;
+                position.unrealized_pnl = (current_price - position.avg_price) * position.quantity;
+            }
+        }
+        for position in self.paired_positions.values_mut() {
+            for leg in position.legs.iter_mut().filter(|l| l.symbol == symbol) {
+                leg.current_price = current_price;
+            }
         }
     }
 
@@ -421,6 +1695,9 @@ impl StrategyManager {
         for position in self.positions.values() {
             total_equity += position.quantity * position.current_price;
         }
+        for position in self.paired_positions.values() {
+            total_equity += position.equity();
+        }
 
         total_equity
     }
@@ -537,42 +1814,1202 @@ impl StrategyManager {
         }
     }
 
-    /// 添加策略
-    pub fn add_strategy(&mut self, symbol: String, params: Option<TurtleParams>) -> Result<()> {
-        let strategy = TurtleStrategy::new(symbol.clone(), params);
-        self.strategies.insert(symbol.clone(), strategy);
-        info!("添加策略: {}", symbol);
+    /// 添加策略实例并分配资金，实例ID在账户内唯一
+    pub fn add_strategy(
+        &mut self,
+        strategy_id: impl Into<String>,
+        mut strategy: Box<dyn Strategy>,
+        allocation: f64,
+    ) -> Result<()> {
+        let strategy_id = strategy_id.into();
+
+        if self.strategies.contains_key(&strategy_id) {
+            return Err(anyhow::anyhow!("策略实例{}已存在", strategy_id));
+        }
+        if !allocation.is_finite() || allocation <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "策略实例{}的分配资金必须大于0，当前为{}",
+                strategy_id,
+                allocation
+            ));
+        }
+
+        self.reserve_allocation(&strategy_id, allocation)?;
+
+        info!(
+            "添加策略实例: {} ({} {}), 分配资金 {:.2}",
+            strategy_id,
+            strategy.name(),
+            strategy.symbol(),
+            allocation
+        );
+        if let Some(position) = self.positions.get(&strategy_id) {
+            strategy.on_position_restored(position);
+        }
+        if let Some(trades) = self.restored_trades.remove(&strategy_id) {
+            strategy.on_trades_restored(&trades);
+        }
+        if let Some(position) = self.paired_positions.get(&strategy_id) {
+            strategy.on_paired_position_restored(position);
+        }
+        self.strategies.insert(
+            strategy_id,
+            StrategySlot {
+                strategy,
+                allocation,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// 添加策略实例并保存其定义，重启后自动重新创建
+    pub async fn register_strategy(
+        &mut self,
+        strategy_id: impl Into<String>,
+        strategy: Box<dyn Strategy>,
+        allocation: f64,
+    ) -> Result<()> {
+        let instance = StrategyInstance {
+            strategy_id: strategy_id.into(),
+            kind: strategy.name().to_string(),
+            symbol: strategy.symbol().to_string(),
+            params: strategy.params(),
+            allocation,
+        };
+
+        self.add_strategy(instance.strategy_id.clone(), strategy, allocation)?;
+        if let Err(e) = self.db.save_strategy_instance(&instance).await {
+            self.remove_strategy(&instance.strategy_id)?;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// 移除策略实例，有持仓时拒绝
+    pub fn remove_strategy(&mut self, strategy_id: &str) -> Result<()> {
+        if self
+            .positions
+            .get(strategy_id)
+            .is_some_and(|p| p.quantity > QUANTITY_EPSILON)
+            || self.paired_positions.contains_key(strategy_id)
+        {
+            return Err(anyhow::anyhow!("策略实例{}仍有持仓，无法移除", strategy_id));
+        }
+
+        self.funding_cursor.remove(strategy_id);
+        self.strategies
+            .remove(strategy_id)
+            .map(|_| ())
+            .ok_or_else(|| anyhow::anyhow!("未找到策略实例{}", strategy_id))
+    }
+
+    /// 调整策略实例的分配资金
+    pub fn set_allocation(&mut self, strategy_id: &str, allocation: f64) -> Result<()> {
+        if !allocation.is_finite() || allocation <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "策略实例{}的分配资金必须大于0，当前为{}",
+                strategy_id,
+                allocation
+            ));
+        }
+
+        if !self.strategies.contains_key(strategy_id) {
+            return Err(anyhow::anyhow!("未找到策略实例{}", strategy_id));
+        }
+        self.reserve_allocation(strategy_id, allocation)?;
+        if let Some(slot) = self.strategies.get_mut(strategy_id) {
+            slot.allocation = allocation;
+        }
+
         Ok(())
     }
 
-    /// 获取当前持仓
+    /// 校验各实例分配资金合计不超过账户权益
+    ///
+    /// 默认实例只占用其他实例分配后剩余的资金：合计超出时先从默认实例的分配中扣减，
+    /// 扣减后默认实例仍需保留正的分配资金，否则拒绝。
+    fn reserve_allocation(&mut self, strategy_id: &str, allocation: f64) -> Result<()> {
+        let equity = self.calculate_total_equity();
+        let others: f64 = self
+            .strategies
+            .iter()
+            .filter(|(id, _)| id.as_str() != strategy_id)
+            .map(|(_, slot)| slot.allocation)
+            .sum();
+        let excess = others + allocation - equity;
+        if excess <= QUANTITY_EPSILON {
+            return Ok(());
+        }
+
+        if strategy_id != DEFAULT_STRATEGY_ID
+            && let Some(default) = self.strategies.get_mut(DEFAULT_STRATEGY_ID)
+            && default.allocation - excess > QUANTITY_EPSILON
+        {
+            default.allocation -= excess;
+            info!(
+                "默认策略实例{}的分配资金调整为剩余的{:.2}",
+                DEFAULT_STRATEGY_ID, default.allocation
+            );
+            return Ok(());
+        }
+
+        Err(anyhow::anyhow!(
+            "策略分配资金合计{:.2}将超过账户权益{:.2}，{}最多可分配{:.2}",
+            others + allocation,
+            equity,
+            strategy_id,
+            (equity - others).max(0.0)
+        ))
+    }
+
+    /// 获取策略实例ID列表
+    pub fn strategy_ids(&self) -> Vec<String> {
+        self.strategies.keys().cloned().collect()
+    }
+
+    /// 各策略实例需要采集K线的交易对 (单腿策略的交易对与多腿策略的全部腿，去重)
+    pub fn candle_symbols(&self) -> BTreeSet<String> {
+        self.strategies
+            .values()
+            .flat_map(|slot| {
+                let mut symbols = slot.strategy.leg_symbols();
+                symbols.push(slot.strategy.symbol().to_string());
+                symbols
+            })
+            .collect()
+    }
+
+    /// 各策略实例需要采集资金费率的永续合约交易对 (去重)
+    pub fn funding_symbols(&self) -> BTreeSet<String> {
+        self.strategies
+            .values()
+            .flat_map(|slot| slot.strategy.funding_symbols())
+            .collect()
+    }
+
+    /// 获取当前持仓 (按策略实例ID)
     pub fn get_positions(&self) -> &HashMap<String, Position> {
         &self.positions
     }
 
-    /// 获取当前余额
+    /// 获取多腿策略的配对持仓 (按策略实例ID)
+    pub fn get_paired_positions(&self) -> &HashMap<String, PairedPosition> {
+        &self.paired_positions
+    }
+
+    /// 获取账户现金余额
     pub fn get_balance(&self) -> f64 {
         self.balance
     }
 
-    /// 更新余额
-    pub fn set_balance(&mut self, balance: f64) {
+    /// 更新账户现金余额 (如充值或提现)
+    pub async fn set_balance(&mut self, balance: f64) -> Result<()> {
         self.balance = balance;
+        self.persist_account_state().await
     }
 
-    /// 获取策略参数
-    pub fn get_strategy_params(&self, symbol: &str) -> Option<&TurtleParams> {
-        self.strategies.get(symbol).map(|s| s.get_params())
+    /// 获取策略实例参数
+    pub fn get_strategy_params(&self, strategy_id: &str) -> Option<serde_json::Value> {
+        self.strategies
+            .get(strategy_id)
+            .map(|s| s.strategy.params())
     }
 
-    /// 更新策略参数
-    pub fn update_strategy_params(&mut self, symbol: &str, params: TurtleParams) -> Result<()> {
-        if let Some(strategy) = self.strategies.get_mut(symbol) {
-            TurtleStrategy::validate_params(&params)?;
-            strategy.update_params(params);
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("策略不存在: {}", symbol))
+    /// 汇总各策略实例的资金与盈亏，并合计到账户层面
+    pub fn portfolio_summary(&self) -> PortfolioSummary {
+        let strategies: Vec<StrategySummary> = self
+            .strategies
+            .iter()
+            .map(|(id, slot)| {
+                let position = self.positions.get(id);
+                let paired = self.paired_positions.get(id);
+                StrategySummary {
+                    strategy_id: id.clone(),
+                    strategy: slot.strategy.name().to_string(),
+                    symbol: slot.strategy.symbol().to_string(),
+                    allocation: slot.allocation,
+                    available: self.available_capital(id),
+                    position_value: position.map_or(0.0, |p| p.quantity * p.current_price)
+                        + paired.map_or(0.0, PairedPosition::equity),
+                    realized_pnl: self.realized_pnl.get(id).copied().unwrap_or(0.0),
+                    unrealized_pnl: position.map_or(0.0, |p| p.unrealized_pnl)
+                        + paired.map_or(0.0, PairedPosition::unrealized_pnl),
+                }
+            })
+            .collect();
+
+        // 已移除实例的持仓和盈亏仍计入账户合计
+        let position_value = self
+            .positions
+            .values()
+            .map(|p| p.quantity * p.current_price)
+            .sum::<f64>()
+            + self
+                .paired_positions
+                .values()
+                .map(PairedPosition::equity)
+                .sum::<f64>();
+        let available: f64 = strategies.iter().map(|s| s.available).sum();
+
+        PortfolioSummary {
+            cash: self.balance,
+            unallocated: (self.balance - available).max(0.0),
+            position_value,
+            realized_pnl: self.realized_pnl.values().sum(),
+            unrealized_pnl: self
+                .positions
+                .values()
+                .map(|p| p.unrealized_pnl)
+                .sum::<f64>()
+                + self
+                    .paired_positions
+                    .values()
+                    .map(PairedPosition::unrealized_pnl)
+                    .sum::<f64>(),
+            strategies,
+        }
+    }
+}
+
+/// 生成止损离场信号
+fn stop_loss_signal(
+    symbol: &str,
+    price: f64,
+    stop_price: f64,
+    timestamp: DateTime<Utc>,
+) -> TradingSignal {
+    TradingSignal {
+        id: None,
+        symbol: symbol.to_string(),
+        signal_type: SignalType::Sell,
+        price,
+        timestamp,
+        strategy: STOP_LOSS_STRATEGY.to_string(),
+        reason: format!("价格{:.4}触及止损价{:.4}", price, stop_price),
+        confidence: 1.0,
+        atr: None,
+    }
+}
+
+/// 按策略给出的平仓数量计算平仓结果，返回 (平仓数量, 盈亏, 剩余持仓)
+///
+/// 策略未指定数量或数量不小于持仓时全部平仓 (剩余持仓为None)；部分平仓的盈亏按策略指定单位的入场价计算，
+/// 未指定时按后进先出的单位成本计算。
+fn plan_exit(
+    strategy: Option<&dyn Strategy>,
+    signal: &TradingSignal,
+    position: &Position,
+) -> (f64, f64, Option<Position>) {
+    let partial = strategy
+        .and_then(|s| s.exit_size(signal, position))
+        .filter(|&quantity| quantity > 0.0 && quantity < position.quantity - QUANTITY_EPSILON);
+
+    match partial {
+        Some(quantity) => {
+            let mut remaining = position.clone();
+            let cost = match strategy.and_then(|s| s.exit_unit(signal, position)) {
+                Some(index) => remaining.reduce_unit(index, quantity),
+                None => remaining.reduce(quantity),
+            };
+            (quantity, quantity * signal.price - cost, Some(remaining))
+        }
+        None => (
+            position.quantity,
+            (signal.price - position.avg_price) * position.quantity,
+            None,
+        ),
+    }
+}
+
+/// 计算K线数据指纹 (数量、首尾时间和FNV-1a哈希)，用于判断两次回测是否基于相同数据
+fn data_fingerprint(candles: &[CandleData]) -> String {
+    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let mut hash = FNV_OFFSET;
+    for candle in candles {
+        let fields = [
+            candle.timestamp.timestamp_millis() as u64,
+            candle.open.to_bits(),
+            candle.high.to_bits(),
+            candle.low.to_bits(),
+            candle.close.to_bits(),
+            candle.volume.to_bits(),
+        ];
+        for field in fields {
+            for byte in field.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+    }
+
+    let first = candles
+        .first()
+        .map_or(0, |c| c.timestamp.timestamp_millis());
+    let last = candles.last().map_or(0, |c| c.timestamp.timestamp_millis());
+
+    format!("{}-{}-{}-{:016x}", candles.len(), first, last, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_db::MemoryDatabase;
+    use crate::types::SignalLeg;
+
+    /// 交替买卖的测试策略，参数中记录成交次数
+    struct AlternatingStrategy {
+        fills: usize,
+    }
+
+    impl Strategy for AlternatingStrategy {
+        fn name(&self) -> &str {
+            "Alternating"
+        }
+
+        fn symbol(&self) -> &str {
+            "SOL-USDT"
+        }
+
+        fn warmup_bars(&self) -> usize {
+            2
+        }
+
+        fn params(&self) -> serde_json::Value {
+            serde_json::json!({ "fills": self.fills })
+        }
+
+        fn fresh(&self) -> Box<dyn Strategy> {
+            Box::new(AlternatingStrategy { fills: 0 })
+        }
+
+        fn on_bar(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+            let candle = candles.last().unwrap();
+            let signal_type = if self.fills.is_multiple_of(2) {
+                SignalType::Buy
+            } else {
+                SignalType::Sell
+            };
+            Ok(vec![TradingSignal {
+                id: None,
+                symbol: candle.symbol.clone(),
+                signal_type,
+                price: candle.close,
+                timestamp: candle.timestamp,
+                strategy: self.name().to_string(),
+                reason: "test".to_string(),
+                confidence: 1.0,
+                atr: None,
+            }])
+        }
+
+        fn on_fill(&mut self, _trade: &Trade) {
+            self.fills += 1;
+        }
+
+        fn position_size(
+            &self,
+            signal: &TradingSignal,
+            balance: f64,
+            _candles: &[CandleData],
+        ) -> Result<Option<f64>> {
+            Ok(Some(balance * 0.5 / signal.price))
+        }
+    }
+
+    /// 交替开平仓的两腿测试策略: 买入1个SOL、卖出2个ETH
+    struct PairLegsStrategy {
+        open: bool,
+    }
+
+    impl PairLegsStrategy {
+        fn leg(bar: &CandleData, signal_type: SignalType, ratio: f64) -> SignalLeg {
+            SignalLeg {
+                signal: TradingSignal {
+                    id: None,
+                    symbol: bar.symbol.clone(),
+                    signal_type,
+                    price: bar.close,
+                    timestamp: bar.timestamp,
+                    strategy: "PairLegs".to_string(),
+                    reason: "test".to_string(),
+                    confidence: 1.0,
+                    atr: None,
+                },
+                ratio,
+            }
+        }
+    }
+
+    impl Strategy for PairLegsStrategy {
+        fn name(&self) -> &str {
+            "PairLegs"
+        }
+
+        fn symbol(&self) -> &str {
+            "SOL-USDT"
+        }
+
+        fn warmup_bars(&self) -> usize {
+            1
+        }
+
+        fn params(&self) -> serde_json::Value {
+            serde_json::json!({})
+        }
+
+        fn fresh(&self) -> Box<dyn Strategy> {
+            Box::new(PairLegsStrategy { open: false })
+        }
+
+        fn on_bar(&mut self, _candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+            Ok(Vec::new())
+        }
+
+        fn on_fill(&mut self, trade: &Trade) {
+            if trade.symbol == "SOL-USDT" {
+                self.open = trade.side == "buy";
+            }
+        }
+
+        fn position_size(
+            &self,
+            _signal: &TradingSignal,
+            _balance: f64,
+            _candles: &[CandleData],
+        ) -> Result<Option<f64>> {
+            Ok(None)
+        }
+
+        fn leg_symbols(&self) -> Vec<String> {
+            vec!["SOL-USDT".to_string(), "ETH-USDT".to_string()]
+        }
+
+        fn on_legs_bar(&mut self, legs: &[&[CandleData]]) -> Result<Vec<MultiLegSignal>> {
+            let (Some(sol), Some(eth)) = (legs[0].last(), legs[1].last()) else {
+                return Ok(Vec::new());
+            };
+            let (action, sol_type, eth_type) = if self.open {
+                (MultiLegAction::Close, SignalType::Sell, SignalType::Buy)
+            } else {
+                (MultiLegAction::Open, SignalType::Buy, SignalType::Sell)
+            };
+            Ok(vec![MultiLegSignal {
+                action,
+                legs: vec![Self::leg(sol, sol_type, 1.0), Self::leg(eth, eth_type, 2.0)],
+            }])
+        }
+
+        fn legs_size(&self, _signal: &MultiLegSignal, _balance: f64) -> Result<Option<f64>> {
+            Ok(Some(1.0))
+        }
+
+        fn on_paired_position_restored(&mut self, _position: &PairedPosition) {
+            self.open = true;
+        }
+    }
+
+    /// 只按收盘价买入一次、止损设在买入价下方1的测试策略
+    struct BuyOnceStrategy {
+        bought: bool,
+    }
+
+    impl Strategy for BuyOnceStrategy {
+        fn name(&self) -> &str {
+            "BuyOnce"
+        }
+
+        fn symbol(&self) -> &str {
+            "SOL-USDT"
+        }
+
+        fn warmup_bars(&self) -> usize {
+            2
+        }
+
+        fn params(&self) -> serde_json::Value {
+            serde_json::json!({})
+        }
+
+        fn fresh(&self) -> Box<dyn Strategy> {
+            Box::new(BuyOnceStrategy { bought: false })
+        }
+
+        fn on_bar(&mut self, candles: &[CandleData]) -> Result<Vec<TradingSignal>> {
+            if self.bought {
+                return Ok(Vec::new());
+            }
+            let candle = candles.last().unwrap();
+            Ok(vec![TradingSignal {
+                id: None,
+                symbol: candle.symbol.clone(),
+                signal_type: SignalType::Buy,
+                price: candle.close,
+                timestamp: candle.timestamp,
+                strategy: self.name().to_string(),
+                reason: "test".to_string(),
+                confidence: 1.0,
+                atr: None,
+            }])
+        }
+
+        fn on_fill(&mut self, trade: &Trade) {
+            self.bought |= trade.side == "buy";
+        }
+
+        fn position_size(
+            &self,
+            signal: &TradingSignal,
+            balance: f64,
+            _candles: &[CandleData],
+        ) -> Result<Option<f64>> {
+            Ok(Some(balance * 0.5 / signal.price))
+        }
+
+        fn stop_price(
+            &self,
+            signal: &TradingSignal,
+            _candles: &[CandleData],
+        ) -> Result<Option<f64>> {
+            Ok(Some(signal.price - 1.0))
+        }
+    }
+
+    /// 逐分钟写入K线 (开盘价, 最低价, 收盘价)，最高价取开盘价与收盘价的较大者
+    async fn save_candles(db: &Arc<dyn Storage>, start: DateTime<Utc>, bars: &[(f64, f64, f64)]) {
+        for (i, &(open, low, close)) in bars.iter().enumerate() {
+            db.save_candle(&CandleData {
+                timestamp: start + chrono::Duration::minutes(i as i64),
+                symbol: "SOL-USDT".to_string(),
+                open,
+                high: open.max(close),
+                low,
+                close,
+                volume: 1.0,
+            })
+            .await
+            .unwrap();
+        }
+    }
+
+    fn ticker_at(time: DateTime<Utc>, last: f64) -> TickerData {
+        let last = last.to_string();
+        TickerData {
+            inst_id: "SOL-USDT".to_string(),
+            last: last.clone(),
+            last_sz: "1".to_string(),
+            ask_px: last.clone(),
+            ask_sz: "1".to_string(),
+            bid_px: last.clone(),
+            bid_sz: "1".to_string(),
+            open24h: "100".to_string(),
+            high24h: last.clone(),
+            low24h: last,
+            vol_ccy24h: "0".to_string(),
+            vol24h: "0".to_string(),
+            ts: time.timestamp_millis().to_string(),
+        }
+    }
+
+    /// 写入从100开始逐分钟上涨的K线
+    async fn save_rising_candles(db: &Arc<dyn Storage>, start: DateTime<Utc>, count: i64) {
+        for i in 0..count {
+            let price = 100.0 + i as f64;
+            db.save_candle(&CandleData {
+                timestamp: start + chrono::Duration::minutes(i),
+                symbol: "SOL-USDT".to_string(),
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: 1.0,
+            })
+            .await
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_account_state_survives_restart() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        assert_eq!(manager.get_balance(), DEFAULT_BALANCE);
+
+        manager.positions.insert(
+            DEFAULT_STRATEGY_ID.to_string(),
+            Position {
+                strategy_id: DEFAULT_STRATEGY_ID.to_string(),
+                symbol: "SOL-USDT".to_string(),
+                quantity: 2.5,
+                avg_price: 150.0,
+                current_price: 150.0,
+                unrealized_pnl: 0.0,
+                timestamp: Utc::now(),
+                units: Vec::new(),
+            },
+        );
+        manager.set_balance(9625.0).await.unwrap();
+
+        let restored = StrategyManager::new(db.clone()).await.unwrap();
+        assert_eq!(restored.get_balance(), 9625.0);
+        assert_eq!(restored.get_positions()[DEFAULT_STRATEGY_ID].quantity, 2.5);
+
+        // 没有单位记录的旧持仓恢复为一个单位
+        let units = &restored.get_positions()[DEFAULT_STRATEGY_ID].units;
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].entry_price, 150.0);
+
+        // 没有对应的买入记录，应报告不一致
+        let mismatches = restored.check_position_consistency().await.unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].strategy_id, DEFAULT_STRATEGY_ID);
+        assert_eq!(mismatches[0].expected_quantity, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_drives_boxed_strategy() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        save_rising_candles(&db, start, 5).await;
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager
+            .add_strategy("alt", Box::new(AlternatingStrategy { fills: 0 }), 1000.0)
+            .unwrap();
+
+        let run = manager
+            .run_backtest("alt", start, start + chrono::Duration::minutes(4), 1000.0)
+            .await
+            .unwrap();
+
+        // 预热2根K线后对第2~5根各调用一次on_bar: 买/卖/买/卖
+        assert_eq!(run.strategy, "Alternating");
+        assert_eq!(run.params["fills"], 4);
+        assert_eq!(run.report.total_trades, 4);
+
+        // 回测使用独立实例，实盘策略状态不受影响
+        assert_eq!(manager.get_strategy_params("alt").unwrap()["fills"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_strategies_share_symbol_with_separate_capital() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        save_rising_candles(&db, start, 3).await;
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        manager
+            .add_strategy("alt-a", Box::new(AlternatingStrategy { fills: 0 }), 4000.0)
+            .unwrap();
+        manager
+            .add_strategy("alt-b", Box::new(AlternatingStrategy { fills: 0 }), 2000.0)
+            .unwrap();
+        assert!(
+            manager
+                .add_strategy("alt-a", Box::new(AlternatingStrategy { fills: 0 }), 1.0)
+                .is_err()
+        );
+
+        let ticker = ticker_at(start + chrono::Duration::minutes(2), 102.0);
+
+        // 两个实例各自按可用资金的一半开仓 (已收盘K线收盘价101)
+        let executed = manager.process_real_time_data(&ticker).await.unwrap();
+        assert_eq!(executed.len(), 2);
+
+        let positions = manager.get_positions();
+        assert!((positions["alt-a"].quantity - 2000.0 / 101.0).abs() < 1e-9);
+        assert!((positions["alt-b"].quantity - 1000.0 / 101.0).abs() < 1e-9);
+
+        // 同一根K线不会重复触发on_bar
+        assert!(
+            manager
+                .process_real_time_data(&ticker)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let summary = manager.portfolio_summary();
+        assert_eq!(summary.strategies.len(), 2);
+        assert!((summary.strategies[0].available - 2000.0).abs() < 1e-9);
+        assert!((summary.strategies[1].available - 1000.0).abs() < 1e-9);
+        assert!((summary.cash - (DEFAULT_BALANCE - 3000.0)).abs() < 1e-9);
+        assert!((summary.position_value - 3000.0 / 101.0 * 102.0).abs() < 1e-9);
+        assert!((summary.equity() - (summary.cash + summary.position_value)).abs() < 1e-9);
+
+        // 成交记录归属各自的策略实例，重启后持仓一致
+        let restored = StrategyManager::new(db.clone()).await.unwrap();
+        assert!(
+            restored
+                .check_position_consistency()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registered_instances_survive_restart() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let params =
+            serde_json::json!({"fast_period": 5, "slow_period": 20, "position_fraction": 0.5});
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        let instance = strategy::create_strategy("MaCross", "ETH-USDT", Some(params)).unwrap();
+        let expected_params = instance.params();
+        manager
+            .register_strategy("ma-eth", instance, 4000.0)
+            .await
+            .unwrap();
+        let unit = PositionUnit {
+            entry_price: 50.0,
+            quantity: 2.0,
+            stop_price: None,
+            timestamp: Utc::now(),
+        };
+        manager.positions.insert(
+            "ma-eth".to_string(),
+            Position::open("ma-eth", "ETH-USDT", unit),
+        );
+        manager.set_balance(9900.0).await.unwrap();
+
+        // 重启后实例在恢复账户状态前重新创建，持仓归属该实例
+        let mut restored = StrategyManager::new(db.clone()).await.unwrap();
+        assert_eq!(restored.strategy_ids(), vec![DEFAULT_STRATEGY_ID, "ma-eth"]);
+        assert_eq!(
+            restored.get_strategy_params("ma-eth"),
+            Some(expected_params)
+        );
+        assert_eq!(restored.get_positions()["ma-eth"].quantity, 2.0);
+        assert!(restored.remove_strategy("ma-eth").is_err());
+
+        // 默认实例只保留其余实例分配后剩余的资金
+        let summary = restored.portfolio_summary();
+        let allocation = |id: &str| {
+            summary
+                .strategies
+                .iter()
+                .find(|s| s.strategy_id == id)
+                .unwrap()
+                .allocation
+        };
+        assert_eq!(allocation("ma-eth"), 4000.0);
+        assert!((allocation(DEFAULT_STRATEGY_ID) - (9900.0 + 100.0 - 4000.0)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_allocations_limited_to_equity() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let mut manager = StrategyManager::new(db).await.unwrap();
+
+        // 默认实例让出新实例所需的资金
+        manager
+            .add_strategy("alt-a", Box::new(AlternatingStrategy { fills: 0 }), 4000.0)
+            .unwrap();
+        let allocation = |manager: &StrategyManager, id: &str| {
+            manager
+                .portfolio_summary()
+                .strategies
+                .iter()
+                .find(|s| s.strategy_id == id)
+                .unwrap()
+                .allocation
+        };
+        assert!((allocation(&manager, DEFAULT_STRATEGY_ID) - 6000.0).abs() < 1e-9);
+
+        // 默认实例不能让出全部资金，显式调整的分配不能超过剩余权益
+        assert!(
+            manager
+                .add_strategy("alt-b", Box::new(AlternatingStrategy { fills: 0 }), 6000.0)
+                .is_err()
+        );
+        assert!(manager.set_allocation(DEFAULT_STRATEGY_ID, 7000.0).is_err());
+        manager
+            .add_strategy("alt-b", Box::new(AlternatingStrategy { fills: 0 }), 5000.0)
+            .unwrap();
+        assert!((allocation(&manager, DEFAULT_STRATEGY_ID) - 1000.0).abs() < 1e-9);
+
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        assert!(manager.set_allocation("alt-a", 6000.0).is_err());
+        manager.set_allocation("alt-a", 5000.0).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_live_bars_are_appended_to_cache() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        save_rising_candles(&db, start, 3).await;
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        manager
+            .add_strategy("alt", Box::new(AlternatingStrategy { fills: 0 }), 1000.0)
+            .unwrap();
+
+        let executed = manager
+            .process_real_time_data(&ticker_at(start + chrono::Duration::minutes(2), 102.0))
+            .await
+            .unwrap();
+        assert_eq!(executed[0].price, 101.0);
+        assert_eq!(manager.bar_cache["SOL-USDT"].len(), 2);
+
+        // 新K线出现后只追加刚收盘的一根，策略按其收盘价102卖出
+        save_rising_candles(&db, start + chrono::Duration::minutes(3), 1).await;
+        let executed = manager
+            .process_real_time_data(&ticker_at(start + chrono::Duration::minutes(3), 100.0))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 1);
+        assert_eq!(executed[0].signal_type, SignalType::Sell);
+        assert_eq!(executed[0].price, 102.0);
+
+        let cached: Vec<f64> = manager.bar_cache["SOL-USDT"]
+            .iter()
+            .map(|c| c.close)
+            .collect();
+        assert_eq!(cached, vec![100.0, 101.0, 102.0]);
+    }
+
+    #[tokio::test]
+    async fn test_stop_loss_exits_in_backtest() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        // 第2根K线收盘101买入，止损100；第3根最低100.5未触及；第4根跳空低开99
+        save_candles(
+            &db,
+            start,
+            &[
+                (100.0, 100.0, 100.0),
+                (100.0, 100.0, 101.0),
+                (101.0, 100.5, 101.0),
+                (99.0, 98.0, 99.0),
+            ],
+        )
+        .await;
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager
+            .add_strategy("once", Box::new(BuyOnceStrategy { bought: false }), 1000.0)
+            .unwrap();
+
+        let run = manager
+            .run_backtest("once", start, start + chrono::Duration::minutes(3), 1000.0)
+            .await
+            .unwrap();
+
+        let trades = db.get_backtest_trades(run.id.unwrap()).await.unwrap();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[1].side, "sell");
+        assert_eq!(trades[1].strategy, STOP_LOSS_STRATEGY);
+        // 跳空低于止损价时按开盘价成交
+        assert_eq!(trades[1].price, 99.0);
+        assert_eq!(trades[1].timestamp, start + chrono::Duration::minutes(3));
+    }
+
+    #[tokio::test]
+    async fn test_paired_position_opens_closes_and_survives_restart() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        save_rising_candles(&db, start, 3).await;
+        // ETH从50开始逐分钟下跌
+        for i in 0..4 {
+            let price = 50.0 - i as f64;
+            db.save_candle(&CandleData {
+                timestamp: start + chrono::Duration::minutes(i),
+                symbol: "ETH-USDT".to_string(),
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: 1.0,
+            })
+            .await
+            .unwrap();
+        }
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        manager
+            .add_strategy("pair", Box::new(PairLegsStrategy { open: false }), 1000.0)
+            .unwrap();
+
+        // 已收盘K线: SOL 101买入1个，ETH 49卖出2个
+        let forming = start + chrono::Duration::minutes(2);
+        let executed = manager
+            .process_real_time_data(&ticker_at(forming, 101.5))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 2);
+        let legs = &manager.get_paired_positions()["pair"].legs;
+        assert_eq!(
+            legs.iter()
+                .map(|l| (l.symbol.as_str(), l.quantity, l.entry_price))
+                .collect::<Vec<_>>(),
+            vec![("SOL-USDT", 1.0, 101.0), ("ETH-USDT", -2.0, 49.0)]
+        );
+        // 空头腿的卖出所得不计入现金，另冻结等额保证金
+        assert_eq!(manager.get_balance(), 10000.0 - 101.0 - 98.0);
+        // 配对持仓占用两腿金额之和
+        assert_eq!(manager.available_capital("pair"), 1000.0 - 101.0 - 98.0);
+
+        // 非策略主交易对的行情同样更新配对持仓的价格，空头腿在下跌时盈利
+        let eth_ticker = TickerData {
+            inst_id: "ETH-USDT".to_string(),
+            ..ticker_at(forming, 45.0)
+        };
+        assert!(
+            manager
+                .process_real_time_data(&eth_ticker)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let summary = manager.portfolio_summary();
+        assert!((summary.unrealized_pnl - (0.0 + 8.0)).abs() < 1e-9);
+        assert!((summary.equity() - (10000.0 - 3.0 + 101.0 - 90.0)).abs() < 1e-9);
+        assert!(
+            manager
+                .check_position_consistency()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // 重启后恢复配对持仓，下一根K线两腿同时平仓
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        let legs = &manager.get_paired_positions()["pair"].legs;
+        assert_eq!(
+            legs.iter().map(|l| l.margin).collect::<Vec<_>>(),
+            vec![101.0, 98.0]
+        );
+        // 恢复的现金不重复扣除两腿冻结的金额
+        assert_eq!(manager.get_balance(), 10000.0 - 101.0 - 98.0);
+        assert!(manager.remove_strategy("pair").is_err());
+        manager
+            .add_strategy("pair", Box::new(PairLegsStrategy { open: false }), 1000.0)
+            .unwrap();
+        save_rising_candles(&db, start, 4).await;
+
+        let executed = manager
+            .process_real_time_data(&ticker_at(start + chrono::Duration::minutes(3), 102.5))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 2);
+        assert!(manager.get_paired_positions().is_empty());
+
+        let trades = db.get_recent_trades(10).await.unwrap();
+        let pnl: f64 = trades.iter().filter_map(|t| t.pnl).sum();
+        // SOL 101->102 盈利1，ETH 49->48 空头盈利2
+        assert!((pnl - 3.0).abs() < 1e-9);
+        assert_eq!(manager.get_balance(), 10000.0 + 3.0);
+        assert!(
+            manager
+                .check_position_consistency()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hedge_leg_candles_are_collected_and_drive_legs() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        save_rising_candles(&db, start, 3).await;
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        assert_eq!(
+            manager.candle_symbols().into_iter().collect::<Vec<_>>(),
+            vec!["SOL-USDT"]
+        );
+        // 配对交易实例的对冲腿也需要采集K线
+        let params = serde_json::json!({
+            "hedge_symbol": "ETH-USDT", "method": "Kalman", "lookback": 240,
+            "entry_z": 2.5, "exit_z": 0.5, "position_fraction": 0.5
+        });
+        let pairs = strategy::create_strategy("Pairs", "BTC-USDT", Some(params)).unwrap();
+        manager
+            .register_strategy("pairs", pairs, 1000.0)
+            .await
+            .unwrap();
+        assert_eq!(
+            manager.candle_symbols().into_iter().collect::<Vec<_>>(),
+            vec!["BTC-USDT", "ETH-USDT", "SOL-USDT"]
+        );
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        manager.remove_strategy("pairs").unwrap();
+        manager
+            .add_strategy("pair", Box::new(PairLegsStrategy { open: false }), 1000.0)
+            .unwrap();
+        assert_eq!(
+            manager.candle_symbols().into_iter().collect::<Vec<_>>(),
+            vec!["ETH-USDT", "SOL-USDT"]
+        );
+
+        // 对冲腿尚无K线时不发出信号
+        assert!(
+            manager
+                .process_real_time_data(&ticker_at(start + chrono::Duration::minutes(2), 101.5))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // 数据处理器写入对冲腿K线后，下一根K线两腿同时开仓
+        save_rising_candles(&db, start, 4).await;
+        for i in 0..4 {
+            let price = 50.0 - i as f64;
+            db.save_candle(&CandleData {
+                timestamp: start + chrono::Duration::minutes(i),
+                symbol: "ETH-USDT".to_string(),
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: 1.0,
+            })
+            .await
+            .unwrap();
         }
+        let executed = manager
+            .process_real_time_data(&ticker_at(start + chrono::Duration::minutes(3), 102.5))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 2);
+        let legs = &manager.get_paired_positions()["pair"].legs;
+        assert_eq!(
+            legs.iter()
+                .map(|l| (l.symbol.as_str(), l.entry_price))
+                .collect::<Vec<_>>(),
+            vec![("SOL-USDT", 102.0), ("ETH-USDT", 48.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_late_funding_rate_is_still_accrued() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        let save_bars = |minutes: std::ops::Range<i64>| {
+            let db = db.clone();
+            async move {
+                for minute in minutes {
+                    for symbol in ["SOL-USDT", "SOL-USDT-SWAP"] {
+                        db.save_candle(&CandleData {
+                            timestamp: start + chrono::Duration::minutes(minute),
+                            symbol: symbol.to_string(),
+                            open: 100.0,
+                            high: 100.0,
+                            low: 100.0,
+                            close: 100.0,
+                            volume: 1.0,
+                        })
+                        .await
+                        .unwrap();
+                    }
+                }
+            }
+        };
+        let rate = |minutes: i64, funding_rate: f64| FundingRate {
+            symbol: "SOL-USDT-SWAP".to_string(),
+            funding_time: start + chrono::Duration::minutes(minutes),
+            funding_rate,
+        };
+        save_bars(0..3).await;
+        db.save_funding_rates(&[rate(-960, 0.0005), rate(-480, 0.0005), rate(0, 0.0005)])
+            .await
+            .unwrap();
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        let arb = strategy::create_strategy("FundingArb", "SOL-USDT", None).unwrap();
+        manager.add_strategy("arb", arb, 1000.0).unwrap();
+
+        // 历史费率预测较高，开仓: 现货买入、永续做空3.375个
+        let executed = manager
+            .process_real_time_data(&ticker_at(start + chrono::Duration::minutes(2), 100.0))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 2);
+        let balance = manager.get_balance();
+
+        // 处理下一根K线时尚无新的结算记录
+        save_bars(3..4).await;
+        manager
+            .process_real_time_data(&ticker_at(start + chrono::Duration::minutes(3), 100.0))
+            .await
+            .unwrap();
+        assert_eq!(manager.get_balance(), balance);
+
+        // 结算时间早于已处理K线的费率晚到，下一根K线仍记入资金费
+        db.save_funding_rates(&[rate(1, 0.001)]).await.unwrap();
+        save_bars(4..5).await;
+        manager
+            .process_real_time_data(&ticker_at(start + chrono::Duration::minutes(4), 100.0))
+            .await
+            .unwrap();
+        assert!((manager.get_balance() - (balance + 3.375 * 100.0 * 0.001)).abs() < 1e-9);
+        let payments = db
+            .get_funding_payments(DateTime::<Utc>::UNIX_EPOCH, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(payments.len(), 1);
+        assert_eq!(
+            payments[0].funding_time,
+            start + chrono::Duration::minutes(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stop_loss_exits_on_tick() {
+        let db: Arc<dyn Storage> = Arc::new(MemoryDatabase::new());
+        let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        save_rising_candles(&db, start, 3).await;
+
+        let mut manager = StrategyManager::new(db.clone()).await.unwrap();
+        manager.remove_strategy(DEFAULT_STRATEGY_ID).unwrap();
+        manager
+            .add_strategy("once", Box::new(BuyOnceStrategy { bought: false }), 1000.0)
+            .unwrap();
+
+        // 已收盘K线收盘价101买入，止损100
+        let forming = start + chrono::Duration::minutes(2);
+        let executed = manager
+            .process_real_time_data(&ticker_at(forming, 101.5))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 1);
+        assert_eq!(manager.get_positions()["once"].stop_price(), Some(100.0));
+
+        // 高于止损价的行情不触发
+        assert!(
+            manager
+                .process_real_time_data(&ticker_at(forming, 100.2))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let executed = manager
+            .process_real_time_data(&ticker_at(forming, 99.8))
+            .await
+            .unwrap();
+        assert_eq!(executed.len(), 1);
+        assert_eq!(executed[0].strategy, STOP_LOSS_STRATEGY);
+        assert!(manager.get_positions().is_empty());
+
+        let trades = db.get_recent_trades(10).await.unwrap();
+        let exit = trades.iter().find(|t| t.side == "sell").unwrap();
+        assert_eq!(exit.strategy, STOP_LOSS_STRATEGY);
+        assert_eq!(exit.strategy_id, "once");
+        assert_eq!(exit.price, 99.8);
+        assert!((exit.pnl.unwrap() - (99.8 - 101.0) * exit.quantity).abs() < 1e-9);
     }
 }
@@ -1,5 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::indicators::{AtrSmoothing, MaType};
 
 /// 交易对符号
 pub type Symbol = String;
@@ -29,6 +32,31 @@ pub struct TickerData {
     pub ts: String,   // 时间戳
 }
 
+/// 数据库中保存的Ticker快照
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TickerSnapshot {
+    pub timestamp: DateTime<Utc>, // 时间戳
+    pub symbol: String,           // 交易对
+    pub last_price: f64,          // 最新价格
+    pub bid_price: f64,           // 买一价
+    pub ask_price: f64,           // 卖一价
+    pub volume_24h: f64,          // 24小时成交量(计价货币)
+}
+
+impl From<&TickerData> for TickerSnapshot {
+    fn from(ticker: &TickerData) -> Self {
+        let timestamp_ms: i64 = ticker.ts.parse().unwrap_or(0);
+        TickerSnapshot {
+            timestamp: DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default(),
+            symbol: ticker.inst_id.clone(),
+            last_price: ticker.last.parse().unwrap_or(0.0),
+            bid_price: ticker.bid_px.parse().unwrap_or(0.0),
+            ask_price: ticker.ask_px.parse().unwrap_or(0.0),
+            volume_24h: ticker.vol_ccy24h.parse().unwrap_or(0.0),
+        }
+    }
+}
+
 /// OKX REST API K线数据结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CandleData {
@@ -41,6 +69,132 @@ pub struct CandleData {
     pub volume: f64,              // 成交量
 }
 
+/// K线周期 (与OKX bar参数对应)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Timeframe {
+    M1,  // 1分钟
+    M3,  // 3分钟
+    M5,  // 5分钟
+    M15, // 15分钟
+    M30, // 30分钟
+    H1,  // 1小时
+    H2,  // 2小时
+    H4,  // 4小时
+    H6,  // 6小时
+    H12, // 12小时
+    D1,  // 1天
+    W1,  // 1周
+}
+
+impl Timeframe {
+    /// OKX接口使用的字符串表示
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Timeframe::M1 => "1m",
+            Timeframe::M3 => "3m",
+            Timeframe::M5 => "5m",
+            Timeframe::M15 => "15m",
+            Timeframe::M30 => "30m",
+            Timeframe::H1 => "1H",
+            Timeframe::H2 => "2H",
+            Timeframe::H4 => "4H",
+            Timeframe::H6 => "6H",
+            Timeframe::H12 => "12H",
+            Timeframe::D1 => "1D",
+            Timeframe::W1 => "1W",
+        }
+    }
+
+    /// 每根K线的时长 (毫秒)
+    pub fn duration_ms(&self) -> i64 {
+        const MINUTE: i64 = 60_000;
+        match self {
+            Timeframe::M1 => MINUTE,
+            Timeframe::M3 => 3 * MINUTE,
+            Timeframe::M5 => 5 * MINUTE,
+            Timeframe::M15 => 15 * MINUTE,
+            Timeframe::M30 => 30 * MINUTE,
+            Timeframe::H1 => 60 * MINUTE,
+            Timeframe::H2 => 120 * MINUTE,
+            Timeframe::H4 => 240 * MINUTE,
+            Timeframe::H6 => 360 * MINUTE,
+            Timeframe::H12 => 720 * MINUTE,
+            Timeframe::D1 => 1440 * MINUTE,
+            Timeframe::W1 => 7 * 1440 * MINUTE,
+        }
+    }
+
+    /// OKX接口的bar参数，UTC会话的6H及以上周期带 `utc` 后缀 (如 `1Dutc`)
+    pub fn okx_bar(&self, session: SessionBoundary) -> String {
+        match (self, session) {
+            (
+                Timeframe::H6 | Timeframe::H12 | Timeframe::D1 | Timeframe::W1,
+                SessionBoundary::Utc,
+            ) => format!("{}utc", self.as_str()),
+            _ => self.as_str().to_string(),
+        }
+    }
+
+    /// 分桶对齐偏移 (毫秒)，满足 (桶起始时间 + 偏移) 能被周期整除
+    ///
+    /// 周线以周一为起点，1970-01-01为周四，因此额外偏移3天。
+    pub fn bucket_shift_ms(&self, session: SessionBoundary) -> i64 {
+        let week_shift = match self {
+            Timeframe::W1 => 3 * Timeframe::D1.duration_ms(),
+            _ => 0,
+        };
+        session.offset_ms() + week_shift
+    }
+
+    /// 计算时间戳所属K线的起始时间 (毫秒)
+    pub fn bucket_start(&self, timestamp_ms: i64, session: SessionBoundary) -> i64 {
+        timestamp_ms - (timestamp_ms + self.bucket_shift_ms(session)).rem_euclid(self.duration_ms())
+    }
+}
+
+/// K线分桶的会话边界
+///
+/// 对应OKX的 `1D` (香港时间 UTC+8 零点) 与 `1Dutc` (UTC零点)。
+/// 小时级周期的边界在两种会话下相同 (6H/12H除外)。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SessionBoundary {
+    Utc, // UTC零点
+    #[default]
+    Utc8, // UTC+8零点 (OKX默认)
+}
+
+impl SessionBoundary {
+    /// 相对UTC的时区偏移 (毫秒)
+    pub fn offset_ms(&self) -> i64 {
+        match self {
+            SessionBoundary::Utc => 0,
+            SessionBoundary::Utc8 => 8 * 3_600_000,
+        }
+    }
+}
+
+impl FromStr for Timeframe {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1m" => Ok(Timeframe::M1),
+            "3m" => Ok(Timeframe::M3),
+            "5m" => Ok(Timeframe::M5),
+            "15m" => Ok(Timeframe::M15),
+            "30m" => Ok(Timeframe::M30),
+            "1H" | "1h" => Ok(Timeframe::H1),
+            "2H" | "2h" => Ok(Timeframe::H2),
+            "4H" | "4h" => Ok(Timeframe::H4),
+            "6H" | "6h" => Ok(Timeframe::H6),
+            "12H" | "12h" => Ok(Timeframe::H12),
+            "1D" | "1d" => Ok(Timeframe::D1),
+            "1W" | "1w" => Ok(Timeframe::W1),
+            _ => Err(anyhow::anyhow!("未知的K线周期: {}", s)),
+        }
+    }
+}
+
 /// 交易信号类型
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SignalType {
@@ -49,9 +203,23 @@ pub enum SignalType {
     Hold, // 持有信号
 }
 
+impl FromStr for SignalType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Buy" => Ok(SignalType::Buy),
+            "Sell" => Ok(SignalType::Sell),
+            "Hold" => Ok(SignalType::Hold),
+            _ => Err(anyhow::anyhow!("未知的信号类型: {}", s)),
+        }
+    }
+}
+
 /// 交易信号
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradingSignal {
+    pub id: Option<i64>,          // 信号ID (保存后生成)
     pub symbol: String,           // 交易对
     pub signal_type: SignalType,  // 信号类型
     pub price: f64,               // 触发价格
@@ -59,6 +227,81 @@ pub struct TradingSignal {
     pub strategy: String,         // 策略名称
     pub reason: String,           // 信号原因
     pub confidence: f64,          // 信号置信度 (0.0-1.0)
+    #[serde(default)]
+    pub atr: Option<f64>, // 信号产生时的ATR (海龟策略的N)
+}
+
+/// 多腿信号的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MultiLegAction {
+    Open,  // 各腿同时开仓
+    Close, // 平掉该策略实例的配对持仓
+}
+
+/// 多腿信号中的一腿
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalLeg {
+    pub signal: TradingSignal, // 该腿的信号 (开仓时Buy为做多、Sell为做空)
+    pub ratio: f64,            // 开仓时该腿数量相对第一腿的比例 (对冲比例，平仓时忽略)
+}
+
+/// 多腿信号: 所有腿作为一个整体执行，任何一腿无法执行时整体不执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiLegSignal {
+    pub action: MultiLegAction, // 开仓或平仓
+    pub legs: Vec<SignalLeg>,   // 各腿信号
+}
+
+/// 信号处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SignalOutcome {
+    Executed,          // 已执行
+    AlreadyPositioned, // 已有同向持仓
+    NoPosition,        // 没有可平的持仓
+    InsufficientData,  // 数据不足，无法计算仓位
+    SizeTooSmall,      // 计算出的仓位过小
+    RiskRejected,      // 被风控拒绝
+    Ignored,           // 无需处理 (如持有信号)
+}
+
+impl SignalOutcome {
+    /// 数据库使用的字符串表示
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignalOutcome::Executed => "executed",
+            SignalOutcome::AlreadyPositioned => "already_positioned",
+            SignalOutcome::NoPosition => "no_position",
+            SignalOutcome::InsufficientData => "insufficient_data",
+            SignalOutcome::SizeTooSmall => "size_too_small",
+            SignalOutcome::RiskRejected => "risk_rejected",
+            SignalOutcome::Ignored => "ignored",
+        }
+    }
+}
+
+impl FromStr for SignalOutcome {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "executed" => Ok(SignalOutcome::Executed),
+            "already_positioned" => Ok(SignalOutcome::AlreadyPositioned),
+            "no_position" => Ok(SignalOutcome::NoPosition),
+            "insufficient_data" => Ok(SignalOutcome::InsufficientData),
+            "size_too_small" => Ok(SignalOutcome::SizeTooSmall),
+            "risk_rejected" => Ok(SignalOutcome::RiskRejected),
+            "ignored" => Ok(SignalOutcome::Ignored),
+            _ => Err(anyhow::anyhow!("未知的信号处理结果: {}", s)),
+        }
+    }
+}
+
+/// 已保存的交易信号及其处理结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalRecord {
+    pub signal: TradingSignal,          // 交易信号
+    pub outcome: Option<SignalOutcome>, // 处理结果 (尚未处理时为空)
+    pub outcome_reason: Option<String>, // 处理结果说明 (如拒绝原因)
 }
 
 /// 交易记录
@@ -71,18 +314,397 @@ pub struct Trade {
     pub quantity: f64,            // 成交数量
     pub timestamp: DateTime<Utc>, // 成交时间
     pub strategy: String,         // 执行策略
+    pub strategy_id: String,      // 策略实例ID (盈亏归属)
     pub pnl: Option<f64>,         // 盈亏 (仅对已平仓交易)
+    pub signal_id: Option<i64>,   // 触发该交易的信号ID
+}
+
+/// 永续合约在一个结算时点的资金费率
+///
+/// 费率为正时多头向空头支付，每个结算时点的资金费 = 持仓数量 × 标记价格 × 费率。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRate {
+    pub symbol: String,              // 永续合约交易对 (如 SOL-USDT-SWAP)
+    pub funding_time: DateTime<Utc>, // 结算时间
+    pub funding_rate: f64,           // 该时点结算的资金费率
+}
+
+/// 配对持仓在资金费结算时点收付的资金费
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingPayment {
+    pub id: Option<i64>,             // 记录ID
+    pub strategy_id: String,         // 策略实例ID (盈亏归属)
+    pub symbol: String,              // 永续合约交易对
+    pub funding_time: DateTime<Utc>, // 结算时间
+    pub funding_rate: f64,           // 结算费率
+    pub quantity: f64,               // 结算时的持仓数量 (空头为负)
+    pub price: f64,                  // 结算价格 (该腿的最新价)
+    pub amount: f64,                 // 资金费收支 (正数为收入)
+}
+
+/// 订单类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OrderType {
+    Market, // 市价单
+    Limit,  // 限价单
+}
+
+impl OrderType {
+    /// 数据库/OKX接口使用的字符串表示
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderType::Market => "market",
+            OrderType::Limit => "limit",
+        }
+    }
+}
+
+impl FromStr for OrderType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "market" => Ok(OrderType::Market),
+            "limit" => Ok(OrderType::Limit),
+            _ => Err(anyhow::anyhow!("未知的订单类型: {}", s)),
+        }
+    }
+}
+
+/// 订单状态 (与OKX订单状态对应)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OrderState {
+    Pending,         // 已创建，尚未被交易所确认
+    Live,            // 等待成交
+    PartiallyFilled, // 部分成交
+    Filled,          // 完全成交
+    Canceled,        // 已撤销
+    Rejected,        // 被拒绝
+}
+
+impl OrderState {
+    /// 数据库/OKX接口使用的字符串表示
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderState::Pending => "pending",
+            OrderState::Live => "live",
+            OrderState::PartiallyFilled => "partially_filled",
+            OrderState::Filled => "filled",
+            OrderState::Canceled => "canceled",
+            OrderState::Rejected => "rejected",
+        }
+    }
+
+    /// 是否仍为未完结订单
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            OrderState::Pending | OrderState::Live | OrderState::PartiallyFilled
+        )
+    }
+}
+
+impl FromStr for OrderState {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(OrderState::Pending),
+            "live" => Ok(OrderState::Live),
+            "partially_filled" => Ok(OrderState::PartiallyFilled),
+            "filled" => Ok(OrderState::Filled),
+            "canceled" | "mmp_canceled" => Ok(OrderState::Canceled),
+            "rejected" => Ok(OrderState::Rejected),
+            _ => Err(anyhow::anyhow!("未知的订单状态: {}", s)),
+        }
+    }
+}
+
+/// 订单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub id: Option<i64>,                   // 订单ID
+    pub client_order_id: String,           // 客户端订单ID (clOrdId)
+    pub exchange_order_id: Option<String>, // 交易所订单ID (ordId)
+    pub symbol: String,                    // 交易对
+    pub side: String,                      // 买卖方向 ("buy" or "sell")
+    pub order_type: OrderType,             // 订单类型
+    pub price: Option<f64>,                // 委托价格 (市价单为空)
+    pub size: f64,                         // 委托数量
+    pub state: OrderState,                 // 订单状态
+    pub filled_size: f64,                  // 已成交数量
+    pub avg_fill_price: Option<f64>,       // 成交均价
+    pub fee: f64,                          // 累计手续费
+    pub strategy: String,                  // 下单策略
+    pub signal_id: Option<i64>,            // 触发该订单的信号ID
+    pub created_at: DateTime<Utc>,         // 创建时间
+    pub updated_at: DateTime<Utc>,         // 更新时间
+}
+
+/// 订单成交明细
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub id: Option<i64>,                   // 成交ID
+    pub order_id: i64,                     // 所属订单ID
+    pub exchange_trade_id: Option<String>, // 交易所成交ID (tradeId)
+    pub price: f64,                        // 成交价格
+    pub size: f64,                         // 成交数量
+    pub fee: f64,                          // 手续费
+    pub fee_ccy: String,                   // 手续费币种
+    pub timestamp: DateTime<Utc>,          // 成交时间
+}
+
+/// 策略自身维护的限价挂单 (实盘同步到订单表)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RestingOrder {
+    pub side: SignalType, // 买卖方向
+    pub price: f64,       // 挂单价格
+    pub quantity: f64,    // 挂单数量
+}
+
+impl RestingOrder {
+    /// 订单表使用的买卖方向
+    pub fn side_str(&self) -> &'static str {
+        match self.side {
+            SignalType::Buy => "buy",
+            _ => "sell",
+        }
+    }
+
+    /// 订单表中的订单是否为该挂单 (方向、价格和数量都相同)
+    pub fn matches(&self, order: &Order) -> bool {
+        order.side == self.side_str()
+            && order
+                .price
+                .is_some_and(|price| (price - self.price).abs() <= f64::EPSILON * price.abs())
+            && (order.size - self.quantity).abs() <= f64::EPSILON * order.size.abs()
+    }
 }
 
 /// 持仓信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
+    pub strategy_id: String,      // 所属策略实例ID
     pub symbol: String,           // 交易对
     pub quantity: f64,            // 持仓数量
     pub avg_price: f64,           // 平均成本价
     pub current_price: f64,       // 当前价格
     pub unrealized_pnl: f64,      // 未实现盈亏
     pub timestamp: DateTime<Utc>, // 更新时间
+    #[serde(default)]
+    pub units: Vec<PositionUnit>, // 分批建仓的各个单位 (按加仓顺序)
+}
+
+/// 持仓中的一个单位 (一次入场或加仓)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionUnit {
+    pub entry_price: f64,         // 入场价
+    pub quantity: f64,            // 数量
+    pub stop_price: Option<f64>,  // 止损价
+    pub timestamp: DateTime<Utc>, // 入场时间
+}
+
+impl Position {
+    /// 以第一个单位开仓
+    pub fn open(strategy_id: &str, symbol: &str, unit: PositionUnit) -> Self {
+        Self {
+            strategy_id: strategy_id.to_string(),
+            symbol: symbol.to_string(),
+            quantity: unit.quantity,
+            avg_price: unit.entry_price,
+            current_price: unit.entry_price,
+            unrealized_pnl: 0.0,
+            timestamp: unit.timestamp,
+            units: vec![unit],
+        }
+    }
+
+    /// 当前止损价 (各单位止损价中最高的一个)
+    pub fn stop_price(&self) -> Option<f64> {
+        self.units
+            .iter()
+            .filter_map(|u| u.stop_price)
+            .reduce(f64::max)
+    }
+
+    /// 加仓一个单位，并按加权平均更新成本价；新单位带止损时，已有单位的止损上移到同一价位
+    pub fn add_unit(&mut self, unit: PositionUnit) {
+        if let Some(stop) = unit.stop_price {
+            for existing in &mut self.units {
+                existing.stop_price = Some(existing.stop_price.map_or(stop, |s| s.max(stop)));
+            }
+        }
+
+        let cost = self.quantity * self.avg_price + unit.quantity * unit.entry_price;
+        self.quantity += unit.quantity;
+        self.avg_price = cost / self.quantity;
+        self.current_price = unit.entry_price;
+        self.unrealized_pnl = (self.current_price - self.avg_price) * self.quantity;
+        self.timestamp = unit.timestamp;
+        self.units.push(unit);
+    }
+
+    /// 部分平仓: 先从指定单位扣减数量，不足的部分再按后进先出扣减，返回被扣减部分的成本
+    pub fn reduce_unit(&mut self, index: usize, quantity: f64) -> f64 {
+        let Some(unit) = self.units.get_mut(index) else {
+            return self.reduce(quantity);
+        };
+        let taken = unit.quantity.min(quantity);
+        let cost = taken * unit.entry_price;
+        unit.quantity -= taken;
+        if unit.quantity <= f64::EPSILON * taken.max(1.0) {
+            self.units.remove(index);
+        }
+
+        cost + self.reduce(quantity - taken)
+    }
+
+    /// 部分平仓: 从最后加仓的单位开始扣减数量 (后进先出)，按剩余单位重新计算成本价，
+    /// 返回被扣减部分的成本
+    pub fn reduce(&mut self, quantity: f64) -> f64 {
+        let mut remaining = quantity.min(self.quantity);
+        let mut cost = 0.0;
+        while remaining > 0.0
+            && let Some(unit) = self.units.last_mut()
+        {
+            let taken = unit.quantity.min(remaining);
+            cost += taken * unit.entry_price;
+            unit.quantity -= taken;
+            remaining -= taken;
+            if unit.quantity <= f64::EPSILON * taken.max(1.0) {
+                self.units.pop();
+            }
+        }
+
+        self.quantity = self.units.iter().map(|u| u.quantity).sum();
+        if self.quantity > 0.0 {
+            self.avg_price = self
+                .units
+                .iter()
+                .map(|u| u.quantity * u.entry_price)
+                .sum::<f64>()
+                / self.quantity;
+        }
+        self.unrealized_pnl = (self.current_price - self.avg_price) * self.quantity;
+        cost
+    }
+}
+
+/// 配对持仓中的一腿
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionLeg {
+    pub symbol: String,     // 交易对
+    pub quantity: f64,      // 持仓数量 (正数为多头，负数为空头)
+    pub entry_price: f64,   // 入场价
+    pub current_price: f64, // 当前价格
+    pub margin: f64,        // 该腿冻结的现金 (多头为买入成本，空头为保证金)
+}
+
+impl PositionLeg {
+    /// 未实现盈亏 (空头在价格下跌时盈利)
+    pub fn unrealized_pnl(&self) -> f64 {
+        (self.current_price - self.entry_price) * self.quantity
+    }
+
+    /// 该腿的权益 (冻结的现金+未实现盈亏)，多头即为持仓市值
+    pub fn equity(&self) -> f64 {
+        self.margin + self.unrealized_pnl()
+    }
+}
+
+/// 多腿策略的配对持仓，各腿同时开仓、同时平仓
+///
+/// 模拟账户中开仓时从现金冻结各腿的 `margin`；做空一腿的卖出所得随持仓冻结、不计入现金，
+/// 平仓时各腿的保证金连同盈亏一并退回现金。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairedPosition {
+    pub strategy_id: String,      // 所属策略实例ID
+    pub legs: Vec<PositionLeg>,   // 各腿持仓
+    pub timestamp: DateTime<Utc>, // 开仓时间
+}
+
+impl PairedPosition {
+    /// 持仓权益 (各腿冻结的现金+未实现盈亏)
+    pub fn equity(&self) -> f64 {
+        self.legs.iter().map(PositionLeg::equity).sum()
+    }
+
+    /// 各腿冻结的现金之和，占用策略实例的可用资金
+    pub fn margin(&self) -> f64 {
+        self.legs.iter().map(|l| l.margin).sum()
+    }
+
+    /// 未实现盈亏合计
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.legs.iter().map(PositionLeg::unrealized_pnl).sum()
+    }
+}
+
+/// 账户状态快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountState {
+    pub balance: f64,             // 账户余额
+    pub positions: Vec<Position>, // 当前持仓
+    #[serde(default)]
+    pub paired_positions: Vec<PairedPosition>, // 多腿策略的配对持仓
+    pub updated_at: DateTime<Utc>, // 更新时间
+}
+
+/// 运行时添加的策略实例定义 (重启时据此重新创建实例)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrategyInstance {
+    pub strategy_id: String,       // 实例ID
+    pub kind: String,              // 策略名称 (见 `strategy::STRATEGY_KINDS`)
+    pub symbol: String,            // 交易对
+    pub params: serde_json::Value, // 策略参数
+    pub allocation: f64,           // 分配资金
+}
+
+/// 策略实例的资金与盈亏汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategySummary {
+    pub strategy_id: String, // 策略实例ID
+    pub strategy: String,    // 策略名称
+    pub symbol: String,      // 交易对
+    pub allocation: f64,     // 分配资金
+    pub available: f64,      // 可用资金 (分配资金+已实现盈亏-持仓成本)
+    pub position_value: f64, // 持仓市值
+    pub realized_pnl: f64,   // 已实现盈亏
+    pub unrealized_pnl: f64, // 未实现盈亏
+}
+
+impl StrategySummary {
+    /// 策略权益 (可用资金+持仓市值)
+    pub fn equity(&self) -> f64 {
+        self.available + self.position_value
+    }
+}
+
+/// 账户层面的组合汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioSummary {
+    pub cash: f64,                        // 账户现金余额
+    pub unallocated: f64,                 // 未分配给任何策略的现金
+    pub position_value: f64,              // 持仓总市值
+    pub realized_pnl: f64,                // 已实现盈亏合计
+    pub unrealized_pnl: f64,              // 未实现盈亏合计
+    pub strategies: Vec<StrategySummary>, // 各策略实例汇总
+}
+
+impl PortfolioSummary {
+    /// 账户总权益 (现金+持仓市值)
+    pub fn equity(&self) -> f64 {
+        self.cash + self.position_value
+    }
+}
+
+/// 持仓与交易历史不一致的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionMismatch {
+    pub strategy_id: String,    // 策略实例ID
+    pub symbol: String,         // 交易对
+    pub restored_quantity: f64, // 恢复的持仓数量
+    pub expected_quantity: f64, // 按交易历史推算的持仓数量
 }
 
 /// 回测报告
@@ -101,24 +723,700 @@ pub struct BacktestReport {
     pub end_time: DateTime<Utc>,   // 回测结束时间
 }
 
+/// 已保存的回测记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestRun {
+    pub id: Option<i64>,           // 回测记录ID
+    pub symbol: String,            // 交易对
+    pub strategy: String,          // 策略名称
+    pub params: serde_json::Value, // 回测使用的策略参数
+    pub candle_count: usize,       // 回测使用的K线数量
+    pub data_fingerprint: String,  // 回测数据指纹
+    pub code_version: String,      // 代码版本
+    pub created_at: DateTime<Utc>, // 回测执行时间
+    pub report: BacktestReport,    // 回测报告
+}
+
+/// K线缺口 (两根相邻K线之间缺失的数据)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataGap {
+    pub after: DateTime<Utc>,  // 缺口前最后一根K线时间
+    pub before: DateTime<Utc>, // 缺口后第一根K线时间
+    pub missing_bars: usize,   // 缺失的K线数量
+}
+
+/// OHLC合理性问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OhlcViolationKind {
+    HighBelowOpenClose, // 最高价低于开盘价或收盘价
+    LowAboveOpenClose,  // 最低价高于开盘价或收盘价
+    NonPositivePrice,   // 价格小于等于0
+    NegativeVolume,     // 成交量为负
+}
+
+/// OHLC合理性问题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OhlcViolation {
+    pub timestamp: DateTime<Utc>, // K线时间
+    pub kind: OhlcViolationKind,  // 问题类型
+}
+
+/// 连续零成交量区间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZeroVolumeStreak {
+    pub start: DateTime<Utc>, // 第一根零成交量K线时间
+    pub end: DateTime<Utc>,   // 最后一根零成交量K线时间
+    pub bars: usize,          // 连续K线数量
+}
+
+/// K线写入时累计的质量计数 (存储按交易对和时间戳去重，重复与乱序只能在写入时发现)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CandleIngestStats {
+    pub duplicate_count: usize,    // 已收盘K线被不同数据重复写入的次数
+    pub out_of_order_count: usize, // 写入批次中逆着批次时间方向的K线数量
+}
+
+/// 单个交易对、单个周期的数据覆盖与质量报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataCoverageReport {
+    pub symbol: String,                             // 交易对
+    pub timeframe: Timeframe,                       // K线周期
+    pub first_timestamp: Option<DateTime<Utc>>,     // 第一根K线时间
+    pub last_timestamp: Option<DateTime<Utc>>,      // 最后一根K线时间
+    pub expected_bars: usize,                       // 首尾之间应有的K线数量
+    pub actual_bars: usize,                         // 实际K线数量 (去重后)
+    pub coverage: f64,                              // 覆盖率 (实际/应有)
+    pub gaps: Vec<DataGap>,                         // 缺口列表
+    pub duplicate_count: usize,                     // 重复时间戳数量 (含写入时的重复)
+    pub out_of_order_count: usize,                  // 时间倒序的K线数量 (含写入时的乱序)
+    pub misaligned_count: usize,                    // 未对齐周期边界的K线数量
+    pub ohlc_violations: Vec<OhlcViolation>,        // OHLC合理性问题
+    pub zero_volume_streaks: Vec<ZeroVolumeStreak>, // 连续零成交量区间
+}
+
+/// 归档文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Csv,
+    Parquet,
+}
+
+impl ArchiveFormat {
+    /// 根据文件扩展名判断格式
+    pub fn from_path(path: &std::path::Path) -> anyhow::Result<Self> {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("csv") => Ok(ArchiveFormat::Csv),
+            Some("parquet") => Ok(ArchiveFormat::Parquet),
+            _ => Err(anyhow::anyhow!(
+                "无法识别归档格式 (需要 .csv 或 .parquet): {}",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// 可导出的数据集
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveDataset {
+    Candles,             // K线 (按周期聚合)
+    Tickers,             // Ticker快照
+    Trades,              // 实盘成交记录
+    BacktestRuns,        // 回测记录汇总 (参数与绩效)
+    BacktestEquity(i64), // 指定回测记录的权益曲线
+}
+
+/// 导出请求
+#[derive(Debug, Clone)]
+pub struct ExportRequest {
+    pub dataset: ArchiveDataset,   // 数据集
+    pub symbol: String,            // 交易对 (权益曲线导出时忽略)
+    pub timeframe: Timeframe,      // K线周期 (仅K线导出使用)
+    pub session: SessionBoundary,  // 会话边界 (仅K线导出使用)
+    pub start_time: DateTime<Utc>, // 开始时间
+    pub end_time: DateTime<Utc>,   // 结束时间
+}
+
+/// 导入结果汇总
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub rows_read: usize,  // 读取行数
+    pub imported: usize,   // 新写入行数
+    pub duplicates: usize, // 文件内或数据库中已存在的重复行
+    pub skipped: usize,    // 无法解析或未确认的行
+}
+
+/// 海龟交易系统
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TurtleSystem {
+    #[default]
+    System1, // 20日突破入场/10日离场，上次突破盈利时跳过本次突破
+    System2, // 55日突破入场/20日离场，不过滤突破
+}
+
 /// 海龟策略参数
+///
+/// 从JSON解析时入场/离场周期可省略，省略时取所选交易系统的原始周期 (见 `for_system`)。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "TurtleParamsInput")]
 pub struct TurtleParams {
-    pub entry_period: usize, // 入场周期 (默认20)
-    pub exit_period: usize,  // 离场周期 (默认10)
-    pub atr_period: usize,   // ATR周期 (默认20)
-    pub risk_per_trade: f64, // 每笔交易风险 (默认0.02, 即2%)
-    pub max_units: usize,    // 最大仓位单位 (默认4)
+    pub system: TurtleSystem,         // 交易系统 (默认System1)
+    pub entry_period: usize,          // 入场周期 (System1默认20，System2默认55)
+    pub exit_period: usize,           // 离场周期 (System1默认10，System2默认20)
+    pub atr_period: usize,            // ATR周期 (默认20)
+    pub atr_smoothing: AtrSmoothing,  // N的平滑方式 (默认Wilder)
+    pub risk_per_trade: f64,          // 每笔交易风险 (默认0.02, 即2%)
+    pub max_units: usize,             // 最大仓位单位 (默认4)
+    pub stop_n: f64,                  // 止损距离 (单位N，默认2.0)
+    pub fallback_entry_period: usize, // System 1跳过突破后的保底入场周期 (默认55)
+}
+
+/// 海龟策略参数的JSON输入
+#[derive(Deserialize)]
+struct TurtleParamsInput {
+    #[serde(default)]
+    system: TurtleSystem,
+    entry_period: Option<usize>,
+    exit_period: Option<usize>,
+    atr_period: usize,
+    #[serde(default)]
+    atr_smoothing: AtrSmoothing,
+    risk_per_trade: f64,
+    max_units: usize,
+    #[serde(default = "default_stop_n")]
+    stop_n: f64,
+    #[serde(default = "default_fallback_entry_period")]
+    fallback_entry_period: usize,
+}
+
+impl From<TurtleParamsInput> for TurtleParams {
+    fn from(input: TurtleParamsInput) -> Self {
+        let windows = Self::for_system(input.system);
+        Self {
+            system: input.system,
+            entry_period: input.entry_period.unwrap_or(windows.entry_period),
+            exit_period: input.exit_period.unwrap_or(windows.exit_period),
+            atr_period: input.atr_period,
+            atr_smoothing: input.atr_smoothing,
+            risk_per_trade: input.risk_per_trade,
+            max_units: input.max_units,
+            stop_n: input.stop_n,
+            fallback_entry_period: input.fallback_entry_period,
+        }
+    }
+}
+
+impl TurtleParams {
+    /// 按交易系统的原始规则生成参数 (System 1: 20/10, System 2: 55/20)
+    pub fn for_system(system: TurtleSystem) -> Self {
+        match system {
+            TurtleSystem::System1 => Self::default(),
+            TurtleSystem::System2 => Self {
+                system,
+                entry_period: 55,
+                exit_period: 20,
+                ..Self::default()
+            },
+        }
+    }
 }
 
 impl Default for TurtleParams {
     fn default() -> Self {
         Self {
+            system: TurtleSystem::System1,
             entry_period: 20,
             exit_period: 10,
             atr_period: 20,
+            atr_smoothing: AtrSmoothing::Wilder,
             risk_per_trade: 0.02,
             max_units: 4,
+            stop_n: default_stop_n(),
+            fallback_entry_period: default_fallback_entry_period(),
+        }
+    }
+}
+
+fn default_fallback_entry_period() -> usize {
+    55
+}
+
+fn default_stop_n() -> f64 {
+    2.0
+}
+
+/// 均线交叉策略参数
+///
+/// 快线上穿慢线 (金叉) 做多，下穿 (死叉) 离场；设置趋势线时为三均线模式，
+/// 只在慢线位于趋势线上方时做多。ADX与成交量过滤只作用于入场。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaCrossParams {
+    #[serde(default)]
+    pub ma_type: MaType, // 均线类型 (默认SMA)
+    pub fast_period: usize, // 快线周期 (默认10)
+    pub slow_period: usize, // 慢线周期 (默认30)
+    #[serde(default)]
+    pub trend_period: Option<usize>, // 趋势线周期，None为双均线模式 (默认None)
+    #[serde(default)]
+    pub adx_period: Option<usize>, // ADX趋势强度过滤周期，None为不过滤 (默认None)
+    #[serde(default = "default_adx_threshold")]
+    pub adx_threshold: f64, // ADX不低于该值才入场 (默认25)
+    #[serde(default)]
+    pub volume_period: Option<usize>, // 成交量过滤的均量周期，None为不过滤 (默认None)
+    #[serde(default = "default_volume_multiplier")]
+    pub volume_multiplier: f64, // 交叉K线成交量不低于均量的倍数 (默认1.5)
+    pub position_fraction: f64, // 每次入场使用可用资金的比例 (默认0.9)
+}
+
+impl Default for MaCrossParams {
+    fn default() -> Self {
+        Self {
+            ma_type: MaType::Sma,
+            fast_period: 10,
+            slow_period: 30,
+            trend_period: None,
+            adx_period: None,
+            adx_threshold: default_adx_threshold(),
+            volume_period: None,
+            volume_multiplier: default_volume_multiplier(),
+            position_fraction: 0.9,
+        }
+    }
+}
+
+fn default_adx_threshold() -> f64 {
+    25.0
+}
+
+fn default_volume_multiplier() -> f64 {
+    1.5
+}
+
+/// 均值回归的入场触发方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MeanReversionTrigger {
+    #[default]
+    Band, // 收盘价跌破布林带下轨 (均值与标准差包含当前K线)
+    ZScore, // 收盘价相对之前period根K线的z-score低于阈值 (不含当前K线)
+}
+
+/// 均值回归策略参数
+///
+/// 价格向下偏离均值达到 `entry_std` 个标准差时做多，回到均值上方 `exit_std` 个标准差时离场，
+/// 持仓超过 `max_holding_bars` 根K线仍未回归时按时间止损离场。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeanReversionParams {
+    pub period: usize, // 均值与标准差周期 (默认20)
+    #[serde(default)]
+    pub trigger: MeanReversionTrigger, // 入场触发方式 (默认布林带)
+    pub entry_std: f64, // 入场偏离，标准差倍数 (默认2.0)
+    #[serde(default)]
+    pub exit_std: f64, // 离场位置，均值上方的标准差倍数 (默认0，即回到均值)
+    #[serde(default)]
+    pub rsi_period: Option<usize>, // RSI确认周期，None为不确认 (默认None)
+    #[serde(default = "default_rsi_oversold")]
+    pub rsi_oversold: f64, // RSI不高于该值才入场 (默认30)
+    #[serde(default = "default_max_holding_bars")]
+    pub max_holding_bars: Option<usize>, // 时间止损的最长持仓K线数，None为不设时间止损 (默认60)
+    pub position_fraction: f64, // 每次入场使用可用资金的比例 (默认0.5)
+}
+
+impl Default for MeanReversionParams {
+    fn default() -> Self {
+        Self {
+            period: 20,
+            trigger: MeanReversionTrigger::Band,
+            entry_std: 2.0,
+            exit_std: 0.0,
+            rsi_period: None,
+            rsi_oversold: default_rsi_oversold(),
+            max_holding_bars: default_max_holding_bars(),
+            position_fraction: 0.5,
+        }
+    }
+}
+
+fn default_rsi_oversold() -> f64 {
+    30.0
+}
+
+fn default_max_holding_bars() -> Option<usize> {
+    Some(60)
+}
+
+/// 网格价位的间距方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GridSpacing {
+    #[default]
+    Arithmetic, // 等差: 相邻价位相差固定金额
+    Geometric, // 等比: 相邻价位相差固定比例
+}
+
+/// 网格策略参数
+///
+/// 区间 [lower_price, upper_price] 按 grid_count 格划分为 grid_count+1 个价位，
+/// 每格在下沿挂买单，买单成交后在上沿挂卖单，卖单成交后重新在下沿挂买单。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridParams {
+    pub lower_price: f64,  // 区间下边界
+    pub upper_price: f64,  // 区间上边界
+    pub grid_count: usize, // 网格数量
+    #[serde(default)]
+    pub spacing: GridSpacing, // 间距方式 (默认等差)
+    pub quantity_per_grid: f64, // 每格买卖数量 (基础货币)
+    #[serde(default)]
+    pub stop_loss_price: Option<f64>, // 止损价 (低于下边界)，触及后清仓并停止网格 (默认None)
+    #[serde(default)]
+    pub rebalance_bars: Option<usize>, // 连续N根K线收盘在区间外时以收盘价为中心重建网格，None为不重建 (默认None)
+}
+
+impl Default for GridParams {
+    fn default() -> Self {
+        Self {
+            lower_price: 100.0,
+            upper_price: 200.0,
+            grid_count: 10,
+            spacing: GridSpacing::Arithmetic,
+            quantity_per_grid: 0.1,
+            stop_loss_price: None,
+            rebalance_bars: None,
+        }
+    }
+}
+
+/// 定投加倍档位: 价格低于均线达到below_pct%时按multiplier倍金额买入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DipMultiplier {
+    pub below_pct: f64,  // 低于均线的百分比
+    pub multiplier: f64, // 买入金额倍数
+}
+
+/// 定投止盈档位: 收盘价高于持仓成本gain_pct%时卖出当前持仓的sell_fraction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TakeProfitLevel {
+    pub gain_pct: f64,      // 相对持仓成本的涨幅百分比
+    pub sell_fraction: f64, // 卖出当前持仓的比例 (1为全部卖出)
+}
+
+/// 定投策略参数
+///
+/// 每个定投周期的第一根K线按quote_amount (计价货币) 买入，价格低于均线时按加倍档位放大金额；
+/// 止盈档位在每轮持仓中各触发一次，清仓后重新开始。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DcaParams {
+    pub quote_amount: f64,     // 每期买入金额 (计价货币)
+    pub interval_minutes: i64, // 定投周期 (分钟，按UTC对齐，1440为每天)
+    #[serde(default)]
+    pub dip_ma_type: MaType, // 加倍参考均线类型 (默认SMA)
+    #[serde(default = "default_dip_ma_period")]
+    pub dip_ma_period: usize, // 加倍参考均线周期 (默认200)
+    #[serde(default)]
+    pub dip_multipliers: Vec<DipMultiplier>, // 加倍档位，为空时每期金额固定
+    #[serde(default)]
+    pub max_allocation: Option<f64>, // 持仓成本上限 (计价货币)，None为不限
+    #[serde(default)]
+    pub take_profit: Vec<TakeProfitLevel>, // 止盈档位 (按gain_pct从低到高)，为空时只买不卖
+}
+
+impl Default for DcaParams {
+    fn default() -> Self {
+        Self {
+            quote_amount: 100.0,
+            interval_minutes: 1440,
+            dip_ma_type: MaType::Sma,
+            dip_ma_period: default_dip_ma_period(),
+            dip_multipliers: Vec::new(),
+            max_allocation: None,
+            take_profit: Vec::new(),
+        }
+    }
+}
+
+fn default_dip_ma_period() -> usize {
+    200
+}
+
+/// 配对交易的对冲比例估计方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HedgeRatioMethod {
+    #[default]
+    RollingOls, // 滚动窗口最小二乘回归
+    Kalman, // 卡尔曼滤波动态估计截距与斜率
+}
+
+/// 配对交易策略参数
+///
+/// 以 symbol 为第一腿 (y)、hedge_symbol 为第二腿 (x)，价差为 y - (alpha + beta * x)。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairsParams {
+    pub hedge_symbol: String, // 第二腿交易对
+    #[serde(default)]
+    pub method: HedgeRatioMethod, // 对冲比例估计方法 (默认滚动OLS)
+    pub lookback: usize,      // 回归与协整检验窗口 (K线数)
+    pub entry_z: f64,         // 价差z-score绝对值达到该值时开仓
+    pub exit_z: f64,          // 价差z-score绝对值回落到该值以内时平仓
+    #[serde(default)]
+    pub stop_z: Option<f64>, // 价差继续扩大到该z-score时止损平仓，None为不设
+    #[serde(default = "default_adf_critical")]
+    pub adf_critical: f64, // ADF统计量低于该临界值才视为协整 (默认-3.34，两变量Engle-Granger 5%水平)
+    #[serde(default = "default_kalman_delta")]
+    pub kalman_delta: f64, // 卡尔曼滤波状态噪声 (默认1e-4，越大对冲比例变化越快)
+    #[serde(default = "default_kalman_observation_var")]
+    pub kalman_observation_var: f64, // 卡尔曼滤波观测噪声方差 (默认1e-3)
+    pub position_fraction: f64, // 开仓时两腿金额合计占可用资金的比例
+}
+
+impl Default for PairsParams {
+    fn default() -> Self {
+        Self {
+            hedge_symbol: "ETH-USDT".to_string(),
+            method: HedgeRatioMethod::RollingOls,
+            lookback: 120,
+            entry_z: 2.0,
+            exit_z: 0.5,
+            stop_z: Some(4.0),
+            adf_critical: default_adf_critical(),
+            kalman_delta: default_kalman_delta(),
+            kalman_observation_var: default_kalman_observation_var(),
+            position_fraction: 0.5,
+        }
+    }
+}
+
+fn default_adf_critical() -> f64 {
+    -3.34
+}
+
+fn default_kalman_delta() -> f64 {
+    1e-4
+}
+
+fn default_kalman_observation_var() -> f64 {
+    1e-3
+}
+
+/// 期现资金费率套利策略参数
+///
+/// 以 symbol 为现货腿、perp_symbol 为USDT本位永续合约腿，开仓时买入现货并做空等量永续。
+/// 资金费率均为单次结算的费率 (如0.0001即0.01%)。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingArbParams {
+    pub perp_symbol: String, // 永续合约交易对
+    pub entry_funding: f64,  // 预测资金费率不低于该值时开仓
+    pub exit_funding: f64,   // 预测资金费率回落到该值以下时平仓
+    #[serde(default = "default_forecast_periods")]
+    pub forecast_periods: usize, // 预测资金费率取最近N次结算费率的均值
+    #[serde(default = "default_leverage")]
+    pub leverage: f64, // 永续腿杠杆倍数 (逐仓保证金 = 名义价值 / 杠杆)
+    #[serde(default = "default_maintenance_margin_rate")]
+    pub maintenance_margin_rate: f64, // 维持保证金率
+    #[serde(default = "default_min_liquidation_distance")]
+    pub min_liquidation_distance: f64, // 价格距强平价小于该比例时平仓 (如0.1即10%)
+    pub position_fraction: f64, // 开仓时两腿金额合计占可用资金的比例
+}
+
+impl Default for FundingArbParams {
+    fn default() -> Self {
+        Self {
+            perp_symbol: "SOL-USDT-SWAP".to_string(),
+            entry_funding: 0.0003,
+            exit_funding: 0.00005,
+            forecast_periods: default_forecast_periods(),
+            leverage: default_leverage(),
+            maintenance_margin_rate: default_maintenance_margin_rate(),
+            min_liquidation_distance: default_min_liquidation_distance(),
+            position_fraction: 0.45,
+        }
+    }
+}
+
+fn default_forecast_periods() -> usize {
+    3
+}
+
+fn default_leverage() -> f64 {
+    3.0
+}
+
+fn default_maintenance_margin_rate() -> f64 {
+    0.005
+}
+
+fn default_min_liquidation_distance() -> f64 {
+    0.1
+}
+
+/// 做市报价的公允价格来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FairPrice {
+    #[default]
+    Mid, // 买一与卖一的中间价
+    Microprice, // 按买卖一数量加权的微价格 (数量未知时退化为中间价)
+}
+
+/// 做市策略参数
+///
+/// 以公允价格为中心双边挂单: 半价差 = 公允价格 × (half_spread_bps/10000 + volatility_multiplier × 波动率)，
+/// 报价中心按库存比例向下偏移 inventory_skew 个半价差，库存达到上限时只挂卖单。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketMakingParams {
+    #[serde(default)]
+    pub fair_price: FairPrice, // 公允价格来源 (默认Mid)
+    pub half_spread_bps: f64,       // 基础半价差 (基点)
+    pub volatility_window: usize,   // 波动率窗口 (K线收盘价对数收益率的标准差)
+    pub volatility_multiplier: f64, // 波动率对半价差的放大倍数
+    pub inventory_skew: f64,        // 满库存时报价中心下移的半价差个数
+    pub order_size: f64,            // 每笔挂单数量 (基础货币)
+    pub max_inventory: f64,         // 库存上限 (基础货币)
+    pub refresh_threshold_bps: f64, // 公允价格变化超过该值 (基点) 时重新报价
+}
+
+impl Default for MarketMakingParams {
+    fn default() -> Self {
+        Self {
+            fair_price: FairPrice::Mid,
+            half_spread_bps: 10.0,
+            volatility_window: 20,
+            volatility_multiplier: 1.0,
+            inventory_skew: 1.0,
+            order_size: 0.1,
+            max_inventory: 1.0,
+            refresh_threshold_bps: 2.0,
+        }
+    }
+}
+
+/// 现货交易对的下单规则 (对应OKX公共接口 /api/v5/public/instruments 的 lotSz、minSz)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstrumentRules {
+    pub symbol: String,         // 交易对 (如 ETH-BTC)
+    pub base_currency: String,  // 基础货币 (ETH)
+    pub quote_currency: String, // 计价货币 (BTC)
+    pub lot_size: f64,          // 下单数量精度 (基础货币)
+    pub min_size: f64,          // 最小下单数量 (基础货币)
+}
+
+impl InstrumentRules {
+    /// 将下单数量按精度向下取整
+    pub fn round_lot(&self, quantity: f64) -> f64 {
+        if self.lot_size <= 0.0 {
+            return quantity;
+        }
+        // 容差避免 0.3 / 0.1 这类浮点误差多舍掉一个单位
+        (quantity / self.lot_size + 1e-9).floor() * self.lot_size
+    }
+}
+
+/// 交易对的最优买卖报价
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookQuote {
+    pub symbol: String,           // 交易对
+    pub timestamp: DateTime<Utc>, // 报价时间
+    pub bid_price: f64,           // 买一价
+    pub bid_size: Option<f64>,    // 买一数量 (基础货币)，未知时为None
+    pub ask_price: f64,           // 卖一价
+    pub ask_size: Option<f64>,    // 卖一数量 (基础货币)，未知时为None
+}
+
+impl From<&TickerData> for BookQuote {
+    fn from(ticker: &TickerData) -> Self {
+        let timestamp_ms: i64 = ticker.ts.parse().unwrap_or(0);
+        BookQuote {
+            symbol: ticker.inst_id.clone(),
+            timestamp: DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default(),
+            bid_price: ticker.bid_px.parse().unwrap_or(0.0),
+            bid_size: ticker.bid_sz.parse().ok(),
+            ask_price: ticker.ask_px.parse().unwrap_or(0.0),
+            ask_size: ticker.ask_sz.parse().ok(),
+        }
+    }
+}
+
+impl From<&TickerSnapshot> for BookQuote {
+    fn from(snapshot: &TickerSnapshot) -> Self {
+        BookQuote {
+            symbol: snapshot.symbol.clone(),
+            timestamp: snapshot.timestamp,
+            bid_price: snapshot.bid_price,
+            bid_size: None,
+            ask_price: snapshot.ask_price,
+            ask_size: None,
+        }
+    }
+}
+
+/// 三角套利检测配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriangularArbConfig {
+    pub start_currency: String,      // 起始与结算币种
+    pub triangles: Vec<[String; 3]>, // 三角组合 (三个交易对，顺序不限)
+    pub fee_rate: f64,               // 每笔吃单手续费率
+    pub min_edge: f64,               // 扣费后收益率达到该值才视为机会
+    pub start_amount: f64,           // 每次套利投入的起始币种数量 (受盘口数量限制可能减少)
+    #[serde(default)]
+    pub latency_ms: Option<i64>, // 纸面执行延迟 (毫秒)，None为只检测不模拟执行
+}
+
+impl Default for TriangularArbConfig {
+    fn default() -> Self {
+        Self {
+            start_currency: "USDT".to_string(),
+            triangles: vec![[
+                "BTC-USDT".to_string(),
+                "ETH-BTC".to_string(),
+                "ETH-USDT".to_string(),
+            ]],
+            fee_rate: 0.001,
+            min_edge: 0.0005,
+            start_amount: 1000.0,
+            latency_ms: Some(200),
+        }
+    }
+}
+
+/// 三角套利路径中的一笔交易
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArbitrageLeg {
+    pub symbol: String, // 交易对
+    pub side: String,   // 买卖方向 ("buy" or "sell")
+    pub price: f64,     // 成交价 (买入按卖一价、卖出按买一价)
+    pub quantity: f64,  // 按下单精度取整后的数量 (基础货币)
+    pub received: f64,  // 扣除手续费后收到的币种数量
+}
+
+/// 三角套利机会
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArbitrageOpportunity {
+    pub timestamp: DateTime<Utc>, // 检测时间 (触发检测的报价时间)
+    pub path: String,             // 兑换路径 (如 USDT>BTC>ETH>USDT)
+    pub legs: Vec<ArbitrageLeg>,  // 三笔交易
+    pub start_amount: f64,        // 实际投入的起始币种数量
+    pub end_amount: f64,          // 换回的起始币种数量
+    pub gross_edge: f64,          // 不计手续费与取整的理论收益率
+    pub edge: f64,                // 扣除手续费、按下单规则取整后的收益率
+}
+
+/// 纸面执行的延迟存活统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencySurvival {
+    pub detected: usize,        // 检测到的机会数
+    pub checked: usize,         // 已到达延迟时间并重新评估的机会数
+    pub survived: usize,        // 延迟后收益率仍达到阈值的机会数
+    pub detected_edge_sum: f64, // 已评估机会在检测时的收益率之和
+    pub survived_edge_sum: f64, // 存活机会在延迟后的收益率之和
+}
+
+impl LatencySurvival {
+    /// 存活率 (存活数 / 已评估数)
+    pub fn survival_rate(&self) -> f64 {
+        if self.checked == 0 {
+            0.0
+        } else {
+            self.survived as f64 / self.checked as f64
         }
     }
 }